@@ -0,0 +1,11 @@
+fn main() -> Result<(), Box<dyn std::error::Error>> {
+    println!("cargo:rerun-if-changed=build.rs");
+    // protox compiles the service definition without a protoc installation
+    #[cfg(feature = "grpc")]
+    {
+        println!("cargo:rerun-if-changed=proto/simulator.proto");
+        let descriptors = protox::compile(["proto/simulator.proto"], ["proto"])?;
+        tonic_build::configure().compile_fds(descriptors)?;
+    }
+    Ok(())
+}
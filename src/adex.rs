@@ -0,0 +1,141 @@
+use serde::{Deserialize, Serialize};
+
+use crate::neuron::{interpolate_crossing, NeuronModel, SynapseType};
+
+/// Parameters of an adaptive exponential integrate-and-fire neuron (Brette & Gerstner 2005),
+/// a second [`NeuronModel`] whose membrane potential `V` and adaptation current `w` take the
+/// place of `v` and `u`:
+///
+/// `C dV/dt = -g_L (V - E_L) + g_L Δ_T exp((V - V_T) / Δ_T) - w + C I`,
+/// `τ_w dw/dt = a (V - E_L) - w`,
+///
+/// with `V ← V_r` and `w ← w + b` once `V` reaches `V_peak`. The current `I` in pA is the input of
+/// the network scaled by `input_gain`, as weights and noise are in the units of the Izhikevich
+/// model.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct AdExParameters {
+    /// Membrane capacitance `C` in pF.
+    pub capacitance: f64,
+    /// Leak conductance `g_L` in nS.
+    pub leak_conductance: f64,
+    /// Leak reversal potential `E_L` in mV.
+    pub leak_reversal: f64,
+    /// Threshold potential `V_T` in mV.
+    pub threshold: f64,
+    /// Slope factor `Δ_T` of the spike initiation in mV.
+    pub slope: f64,
+    /// Subthreshold adaptation `a` in nS.
+    pub a: f64,
+    /// Spike-triggered adaptation increment `b` in pA.
+    pub b: f64,
+    /// Adaptation time constant `τ_w` in ms.
+    pub tau_w: f64,
+    /// Reset potential `V_r` in mV.
+    pub reset: f64,
+    /// Spike cutoff `V_peak` in mV.
+    pub peak: f64,
+    /// Current in pA per unit of network input.
+    pub input_gain: f64,
+}
+
+/// Firing patterns of the AdEx model, with the parameters of Naud et al. (2008) and the cortical
+/// pyramidal neuron of Brette & Gerstner (2005).
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum AdExPreset {
+    /// Regular spiking pyramidal neuron with adaptation, fit by Brette & Gerstner.
+    BretteGerstner,
+    /// Tonic spiking without adaptation, resembling fast spiking interneurons.
+    Tonic,
+    /// Spike frequency adaptation, resembling regular spiking neurons.
+    Adapting,
+    /// An initial burst followed by tonic spiking.
+    InitialBursting,
+    /// Regular bursting.
+    Bursting,
+}
+
+impl AdExPreset {
+    pub fn params(&self) -> AdExParameters {
+        // capacitance, leak conductance and reversal, a, b, τ_w, reset
+        let (capacitance, leak_conductance, leak_reversal, a, b, tau_w, reset) = match self {
+            AdExPreset::BretteGerstner => (281., 30., -70.6, 4., 80.5, 144., -70.6),
+            AdExPreset::Tonic => (200., 10., -70., 2., 0., 30., -58.),
+            AdExPreset::Adapting => (200., 12., -70., 2., 60., 300., -58.),
+            AdExPreset::InitialBursting => (130., 18., -58., 4., 120., 150., -50.),
+            AdExPreset::Bursting => (200., 10., -58., 2., 100., 120., -46.),
+        };
+        let (threshold, slope) = match self {
+            AdExPreset::BretteGerstner => (-50.4, 2.),
+            _ => (-50., 2.),
+        };
+        AdExParameters {
+            capacitance,
+            leak_conductance,
+            leak_reversal,
+            threshold,
+            slope,
+            a,
+            b,
+            tau_w,
+            reset,
+            // where the exponential has taken over, as usual for simulations
+            peak: threshold + 5. * slope,
+            // white noise makes the potential of a resting regular spiking Izhikevich neuron
+            // fluctuate like a membrane with a time constant of about 1.25 ms, whose variance
+            // this matches given the slower time constant C / (g_L + a) of the preset
+            input_gain: (1.25 * capacitance * (leak_conductance + a)).sqrt(),
+        }
+    }
+
+    /// Preset standing in for the randomized neurons of the paper: adapting excitatory and
+    /// tonic inhibitory neurons.
+    pub fn for_synapse_type(synapse_type: &SynapseType) -> Self {
+        match synapse_type {
+            SynapseType::Excitatory => AdExPreset::Adapting,
+            SynapseType::Inhibitory => AdExPreset::Tonic,
+        }
+    }
+}
+
+impl AdExParameters {
+    /// Derivatives of `V` and `w`, with the exponential capped at the spike cutoff so large steps
+    /// cannot overflow it.
+    fn derivatives(&self, v: f64, w: f64, i: f64) -> (f64, f64) {
+        let spike = self.slope * ((v.min(self.peak) - self.threshold) / self.slope).exp();
+        let dv = (self.leak_conductance * (spike - (v - self.leak_reversal)) - w
+            + self.input_gain * i)
+            / self.capacitance;
+        let dw = (self.a * (v - self.leak_reversal) - w) / self.tau_w;
+        (dv, dw)
+    }
+}
+
+impl NeuronModel for AdExParameters {
+    fn integrate(&mut self, v: &mut f64, u: &mut f64, i: f64, dt: f64) {
+        // in 2 half steps like the Izhikevich model
+        let half_dt = 0.5 * dt;
+        for _ in 0..2 {
+            let (dv, dw) = self.derivatives(*v, *u, i);
+            *v += half_dt * dv;
+            *u += half_dt * dw;
+        }
+    }
+
+    fn fired(&self, v: f64) -> bool {
+        v >= self.peak
+    }
+
+    fn crossing(&self, v_before: f64, v_after: f64) -> f64 {
+        interpolate_crossing(self.peak, v_before, v_after)
+    }
+
+    fn reset(&mut self, v: &mut f64, u: &mut f64) {
+        *v = self.reset;
+        *u += self.b;
+    }
+
+    fn hold(&mut self, v: &mut f64, u: &mut f64, dt: f64) {
+        *v = self.reset;
+        *u += dt * (self.a * (*v - self.leak_reversal) - *u) / self.tau_w;
+    }
+}
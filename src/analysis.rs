@@ -0,0 +1,1137 @@
+use std::collections::HashMap;
+use std::error::Error;
+use std::fmt;
+use std::fs;
+use std::io::{self, Write};
+use std::ops::Range;
+use std::path::Path;
+
+use serde::{Deserialize, Serialize};
+
+use crate::neuron::SynapseType;
+use crate::simulator::{SparseConnectivity, WorldState};
+
+/// Spike times of every neuron in milliseconds, decoupled from the simulator so recorded and
+/// loaded data are analyzed alike.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct SpikeTrains {
+    /// Sorted spike times of each neuron.
+    pub trains: Vec<Vec<f64>>,
+    /// Time the recording begins in milliseconds.
+    pub start: f64,
+    /// Length of the recording in milliseconds.
+    pub duration: f64,
+}
+
+impl SpikeTrains {
+    /// Collects `(time, neuron_idx)` spikes, with times in milliseconds.
+    pub fn from_spikes(
+        neurons: usize,
+        duration: f64,
+        spikes: impl IntoIterator<Item = (f64, usize)>,
+    ) -> Self {
+        let mut trains = vec![vec![]; neurons];
+        for (time, neuron_idx) in spikes {
+            trains[neuron_idx].push(time);
+        }
+        for train in &mut trains {
+            train.sort_by(f64::total_cmp);
+        }
+        SpikeTrains {
+            trains,
+            start: 0.,
+            duration,
+        }
+    }
+
+    /// Reads the `time,neuron_id` spikes written with `--spikes-out`, see
+    /// [`SpikeTrains::read_csv_populations`].
+    pub fn read_csv(path: &Path) -> Result<Self, Box<dyn Error>> {
+        Self::read_csv_populations(path).map(|(trains, _)| trains)
+    }
+
+    /// Reads the `time,neuron_id` spikes written with `--spikes-out` and the populations in its
+    /// `# population=name:start..end` comments, skipping other `#` comment lines. The recording
+    /// covers the neurons and time of its `# neurons=` and `# start=` and `# duration=` comments,
+    /// or else the neurons up to the highest index until the last spike.
+    #[allow(clippy::type_complexity)]
+    pub fn read_csv_populations(
+        path: &Path,
+    ) -> Result<(Self, Vec<(String, Range<usize>)>), Box<dyn Error>> {
+        let text = fs::read_to_string(path)?;
+        let (mut neurons, mut start, mut duration) = (None, 0., None);
+        let mut populations = vec![];
+        for (number, line) in text.lines().enumerate() {
+            let Some((key, value)) = line
+                .strip_prefix('#')
+                .and_then(|comment| comment.trim().split_once('='))
+            else {
+                continue;
+            };
+            let invalid = || format!("line {}: invalid {key} `{value}`", number + 1);
+            match key {
+                "neurons" => neurons = Some(value.parse::<usize>().map_err(|_| invalid())?),
+                "start" => start = value.parse().map_err(|_| invalid())?,
+                "duration" => duration = Some(value.parse().map_err(|_| invalid())?),
+                "population" => {
+                    let (name, range) = value.rsplit_once(':').ok_or_else(invalid)?;
+                    let (first, end) = range.split_once("..").ok_or_else(invalid)?;
+                    let first = first.parse().map_err(|_| invalid())?;
+                    let end = end.parse().map_err(|_| invalid())?;
+                    populations.push((name.to_string(), first..end));
+                }
+                _ => {}
+            }
+        }
+
+        let mut lines = text
+            .lines()
+            .enumerate()
+            .filter(|(_, line)| !line.trim().is_empty() && !line.starts_with('#'));
+        let spikes = match lines.next() {
+            Some((_, header)) if header.trim() == "time,neuron_id" => lines
+                .map(|(number, line)| {
+                    let (time, neuron) = line
+                        .split_once(',')
+                        .ok_or_else(|| format!("line {}: expected `time,neuron_id`", number + 1))?;
+                    let time: f64 = time
+                        .trim()
+                        .parse()
+                        .map_err(|err| format!("line {}: `{time}`: {err}", number + 1))?;
+                    let neuron: usize = neuron
+                        .trim()
+                        .parse()
+                        .map_err(|err| format!("line {}: `{neuron}`: {err}", number + 1))?;
+                    Ok((time, neuron))
+                })
+                .collect::<Result<Vec<_>, String>>()?,
+            Some((_, header)) => {
+                return Err(format!("expected `time,neuron_id`, got `{header}`").into())
+            }
+            None => vec![],
+        };
+        let highest = spikes
+            .iter()
+            .map(|&(_, neuron)| neuron + 1)
+            .chain(populations.iter().map(|(_, range)| range.end))
+            .max()
+            .unwrap_or(0);
+        let neurons = neurons.unwrap_or(highest).max(highest);
+        let last = spikes.iter().map(|&(time, _)| time).fold(start, f64::max);
+        let duration = duration.unwrap_or(last - start);
+        let mut trains = Self::from_spikes(neurons, duration, spikes);
+        trains.start = start;
+        Ok((trains, populations))
+    }
+
+    /// Writes the spikes as `time,neuron_id` rows sorted by time, as [`SpikeTrains::read_csv`]
+    /// reads them.
+    pub fn write_csv<W: Write>(&self, mut writer: W) -> io::Result<()> {
+        let mut spikes: Vec<(f64, usize)> = self
+            .trains
+            .iter()
+            .enumerate()
+            .flat_map(|(neuron, train)| train.iter().map(move |&time| (time, neuron)))
+            .collect();
+        spikes.sort_by(|a, b| a.0.total_cmp(&b.0).then(a.1.cmp(&b.1)));
+        writeln!(writer, "time,neuron_id")?;
+        for (time, neuron) in spikes {
+            writeln!(writer, "{time},{neuron}")?;
+        }
+        writer.flush()
+    }
+
+    /// Spikes kept by `world`, beginning with the step before the first kept one if it only
+    /// keeps a window of them.
+    pub fn from_world(world: &WorldState) -> Self {
+        let dt = world.dt;
+        let start = world.spike_record().first_step().saturating_sub(1) as f64 * dt;
+        let mut trains = Self::from_spikes(
+            world.neurons.len(),
+            world.time() - start,
+            world.spike_times(),
+        );
+        trains.start = start;
+        trains
+    }
+
+    /// The recording from `start` in milliseconds on, e.g. to leave out a transient.
+    pub fn after(&self, start: f64) -> Self {
+        let start = start.clamp(self.start, self.start + self.duration);
+        SpikeTrains {
+            trains: self
+                .trains
+                .iter()
+                .map(|train| train.iter().copied().filter(|&time| time > start).collect())
+                .collect(),
+            start,
+            duration: self.start + self.duration - start,
+        }
+    }
+
+    pub fn neurons(&self) -> usize {
+        self.trains.len()
+    }
+
+    /// Mean firing rate of every neuron in Hz.
+    pub fn mean_rates(&self) -> Vec<f64> {
+        self.trains
+            .iter()
+            .map(|train| rate_hz(train.len(), 1, self.duration))
+            .collect()
+    }
+
+    /// Spikes of `neurons` counted in consecutive bins of `bin` milliseconds.
+    ///
+    /// # Panics
+    ///
+    /// If `bin` is not positive.
+    pub fn binned_counts(&self, neurons: Range<usize>, bin: f64) -> Vec<usize> {
+        assert!(bin > 0., "the bin width must be positive");
+        let bins = (self.duration / bin).ceil() as usize;
+        let mut counts = vec![0; bins];
+        if bins == 0 {
+            return counts;
+        }
+        for train in &self.trains[neurons] {
+            for &time in train {
+                let idx = (((time - self.start) / bin) as usize).min(bins - 1);
+                counts[idx] += 1;
+            }
+        }
+        counts
+    }
+
+    /// Population rate of `neurons` in Hz per neuron in consecutive bins of `bin` milliseconds.
+    pub fn population_rate(&self, neurons: Range<usize>, bin: f64) -> Vec<f64> {
+        let size = neurons.len();
+        self.binned_counts(neurons, bin)
+            .into_iter()
+            .map(|count| rate_hz(count, size, bin))
+            .collect()
+    }
+}
+
+impl SpikeTrains {
+    /// Inter-spike intervals of every neuron in milliseconds.
+    pub fn isis(&self) -> Vec<Vec<f64>> {
+        self.trains
+            .iter()
+            .map(|train| train.windows(2).map(|pair| pair[1] - pair[0]).collect())
+            .collect()
+    }
+
+    /// Histogram of the inter-spike intervals of `neurons` pooled together.
+    ///
+    /// # Panics
+    ///
+    /// If `bin_width` is not positive and finite.
+    pub fn isi_histogram(&self, neurons: Range<usize>, bin_width: f64) -> Histogram {
+        let isis: Vec<f64> = self.trains[neurons]
+            .iter()
+            .flat_map(|train| train.windows(2).map(|pair| pair[1] - pair[0]))
+            .collect();
+        Histogram::new(&isis, bin_width)
+    }
+
+    /// Coefficient of variation of each neuron's inter-spike intervals: around 0 for regular
+    /// firing, 1 for Poisson-like irregular firing and above 1 for bursting. `None` for neurons
+    /// with fewer than two intervals.
+    pub fn isi_cv(&self) -> Vec<Option<f64>> {
+        self.isis()
+            .iter()
+            .map(|isis| {
+                if isis.len() < 2 {
+                    return None;
+                }
+                let mean = isis.iter().sum::<f64>() / isis.len() as f64;
+                let variance =
+                    isis.iter().map(|isi| (isi - mean).powi(2)).sum::<f64>() / isis.len() as f64;
+                Some(variance.sqrt() / mean)
+            })
+            .collect()
+    }
+}
+
+/// Neurons that fired in a time-locked cascade along strong synapses, where every firing after
+/// the anchors was triggered by coincident input, see [`SpikeTrains::polychronous_groups`].
+#[derive(Clone, Debug, PartialEq)]
+pub struct PolychronousGroup {
+    /// `(neuron, time)` of the firings of the first activation, with times relative to its first
+    /// firing in milliseconds, sorted by time.
+    pub firings: Vec<(usize, f64)>,
+    /// Length of the longest chain of triggered firings, counting the anchors as the first layer.
+    pub layers: usize,
+    /// Number of activations involving exactly the same neurons.
+    pub activations: usize,
+}
+
+impl SpikeTrains {
+    /// Mines the polychronous groups active in the recording. A firing counts as triggered when
+    /// spikes of at least `min_inputs` different neurons arrived at it along synapses of weight
+    /// `min_weight` or more within `jitter` milliseconds before it, with delays in steps of `dt`.
+    /// Triggered firings and the firings that triggered them are joined into activations, and
+    /// activations of the same neurons into one group. Groups are sorted by decreasing number of
+    /// activations, then by size.
+    pub fn polychronous_groups(
+        &self,
+        connectivity: &SparseConnectivity,
+        dt: f64,
+        min_weight: f64,
+        jitter: f64,
+        min_inputs: usize,
+    ) -> Vec<PolychronousGroup> {
+        let mut incoming = vec![vec![]; self.neurons()];
+        for pre in 0..connectivity.neurons() {
+            for synapse in connectivity.row(pre) {
+                if connectivity.weights()[synapse] >= min_weight {
+                    let delay = connectivity.delays()[synapse] as f64 * dt;
+                    incoming[connectivity.targets()[synapse]].push((pre, delay));
+                }
+            }
+        }
+
+        // every spike gets an id, assigned neuron by neuron
+        let offsets: Vec<usize> = self
+            .trains
+            .iter()
+            .scan(0, |offset, train| {
+                let start = *offset;
+                *offset += train.len();
+                Some(start)
+            })
+            .collect();
+        let mut spikes: Vec<(f64, usize, usize)> = self
+            .trains
+            .iter()
+            .enumerate()
+            .flat_map(|(neuron, train)| {
+                let offset = offsets[neuron];
+                train
+                    .iter()
+                    .enumerate()
+                    .map(move |(k, &time)| (time, neuron, offset + k))
+            })
+            .collect();
+        spikes.sort_by(|a, b| a.0.total_cmp(&b.0));
+
+        let total = spikes.len();
+        let mut parent: Vec<usize> = (0..total).collect();
+        let mut layer = vec![1; total];
+        let mut involved = vec![false; total];
+        fn find(parent: &mut [usize], mut id: usize) -> usize {
+            while parent[id] != id {
+                parent[id] = parent[parent[id]];
+                id = parent[id];
+            }
+            id
+        }
+
+        // in time order, so the layers of the causes are final
+        for &(time, neuron, id) in &spikes {
+            let mut causes = vec![];
+            let mut inputs = 0;
+            for &(pre, delay) in &incoming[neuron] {
+                let train = &self.trains[pre];
+                let from = train.partition_point(|&t| t + delay < time - jitter);
+                let to = train.partition_point(|&t| t + delay <= time);
+                if from < to {
+                    inputs += 1;
+                    causes.extend((from..to).map(|k| offsets[pre] + k));
+                }
+            }
+            if inputs < min_inputs {
+                continue;
+            }
+            involved[id] = true;
+            for cause in causes {
+                involved[cause] = true;
+                layer[id] = layer[id].max(layer[cause] + 1);
+                let (a, b) = (find(&mut parent, id), find(&mut parent, cause));
+                parent[a] = b;
+            }
+        }
+
+        let mut activations: HashMap<usize, Vec<(f64, usize, usize)>> = HashMap::new();
+        for &(time, neuron, id) in spikes.iter().filter(|&&(_, _, id)| involved[id]) {
+            let root = find(&mut parent, id);
+            activations
+                .entry(root)
+                .or_default()
+                .push((time, neuron, layer[id]));
+        }
+
+        // in order of onset, so every group is represented by its first activation
+        let mut activations: Vec<Vec<(f64, usize, usize)>> = activations.into_values().collect();
+        activations.sort_by(|a, b| a[0].0.total_cmp(&b[0].0));
+        let mut groups: Vec<(f64, PolychronousGroup)> = vec![];
+        let mut index: HashMap<Vec<usize>, usize> = HashMap::new();
+        for firings in activations {
+            let mut neurons: Vec<usize> = firings.iter().map(|&(_, neuron, _)| neuron).collect();
+            neurons.sort_unstable();
+            neurons.dedup();
+            let idx = *index.entry(neurons).or_insert_with(|| {
+                let start = firings[0].0;
+                groups.push((
+                    start,
+                    PolychronousGroup {
+                        firings: firings
+                            .iter()
+                            .map(|&(time, neuron, _)| (neuron, time - start))
+                            .collect(),
+                        layers: firings
+                            .iter()
+                            .map(|&(_, _, layer)| layer)
+                            .max()
+                            .unwrap_or(1),
+                        activations: 0,
+                    },
+                ));
+                groups.len() - 1
+            });
+            groups[idx].1.activations += 1;
+        }
+
+        groups.sort_by(|(a_start, a), (b_start, b)| {
+            b.activations
+                .cmp(&a.activations)
+                .then(b.firings.len().cmp(&a.firings.len()))
+                .then(a_start.total_cmp(b_start))
+        });
+        groups.into_iter().map(|(_, group)| group).collect()
+    }
+}
+
+/// Peri-stimulus time histogram: rate around event onsets, averaged over trials and neurons.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Psth {
+    /// Bin width in milliseconds.
+    pub bin: f64,
+    /// Time of the first bin's lower edge relative to onset, in milliseconds (negative).
+    pub start: f64,
+    /// Number of onsets averaged over.
+    pub trials: usize,
+    /// Rate in Hz per neuron in each bin.
+    pub rates: Vec<f64>,
+}
+
+impl Psth {
+    /// Lower edge of every bin relative to onset.
+    pub fn bin_starts(&self) -> impl Iterator<Item = f64> + '_ {
+        (0..self.rates.len()).map(|i| self.start + i as f64 * self.bin)
+    }
+}
+
+impl SpikeTrains {
+    /// PSTH of `neurons` from `before` milliseconds before to `after` milliseconds after each
+    /// onset. Onsets whose window does not fit in the recording are skipped.
+    ///
+    /// # Panics
+    ///
+    /// If `bin` is not positive and finite or the window is negative or infinite.
+    pub fn psth(
+        &self,
+        neurons: Range<usize>,
+        onsets: &[f64],
+        before: f64,
+        after: f64,
+        bin: f64,
+    ) -> Psth {
+        assert!(
+            bin > 0. && bin.is_finite(),
+            "the bin width must be positive and finite"
+        );
+        assert!(
+            before + after >= 0. && (before + after).is_finite(),
+            "the window must not be negative or infinite"
+        );
+        let bins = ((before + after) / bin).ceil() as usize;
+        let mut counts = vec![0usize; bins];
+        let trials: Vec<f64> = onsets
+            .iter()
+            .copied()
+            .filter(|&onset| {
+                onset - before >= self.start && onset + after <= self.start + self.duration
+            })
+            .collect();
+
+        for train in &self.trains[neurons.clone()] {
+            for &onset in &trials {
+                let from = train.partition_point(|&t| t < onset - before);
+                for &time in train[from..].iter().take_while(|&&t| t < onset + after) {
+                    counts[((time - onset + before) / bin) as usize] += 1;
+                }
+            }
+        }
+
+        let norm = trials.len() * neurons.len();
+        Psth {
+            bin,
+            start: -before,
+            trials: trials.len(),
+            rates: counts
+                .into_iter()
+                .map(|count| rate_hz(count, norm, bin))
+                .collect(),
+        }
+    }
+}
+
+/// Average of a signal over the window before the spikes of a neuron.
+#[derive(Clone, Debug, PartialEq)]
+pub struct SpikeTriggeredAverage {
+    /// Sampling interval of the signal in milliseconds.
+    pub dt: f64,
+    /// Number of spikes averaged over.
+    pub spikes: usize,
+    /// Average of the samples, the last over the step ending at the spike.
+    pub average: Vec<f64>,
+}
+
+impl SpikeTriggeredAverage {
+    /// End of every sample's step relative to the spike, the last at 0.
+    pub fn lags(&self) -> impl Iterator<Item = f64> + '_ {
+        let len = self.average.len();
+        (0..len).map(move |i| (i as f64 + 1. - len as f64) * self.dt)
+    }
+}
+
+impl SpikeTrains {
+    /// Spike-triggered average of `signal` over the `window` milliseconds before each spike of
+    /// `neuron`, where `signal` holds one sample per step of `dt` milliseconds, the first over
+    /// the step ending at `first`. Spikes without a full window of samples are skipped.
+    ///
+    /// # Panics
+    ///
+    /// If `dt` or `window` is not positive and finite.
+    pub fn spike_triggered_average(
+        &self,
+        neuron: usize,
+        signal: &[f64],
+        first: f64,
+        dt: f64,
+        window: f64,
+    ) -> SpikeTriggeredAverage {
+        assert!(
+            dt > 0. && dt.is_finite() && window > 0. && window.is_finite(),
+            "the sampling interval and window must be positive and finite"
+        );
+        let len = ((window / dt).round() as usize).max(1);
+        let mut sum = vec![0.; len];
+        let mut spikes = 0;
+        for &time in &self.trains[neuron] {
+            let end = ((time - first) / dt).round();
+            if end < 0. || end as usize >= signal.len() || (end as usize) + 1 < len {
+                continue;
+            }
+            let end = end as usize;
+            for (total, sample) in sum.iter_mut().zip(&signal[end + 1 - len..=end]) {
+                *total += sample;
+            }
+            spikes += 1;
+        }
+        SpikeTriggeredAverage {
+            dt,
+            spikes,
+            average: sum
+                .into_iter()
+                .map(|total| total / spikes.max(1) as f64)
+                .collect(),
+        }
+    }
+}
+
+/// Cross-correlogram: rate of the spikes of target neurons around the spikes of reference
+/// neurons, per target neuron.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Correlogram {
+    /// Bin width in milliseconds.
+    pub bin: f64,
+    /// Lag of the first bin's lower edge, in milliseconds (negative).
+    pub start: f64,
+    /// Number of reference spikes averaged over.
+    pub references: usize,
+    /// Rate in Hz per target neuron in each bin, at the target spike time minus the reference
+    /// spike time.
+    pub rates: Vec<f64>,
+}
+
+impl Correlogram {
+    /// Lower edge of every bin.
+    pub fn bin_starts(&self) -> impl Iterator<Item = f64> + '_ {
+        (0..self.rates.len()).map(|i| self.start + i as f64 * self.bin)
+    }
+}
+
+impl SpikeTrains {
+    /// Cross-correlogram of the spikes of `targets` from `window` milliseconds before to `window`
+    /// milliseconds after each spike of `references`, pooled over the neurons of both. Single
+    /// neurons are ranges of one, and a neuron in both ranges is not correlated with itself, so
+    /// a range against itself gives the mean cross-correlogram of its pairs.
+    ///
+    /// # Panics
+    ///
+    /// If `bin` is not positive.
+    pub fn cross_correlogram(
+        &self,
+        references: Range<usize>,
+        targets: Range<usize>,
+        window: f64,
+        bin: f64,
+    ) -> Correlogram {
+        assert!(bin > 0., "the bin width must be positive");
+        let bins = ((2. * window / bin).ceil() as usize).max(1);
+        let mut counts = vec![0usize; bins];
+        let mut target_spikes: Vec<(f64, usize)> = targets
+            .clone()
+            .flat_map(|idx| self.trains[idx].iter().map(move |&time| (time, idx)))
+            .collect();
+        target_spikes.sort_by(|a, b| a.0.total_cmp(&b.0));
+
+        let mut spikes = 0;
+        for reference in references.clone() {
+            for &time in &self.trains[reference] {
+                spikes += 1;
+                let from = target_spikes.partition_point(|&(t, _)| t < time - window);
+                for &(t, target) in target_spikes[from..]
+                    .iter()
+                    .take_while(|&&(t, _)| t < time + window)
+                {
+                    if target != reference {
+                        let idx = ((t - time + window) / bin) as usize;
+                        counts[idx.min(bins - 1)] += 1;
+                    }
+                }
+            }
+        }
+
+        // a neuron in both ranges has one target fewer
+        let overlap = references.start.max(targets.start)..references.end.min(targets.end);
+        let own: usize = overlap.map(|idx| self.trains[idx].len()).sum();
+        let norm = spikes * targets.len() - own;
+        Correlogram {
+            bin,
+            start: -window,
+            references: spikes,
+            rates: counts
+                .into_iter()
+                .map(|count| rate_hz(count, norm, bin))
+                .collect(),
+        }
+    }
+}
+
+/// Counts of values in consecutive bins starting at 0.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct Histogram {
+    pub bin_width: f64,
+    pub counts: Vec<usize>,
+}
+
+impl Histogram {
+    /// Bins non-negative `values`; the last bin holds the largest value.
+    ///
+    /// # Panics
+    ///
+    /// If `bin_width` is not positive and finite.
+    pub fn new(values: &[f64], bin_width: f64) -> Self {
+        assert!(
+            bin_width > 0. && bin_width.is_finite(),
+            "the bin width must be positive and finite"
+        );
+        let max = values.iter().copied().fold(0., f64::max);
+        let mut counts = vec![0; (max / bin_width) as usize + 1];
+        for &value in values {
+            counts[(value / bin_width) as usize] += 1;
+        }
+        Histogram { bin_width, counts }
+    }
+
+    /// Lower edge of every bin.
+    pub fn bin_starts(&self) -> impl Iterator<Item = f64> + '_ {
+        (0..self.counts.len()).map(|i| i as f64 * self.bin_width)
+    }
+}
+
+/// Snapshots of the weights of the synapses from excitatory neurons, the ones plasticity
+/// changes, taken every few steps: their distribution and the weights of an evenly spread
+/// sample of them.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct WeightHistory {
+    /// Steps between snapshots.
+    every: usize,
+    /// Step of the first snapshot.
+    first_step: usize,
+    /// Synapses from excitatory neurons, aligned with [`SparseConnectivity`].
+    synapses: Vec<usize>,
+    /// Bin width of the distributions.
+    pub bin_width: f64,
+    /// `(pre, post)` of the sampled synapses.
+    pub sampled: Vec<(usize, usize)>,
+    sampled_synapses: Vec<usize>,
+    /// Time of every snapshot in milliseconds.
+    pub times: Vec<f64>,
+    /// Distribution of the weights at every snapshot.
+    pub distributions: Vec<Histogram>,
+    /// Weights of every sampled synapse at every snapshot.
+    pub traces: Vec<Vec<f64>>,
+}
+
+impl WeightHistory {
+    /// Starts with a snapshot at `time_step` of steps of `dt`, to be followed by one every
+    /// `every` steps, of up to `samples` sampled synapses and distributions in bins of
+    /// `bin_width`.
+    pub fn new(
+        connectivity: &SparseConnectivity,
+        synapse_types: &[SynapseType],
+        time_step: usize,
+        dt: f64,
+        every: usize,
+        samples: usize,
+        bin_width: f64,
+    ) -> Self {
+        let mut pre_of = vec![];
+        let mut synapses = vec![];
+        for (pre, synapse_type) in synapse_types.iter().enumerate() {
+            if *synapse_type == SynapseType::Excitatory {
+                for synapse in connectivity.row(pre) {
+                    synapses.push(synapse);
+                    pre_of.push(pre);
+                }
+            }
+        }
+        let stride = synapses.len().div_ceil(samples.max(1)).max(1);
+        let sampled_idx: Vec<usize> = (0..synapses.len()).step_by(stride).collect();
+        let mut history = WeightHistory {
+            every: every.max(1),
+            first_step: time_step,
+            bin_width,
+            sampled: sampled_idx
+                .iter()
+                .map(|&k| (pre_of[k], connectivity.targets()[synapses[k]]))
+                .collect(),
+            sampled_synapses: sampled_idx.iter().map(|&k| synapses[k]).collect(),
+            synapses,
+            times: vec![],
+            distributions: vec![],
+            traces: vec![vec![]; sampled_idx.len()],
+        };
+        history.snapshot(time_step as f64 * dt, connectivity);
+        history
+    }
+
+    /// Takes a snapshot if one is due at the end of `time_step`.
+    pub fn on_step(&mut self, time_step: usize, dt: f64, connectivity: &SparseConnectivity) {
+        if time_step >= self.first_step && (time_step - self.first_step).is_multiple_of(self.every)
+        {
+            self.snapshot(time_step as f64 * dt, connectivity);
+        }
+    }
+
+    fn snapshot(&mut self, time: f64, connectivity: &SparseConnectivity) {
+        let weights = connectivity.weights();
+        let values: Vec<f64> = self
+            .synapses
+            .iter()
+            .map(|&synapse| weights[synapse])
+            .collect();
+        self.times.push(time);
+        self.distributions
+            .push(Histogram::new(&values, self.bin_width));
+        for (trace, &synapse) in self.traces.iter_mut().zip(&self.sampled_synapses) {
+            trace.push(weights[synapse]);
+        }
+    }
+}
+
+/// Rate in Hz per neuron of `count` spikes from `neurons` neurons over `duration` milliseconds.
+fn rate_hz(count: usize, neurons: usize, duration: f64) -> f64 {
+    if neurons == 0 || duration <= 0. {
+        return 0.;
+    }
+    count as f64 * 1000. / (neurons as f64 * duration)
+}
+
+/// Firing statistics of one population.
+#[derive(Clone, Debug, PartialEq)]
+pub struct PopulationRates {
+    pub name: String,
+    pub neurons: usize,
+    pub spikes: usize,
+    /// Mean rate over neurons and time in Hz.
+    pub mean_rate: f64,
+    /// Standard deviation of the per-neuron mean rates in Hz.
+    pub rate_std: f64,
+    /// Highest binned population rate in Hz.
+    pub peak_rate: f64,
+}
+
+/// Per-population summary table of a run.
+#[derive(Clone, Debug, PartialEq)]
+pub struct RateSummary {
+    /// Bin width of the population rates in milliseconds.
+    pub bin: f64,
+    pub populations: Vec<PopulationRates>,
+}
+
+impl RateSummary {
+    pub fn new(trains: &SpikeTrains, populations: &[(String, Range<usize>)], bin: f64) -> Self {
+        let rates = trains.mean_rates();
+        let populations = populations
+            .iter()
+            .map(|(name, neurons)| {
+                let population_rates = &rates[neurons.clone()];
+                let size = neurons.len().max(1) as f64;
+                let mean_rate = population_rates.iter().sum::<f64>() / size;
+                let variance = population_rates
+                    .iter()
+                    .map(|rate| (rate - mean_rate).powi(2))
+                    .sum::<f64>()
+                    / size;
+                PopulationRates {
+                    name: name.clone(),
+                    neurons: neurons.len(),
+                    spikes: trains.trains[neurons.clone()].iter().map(Vec::len).sum(),
+                    mean_rate,
+                    rate_std: variance.sqrt(),
+                    peak_rate: trains
+                        .population_rate(neurons.clone(), bin)
+                        .into_iter()
+                        .fold(0., f64::max),
+                }
+            })
+            .collect();
+        RateSummary { bin, populations }
+    }
+}
+
+impl fmt::Display for RateSummary {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(
+            f,
+            "{:<12} {:>8} {:>8} {:>10} {:>10} {:>16}",
+            "population",
+            "neurons",
+            "spikes",
+            "rate (Hz)",
+            "std (Hz)",
+            format!("peak/{}ms (Hz)", self.bin)
+        )?;
+        for population in &self.populations {
+            writeln!(
+                f,
+                "{:<12} {:>8} {:>8} {:>10.2} {:>10.2} {:>16.2}",
+                population.name,
+                population.neurons,
+                population.spikes,
+                population.mean_rate,
+                population.rate_std,
+                population.peak_rate
+            )?;
+        }
+        Ok(())
+    }
+}
+
+/// Share of the neurons that must have a phase at a sample of the Kuramoto order parameter.
+const KURAMOTO_COVERAGE: f64 = 0.95;
+
+impl SpikeTrains {
+    /// Mean Pearson correlation of the spike counts of all pairs of different `neurons` in
+    /// consecutive bins of `bin` milliseconds, leaving out neurons whose count never changes.
+    /// `None` for fewer than two such neurons.
+    ///
+    /// # Panics
+    ///
+    /// If `bin` is not positive.
+    pub fn spike_count_correlation(&self, neurons: Range<usize>, bin: f64) -> Option<f64> {
+        assert!(bin > 0., "the bin width must be positive");
+        let bins = (self.duration / bin).ceil() as usize;
+        if bins == 0 {
+            return None;
+        }
+        // with every neuron's counts standardized, the correlations of all pairs sum to that of
+        // the summed counts less each neuron's with itself
+        let mut total = vec![0.; bins];
+        let mut standardized = 0;
+        for train in &self.trains[neurons] {
+            let mut counts = vec![0.; bins];
+            for &time in train {
+                counts[(((time - self.start) / bin) as usize).min(bins - 1)] += 1.;
+            }
+            let mean = counts.iter().sum::<f64>() / bins as f64;
+            let std = (counts.iter().map(|c| (c - mean).powi(2)).sum::<f64>() / bins as f64).sqrt();
+            if std == 0. {
+                continue;
+            }
+            for (total, count) in total.iter_mut().zip(&counts) {
+                *total += (count - mean) / std;
+            }
+            standardized += 1;
+        }
+        if standardized < 2 {
+            return None;
+        }
+        let n = standardized as f64;
+        let pairs = total.iter().map(|z| z * z).sum::<f64>() / bins as f64 - n;
+        Some(pairs / (n * (n - 1.)))
+    }
+
+    /// Kuramoto order parameter of `neurons` averaged over samples every `bin` milliseconds, from
+    /// 1 for identical phases to about `1 / sqrt(N)` for `N` independent ones. Between
+    /// consecutive spikes the phase of a neuron advances linearly by 2π. Near either end of the
+    /// recording only the neurons that happened to spike close to the sample have a phase, which
+    /// biases them towards the same value, so samples at which fewer than 95% of the neurons
+    /// that spiked twice have a phase are left out. `None` if no sample is left.
+    ///
+    /// # Panics
+    ///
+    /// If `bin` is not positive.
+    pub fn kuramoto_order(&self, neurons: Range<usize>, bin: f64) -> Option<f64> {
+        assert!(bin > 0., "the sampling interval must be positive");
+        let samples = (self.duration / bin).floor() as usize;
+        let mut sums = vec![(0., 0., 0usize); samples];
+        let oscillators = self.trains[neurons.clone()]
+            .iter()
+            .filter(|train| train.len() >= 2)
+            .count();
+        for train in &self.trains[neurons] {
+            for pair in train.windows(2) {
+                let (start, end) = (pair[0], pair[1]);
+                // samples at (k + 0.5) bin within [start, end)
+                let first = (((start - self.start) / bin - 0.5).ceil().max(0.)) as usize;
+                for (k, (cos, sin, count)) in sums.iter_mut().enumerate().skip(first) {
+                    let t = self.start + (k as f64 + 0.5) * bin;
+                    if t >= end {
+                        break;
+                    }
+                    let phase = 2. * std::f64::consts::PI * (t - start) / (end - start);
+                    *cos += phase.cos();
+                    *sin += phase.sin();
+                    *count += 1;
+                }
+            }
+        }
+        let orders: Vec<f64> = sums
+            .into_iter()
+            .filter(|&(_, _, count)| {
+                count >= 2 && count as f64 >= KURAMOTO_COVERAGE * oscillators as f64
+            })
+            .map(|(cos, sin, count)| (cos * cos + sin * sin).sqrt() / count as f64)
+            .collect();
+        (!orders.is_empty()).then(|| orders.iter().sum::<f64>() / orders.len() as f64)
+    }
+}
+
+/// Running moments of the membrane potentials of a group of neurons for the synchrony index of
+/// Golomb and Rinzel (1993), see [`VoltageSynchrony::chi`].
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct VoltageSynchrony {
+    pub neurons: Range<usize>,
+    pub samples: usize,
+    sum: Vec<f64>,
+    sum_sq: Vec<f64>,
+    mean_sum: f64,
+    mean_sum_sq: f64,
+}
+
+impl VoltageSynchrony {
+    pub fn new(neurons: Range<usize>) -> Self {
+        VoltageSynchrony {
+            samples: 0,
+            sum: vec![0.; neurons.len()],
+            sum_sq: vec![0.; neurons.len()],
+            mean_sum: 0.,
+            mean_sum_sq: 0.,
+            neurons,
+        }
+    }
+
+    /// Adds the membrane potentials `v` of all neurons of the network at one step.
+    pub fn on_step(&mut self, v: &[f64]) {
+        let v = &v[self.neurons.clone()];
+        for ((sum, sum_sq), v) in self.sum.iter_mut().zip(&mut self.sum_sq).zip(v) {
+            *sum += v;
+            *sum_sq += v * v;
+        }
+        let mean = v.iter().sum::<f64>() / v.len().max(1) as f64;
+        self.mean_sum += mean;
+        self.mean_sum_sq += mean * mean;
+        self.samples += 1;
+    }
+
+    /// Square root of the variance over time of the mean potential of the group divided by the
+    /// mean over its neurons of the variance of their own potentials, 1 for identical and about
+    /// `1 / sqrt(N)` for independent potentials of `N` neurons. `None` before two samples or
+    /// for constant potentials. The reset of Izhikevich neurons hides their spikes, so this
+    /// measures the synchrony of the subthreshold potentials.
+    pub fn chi(&self) -> Option<f64> {
+        if self.samples < 2 || self.sum.is_empty() {
+            return None;
+        }
+        let samples = self.samples as f64;
+        let variance = |sum: f64, sum_sq: f64| (sum_sq / samples - (sum / samples).powi(2)).max(0.);
+        let individual = self
+            .sum
+            .iter()
+            .zip(&self.sum_sq)
+            .map(|(&sum, &sum_sq)| variance(sum, sum_sq))
+            .sum::<f64>()
+            / self.sum.len() as f64;
+        (individual > 0.).then(|| (variance(self.mean_sum, self.mean_sum_sq) / individual).sqrt())
+    }
+}
+
+/// Synchrony measures of one population, `None` where undefined.
+#[derive(Clone, Debug, PartialEq)]
+pub struct PopulationSynchrony {
+    pub name: String,
+    /// See [`SpikeTrains::spike_count_correlation`].
+    pub spike_count_correlation: Option<f64>,
+    /// See [`SpikeTrains::kuramoto_order`].
+    pub kuramoto_order: Option<f64>,
+    /// See [`VoltageSynchrony::chi`], if the potentials of the population were tracked.
+    pub golomb_rinzel: Option<f64>,
+}
+
+/// Per-population synchrony table of a run.
+#[derive(Clone, Debug, PartialEq)]
+pub struct SynchronySummary {
+    /// Bin width of the spike counts and phase samples in milliseconds.
+    pub bin: f64,
+    pub populations: Vec<PopulationSynchrony>,
+}
+
+impl SynchronySummary {
+    /// Measures the synchrony of every population, taking the Golomb–Rinzel index from the
+    /// entry of `voltages` tracking exactly its neurons.
+    pub fn new(
+        trains: &SpikeTrains,
+        populations: &[(String, Range<usize>)],
+        voltages: &[VoltageSynchrony],
+        bin: f64,
+    ) -> Self {
+        let populations = populations
+            .iter()
+            .map(|(name, neurons)| PopulationSynchrony {
+                name: name.clone(),
+                spike_count_correlation: trains.spike_count_correlation(neurons.clone(), bin),
+                kuramoto_order: trains.kuramoto_order(neurons.clone(), bin),
+                golomb_rinzel: voltages
+                    .iter()
+                    .find(|voltage| voltage.neurons == *neurons)
+                    .and_then(VoltageSynchrony::chi),
+            })
+            .collect();
+        SynchronySummary { bin, populations }
+    }
+}
+
+impl fmt::Display for SynchronySummary {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let cell = |value: Option<f64>| value.map_or("-".to_string(), |v| format!("{v:.3}"));
+        writeln!(
+            f,
+            "{:<12} {:>20} {:>12} {:>14}",
+            "population",
+            format!("count corr/{}ms", self.bin),
+            "kuramoto",
+            "golomb-rinzel"
+        )?;
+        for population in &self.populations {
+            writeln!(
+                f,
+                "{:<12} {:>20} {:>12} {:>14}",
+                population.name,
+                cell(population.spike_count_correlation),
+                cell(population.kuramoto_order),
+                cell(population.golomb_rinzel)
+            )?;
+        }
+        Ok(())
+    }
+}
+
+/// Victor–Purpura distance between the sorted spike times `a` and `b` in milliseconds: the
+/// cheapest way to turn one into the other, where inserting or deleting a spike costs 1 and
+/// moving one costs `cost` per millisecond, so spikes further apart than `2 / cost` count as
+/// different.
+pub fn victor_purpura_distance(a: &[f64], b: &[f64], cost: f64) -> f64 {
+    // row j of the table holds the distance between the first i spikes of a and first j of b
+    let mut previous: Vec<f64> = (0..=b.len()).map(|j| j as f64).collect();
+    let mut current = vec![0.; b.len() + 1];
+    for (i, &spike) in a.iter().enumerate() {
+        current[0] = (i + 1) as f64;
+        for (j, &other) in b.iter().enumerate() {
+            current[j + 1] = (previous[j + 1] + 1.)
+                .min(current[j] + 1.)
+                .min(previous[j] + cost * (spike - other).abs());
+        }
+        std::mem::swap(&mut previous, &mut current);
+    }
+    previous[b.len()]
+}
+
+/// Van Rossum distance between the sorted spike times `a` and `b` in milliseconds: the
+/// distance of the trains convolved with an exponential kernel of time constant `tau`, scaled
+/// so that a single unmatched spike is at `1 / sqrt(2)`.
+pub fn van_rossum_distance(a: &[f64], b: &[f64], tau: f64) -> f64 {
+    let squared =
+        0.5 * (kernel_sum(a, a, tau) + kernel_sum(b, b, tau) - 2. * kernel_sum(a, b, tau));
+    squared.max(0.).sqrt()
+}
+
+/// `Σ_ij exp(-|x_i - y_j| / tau)` over sorted spike times in linear time, from the kernels of the
+/// spikes of `y` up to and after every spike of `x`.
+fn kernel_sum(x: &[f64], y: &[f64], tau: f64) -> f64 {
+    let mut total = 0.;
+    // spikes of y at or before each spike of x, decayed to it
+    let (mut trace, mut time, mut j) = (0., f64::NEG_INFINITY, 0);
+    for &t in x {
+        while j < y.len() && y[j] <= t {
+            trace = trace * ((time - y[j]) / tau).exp() + 1.;
+            time = y[j];
+            j += 1;
+        }
+        total += trace * ((time - t) / tau).exp();
+    }
+    // and those after it, decayed back to it
+    let (mut trace, mut time, mut j) = (0., f64::INFINITY, y.len());
+    for &t in x.iter().rev() {
+        while j > 0 && y[j - 1] > t {
+            trace = trace * ((y[j - 1] - time) / tau).exp() + 1.;
+            time = y[j - 1];
+            j -= 1;
+        }
+        total += trace * ((t - time) / tau).exp();
+    }
+    total
+}
+
+impl SpikeTrains {
+    /// Victor–Purpura distance of every one of `neurons` between this and `other` recording,
+    /// see [`victor_purpura_distance`], e.g. between trials.
+    pub fn victor_purpura(
+        &self,
+        other: &SpikeTrains,
+        neurons: Range<usize>,
+        cost: f64,
+    ) -> Vec<f64> {
+        neurons
+            .map(|idx| victor_purpura_distance(self.train(idx), other.train(idx), cost))
+            .collect()
+    }
+
+    /// Van Rossum distance of every one of `neurons` between this and `other` recording, see
+    /// [`van_rossum_distance`].
+    pub fn van_rossum(&self, other: &SpikeTrains, neurons: Range<usize>, tau: f64) -> Vec<f64> {
+        neurons
+            .map(|idx| van_rossum_distance(self.train(idx), other.train(idx), tau))
+            .collect()
+    }
+
+    /// Spike times of neuron `idx`, empty beyond the recorded neurons.
+    fn train(&self, idx: usize) -> &[f64] {
+        self.trains.get(idx).map_or(&[], Vec::as_slice)
+    }
+}
@@ -0,0 +1,284 @@
+use std::fmt;
+use std::io::{self, Write};
+
+use rayon::prelude::*;
+
+use crate::analysis::{RateSummary, SpikeTrains, SynchronySummary};
+use crate::simulator::WorldStateBuilder;
+use crate::spectrum::{Band, PowerSpectrum};
+
+/// Runs independent realizations of a network in parallel, each built and driven from a seed of
+/// its own, for statistics over the random draws of neurons, synapses and noise.
+#[derive(Clone, Debug)]
+pub struct Batch {
+    /// Number of trials.
+    pub trials: usize,
+    /// Seed of the first trial, which the others follow consecutively.
+    pub seed: u64,
+    /// Simulated time of every trial in milliseconds.
+    pub duration: f64,
+    /// Bin width of the population rates in milliseconds.
+    pub rate_bin: f64,
+    /// Bin width of the synchrony measures in milliseconds.
+    pub synchrony_bin: f64,
+    /// Milliseconds of the Welch segments of the population rate spectrum.
+    pub spectrum_segment: f64,
+}
+
+/// Outcome of one trial of a [`Batch`], for the whole network followed by every population.
+#[derive(Clone, Debug, PartialEq)]
+pub struct TrialSummary {
+    pub seed: u64,
+    pub trains: SpikeTrains,
+    pub rates: RateSummary,
+    pub synchrony: SynchronySummary,
+    /// Power spectrum of the population rate of all neurons.
+    pub spectrum: PowerSpectrum,
+}
+
+impl Batch {
+    /// Builds a world from `builder` with the seed of every trial and runs it, tracking the
+    /// membrane potential synchrony of every population.
+    pub fn run(&self, builder: &WorldStateBuilder) -> Vec<TrialSummary> {
+        (0..self.trials)
+            .into_par_iter()
+            .map(|trial| {
+                let seed = self.seed.wrapping_add(trial as u64);
+                let mut world = builder.clone().seed(seed).build();
+                let n = world.neurons.len();
+                let mut populations = vec![("all".to_string(), 0..n)];
+                populations.extend(world.populations());
+                for (_, neurons) in &populations {
+                    world.track_voltage_synchrony(neurons.clone());
+                }
+                world.run_for(self.duration);
+
+                let trains = SpikeTrains::from_world(&world);
+                let rate = trains.population_rate(0..n, world.dt);
+                TrialSummary {
+                    seed,
+                    rates: RateSummary::new(&trains, &populations, self.rate_bin),
+                    synchrony: SynchronySummary::new(
+                        &trains,
+                        &populations,
+                        &world.voltage_synchrony,
+                        self.synchrony_bin,
+                    ),
+                    spectrum: PowerSpectrum::welch(&rate, world.dt, self.spectrum_segment),
+                    trains,
+                }
+            })
+            .collect()
+    }
+
+    /// Writes one CSV row per trial with its seed, the mean and peak rate in Hz, spike count
+    /// correlation, Kuramoto order and Golomb–Rinzel index of every population, and the power in
+    /// every band, leaving undefined values empty.
+    pub fn write_csv<W: Write>(&self, mut writer: W, trials: &[TrialSummary]) -> io::Result<()> {
+        let mut header = vec!["seed".to_string()];
+        if let Some(trial) = trials.first() {
+            for population in &trial.rates.populations {
+                for column in [
+                    "rate",
+                    "peak_rate",
+                    "count_correlation",
+                    "kuramoto",
+                    "golomb_rinzel",
+                ] {
+                    header.push(format!("{}_{column}", population.name));
+                }
+            }
+        }
+        for band in Band::ALL {
+            header.push(format!("{band}_power"));
+        }
+        writeln!(writer, "{}", header.join(","))?;
+
+        let cell = |value: Option<f64>| value.map_or(String::new(), |v| v.to_string());
+        for trial in trials {
+            let mut row = vec![trial.seed.to_string()];
+            for (rates, synchrony) in trial
+                .rates
+                .populations
+                .iter()
+                .zip(&trial.synchrony.populations)
+            {
+                row.push(rates.mean_rate.to_string());
+                row.push(rates.peak_rate.to_string());
+                row.push(cell(synchrony.spike_count_correlation));
+                row.push(cell(synchrony.kuramoto_order));
+                row.push(cell(synchrony.golomb_rinzel));
+            }
+            for band in Band::ALL {
+                row.push(cell(band_power(&trial.spectrum, band)));
+            }
+            writeln!(writer, "{}", row.join(","))?;
+        }
+        writer.flush()
+    }
+}
+
+/// Power of `spectrum` in `band`, or `None` beyond the Nyquist frequency.
+fn band_power(spectrum: &PowerSpectrum, band: Band) -> Option<f64> {
+    spectrum
+        .peak(band.range())
+        .map(|_| spectrum.band_power(band.range()))
+}
+
+/// Mean and sample standard deviation of a measure over trials.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct MeanStd {
+    pub mean: f64,
+    /// 0 for a single trial.
+    pub std: f64,
+}
+
+impl MeanStd {
+    /// Over the defined `values`, or `None` if there are none.
+    pub fn new(values: impl IntoIterator<Item = Option<f64>>) -> Option<Self> {
+        let values: Vec<f64> = values.into_iter().flatten().collect();
+        if values.is_empty() {
+            return None;
+        }
+        let count = values.len() as f64;
+        let mean = values.iter().sum::<f64>() / count;
+        let variance =
+            values.iter().map(|v| (v - mean).powi(2)).sum::<f64>() / (count - 1.).max(1.);
+        Some(MeanStd {
+            mean,
+            std: variance.sqrt(),
+        })
+    }
+}
+
+/// Formats as `mean ± std` with the precision of the formatter, 2 decimals by default.
+impl fmt::Display for MeanStd {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let precision = f.precision().unwrap_or(2);
+        write!(f, "{:.precision$} ± {:.precision$}", self.mean, self.std)
+    }
+}
+
+/// Statistics of one population over the trials of a [`Batch`].
+#[derive(Clone, Debug, PartialEq)]
+pub struct PopulationStatistics {
+    pub name: String,
+    /// Mean rate in Hz.
+    pub mean_rate: Option<MeanStd>,
+    /// Highest binned population rate in Hz.
+    pub peak_rate: Option<MeanStd>,
+    pub spike_count_correlation: Option<MeanStd>,
+    pub kuramoto_order: Option<MeanStd>,
+    pub golomb_rinzel: Option<MeanStd>,
+}
+
+/// Mean ± standard deviation of the rates, synchrony and spectra of the trials of a [`Batch`],
+/// each over the trials in which it is defined.
+#[derive(Clone, Debug, PartialEq)]
+pub struct BatchSummary {
+    pub trials: usize,
+    /// Bin width of the population rates in milliseconds.
+    pub rate_bin: f64,
+    /// Bin width of the synchrony measures in milliseconds.
+    pub synchrony_bin: f64,
+    pub populations: Vec<PopulationStatistics>,
+    /// Power of the population rate of all neurons in every band.
+    pub band_power: Vec<(Band, Option<MeanStd>)>,
+}
+
+impl BatchSummary {
+    pub fn new(batch: &Batch, trials: &[TrialSummary]) -> Self {
+        let names = trials.first().map_or(vec![], |trial| {
+            trial
+                .rates
+                .populations
+                .iter()
+                .map(|population| population.name.clone())
+                .collect()
+        });
+        let populations = names
+            .into_iter()
+            .enumerate()
+            .map(|(idx, name)| {
+                let rates = || {
+                    trials
+                        .iter()
+                        .map(move |trial| &trial.rates.populations[idx])
+                };
+                let synchrony = || {
+                    trials
+                        .iter()
+                        .map(move |trial| &trial.synchrony.populations[idx])
+                };
+                PopulationStatistics {
+                    name,
+                    mean_rate: MeanStd::new(rates().map(|p| Some(p.mean_rate))),
+                    peak_rate: MeanStd::new(rates().map(|p| Some(p.peak_rate))),
+                    spike_count_correlation: MeanStd::new(
+                        synchrony().map(|p| p.spike_count_correlation),
+                    ),
+                    kuramoto_order: MeanStd::new(synchrony().map(|p| p.kuramoto_order)),
+                    golomb_rinzel: MeanStd::new(synchrony().map(|p| p.golomb_rinzel)),
+                }
+            })
+            .collect();
+        BatchSummary {
+            trials: trials.len(),
+            rate_bin: batch.rate_bin,
+            synchrony_bin: batch.synchrony_bin,
+            populations,
+            band_power: Band::ALL
+                .into_iter()
+                .map(|band| {
+                    let powers = trials.iter().map(|trial| band_power(&trial.spectrum, band));
+                    (band, MeanStd::new(powers))
+                })
+                .collect(),
+        }
+    }
+}
+
+/// Tables of the rates and synchrony of every population and of the band powers, "-" for
+/// measures undefined in every trial.
+impl fmt::Display for BatchSummary {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let cell = |value: Option<MeanStd>, precision: usize| {
+            value.map_or("-".to_string(), |v| format!("{v:.precision$}"))
+        };
+        writeln!(f, "Mean ± std over {} trials", self.trials)?;
+        writeln!(
+            f,
+            "{:<12} {:>16} {:>18} {:>20} {:>16} {:>16}",
+            "population",
+            "rate (Hz)",
+            format!("peak/{}ms (Hz)", self.rate_bin),
+            format!("count corr/{}ms", self.synchrony_bin),
+            "kuramoto",
+            "golomb-rinzel"
+        )?;
+        for population in &self.populations {
+            writeln!(
+                f,
+                "{:<12} {:>16} {:>18} {:>20} {:>16} {:>16}",
+                population.name,
+                cell(population.mean_rate, 2),
+                cell(population.peak_rate, 2),
+                cell(population.spike_count_correlation, 3),
+                cell(population.kuramoto_order, 3),
+                cell(population.golomb_rinzel, 3)
+            )?;
+        }
+        writeln!(f, "{:<8} {:>12} {:>20}", "band", "range (Hz)", "power")?;
+        for (band, power) in &self.band_power {
+            let range = band.range();
+            writeln!(
+                f,
+                "{:<8} {:>12} {:>20}",
+                band.name(),
+                format!("{}-{}", range.start, range.end),
+                cell(*power, 4)
+            )?;
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,215 @@
+use std::fmt;
+use std::io::{self, Write};
+use std::str::FromStr;
+
+use rayon::prelude::*;
+
+use crate::clamp::{CurrentClamp, Waveform};
+use crate::neuron::{Neuron, NeuronParameters};
+
+/// A parameter of a single neuron varied by a [`Bifurcation`].
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum BifurcationParameter {
+    A,
+    B,
+    C,
+    D,
+    /// Constant injected current.
+    Current,
+}
+
+impl BifurcationParameter {
+    pub const ALL: [BifurcationParameter; 5] = [
+        BifurcationParameter::A,
+        BifurcationParameter::B,
+        BifurcationParameter::C,
+        BifurcationParameter::D,
+        BifurcationParameter::Current,
+    ];
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            BifurcationParameter::A => "a",
+            BifurcationParameter::B => "b",
+            BifurcationParameter::C => "c",
+            BifurcationParameter::D => "d",
+            BifurcationParameter::Current => "current",
+        }
+    }
+}
+
+impl fmt::Display for BifurcationParameter {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.name())
+    }
+}
+
+impl FromStr for BifurcationParameter {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        BifurcationParameter::ALL
+            .into_iter()
+            .find(|parameter| parameter.name() == s)
+            .ok_or_else(|| {
+                let names: Vec<_> = BifurcationParameter::ALL.iter().map(|p| p.name()).collect();
+                format!(
+                    "unknown parameter `{s}`, expected one of {}",
+                    names.join(", ")
+                )
+            })
+    }
+}
+
+/// Ratio of the longest to the shortest inter-spike interval above which spikes come in bursts.
+const BURST_RATIO: f64 = 3.;
+
+/// Firing pattern of a neuron under constant current.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum FiringPattern {
+    Quiescent,
+    /// Spikes at a steady rate, possibly after adapting.
+    Tonic,
+    /// Groups of spikes separated by pauses.
+    Bursting,
+}
+
+impl FiringPattern {
+    pub const ALL: [FiringPattern; 3] = [
+        FiringPattern::Quiescent,
+        FiringPattern::Tonic,
+        FiringPattern::Bursting,
+    ];
+
+    /// Classifies inter-spike intervals as bursting when the longest is more than three times
+    /// the shortest, and quiescent for a neuron that did not spike.
+    pub fn classify(spikes: usize, isis: &[f64]) -> Self {
+        let (min, max) = isis.iter().fold((f64::INFINITY, 0f64), |(min, max), &isi| {
+            (min.min(isi), max.max(isi))
+        });
+        if spikes == 0 {
+            FiringPattern::Quiescent
+        } else if isis.len() >= 2 && max > BURST_RATIO * min {
+            FiringPattern::Bursting
+        } else {
+            FiringPattern::Tonic
+        }
+    }
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            FiringPattern::Quiescent => "quiescent",
+            FiringPattern::Tonic => "tonic",
+            FiringPattern::Bursting => "bursting",
+        }
+    }
+}
+
+impl fmt::Display for FiringPattern {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.name())
+    }
+}
+
+/// Firing of the neuron at one value of a [`Bifurcation`], after the transient.
+#[derive(Clone, Debug, PartialEq)]
+pub struct BifurcationPoint {
+    pub value: f64,
+    pub pattern: FiringPattern,
+    /// Rate in Hz.
+    pub rate: f64,
+    /// Inter-spike intervals in milliseconds.
+    pub isis: Vec<f64>,
+}
+
+/// Firing patterns of a neuron under constant current along one of its parameters, see
+/// [`Bifurcation::measure`].
+#[derive(Clone, Debug, PartialEq)]
+pub struct Bifurcation {
+    pub parameter: BifurcationParameter,
+    pub points: Vec<BifurcationPoint>,
+}
+
+impl Bifurcation {
+    /// Drives the Izhikevich `neuron` from its membrane potential by `current`, or by each of
+    /// `values` when the current is the varied parameter, with the parameter set to each of
+    /// `values` in parallel. Each run lasts `duration` milliseconds in steps of `dt`, and only the
+    /// spikes after the first `transient` milliseconds are classified.
+    pub fn measure(
+        neuron: &Neuron,
+        parameter: BifurcationParameter,
+        values: &[f64],
+        current: f64,
+        duration: f64,
+        transient: f64,
+        dt: f64,
+    ) -> Self {
+        let window = (duration - transient).max(dt);
+        let points = values
+            .par_iter()
+            .map(|&value| {
+                let mut parameters = neuron.parameters().clone();
+                let mut amplitude = current;
+                match parameter {
+                    BifurcationParameter::A => parameters.a = value,
+                    BifurcationParameter::B => parameters.b = value,
+                    BifurcationParameter::C => parameters.c = value,
+                    BifurcationParameter::D => parameters.d = value,
+                    BifurcationParameter::Current => amplitude = value,
+                }
+                let clamp = CurrentClamp {
+                    neuron: varied(neuron, parameters),
+                    waveform: Waveform::constant(amplitude),
+                    dt,
+                };
+                let spikes: Vec<f64> = clamp
+                    .run(duration)
+                    .spikes
+                    .into_iter()
+                    .filter(|&t| t > transient)
+                    .collect();
+                let isis: Vec<f64> = spikes.windows(2).map(|pair| pair[1] - pair[0]).collect();
+                BifurcationPoint {
+                    value,
+                    pattern: FiringPattern::classify(spikes.len(), &isis),
+                    rate: spikes.len() as f64 * 1000. / window,
+                    isis,
+                }
+            })
+            .collect();
+        Bifurcation { parameter, points }
+    }
+
+    /// Writes one CSV row per value with its pattern, rate and shortest and longest inter-spike
+    /// intervals, left empty without intervals.
+    pub fn write_csv<W: Write>(&self, mut writer: W) -> io::Result<()> {
+        writeln!(writer, "{},pattern,rate,min_isi,max_isi", self.parameter)?;
+        for point in &self.points {
+            let (min, max) = if point.isis.is_empty() {
+                (String::new(), String::new())
+            } else {
+                (
+                    point
+                        .isis
+                        .iter()
+                        .copied()
+                        .fold(f64::INFINITY, f64::min)
+                        .to_string(),
+                    point.isis.iter().copied().fold(0., f64::max).to_string(),
+                )
+            };
+            writeln!(
+                writer,
+                "{},{},{},{min},{max}",
+                point.value, point.pattern, point.rate
+            )?;
+        }
+        writer.flush()
+    }
+}
+
+/// `neuron` with other Izhikevich parameters, at the same potential with `u` at its new steady
+/// state.
+fn varied(neuron: &Neuron, parameters: NeuronParameters) -> Neuron {
+    Neuron::new(parameters, neuron.synapse_type.clone()).with_potential(neuron.v)
+}
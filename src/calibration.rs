@@ -0,0 +1,119 @@
+use std::ops::Range;
+
+use crate::simulator::WorldState;
+use crate::sweep::SweepParameter;
+
+/// Tunes one parameter of a network until a population fires at a target rate: the network runs
+/// in windows, and after each the parameter is multiplied by the ratio of the target to the
+/// measured rate raised to `gain`, or its inverse for the inhibitory weights, up to a factor of
+/// two either way. The value found is then fixed on a fresh network for the actual run.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Calibration {
+    /// Neurons whose mean rate is matched.
+    pub neurons: Range<usize>,
+    /// Target rate in Hz.
+    pub target: f64,
+    /// The thalamic noise or one of the weight scales.
+    pub parameter: SweepParameter,
+    /// Milliseconds run after every adjustment before measuring, for the network to settle.
+    pub settle: f64,
+    /// Milliseconds the rate is measured over.
+    pub window: f64,
+    /// Exponent of the rate ratio applied to the parameter.
+    pub gain: f64,
+    /// Largest distance in Hz from the target that ends the calibration.
+    pub tolerance: f64,
+    pub max_iterations: usize,
+}
+
+/// Value a [`Calibration`] settled on.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Calibrated {
+    pub parameter: SweepParameter,
+    /// Standard deviation of the thalamic noise, or the factor on the weights as built.
+    pub value: f64,
+    /// Rate in Hz of the last window.
+    pub rate: f64,
+    pub iterations: usize,
+    /// Whether the rate came within the tolerance of the target.
+    pub converged: bool,
+}
+
+impl Calibration {
+    pub fn new(neurons: Range<usize>, target: f64, parameter: SweepParameter) -> Self {
+        Calibration {
+            neurons,
+            target,
+            parameter,
+            settle: 200.,
+            window: 500.,
+            gain: 0.5,
+            tolerance: 0.5,
+            max_iterations: 30,
+        }
+    }
+
+    /// Adjusts the parameter on `world` until the rate matches, leaving it at the value found.
+    /// `world` must keep its spikes for at least a window.
+    ///
+    /// # Panics
+    ///
+    /// If the parameter is the connection probability, which only applies to building, or the
+    /// thalamic noise of a world without it.
+    pub fn run(&self, world: &mut WorldState) -> Calibrated {
+        assert!(
+            self.parameter != SweepParameter::ConnectionProbability,
+            "the connection probability cannot be calibrated on a built network"
+        );
+        let drive = self.parameter == SweepParameter::ThalamicSigma;
+        assert!(
+            !drive || world.thalamic_noise,
+            "the thalamic noise cannot be calibrated without thalamic noise"
+        );
+        let exponent = match self.parameter {
+            // more inhibition lowers the rate
+            SweepParameter::InhibitoryWeightScale => -self.gain,
+            _ => self.gain,
+        };
+        let mut calibrated = Calibrated {
+            parameter: self.parameter,
+            value: if drive { world.thalamic_sigma } else { 1. },
+            rate: 0.,
+            iterations: 0,
+            converged: false,
+        };
+        while calibrated.iterations < self.max_iterations {
+            world.run_for(self.settle);
+            let first_step = world.time_step;
+            world.run_for(self.window);
+            let spikes = world
+                .spike_record()
+                .since(first_step)
+                .filter(|(_, neuron)| self.neurons.contains(neuron))
+                .count();
+            calibrated.rate =
+                1e3 * spikes as f64 / (self.neurons.len().max(1) as f64 * self.window);
+            calibrated.iterations += 1;
+            if (calibrated.rate - self.target).abs() <= self.tolerance {
+                calibrated.converged = true;
+                break;
+            }
+            // a silent population moves the parameter by the whole factor of two
+            let factor = (self.target / calibrated.rate)
+                .powf(exponent)
+                .clamp(0.5, 2.);
+            calibrated.value *= factor;
+            // the weight scales multiply the weights as they are
+            self.parameter
+                .apply(world, if drive { calibrated.value } else { factor });
+        }
+        calibrated
+    }
+}
+
+impl Calibrated {
+    /// Sets the value found on `world`, built like the calibrated one.
+    pub fn apply(&self, world: &mut WorldState) {
+        self.parameter.apply(world, self.value);
+    }
+}
@@ -0,0 +1,29 @@
+use std::fs::File;
+use std::io::{self, BufReader, BufWriter, Write};
+use std::path::Path;
+
+use crate::simulator::{Backend, WorldState};
+
+/// Writes the complete state of `world` to `path`, including weights, membrane state, in-flight
+/// spikes and the random number generator, so [`load_checkpoint`] resumes the run exactly.
+/// Attached stimuli are not saved and have to be attached again after loading.
+pub fn save_checkpoint(path: impl AsRef<Path>, world: &mut WorldState) -> io::Result<()> {
+    write_checkpoint(BufWriter::new(File::create(path)?), world)
+}
+
+/// Writes the checkpoint of [`save_checkpoint`] to `writer`.
+pub(crate) fn write_checkpoint<W: Write>(writer: W, world: &mut WorldState) -> io::Result<()> {
+    // only the CPU backend keeps every in-flight spike in the delay buffer
+    let backend = world.backend();
+    world.set_backend(Backend::Cpu).map_err(io::Error::other)?;
+    let saved = bincode::serialize_into(writer, world).map_err(io::Error::other);
+    world.set_backend(backend).map_err(io::Error::other)?;
+    saved
+}
+
+/// Reads a world written by [`save_checkpoint`]. It steps on the CPU backend until another one
+/// is selected.
+pub fn load_checkpoint(path: impl AsRef<Path>) -> io::Result<WorldState> {
+    let reader = BufReader::new(File::open(path)?);
+    bincode::deserialize_from(reader).map_err(io::Error::other)
+}
@@ -0,0 +1,340 @@
+use std::error::Error;
+use std::f64::consts::PI;
+use std::fs;
+use std::io::{self, Write};
+use std::ops::Range;
+use std::path::Path;
+use std::str::FromStr;
+
+use rayon::prelude::*;
+
+use crate::neuron::Neuron;
+use crate::stimulus::{PulseTrain, Ramp, StepCurrent, Stimulus};
+
+/// Shape of one segment of a [`Waveform`], with times relative to the start of its window.
+#[derive(Clone, Debug, PartialEq)]
+pub enum Shape {
+    Step(StepCurrent),
+    /// Ramp over the whole window.
+    Ramp {
+        from: f64,
+        to: f64,
+    },
+    Pulses(PulseTrain),
+    /// Sinusoid of `amplitude` at `frequency` Hz, starting at zero.
+    Sine {
+        amplitude: f64,
+        frequency: f64,
+    },
+    /// Current interpolated linearly between `(time, current)` samples sorted by time, and held
+    /// before the first and after the last.
+    Table(Vec<(f64, f64)>),
+}
+
+/// A [`Shape`] injected during `window` milliseconds.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Segment {
+    pub shape: Shape,
+    pub window: Range<f64>,
+}
+
+impl Segment {
+    /// Current of the segment at `t` milliseconds after the experiment started.
+    pub fn current(&self, t: f64) -> f64 {
+        if !self.window.contains(&t) {
+            return 0.;
+        }
+        let t = t - self.window.start;
+        match &self.shape {
+            Shape::Step(step) => step.current(t),
+            Shape::Ramp { from, to } => Ramp {
+                from: *from,
+                to: *to,
+                duration: self.window.end - self.window.start,
+            }
+            .current(t),
+            Shape::Pulses(pulses) => pulses.current(t),
+            Shape::Sine {
+                amplitude,
+                frequency,
+            } => amplitude * (2. * PI * frequency * t / 1000.).sin(),
+            Shape::Table(samples) => {
+                let after = samples.partition_point(|&(time, _)| time <= t);
+                match (
+                    after.checked_sub(1).map(|idx| samples[idx]),
+                    samples.get(after),
+                ) {
+                    (Some((t0, i0)), Some(&(t1, i1))) => i0 + (i1 - i0) * (t - t0) / (t1 - t0),
+                    (Some((_, i)), None) | (None, Some(&(_, i))) => i,
+                    (None, None) => 0.,
+                }
+            }
+        }
+    }
+}
+
+impl FromStr for Segment {
+    type Err = String;
+
+    /// Parses `step:AMPLITUDE`, `ramp:FROM:TO`, `pulses:AMPLITUDE:PERIOD:WIDTH` or
+    /// `sine:AMPLITUDE:FREQUENCY`, optionally followed by a window `@START..END` in milliseconds,
+    /// where either end may be left out.
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let (shape, window) = match s.split_once('@') {
+            Some((shape, window)) => (shape, Some(window)),
+            None => (s, None),
+        };
+        let number = |value: &str| {
+            value
+                .trim()
+                .parse::<f64>()
+                .map_err(|err| format!("invalid number `{value}`: {err}"))
+        };
+        let window = match window {
+            Some(window) => {
+                let (start, end) = window
+                    .split_once("..")
+                    .ok_or_else(|| format!("expected a window `START..END`, got `{window}`"))?;
+                let bound = |value: &str, default: f64| match value.trim() {
+                    "" => Ok(default),
+                    value => number(value),
+                };
+                bound(start, 0.)?..bound(end, f64::INFINITY)?
+            }
+            None => 0.0..f64::INFINITY,
+        };
+        let fields: Vec<&str> = shape.split(':').collect();
+        let shape = match fields.as_slice() {
+            ["step", amplitude] => Shape::Step(StepCurrent {
+                amplitude: number(amplitude)?,
+            }),
+            ["ramp", from, to] => {
+                if window.end.is_infinite() {
+                    return Err(format!("a ramp needs a window with an end, got `{s}`"));
+                }
+                Shape::Ramp {
+                    from: number(from)?,
+                    to: number(to)?,
+                }
+            }
+            ["pulses", amplitude, period, width] => Shape::Pulses(PulseTrain {
+                amplitude: number(amplitude)?,
+                period: number(period)?,
+                width: number(width)?,
+            }),
+            ["sine", amplitude, frequency] => Shape::Sine {
+                amplitude: number(amplitude)?,
+                frequency: number(frequency)?,
+            },
+            _ => {
+                return Err(format!(
+                    "expected `step:AMPLITUDE`, `ramp:FROM:TO`, `pulses:AMPLITUDE:PERIOD:WIDTH` \
+                     or `sine:AMPLITUDE:FREQUENCY`, got `{shape}`"
+                ))
+            }
+        };
+        Ok(Segment { shape, window })
+    }
+}
+
+/// Current injected in a [`CurrentClamp`], the sum of its segments.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct Waveform {
+    pub segments: Vec<Segment>,
+}
+
+impl Waveform {
+    /// Current held at `amplitude` for the whole experiment.
+    pub fn constant(amplitude: f64) -> Self {
+        Waveform {
+            segments: vec![Segment {
+                shape: Shape::Step(StepCurrent { amplitude }),
+                window: 0.0..f64::INFINITY,
+            }],
+        }
+    }
+
+    /// Reads a waveform sampled in a CSV file with a header and the columns `time` in
+    /// milliseconds and `current`, interpolated linearly between the samples.
+    pub fn read_csv(path: &Path) -> Result<Self, Box<dyn Error>> {
+        let text = fs::read_to_string(path)?;
+        let mut lines = text
+            .lines()
+            .enumerate()
+            .filter(|(_, line)| !line.trim().is_empty());
+        let header: Vec<&str> = match lines.next() {
+            Some((_, header)) => header.split(',').map(str::trim).collect(),
+            None => return Ok(Waveform::default()),
+        };
+        let column = |name: &str| {
+            header
+                .iter()
+                .position(|&column| column == name)
+                .ok_or_else(|| format!("missing column `{name}`"))
+        };
+        let (time, current) = (column("time")?, column("current")?);
+        let mut samples = lines
+            .map(|(number, line)| {
+                let fields: Vec<&str> = line.split(',').map(str::trim).collect();
+                let number_at = |idx: usize| {
+                    fields
+                        .get(idx)
+                        .ok_or_else(|| {
+                            format!("line {}: missing field `{}`", number + 1, header[idx])
+                        })?
+                        .parse::<f64>()
+                        .map_err(|err| format!("line {}: `{}`: {err}", number + 1, header[idx]))
+                };
+                Ok((number_at(time)?, number_at(current)?))
+            })
+            .collect::<Result<Vec<_>, String>>()?;
+        samples.sort_by(|a, b| a.0.total_cmp(&b.0));
+        Ok(Waveform {
+            segments: vec![Segment {
+                shape: Shape::Table(samples),
+                window: 0.0..f64::INFINITY,
+            }],
+        })
+    }
+}
+
+impl Stimulus for Waveform {
+    fn current(&self, t: f64) -> f64 {
+        self.segments
+            .iter()
+            .fold(0., |current, segment| current + segment.current(t))
+    }
+
+    fn onsets(&self, _duration: f64) -> Vec<f64> {
+        self.segments
+            .iter()
+            .map(|segment| segment.window.start)
+            .collect()
+    }
+}
+
+/// A single neuron driven by a current waveform without noise or synapses, for exploring a
+/// parameter set before embedding it in a network.
+#[derive(Clone, Debug)]
+pub struct CurrentClamp {
+    pub neuron: Neuron,
+    pub waveform: Waveform,
+    /// Integration time step in milliseconds.
+    pub dt: f64,
+}
+
+/// States and input of a [`CurrentClamp`] at the end of every step.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct ClampTrace {
+    pub dt: f64,
+    /// Times at the end of each step in milliseconds.
+    pub time: Vec<f64>,
+    /// Membrane potential, shown at the cutoff of 30 mV on the steps where an Izhikevich neuron
+    /// spiked and was reset.
+    pub v: Vec<f64>,
+    pub u: Vec<f64>,
+    /// Injected current over each step.
+    pub current: Vec<f64>,
+    /// Spike times in milliseconds.
+    pub spikes: Vec<f64>,
+}
+
+impl CurrentClamp {
+    /// Runs the neuron for `duration` milliseconds from its current state, in the units of the
+    /// network input.
+    pub fn run(&self, duration: f64) -> ClampTrace {
+        let mut neuron = self.neuron.clone();
+        let steps = (duration / self.dt).round() as usize;
+        let mut trace = ClampTrace {
+            dt: self.dt,
+            ..ClampTrace::default()
+        };
+        for step in 0..steps {
+            let t = step as f64 * self.dt;
+            let current = self.waveform.current(t);
+            let fired = neuron.step(0., current, self.dt);
+            let end = t + self.dt;
+            if fired {
+                trace.spikes.push(end);
+            }
+            trace.time.push(end);
+            // the reset hides the spike, so show it at the cutoff like the paper's figures
+            let peak = fired && neuron.dynamics.is_none();
+            trace.v.push(if peak { 30. } else { neuron.v });
+            trace.u.push(neuron.u);
+            trace.current.push(current);
+        }
+        trace
+    }
+}
+
+impl ClampTrace {
+    /// Writes one CSV row per step with the time, `v`, `u` and the injected current.
+    pub fn write_csv<W: Write>(&self, mut writer: W) -> io::Result<()> {
+        writeln!(writer, "time,v,u,current")?;
+        for idx in 0..self.time.len() {
+            writeln!(
+                writer,
+                "{},{},{},{}",
+                self.time[idx], self.v[idx], self.u[idx], self.current[idx]
+            )?;
+        }
+        writer.flush()
+    }
+}
+
+/// Steady-state firing rates of a neuron under constant currents, see [`FiCurve::measure`].
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct FiCurve {
+    pub currents: Vec<f64>,
+    /// Rates in Hz.
+    pub rates: Vec<f64>,
+}
+
+impl FiCurve {
+    /// Drives `neuron` from its state by each of `currents` for `duration` milliseconds in steps
+    /// of `dt`, in parallel, and counts its spikes after the first `transient` milliseconds, so
+    /// that bursts at the onset and adaptation do not enter the rate.
+    pub fn measure(
+        neuron: &Neuron,
+        currents: Vec<f64>,
+        duration: f64,
+        transient: f64,
+        dt: f64,
+    ) -> Self {
+        let window = (duration - transient).max(dt);
+        let rates = currents
+            .par_iter()
+            .map(|&amplitude| {
+                let clamp = CurrentClamp {
+                    neuron: neuron.clone(),
+                    waveform: Waveform::constant(amplitude),
+                    dt,
+                };
+                let trace = clamp.run(duration);
+                let spikes = trace.spikes.iter().filter(|&&t| t > transient).count();
+                spikes as f64 * 1000. / window
+            })
+            .collect();
+        FiCurve { currents, rates }
+    }
+
+    /// Smallest current of the curve at which the neuron fires, if any.
+    pub fn rheobase(&self) -> Option<f64> {
+        self.currents
+            .iter()
+            .zip(&self.rates)
+            .filter(|(_, &rate)| rate > 0.)
+            .map(|(&current, _)| current)
+            .reduce(f64::min)
+    }
+
+    /// Writes one CSV row per current with its rate.
+    pub fn write_csv<W: Write>(&self, mut writer: W) -> io::Result<()> {
+        writeln!(writer, "current,rate")?;
+        for (current, rate) in self.currents.iter().zip(&self.rates) {
+            writeln!(writer, "{current},{rate}")?;
+        }
+        writer.flush()
+    }
+}
@@ -0,0 +1,164 @@
+use std::io::BufRead;
+use std::ops::Range;
+
+use crate::simulator::WorldState;
+
+/// Closed-loop stimulation: observes the network before every step and returns the current
+/// injected into each neuron during that step, e.g. to suppress oscillations or clamp the
+/// activity of a population. Attached with [`WorldState::add_controller`].
+pub trait Controller: Send {
+    /// Current into every neuron for the next step of `world`, one entry per neuron, given its
+    /// state and spikes so far.
+    fn control(&mut self, world: &WorldState) -> Vec<f64>;
+}
+
+impl<F> Controller for F
+where
+    F: FnMut(&WorldState) -> Vec<f64> + Send,
+{
+    fn control(&mut self, world: &WorldState) -> Vec<f64> {
+        self(world)
+    }
+}
+
+/// Proportional-integral controller holding the firing rate of a population at a target by
+/// injecting the same current into all of its neurons, positive to excite and negative to
+/// inhibit them.
+#[derive(Clone, Debug, PartialEq)]
+pub struct RateClamp {
+    pub neurons: Range<usize>,
+    /// Target firing rate in Hz.
+    pub target: f64,
+    /// Time constant in milliseconds of the exponential filter estimating the rate.
+    pub tau: f64,
+    /// Current per Hz of rate error.
+    pub proportional_gain: f64,
+    /// Current per Hz of rate error accumulated over a second.
+    pub integral_gain: f64,
+    /// Largest magnitude of the injected current.
+    pub max_current: f64,
+    rate: f64,
+    integral: f64,
+}
+
+impl RateClamp {
+    pub fn new(neurons: Range<usize>, target: f64) -> Self {
+        RateClamp {
+            neurons,
+            target,
+            tau: 20.,
+            proportional_gain: 0.1,
+            integral_gain: 2.,
+            max_current: 20.,
+            rate: 0.,
+            integral: 0.,
+        }
+    }
+
+    /// Rate of the population in Hz as currently estimated.
+    pub fn rate(&self) -> f64 {
+        self.rate
+    }
+}
+
+impl Controller for RateClamp {
+    fn control(&mut self, world: &WorldState) -> Vec<f64> {
+        let dt = world.dt;
+        let spikes = world.last_spikes()[self.neurons.clone()]
+            .iter()
+            .filter(|&&spiked| spiked)
+            .count();
+        let instantaneous = 1000. * spikes as f64 / (self.neurons.len().max(1) as f64 * dt);
+        self.rate += (instantaneous - self.rate) * dt / self.tau;
+
+        let error = self.target - self.rate;
+        let unclamped = self.proportional_gain * error
+            + self.integral_gain * (self.integral + error * dt / 1000.);
+        // Stop integrating while saturated so that the current recovers quickly
+        if unclamped.abs() < self.max_current {
+            self.integral += error * dt / 1000.;
+        }
+        let current = (self.proportional_gain * error + self.integral_gain * self.integral)
+            .clamp(-self.max_current, self.max_current);
+
+        let mut currents = vec![0.; world.neurons.len()];
+        currents[self.neurons.clone()].fill(current);
+        currents
+    }
+}
+
+/// Currents read line by line from a stream, one line per step, so that another process can
+/// drive the network while it runs, e.g. through stdin or a named pipe. Every line holds either
+/// one current per neuron or one per population, given to all of its neurons, separated by
+/// commas or whitespace; an empty line injects nothing. Once the stream ends, no more current is
+/// injected.
+pub struct StreamedInput<R> {
+    reader: R,
+    line: String,
+    /// Lines read so far.
+    lines: usize,
+    ended: bool,
+}
+
+impl<R: BufRead + Send> StreamedInput<R> {
+    pub fn new(reader: R) -> Self {
+        StreamedInput {
+            reader,
+            line: String::new(),
+            lines: 0,
+            ended: false,
+        }
+    }
+}
+
+impl<R: BufRead + Send> Controller for StreamedInput<R> {
+    /// Reads the line of the next step, waiting for it if the writer has not sent it yet.
+    ///
+    /// # Panics
+    ///
+    /// If the stream cannot be read or a line holds neither one number per neuron nor one per
+    /// population.
+    fn control(&mut self, world: &WorldState) -> Vec<f64> {
+        let n = world.neurons.len();
+        let mut currents = vec![0.; n];
+        if self.ended {
+            return currents;
+        }
+        self.line.clear();
+        let read = self
+            .reader
+            .read_line(&mut self.line)
+            .unwrap_or_else(|err| panic!("cannot read the input stream: {err}"));
+        if read == 0 {
+            self.ended = true;
+            return currents;
+        }
+        self.lines += 1;
+        let values: Vec<f64> = self
+            .line
+            .split(|c: char| c == ',' || c.is_whitespace())
+            .filter(|value| !value.is_empty())
+            .map(|value| {
+                value.parse().unwrap_or_else(|err| {
+                    panic!("line {} of the input: `{value}`: {err}", self.lines)
+                })
+            })
+            .collect();
+        let populations = world.populations();
+        if values.len() == n {
+            currents = values;
+        } else if values.len() == populations.len() {
+            for ((_, neurons), current) in populations.into_iter().zip(values) {
+                currents[neurons].fill(current);
+            }
+        } else if !values.is_empty() {
+            panic!(
+                "line {} of the input: expected {n} currents or {} for the populations, got {}",
+                self.lines,
+                populations.len(),
+                values.len()
+            );
+        }
+        currents
+    }
+}
@@ -0,0 +1,235 @@
+use std::collections::VecDeque;
+use std::fs;
+use std::io;
+use std::ops::Range;
+use std::time::{Duration, Instant};
+
+use ratatui::crossterm::event::{self, Event, KeyCode, KeyEventKind, KeyModifiers};
+use ratatui::layout::{Constraint, Layout};
+use ratatui::style::{Color, Style, Stylize};
+use ratatui::widgets::{Block, Gauge, Paragraph, Row, Sparkline, Table};
+use ratatui::{DefaultTerminal, Frame};
+
+use crate::simulator::WorldState;
+
+/// Wall-clock time between redraws.
+const REDRAW: Duration = Duration::from_millis(100);
+/// Milliseconds of simulated time the current rates are averaged over.
+const RATE_WINDOW: f64 = 100.;
+/// Steps of activity kept for the sparkline, more than fit on most terminals.
+const HISTORY: usize = 1000;
+
+/// Terminal dashboard of a running simulation with its progress, the rates of its populations,
+/// a sparkline of the spikes per step and the memory of the process. The terminal is restored
+/// when it is dropped.
+pub struct Dashboard {
+    terminal: DefaultTerminal,
+    last_draw: Option<Instant>,
+    run: Run,
+}
+
+/// What a [`Dashboard`] shows of the run.
+struct Run {
+    started: Instant,
+    /// Step of the world when the run began.
+    first_step: usize,
+    /// Steps of the run.
+    steps: usize,
+    /// "all" followed by the populations of the world.
+    populations: Vec<(String, Range<usize>)>,
+    /// Spikes of each population since the run began.
+    totals: Vec<usize>,
+    /// Spikes of all neurons in each of the most recent steps.
+    activity: VecDeque<u64>,
+}
+
+impl Dashboard {
+    /// Takes over the terminal for a run of `steps` steps of `world` from its current step.
+    pub fn new(world: &WorldState, steps: usize) -> Self {
+        let mut populations = vec![("all".to_string(), 0..world.neurons.len())];
+        populations.extend(world.populations());
+        Dashboard {
+            terminal: ratatui::init(),
+            last_draw: None,
+            run: Run {
+                started: Instant::now(),
+                first_step: world.time_step,
+                steps,
+                totals: vec![0; populations.len()],
+                populations,
+                activity: VecDeque::with_capacity(HISTORY),
+            },
+        }
+    }
+
+    /// Counts the spikes of the step `world` has just simulated and redraws at most every
+    /// 100 ms, returning whether the user asked to stop with `q` or Ctrl-C.
+    pub fn update(&mut self, world: &WorldState) -> io::Result<bool> {
+        let run = &mut self.run;
+        let spikes = world.last_spikes();
+        for (total, (_, neurons)) in run.totals.iter_mut().zip(&run.populations) {
+            *total += spikes[neurons.clone()]
+                .iter()
+                .filter(|&&spiked| spiked)
+                .count();
+        }
+        if run.activity.len() == HISTORY {
+            run.activity.pop_front();
+        }
+        run.activity
+            .push_back(spikes.iter().filter(|&&spiked| spiked).count() as u64);
+
+        let done = world.time_step - run.first_step;
+        let redraw = self.last_draw.is_none_or(|time| time.elapsed() >= REDRAW);
+        if !redraw && done < run.steps {
+            return Ok(false);
+        }
+        self.last_draw = Some(Instant::now());
+        self.terminal.draw(|frame| run.render(frame, world))?;
+
+        // raw mode turns Ctrl-C into a key press instead of a signal
+        while event::poll(Duration::ZERO)? {
+            if let Event::Key(key) = event::read()? {
+                let ctrl_c =
+                    key.code == KeyCode::Char('c') && key.modifiers.contains(KeyModifiers::CONTROL);
+                if key.kind == KeyEventKind::Press && (key.code == KeyCode::Char('q') || ctrl_c) {
+                    return Ok(true);
+                }
+            }
+        }
+        Ok(false)
+    }
+}
+
+impl Run {
+    fn render(&self, frame: &mut Frame, world: &WorldState) {
+        let [progress, status, rates, activity] = Layout::vertical([
+            Constraint::Length(3),
+            Constraint::Length(1),
+            Constraint::Length(self.populations.len() as u16 + 3),
+            Constraint::Min(4),
+        ])
+        .areas(frame.area());
+
+        let done = world.time_step - self.first_step;
+        let ratio = if self.steps == 0 {
+            1.
+        } else {
+            done as f64 / self.steps as f64
+        };
+        frame.render_widget(
+            Gauge::default()
+                .block(Block::bordered().title("Progress"))
+                .gauge_style(Style::default().fg(Color::Cyan))
+                .ratio(ratio.min(1.))
+                .label(format!(
+                    "{:.0} / {:.0} ms",
+                    done as f64 * world.dt,
+                    self.steps as f64 * world.dt
+                )),
+            progress,
+        );
+
+        let elapsed = self.started.elapsed().as_secs_f64();
+        let remaining = if done == 0 {
+            "-".to_string()
+        } else {
+            format!(
+                "{:.1} s",
+                elapsed * (self.steps - done.min(self.steps)) as f64 / done as f64
+            )
+        };
+        let memory = resident_memory().map_or("-".to_string(), |bytes| {
+            format!("{:.1} MB", bytes as f64 / 1e6)
+        });
+        frame.render_widget(
+            Paragraph::new(format!(
+                " elapsed {elapsed:.1} s   remaining {remaining}   memory {memory}   q to stop"
+            )),
+            status,
+        );
+
+        // rates over the last steps, or all of them early in the run or with a shorter spike
+        // window
+        let record = world.spike_record();
+        let kept = record.last_step() - record.first_step();
+        let window = ((RATE_WINDOW / world.dt).round() as usize).clamp(1, done.min(kept).max(1));
+        let recent: Vec<usize> = record
+            .since(record.last_step() + 1 - window)
+            .map(|(_, neuron_idx)| neuron_idx)
+            .collect();
+        let rows = self
+            .populations
+            .iter()
+            .zip(&self.totals)
+            .map(|((name, neurons), &total)| {
+                let spikes = recent.iter().filter(|idx| neurons.contains(idx)).count();
+                let rate = |spikes: usize, steps: usize| {
+                    spikes as f64 * 1000. / (neurons.len().max(1) as f64 * steps as f64 * world.dt)
+                };
+                Row::new([
+                    name.clone(),
+                    neurons.len().to_string(),
+                    format!("{:.2}", rate(spikes, window)),
+                    format!("{:.2}", rate(total, done.max(1))),
+                ])
+            });
+        frame.render_widget(
+            Table::new(
+                rows,
+                [
+                    Constraint::Length(12),
+                    Constraint::Length(8),
+                    Constraint::Length(16),
+                    Constraint::Length(16),
+                ],
+            )
+            .header(
+                Row::new([
+                    "population".to_string(),
+                    "neurons".to_string(),
+                    format!("last {RATE_WINDOW} ms"),
+                    "since start".to_string(),
+                ])
+                .bold(),
+            )
+            .block(Block::bordered().title("Rates (Hz)")),
+            rates,
+        );
+
+        let width = activity.width.saturating_sub(2) as usize;
+        let data: Vec<u64> = self
+            .activity
+            .iter()
+            .skip(self.activity.len().saturating_sub(width))
+            .copied()
+            .collect();
+        frame.render_widget(
+            Sparkline::default()
+                .block(Block::bordered().title("Spikes per step"))
+                .style(Style::default().fg(Color::Yellow))
+                .data(&data),
+            activity,
+        );
+    }
+}
+
+impl Drop for Dashboard {
+    fn drop(&mut self) {
+        ratatui::restore();
+    }
+}
+
+/// Resident memory of the process in bytes, where `/proc` reports it.
+fn resident_memory() -> Option<u64> {
+    let status = fs::read_to_string("/proc/self/status").ok()?;
+    let kilobytes = status
+        .lines()
+        .find_map(|line| line.strip_prefix("VmRSS:"))?
+        .trim()
+        .strip_suffix("kB")?
+        .trim()
+        .parse::<u64>()
+        .ok()?;
+    Some(kilobytes * 1024)
+}
@@ -0,0 +1,99 @@
+use std::ops::Range;
+
+use serde::{Deserialize, Serialize};
+
+use crate::simulator::WorldState;
+
+/// How the recorded spikes of output neurons are mapped back to analog values and class labels,
+/// each the inverse of an [`crate::Encoding`].
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub enum Decoding {
+    /// The rate of the neurons within the window relative to `max_rate` Hz, inverting
+    /// [`crate::Encoding::Rate`].
+    SpikeCount { max_rate: f64 },
+    /// The earliest spike of the neurons within the window, 1 at its start and 0 after
+    /// `max_latency` milliseconds, inverting [`crate::Encoding::Latency`].
+    FirstSpike { max_latency: f64 },
+    /// The mean of the preferred values of the neurons, spread evenly over `[min, max]`,
+    /// weighted by their spike counts within the window, inverting
+    /// [`crate::Encoding::Population`].
+    PopulationVector { min: f64, max: f64 },
+}
+
+impl Decoding {
+    /// Value decoded from the spikes of `neurons` of `world` within `window` milliseconds, or
+    /// `None` if the code needs spikes and there were none.
+    pub fn value(
+        &self,
+        world: &WorldState,
+        neurons: Range<usize>,
+        window: Range<f64>,
+    ) -> Option<f64> {
+        let spikes = window_spikes(world, &neurons, &window);
+        match *self {
+            Decoding::SpikeCount { max_rate } => {
+                let duration = window.end - window.start;
+                let expected = neurons.len() as f64 * max_rate * duration / 1000.;
+                Some(spikes.len() as f64 / expected)
+            }
+            Decoding::FirstSpike { max_latency } => spikes
+                .iter()
+                .map(|&(time, _)| time - window.start)
+                .min_by(f64::total_cmp)
+                .map(|latency| (1. - latency / max_latency).max(0.)),
+            Decoding::PopulationVector { min, max } => {
+                let channels = neurons.len();
+                let preferred = |neuron: usize| {
+                    if channels > 1 {
+                        min + (max - min) * (neuron - neurons.start) as f64 / (channels - 1) as f64
+                    } else {
+                        (min + max) / 2.
+                    }
+                };
+                (!spikes.is_empty()).then(|| {
+                    spikes
+                        .iter()
+                        .map(|&(_, neuron)| preferred(neuron))
+                        .sum::<f64>()
+                        / spikes.len() as f64
+                })
+            }
+        }
+    }
+
+    /// Index of the group of `groups` with the largest decoded value within `window`, e.g. the
+    /// one that spiked most or first, or `None` if no group has a value or the largest is tied.
+    pub fn label(
+        &self,
+        world: &WorldState,
+        groups: &[Range<usize>],
+        window: Range<f64>,
+    ) -> Option<usize> {
+        let values: Vec<Option<f64>> = groups
+            .iter()
+            .map(|neurons| self.value(world, neurons.clone(), window.clone()))
+            .collect();
+        let best = values.iter().flatten().copied().max_by(f64::total_cmp)?;
+        let mut winners = (0..groups.len()).filter(|&group| values[group] == Some(best));
+        match (winners.next(), winners.next()) {
+            (Some(group), None) => Some(group),
+            _ => None,
+        }
+    }
+}
+
+/// `(time, neuron)` spikes of `neurons` within `window` milliseconds among those still kept.
+fn window_spikes(
+    world: &WorldState,
+    neurons: &Range<usize>,
+    window: &Range<f64>,
+) -> Vec<(f64, usize)> {
+    let first_step = (window.start / world.dt).ceil().max(0.) as usize;
+    world
+        .spike_record()
+        .since(first_step)
+        .map(|(time_step, neuron)| (time_step as f64 * world.dt, neuron))
+        .take_while(|&(time, _)| time < window.end)
+        .filter(|&(time, neuron)| window.contains(&time) && neurons.contains(&neuron))
+        .collect()
+}
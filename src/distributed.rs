@@ -0,0 +1,78 @@
+use std::ops::Range;
+
+use mpi::datatype::PartitionMut;
+use mpi::environment::Universe;
+use mpi::traits::*;
+use mpi::Count;
+
+use crate::integrator::Integrator;
+use crate::neuron::Neurons;
+use crate::simulator::BackendError;
+
+/// Splits the neurons into contiguous blocks, one per process of an MPI job, each of which
+/// integrates its own block and exchanges its spikes and membrane state with all others every
+/// step. Every process builds the whole network from the same seed and keeps all of it up to
+/// date, so synaptic input, plasticity and recordings run unchanged and give the same results as
+/// a single process; only the internal state of neuron models other than Izhikevich stays with
+/// the process integrating it.
+pub struct MpiBackend {
+    /// Keeps MPI initialized while the backend is selected.
+    universe: Universe,
+    /// Neurons of every process, in rank order.
+    blocks: Vec<Range<usize>>,
+    rank: usize,
+}
+
+impl MpiBackend {
+    pub fn new(n: usize) -> Result<Self, BackendError> {
+        let universe = mpi::initialize()
+            .ok_or_else(|| BackendError::Unavailable("MPI is already initialized".into()))?;
+        let world = universe.world();
+        let (rank, size) = (world.rank() as usize, world.size() as usize);
+        let blocks = (0..size)
+            .map(|process| process * n / size..(process + 1) * n / size)
+            .collect();
+        Ok(MpiBackend {
+            universe,
+            blocks,
+            rank,
+        })
+    }
+
+    /// Rank of this process.
+    pub fn rank(&self) -> usize {
+        self.rank
+    }
+
+    /// Advances the block of this process and gathers the spikes, `v`, `u` and spike offsets of
+    /// all blocks, returning the spikes of the whole network.
+    pub fn step(
+        &mut self,
+        thalamic_input: &[f64],
+        input_current: &[f64],
+        dt: f64,
+        integrator: Integrator,
+        neurons: &mut Neurons,
+    ) -> Vec<bool> {
+        let block = self.blocks[self.rank].clone();
+        let local =
+            neurons.step_range(block.clone(), thalamic_input, input_current, dt, integrator);
+
+        let world = self.universe.world();
+        let counts: Vec<Count> = self.blocks.iter().map(|b| b.len() as Count).collect();
+        let offsets: Vec<Count> = self.blocks.iter().map(|b| b.start as Count).collect();
+        let mut spikes = vec![false; neurons.len()];
+        world.all_gather_varcount_into(
+            &local[..],
+            &mut PartitionMut::new(&mut spikes[..], &counts[..], &offsets[..]),
+        );
+        for state in [&mut neurons.v, &mut neurons.u, &mut neurons.spike_offset] {
+            let local = state[block.clone()].to_vec();
+            world.all_gather_varcount_into(
+                &local[..],
+                &mut PartitionMut::new(&mut state[..], &counts[..], &offsets[..]),
+            );
+        }
+        spikes
+    }
+}
@@ -0,0 +1,103 @@
+use rand::Rng;
+use serde::{Deserialize, Serialize};
+
+/// How analog values are turned into input channels for the network, fed in with
+/// [`crate::WorldState::encode_spikes`] or [`crate::WorldState::encode_currents`].
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub enum Encoding {
+    /// Every value in `[0, 1]` is one channel spiking as a Poisson process at up to `max_rate`
+    /// Hz in proportion to the value.
+    Rate { max_rate: f64 },
+    /// Every value in `[0, 1]` is one channel spiking once, the earlier the larger the value:
+    /// at once for 1, after `max_latency` milliseconds for values near 0 and never for 0.
+    Latency { max_latency: f64 },
+    /// Every value in `[min, max]` is `channels` channels with Gaussian tuning curves of standard
+    /// deviation `width` whose preferred values are spread evenly over the range, each spiking as
+    /// a Poisson process at up to `max_rate` Hz.
+    Population {
+        channels: usize,
+        min: f64,
+        max: f64,
+        width: f64,
+        max_rate: f64,
+    },
+}
+
+impl Encoding {
+    /// Number of channels encoding `values` values.
+    pub fn channels(&self, values: usize) -> usize {
+        match self {
+            Encoding::Rate { .. } | Encoding::Latency { .. } => values,
+            Encoding::Population { channels, .. } => values * channels,
+        }
+    }
+
+    /// Activation in `[0, 1]` of every channel for `values`: the value itself under rate and
+    /// latency coding, and the tuning curves under population coding.
+    pub fn intensities(&self, values: &[f64]) -> Vec<f64> {
+        match *self {
+            Encoding::Rate { .. } | Encoding::Latency { .. } => {
+                values.iter().map(|value| value.clamp(0., 1.)).collect()
+            }
+            Encoding::Population {
+                channels,
+                min,
+                max,
+                width,
+                ..
+            } => values
+                .iter()
+                .flat_map(|&value| {
+                    (0..channels).map(move |channel| {
+                        let preferred = if channels > 1 {
+                            min + (max - min) * channel as f64 / (channels - 1) as f64
+                        } else {
+                            (min + max) / 2.
+                        };
+                        (-0.5 * ((value - preferred) / width).powi(2)).exp()
+                    })
+                })
+                .collect(),
+        }
+    }
+
+    /// `(time, channel)` spikes encoding `values` over `duration` milliseconds, sorted by time.
+    pub fn spikes<R: Rng + ?Sized>(
+        &self,
+        values: &[f64],
+        duration: f64,
+        dt: f64,
+        rng: &mut R,
+    ) -> Vec<(f64, usize)> {
+        let intensities = self.intensities(values);
+        match *self {
+            Encoding::Latency { max_latency } => {
+                let mut spikes: Vec<(f64, usize)> = intensities
+                    .iter()
+                    .enumerate()
+                    .filter(|&(_, &intensity)| intensity > 0.)
+                    .map(|(channel, intensity)| ((1. - intensity) * max_latency, channel))
+                    .filter(|&(time, _)| time < duration)
+                    .collect();
+                spikes.sort_by(|a, b| a.0.total_cmp(&b.0));
+                spikes
+            }
+            Encoding::Rate { max_rate } | Encoding::Population { max_rate, .. } => {
+                let steps = (duration / dt).round() as usize;
+                (0..steps)
+                    .flat_map(|step| {
+                        let time = step as f64 * dt;
+                        intensities
+                            .iter()
+                            .enumerate()
+                            .filter(|&(_, &intensity)| {
+                                rng.gen::<f64>() < intensity * max_rate * dt / 1000.
+                            })
+                            .map(move |(channel, _)| (time, channel))
+                            .collect::<Vec<_>>()
+                    })
+                    .collect()
+            }
+        }
+    }
+}
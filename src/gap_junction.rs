@@ -0,0 +1,137 @@
+use std::error::Error;
+use std::fs;
+use std::path::Path;
+
+use rand::{Rng, SeedableRng};
+use rand_chacha::ChaCha12Rng;
+use serde::{Deserialize, Serialize};
+
+use crate::simulator::SparseConnectivity;
+
+/// Electrical synapses: every coupled pair of neurons exchanges a current proportional to the
+/// difference of their membrane potentials, in both directions and without delay. Gap junctions
+/// are common between inhibitory interneurons, where they help synchronize them. The coupling is
+/// integrated explicitly, so the summed conductance onto every neuron times the time step should
+/// stay well below 1.
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+pub struct GapJunctions {
+    /// Conductance of every junction as a weight, stored once for each direction, with one row
+    /// per neuron.
+    coupling: SparseConnectivity,
+}
+
+impl GapJunctions {
+    /// Junctions between the `(a, b, conductance)` pairs of `neurons` neurons.
+    ///
+    /// # Panics
+    ///
+    /// If a pair couples a neuron outside the network or a neuron with itself.
+    pub fn new(neurons: usize, pairs: &[(usize, usize, f64)]) -> Self {
+        let synapses = pairs
+            .iter()
+            .flat_map(|&(a, b, conductance)| {
+                assert!(a != b, "gap junction of neuron {a} with itself");
+                [(a, b, conductance, 0.), (b, a, conductance, 0.)]
+            })
+            .collect();
+        GapJunctions {
+            coupling: SparseConnectivity::from_synapses(neurons, synapses, 1.),
+        }
+    }
+
+    /// Junctions of `conductance` between every pair of the `group` neurons with `probability`,
+    /// among `neurons` neurons.
+    pub fn random(
+        neurons: usize,
+        group: &[usize],
+        probability: f64,
+        conductance: f64,
+        seed: u64,
+    ) -> Self {
+        let mut rng = ChaCha12Rng::seed_from_u64(seed);
+        let mut pairs = vec![];
+        for (idx, &a) in group.iter().enumerate() {
+            for &b in &group[idx + 1..] {
+                if rng.gen::<f64>() < probability {
+                    pairs.push((a, b, conductance));
+                }
+            }
+        }
+        GapJunctions::new(neurons, &pairs)
+    }
+
+    /// Reads the junctions of `neurons` neurons from a CSV file of `a,b,conductance` rows, one
+    /// per coupled pair.
+    pub fn read_csv(path: &Path, neurons: usize) -> Result<Self, Box<dyn Error>> {
+        let text = fs::read_to_string(path)?;
+        let mut lines = text
+            .lines()
+            .enumerate()
+            .filter(|(_, line)| !line.trim().is_empty());
+        match lines.next() {
+            Some((_, header)) if header.trim() == "a,b,conductance" => {}
+            Some((_, header)) => {
+                return Err(format!("expected `a,b,conductance`, got `{header}`").into())
+            }
+            None => return Ok(GapJunctions::new(neurons, &[])),
+        }
+        let pairs = lines
+            .map(|(number, line)| {
+                let fields: Vec<&str> = line.split(',').map(str::trim).collect();
+                let [a, b, conductance] = fields[..] else {
+                    return Err(format!("line {}: expected `a,b,conductance`", number + 1));
+                };
+                let index = |field: &str| {
+                    let neuron = field
+                        .parse::<usize>()
+                        .map_err(|err| format!("line {}: `{field}`: {err}", number + 1))?;
+                    if neuron >= neurons {
+                        return Err(format!(
+                            "line {}: neuron {neuron} out of range for {neurons} neurons",
+                            number + 1
+                        ));
+                    }
+                    Ok(neuron)
+                };
+                let (a, b) = (index(a)?, index(b)?);
+                if a == b {
+                    return Err(format!(
+                        "line {}: gap junction of neuron {a} with itself",
+                        number + 1
+                    ));
+                }
+                let conductance = conductance
+                    .parse::<f64>()
+                    .map_err(|err| format!("line {}: `{conductance}`: {err}", number + 1))?;
+                Ok((a, b, conductance))
+            })
+            .collect::<Result<Vec<_>, String>>()?;
+        Ok(GapJunctions::new(neurons, &pairs))
+    }
+
+    /// Number of neurons the junctions were built for.
+    pub fn neurons(&self) -> usize {
+        self.coupling.neurons()
+    }
+
+    /// Number of coupled pairs.
+    pub fn pairs(&self) -> usize {
+        self.coupling.synapses() / 2
+    }
+
+    /// `(partner, conductance)` of every junction of `neuron`.
+    pub fn partners(&self, neuron: usize) -> impl Iterator<Item = (usize, f64)> + '_ {
+        self.coupling.outgoing(neuron)
+    }
+
+    /// Current into every neuron at the membrane potentials `v`.
+    pub fn currents(&self, v: &[f64]) -> Vec<f64> {
+        (0..v.len())
+            .map(|neuron| {
+                self.partners(neuron)
+                    .map(|(partner, conductance)| conductance * (v[partner] - v[neuron]))
+                    .sum()
+            })
+            .collect()
+    }
+}
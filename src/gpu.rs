@@ -0,0 +1,224 @@
+use wgpu::util::DeviceExt;
+
+use crate::neuron::Neurons;
+use crate::simulator::{BackendError, WorldState};
+
+/// Holds membrane state, parameters and weights on the GPU and integrates the network there in
+/// single precision. Recurrent weights are uploaded once, so they must not change afterwards.
+/// Only one-step delays and plain current synapses are supported, which the caller checks.
+pub struct GpuBackend {
+    device: wgpu::Device,
+    queue: wgpu::Queue,
+    pipeline: wgpu::ComputePipeline,
+    /// Bind groups alternating which spike buffer is read and which is written.
+    bind_groups: [wgpu::BindGroup; 2],
+    spikes: [wgpu::Buffer; 2],
+    state: wgpu::Buffer,
+    external: wgpu::Buffer,
+    staging: wgpu::Buffer,
+    n: usize,
+    /// Index of the spike buffer holding the previous step's spikes.
+    parity: usize,
+}
+
+impl GpuBackend {
+    pub fn new(world: &WorldState) -> Result<Self, BackendError> {
+        let instance = wgpu::Instance::default();
+        let adapter = pollster::block_on(instance.request_adapter(&Default::default()))
+            .ok_or_else(|| BackendError::Unavailable("no GPU adapter found".into()))?;
+        let (device, queue) = pollster::block_on(adapter.request_device(&Default::default(), None))
+            .map_err(|e| BackendError::Unavailable(e.to_string()))?;
+
+        let neurons = &world.neurons;
+        let n = neurons.len();
+        let state: Vec<f32> = (0..n)
+            .flat_map(|i| [neurons.v[i] as f32, neurons.u[i] as f32])
+            .collect();
+        let abcd: Vec<f32> = (0..n)
+            .flat_map(|i| {
+                [neurons.a[i], neurons.b[i], neurons.c[i], neurons.d[i]].map(|p| p as f32)
+            })
+            .collect();
+
+        // transpose to one row of incoming synapses per postsynaptic neuron
+        let connectivity = &world.connectivity;
+        let mut row_offsets = vec![0u32; n + 1];
+        for &post in connectivity.targets() {
+            row_offsets[post + 1] += 1;
+        }
+        for i in 0..n {
+            row_offsets[i + 1] += row_offsets[i];
+        }
+        let mut fill = row_offsets.clone();
+        let mut sources = vec![0u32; connectivity.synapses()];
+        let mut weights = vec![0f32; connectivity.synapses()];
+        for pre in 0..n {
+            for (post, weight) in connectivity.outgoing(pre) {
+                let k = fill[post] as usize;
+                sources[k] = pre as u32;
+                weights[k] = weight as f32;
+                fill[post] += 1;
+            }
+        }
+        let last_spikes: Vec<u32> = world.last_spikes().iter().map(|&s| s as u32).collect();
+
+        let storage = |label, contents: &[u8], usage| {
+            device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
+                label: Some(label),
+                contents,
+                usage: wgpu::BufferUsages::STORAGE | usage,
+            })
+        };
+        let params = device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
+            label: Some("params"),
+            contents: bytemuck::cast_slice(&[n as u32, (world.dt as f32).to_bits(), 0, 0]),
+            usage: wgpu::BufferUsages::UNIFORM,
+        });
+        let state = storage(
+            "state",
+            bytemuck::cast_slice(&state),
+            wgpu::BufferUsages::COPY_SRC,
+        );
+        let abcd = storage(
+            "abcd",
+            bytemuck::cast_slice(&abcd),
+            wgpu::BufferUsages::empty(),
+        );
+        // storage buffers may not be empty
+        sources.push(0);
+        weights.push(0.);
+        let row_offsets = storage(
+            "row_offsets",
+            bytemuck::cast_slice(&row_offsets),
+            wgpu::BufferUsages::empty(),
+        );
+        let sources = storage(
+            "sources",
+            bytemuck::cast_slice(&sources),
+            wgpu::BufferUsages::empty(),
+        );
+        let weights = storage(
+            "weights",
+            bytemuck::cast_slice(&weights),
+            wgpu::BufferUsages::empty(),
+        );
+        let spikes = [
+            storage(
+                "spikes_a",
+                bytemuck::cast_slice(&last_spikes),
+                wgpu::BufferUsages::COPY_SRC,
+            ),
+            storage(
+                "spikes_b",
+                bytemuck::cast_slice(&last_spikes),
+                wgpu::BufferUsages::COPY_SRC,
+            ),
+        ];
+        let external = storage(
+            "external",
+            bytemuck::cast_slice(&vec![0f32; n]),
+            wgpu::BufferUsages::COPY_DST,
+        );
+        let staging = device.create_buffer(&wgpu::BufferDescriptor {
+            label: Some("staging"),
+            size: (n * 3 * 4) as u64,
+            usage: wgpu::BufferUsages::MAP_READ | wgpu::BufferUsages::COPY_DST,
+            mapped_at_creation: false,
+        });
+
+        let module = device.create_shader_module(wgpu::include_wgsl!("gpu.wgsl"));
+        let pipeline = device.create_compute_pipeline(&wgpu::ComputePipelineDescriptor {
+            label: Some("izhikevich"),
+            layout: None,
+            module: &module,
+            entry_point: "main",
+            compilation_options: Default::default(),
+            cache: None,
+        });
+        let layout = pipeline.get_bind_group_layout(0);
+        let bind_group = |spikes_in: &wgpu::Buffer, spikes_out: &wgpu::Buffer| {
+            let buffers = [
+                &params,
+                &state,
+                &abcd,
+                &row_offsets,
+                &sources,
+                &weights,
+                spikes_in,
+                spikes_out,
+                &external,
+            ];
+            let entries: Vec<wgpu::BindGroupEntry> = buffers
+                .iter()
+                .enumerate()
+                .map(|(binding, buffer)| wgpu::BindGroupEntry {
+                    binding: binding as u32,
+                    resource: buffer.as_entire_binding(),
+                })
+                .collect();
+            device.create_bind_group(&wgpu::BindGroupDescriptor {
+                label: None,
+                layout: &layout,
+                entries: &entries,
+            })
+        };
+        let bind_groups = [
+            bind_group(&spikes[0], &spikes[1]),
+            bind_group(&spikes[1], &spikes[0]),
+        ];
+
+        Ok(GpuBackend {
+            device,
+            queue,
+            pipeline,
+            bind_groups,
+            spikes,
+            state,
+            external,
+            staging,
+            n,
+            parity: 0,
+        })
+    }
+
+    /// Advances every neuron by one step given the non-recurrent input current, copies the new
+    /// membrane state back into `neurons` and returns the spikes.
+    pub fn step(&mut self, external: &[f32], neurons: &mut Neurons) -> Vec<bool> {
+        let n = self.n as u64;
+        self.queue
+            .write_buffer(&self.external, 0, bytemuck::cast_slice(external));
+
+        let mut encoder = self.device.create_command_encoder(&Default::default());
+        {
+            let mut pass = encoder.begin_compute_pass(&Default::default());
+            pass.set_pipeline(&self.pipeline);
+            pass.set_bind_group(0, &self.bind_groups[self.parity], &[]);
+            pass.dispatch_workgroups(self.n.div_ceil(64) as u32, 1, 1);
+        }
+        let written = &self.spikes[1 - self.parity];
+        encoder.copy_buffer_to_buffer(written, 0, &self.staging, 0, n * 4);
+        encoder.copy_buffer_to_buffer(&self.state, 0, &self.staging, n * 4, n * 8);
+        self.queue.submit([encoder.finish()]);
+        self.parity = 1 - self.parity;
+
+        let slice = self.staging.slice(..);
+        slice.map_async(wgpu::MapMode::Read, |result| {
+            result.expect("failed to map GPU staging buffer")
+        });
+        self.device.poll(wgpu::Maintain::Wait);
+
+        let spikes = {
+            let data = slice.get_mapped_range();
+            let (spikes, state) = data.split_at(self.n * 4);
+            let spikes: &[u32] = bytemuck::cast_slice(spikes);
+            let state: &[f32] = bytemuck::cast_slice(state);
+            for (i, vu) in state.chunks_exact(2).enumerate() {
+                neurons.v[i] = vu[0] as f64;
+                neurons.u[i] = vu[1] as f64;
+            }
+            spikes.iter().map(|&spiked| spiked != 0).collect()
+        };
+        self.staging.unmap();
+        spikes
+    }
+}
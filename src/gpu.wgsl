@@ -0,0 +1,51 @@
+struct Params {
+    n: u32,
+    dt: f32,
+}
+
+@group(0) @binding(0) var<uniform> params: Params;
+// (v, u) of every neuron
+@group(0) @binding(1) var<storage, read_write> state: array<vec2<f32>>;
+// (a, b, c, d) of every neuron
+@group(0) @binding(2) var<storage, read> abcd: array<vec4<f32>>;
+// incoming synapses of neuron i live at row_offsets[i]..row_offsets[i + 1]
+@group(0) @binding(3) var<storage, read> row_offsets: array<u32>;
+@group(0) @binding(4) var<storage, read> sources: array<u32>;
+@group(0) @binding(5) var<storage, read> weights: array<f32>;
+@group(0) @binding(6) var<storage, read> spikes_in: array<u32>;
+@group(0) @binding(7) var<storage, read_write> spikes_out: array<u32>;
+// thalamic, stimulus and external synaptic current computed on the CPU
+@group(0) @binding(8) var<storage, read> drive: array<f32>;
+
+@compute @workgroup_size(64)
+fn main(@builtin(global_invocation_id) id: vec3<u32>) {
+    let i = id.x;
+    if (i >= params.n) {
+        return;
+    }
+
+    var synaptic = 0.0;
+    for (var k = row_offsets[i]; k < row_offsets[i + 1u]; k++) {
+        if (spikes_in[sources[k]] != 0u) {
+            synaptic += weights[k];
+        }
+    }
+    let current = drive[i] + synaptic / params.dt;
+
+    let p = abcd[i];
+    var v = state[i].x;
+    var u = state[i].y;
+    let half_dt = 0.5 * params.dt;
+    v += half_dt * (0.04 * v * v + 5.0 * v + 140.0 - u + current);
+    v += half_dt * (0.04 * v * v + 5.0 * v + 140.0 - u + current);
+    u += params.dt * p.x * (p.y * v - u);
+
+    if (v >= 30.0) {
+        v = p.z;
+        u += p.w;
+        spikes_out[i] = 1u;
+    } else {
+        spikes_out[i] = 0u;
+    }
+    state[i] = vec2<f32>(v, u);
+}
@@ -0,0 +1,215 @@
+use std::io::{self, Write};
+use std::path::Path;
+
+use crate::network::Synapse;
+use crate::neuron::{NeuronDynamics, SynapseType};
+use crate::simulator::WorldState;
+
+/// File formats of the network graph.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum GraphFormat {
+    /// GraphML, e.g. for Gephi or NetworkX.
+    GraphMl,
+    /// Graphviz DOT.
+    Dot,
+}
+
+impl GraphFormat {
+    /// DOT for paths ending in `.dot` or `.gv`, GraphML otherwise.
+    pub fn from_path(path: &Path) -> Self {
+        match path.extension().and_then(|ext| ext.to_str()) {
+            Some("dot" | "gv") => GraphFormat::Dot,
+            _ => GraphFormat::GraphMl,
+        }
+    }
+}
+
+/// Writes the network of `world` as a directed graph in `format`, see [`write_graphml`] and
+/// [`write_dot`].
+pub fn write_graph<W: Write>(
+    writer: W,
+    world: &WorldState,
+    format: GraphFormat,
+    threshold: f64,
+) -> io::Result<()> {
+    match format {
+        GraphFormat::GraphMl => write_graphml(writer, world, threshold),
+        GraphFormat::Dot => write_dot(writer, world, threshold),
+    }
+}
+
+/// Attributes of a neuron of the graph.
+struct Node {
+    population: String,
+    synapse_type: &'static str,
+    model: &'static str,
+    parameters: [f64; 4],
+    position: Option<[f64; 2]>,
+}
+
+fn nodes(world: &WorldState) -> Vec<Node> {
+    let neurons = &world.neurons;
+    let mut population = vec![String::new(); neurons.len()];
+    for (name, range) in world.populations() {
+        for idx in range {
+            population[idx] = name.clone();
+        }
+    }
+    population
+        .into_iter()
+        .enumerate()
+        .map(|(idx, population)| Node {
+            population,
+            synapse_type: match neurons.synapse_types[idx] {
+                SynapseType::Excitatory => "excitatory",
+                SynapseType::Inhibitory => "inhibitory",
+            },
+            model: match neurons.dynamics[idx].as_deref() {
+                None => "izhikevich",
+                Some(NeuronDynamics::AdEx(_)) => "adex",
+                Some(NeuronDynamics::Lif(_)) => "lif",
+                Some(NeuronDynamics::HodgkinHuxley(_)) => "hodgkin-huxley",
+                Some(NeuronDynamics::Izhikevich2007(_)) => "izhikevich2007",
+                Some(NeuronDynamics::TwoCompartment(_)) => "two-compartment",
+            },
+            parameters: [
+                neurons.a[idx],
+                neurons.b[idx],
+                neurons.c[idx],
+                neurons.d[idx],
+            ],
+            position: world.positions.as_ref().map(|positions| positions[idx]),
+        })
+        .collect()
+}
+
+/// `(pre, post, weight, delay)` of the synapses of `world` whose weight is at least `threshold`
+/// in magnitude, with delays in milliseconds.
+fn edges(world: &WorldState, threshold: f64) -> impl Iterator<Item = Synapse> + '_ {
+    let connectivity = &world.connectivity;
+    (0..connectivity.neurons()).flat_map(move |pre| {
+        connectivity.row(pre).filter_map(move |synapse| {
+            let weight = connectivity.weights()[synapse];
+            (weight.abs() >= threshold).then(|| {
+                (
+                    pre,
+                    connectivity.targets()[synapse],
+                    weight,
+                    connectivity.delays()[synapse] as f64 * world.dt,
+                )
+            })
+        })
+    })
+}
+
+/// Writes the network of `world` as a directed GraphML graph with one node per neuron, with its
+/// population, synapse type, model, Izhikevich parameters and position if it has one, and one
+/// edge per synapse whose weight is at least `threshold` in magnitude, with its signed weight
+/// and delay in milliseconds.
+pub fn write_graphml<W: Write>(
+    mut writer: W,
+    world: &WorldState,
+    threshold: f64,
+) -> io::Result<()> {
+    writeln!(writer, r#"<?xml version="1.0" encoding="UTF-8"?>"#)?;
+    writeln!(
+        writer,
+        r#"<graphml xmlns="http://graphml.graphdrawing.org/xmlns">"#
+    )?;
+    let mut node_keys = vec![
+        ("population", "string"),
+        ("synapse_type", "string"),
+        ("model", "string"),
+        ("a", "double"),
+        ("b", "double"),
+        ("c", "double"),
+        ("d", "double"),
+    ];
+    if world.positions.is_some() {
+        node_keys.extend([("x", "double"), ("y", "double")]);
+    }
+    for (name, kind) in node_keys {
+        writeln!(
+            writer,
+            r#"  <key id="{name}" for="node" attr.name="{name}" attr.type="{kind}"/>"#
+        )?;
+    }
+    for name in ["weight", "delay"] {
+        writeln!(
+            writer,
+            r#"  <key id="{name}" for="edge" attr.name="{name}" attr.type="double"/>"#
+        )?;
+    }
+    writeln!(writer, r#"  <graph id="network" edgedefault="directed">"#)?;
+    for (idx, node) in nodes(world).into_iter().enumerate() {
+        writeln!(writer, r#"    <node id="n{idx}">"#)?;
+        writeln!(
+            writer,
+            r#"      <data key="population">{}</data>"#,
+            xml_escape(&node.population)
+        )?;
+        writeln!(
+            writer,
+            r#"      <data key="synapse_type">{}</data>"#,
+            node.synapse_type
+        )?;
+        writeln!(writer, r#"      <data key="model">{}</data>"#, node.model)?;
+        for (name, value) in ["a", "b", "c", "d"].into_iter().zip(node.parameters) {
+            writeln!(writer, r#"      <data key="{name}">{value}</data>"#)?;
+        }
+        if let Some([x, y]) = node.position {
+            writeln!(writer, r#"      <data key="x">{x}</data>"#)?;
+            writeln!(writer, r#"      <data key="y">{y}</data>"#)?;
+        }
+        writeln!(writer, "    </node>")?;
+    }
+    for (pre, post, weight, delay) in edges(world, threshold) {
+        writeln!(
+            writer,
+            r#"    <edge source="n{pre}" target="n{post}"><data key="weight">{weight}</data><data key="delay">{delay}</data></edge>"#
+        )?;
+    }
+    writeln!(writer, "  </graph>")?;
+    writeln!(writer, "</graphml>")?;
+    writer.flush()
+}
+
+/// Writes the network of `world` as a Graphviz digraph with the node and edge attributes of
+/// [`write_graphml`], except that the weight is `synaptic_weight` as Graphviz reserves
+/// `weight` for its layouts, and the positions are `pos`.
+pub fn write_dot<W: Write>(mut writer: W, world: &WorldState, threshold: f64) -> io::Result<()> {
+    writeln!(writer, "digraph network {{")?;
+    for (idx, node) in nodes(world).into_iter().enumerate() {
+        let [a, b, c, d] = node.parameters;
+        write!(
+            writer,
+            r#"  {idx} [population="{}", synapse_type="{}", model="{}", a={a}, b={b}, c={c}, d={d}"#,
+            dot_escape(&node.population),
+            node.synapse_type,
+            node.model
+        )?;
+        if let Some([x, y]) = node.position {
+            write!(writer, r#", pos="{x},{y}""#)?;
+        }
+        writeln!(writer, "];")?;
+    }
+    for (pre, post, weight, delay) in edges(world, threshold) {
+        writeln!(
+            writer,
+            "  {pre} -> {post} [synaptic_weight={weight}, delay={delay}];"
+        )?;
+    }
+    writeln!(writer, "}}")?;
+    writer.flush()
+}
+
+fn xml_escape(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
+
+fn dot_escape(text: &str) -> String {
+    text.replace('\\', "\\\\").replace('"', "\\\"")
+}
@@ -0,0 +1,267 @@
+// tonic requires its large `Status` as the error of every handler
+#![allow(clippy::result_large_err)]
+
+use std::collections::HashMap;
+use std::net::SocketAddr;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, Mutex};
+
+use tonic::transport::Server;
+use tonic::{Request, Response, Status};
+
+use crate::simulator::{WorldState, WorldStateBuilder};
+use crate::stimulus::{PulseTrain, StepCurrent};
+
+/// Messages and service traits generated from `proto/simulator.proto`.
+pub mod proto {
+    tonic::include_proto!("izhikevich");
+}
+
+use proto::simulator_server::{Simulator, SimulatorServer};
+use proto::stimulus_request::Shape;
+
+/// The `Simulator` gRPC service, holding every network created by its clients until they drop
+/// it.
+pub struct SimulatorService {
+    /// Configuration of new networks before the changes requested by the client.
+    template: WorldStateBuilder,
+    networks: Mutex<HashMap<u64, Arc<Mutex<WorldState>>>>,
+    next_id: AtomicU64,
+}
+
+impl SimulatorService {
+    pub fn new(template: WorldStateBuilder) -> Self {
+        SimulatorService {
+            template,
+            networks: Mutex::new(HashMap::new()),
+            next_id: AtomicU64::new(0),
+        }
+    }
+
+    fn network(&self, id: u64) -> Result<Arc<Mutex<WorldState>>, Status> {
+        self.networks
+            .lock()
+            .unwrap()
+            .get(&id)
+            .cloned()
+            .ok_or_else(|| Status::not_found(format!("no network {id}")))
+    }
+
+    /// Runs `f` on network `id` on a blocking thread, as steps may take long.
+    async fn with_network<T: Send + 'static>(
+        &self,
+        id: u64,
+        f: impl FnOnce(&mut WorldState) -> Result<T, Status> + Send + 'static,
+    ) -> Result<T, Status> {
+        let network = self.network(id)?;
+        tokio::task::spawn_blocking(move || f(&mut network.lock().unwrap()))
+            .await
+            .map_err(|err| Status::internal(err.to_string()))?
+    }
+}
+
+/// Spikes of `world` in the steps after `first_step`.
+fn spikes_after(world: &WorldState, first_step: usize) -> proto::Spikes {
+    let (times, neurons) = world
+        .spikes()
+        .filter(|&(time_step, _)| time_step > first_step)
+        .map(|(time_step, idx)| (time_step as f64 * world.dt, idx as u32))
+        .unzip();
+    proto::Spikes {
+        times,
+        neurons,
+        time: world.time(),
+    }
+}
+
+#[tonic::async_trait]
+impl Simulator for SimulatorService {
+    async fn create_network(
+        &self,
+        request: Request<proto::CreateNetworkRequest>,
+    ) -> Result<Response<proto::Network>, Status> {
+        let request = request.into_inner();
+        let mut builder = self.template.clone();
+        if let Some(excitatory) = request.excitatory {
+            builder = builder.excitatory(excitatory as usize);
+        }
+        if let Some(inhibitory) = request.inhibitory {
+            builder = builder.inhibitory(inhibitory as usize);
+        }
+        if let Some(seed) = request.seed {
+            builder = builder.seed(seed);
+        }
+        if let Some(probability) = request.connection_probability {
+            if !(0.0..=1.0).contains(&probability) {
+                return Err(Status::invalid_argument(
+                    "connection probability must lie in [0, 1]",
+                ));
+            }
+            builder = builder.connection_probability(probability);
+        }
+        if let Some(thalamic_noise) = request.thalamic_noise {
+            builder = builder.thalamic_noise(thalamic_noise);
+        }
+        let world = tokio::task::spawn_blocking(move || builder.build())
+            .await
+            .map_err(|err| Status::internal(err.to_string()))?;
+
+        let id = self.next_id.fetch_add(1, Ordering::Relaxed);
+        let network = proto::Network {
+            id,
+            neurons: world.neurons.len() as u32,
+            populations: world
+                .populations()
+                .into_iter()
+                .map(|(name, range)| proto::Population {
+                    name,
+                    start: range.start as u32,
+                    stop: range.end as u32,
+                })
+                .collect(),
+            dt: world.dt,
+        };
+        self.networks
+            .lock()
+            .unwrap()
+            .insert(id, Arc::new(Mutex::new(world)));
+        Ok(Response::new(network))
+    }
+
+    async fn drop_network(
+        &self,
+        request: Request<proto::NetworkId>,
+    ) -> Result<Response<proto::Empty>, Status> {
+        let id = request.into_inner().id;
+        match self.networks.lock().unwrap().remove(&id) {
+            Some(_) => Ok(Response::new(proto::Empty {})),
+            None => Err(Status::not_found(format!("no network {id}"))),
+        }
+    }
+
+    async fn step(
+        &self,
+        request: Request<proto::StepRequest>,
+    ) -> Result<Response<proto::Spikes>, Status> {
+        let request = request.into_inner();
+        let spikes = self
+            .with_network(request.id, move |world| {
+                let first_step = world.time_step;
+                world.run_for(request.milliseconds);
+                Ok(spikes_after(world, first_step))
+            })
+            .await?;
+        Ok(Response::new(spikes))
+    }
+
+    async fn inject_stimulus(
+        &self,
+        request: Request<proto::StimulusRequest>,
+    ) -> Result<Response<proto::Empty>, Status> {
+        let request = request.into_inner();
+        self.with_network(request.id, move |world| {
+            let neurons = request.start as usize..request.stop as usize;
+            if neurons.end > world.neurons.len() {
+                return Err(Status::invalid_argument(format!(
+                    "neuron {} out of range for {} neurons",
+                    neurons.end - 1,
+                    world.neurons.len()
+                )));
+            }
+            let window = request.onset..request.offset;
+            match request.shape {
+                Some(Shape::Step(step)) => world.add_stimulus(
+                    StepCurrent {
+                        amplitude: step.amplitude,
+                    },
+                    neurons,
+                    window,
+                ),
+                Some(Shape::Pulses(pulses)) => world.add_stimulus(
+                    PulseTrain {
+                        amplitude: pulses.amplitude,
+                        period: pulses.period,
+                        width: pulses.width,
+                    },
+                    neurons,
+                    window,
+                ),
+                None => return Err(Status::invalid_argument("missing stimulus shape")),
+            }
+            Ok(())
+        })
+        .await?;
+        Ok(Response::new(proto::Empty {}))
+    }
+
+    async fn get_spikes(
+        &self,
+        request: Request<proto::SpikesRequest>,
+    ) -> Result<Response<proto::Spikes>, Status> {
+        let request = request.into_inner();
+        let spikes = self
+            .with_network(request.id, move |world| {
+                // the last step whose spikes are at or before `after`
+                let first_step = (request.after / world.dt).floor().max(0.) as usize;
+                Ok(spikes_after(world, first_step))
+            })
+            .await?;
+        Ok(Response::new(spikes))
+    }
+
+    async fn get_weights(
+        &self,
+        request: Request<proto::NetworkId>,
+    ) -> Result<Response<proto::Weights>, Status> {
+        let weights = self
+            .with_network(request.into_inner().id, |world| {
+                let connectivity = &world.connectivity;
+                Ok(proto::Weights {
+                    data: connectivity.weights().to_vec(),
+                    indices: connectivity.targets().iter().map(|&i| i as u32).collect(),
+                    indptr: (0..connectivity.neurons())
+                        .map(|pre| connectivity.row(pre).start as u32)
+                        .chain([connectivity.synapses() as u32])
+                        .collect(),
+                    delays: connectivity.delays().to_vec(),
+                })
+            })
+            .await?;
+        Ok(Response::new(weights))
+    }
+
+    async fn set_weights(
+        &self,
+        request: Request<proto::SetWeightsRequest>,
+    ) -> Result<Response<proto::Empty>, Status> {
+        let request = request.into_inner();
+        self.with_network(request.id, move |world| {
+            let target = world.connectivity.weights_mut();
+            if request.weights.len() != target.len() {
+                return Err(Status::invalid_argument(format!(
+                    "expected {} weights, got {}",
+                    target.len(),
+                    request.weights.len()
+                )));
+            }
+            target.copy_from_slice(&request.weights);
+            Ok(())
+        })
+        .await?;
+        Ok(Response::new(proto::Empty {}))
+    }
+}
+
+/// Serves the `Simulator` service on `address` until the process is stopped, building new
+/// networks from `template`.
+pub fn serve(
+    address: SocketAddr,
+    template: WorldStateBuilder,
+) -> Result<(), Box<dyn std::error::Error>> {
+    // the weights of the paper's network alone take about 10 MB
+    let service =
+        SimulatorServer::new(SimulatorService::new(template)).max_decoding_message_size(usize::MAX);
+    let runtime = tokio::runtime::Runtime::new()?;
+    runtime.block_on(Server::builder().add_service(service).serve(address))?;
+    Ok(())
+}
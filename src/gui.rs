@@ -0,0 +1,205 @@
+use eframe::egui;
+use egui_plot::{Legend, Line, Plot, PlotPoints, Points};
+use plotters::style::RGBColor;
+
+use crate::plot::population_color;
+use crate::simulator::{WorldState, WorldStateBuilder};
+
+/// Milliseconds of activity shown in the raster and rate plots.
+const WINDOW: f64 = 500.;
+/// Bin width of the population rates in milliseconds.
+const RATE_BIN: f64 = 5.;
+
+/// Window running a network while it is open, with sliders for the population sizes, the
+/// thalamic noise and the scales of the excitatory and inhibitory weights above the raster and
+/// population rates of the last 500 ms.
+pub struct Gui {
+    builder: WorldStateBuilder,
+    world: WorldState,
+    /// Weights of the world as built, which the scales apply to.
+    base_weights: Vec<f64>,
+    excitatory: usize,
+    inhibitory: usize,
+    thalamic_sigma: f64,
+    excitatory_scale: f64,
+    inhibitory_scale: f64,
+    /// Simulated milliseconds per frame.
+    speed: f64,
+    running: bool,
+}
+
+impl Gui {
+    /// Runs the network of `builder`, which keeps only the spikes shown.
+    pub fn new(builder: WorldStateBuilder) -> Self {
+        let (excitatory, inhibitory) = (builder.excitatory, builder.inhibitory);
+        let builder = builder.spike_window(WINDOW);
+        let world = builder.clone().build();
+        Gui {
+            base_weights: world.connectivity.weights().to_vec(),
+            thalamic_sigma: world.thalamic_sigma,
+            world,
+            builder,
+            excitatory,
+            inhibitory,
+            excitatory_scale: 1.,
+            inhibitory_scale: 1.,
+            speed: 10.,
+            running: true,
+        }
+    }
+
+    /// Opens the window and returns once it is closed.
+    pub fn run(self) -> eframe::Result {
+        eframe::run_native(
+            "izhikevich-rs",
+            eframe::NativeOptions::default(),
+            Box::new(|_| Ok(Box::new(self))),
+        )
+    }
+
+    /// Builds the network anew with the chosen population sizes, keeping the noise and scales.
+    fn restart(&mut self) {
+        self.builder = self
+            .builder
+            .clone()
+            .excitatory(self.excitatory)
+            .inhibitory(self.inhibitory);
+        self.world = self.builder.clone().build();
+        self.base_weights = self.world.connectivity.weights().to_vec();
+        self.world.thalamic_sigma = self.thalamic_sigma;
+        self.scale_weights();
+    }
+
+    /// Sets every weight to its value as built times the scale of its sign, undoing any
+    /// plasticity since.
+    fn scale_weights(&mut self) {
+        for (weight, &base) in self
+            .world
+            .connectivity
+            .weights_mut()
+            .iter_mut()
+            .zip(&self.base_weights)
+        {
+            let scale = if base >= 0. {
+                self.excitatory_scale
+            } else {
+                self.inhibitory_scale
+            };
+            *weight = base * scale;
+        }
+    }
+
+    fn controls(&mut self, ui: &mut egui::Ui) {
+        ui.horizontal(|ui| {
+            ui.add(egui::Slider::new(&mut self.excitatory, 0..=4000).text("excitatory"));
+            ui.add(egui::Slider::new(&mut self.inhibitory, 0..=1000).text("inhibitory"));
+            if ui.button("Restart").clicked() {
+                self.restart();
+            }
+        });
+        ui.horizontal(|ui| {
+            let noise =
+                ui.add(egui::Slider::new(&mut self.thalamic_sigma, 0.0..=3.0).text("noise"));
+            if noise.changed() {
+                self.world.thalamic_sigma = self.thalamic_sigma;
+            }
+            let excitatory = ui.add(
+                egui::Slider::new(&mut self.excitatory_scale, 0.0..=3.0).text("excitatory weights"),
+            );
+            let inhibitory = ui.add(
+                egui::Slider::new(&mut self.inhibitory_scale, 0.0..=3.0).text("inhibitory weights"),
+            );
+            if excitatory.changed() || inhibitory.changed() {
+                self.scale_weights();
+            }
+        });
+        ui.horizontal(|ui| {
+            ui.add(egui::Slider::new(&mut self.speed, 1.0..=50.0).text("ms per frame"));
+            let label = if self.running { "Pause" } else { "Run" };
+            if ui.button(label).clicked() {
+                self.running = !self.running;
+            }
+            ui.label(format!("{:.0} ms", self.world.time()));
+        });
+    }
+
+    fn plots(&self, ui: &mut egui::Ui) {
+        let world = &self.world;
+        let end = world.time();
+        let start = (end - WINDOW).max(0.);
+        let first_step = (start / world.dt) as usize;
+        let populations = world.populations();
+        let color = |idx: usize| {
+            let RGBColor(r, g, b) = population_color(idx);
+            egui::Color32::from_rgb(r, g, b)
+        };
+
+        let bins = (WINDOW / RATE_BIN).ceil() as usize;
+        let mut counts = vec![vec![0; bins]; populations.len()];
+        let mut spikes = vec![vec![]; populations.len()];
+        for (time_step, neuron) in world.spike_record().since(first_step) {
+            let time = time_step as f64 * world.dt;
+            let Some(idx) = populations.iter().position(|(_, n)| n.contains(&neuron)) else {
+                continue;
+            };
+            spikes[idx].push([time, neuron as f64]);
+            let bin = ((time - start) / RATE_BIN) as usize;
+            if let Some(count) = counts[idx].get_mut(bin) {
+                *count += 1;
+            }
+        }
+
+        let height = ui.available_height();
+        Plot::new("raster")
+            .height(0.7 * height)
+            .include_x(start)
+            .include_x(start + WINDOW)
+            .include_y(0.)
+            .include_y(world.neurons.len() as f64)
+            .x_axis_label("Time (ms)")
+            .y_axis_label("Neuron Index")
+            .legend(Legend::default())
+            .show(ui, |plot| {
+                for (idx, ((name, _), spikes)) in populations.iter().zip(spikes).enumerate() {
+                    plot.points(Points::new(spikes).radius(1.).color(color(idx)).name(name));
+                }
+            });
+        Plot::new("rates")
+            .include_x(start)
+            .include_x(start + WINDOW)
+            .include_y(0.)
+            .x_axis_label("Time (ms)")
+            .y_axis_label("Hz")
+            .show(ui, |plot| {
+                for (idx, ((name, neurons), counts)) in populations.iter().zip(&counts).enumerate()
+                {
+                    let rates: PlotPoints = counts
+                        .iter()
+                        .enumerate()
+                        .map(|(bin, &count)| {
+                            let time = start + (bin as f64 + 0.5) * RATE_BIN;
+                            let rate =
+                                1e3 * count as f64 / (neurons.len().max(1) as f64 * RATE_BIN);
+                            [time, rate]
+                        })
+                        .take_while(|&[time, _]| time <= end)
+                        .collect();
+                    plot.line(Line::new(rates).color(color(idx)).name(name));
+                }
+            });
+    }
+}
+
+impl eframe::App for Gui {
+    fn update(&mut self, ctx: &egui::Context, _frame: &mut eframe::Frame) {
+        if self.running {
+            self.world.run_for(self.speed);
+            ctx.request_repaint();
+        }
+        egui::CentralPanel::default().show(ctx, |ui| {
+            self.controls(ui);
+            ui.separator();
+            self.plots(ui);
+        });
+    }
+}
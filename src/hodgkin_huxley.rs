@@ -0,0 +1,144 @@
+use serde::{Deserialize, Serialize};
+
+use crate::neuron::NeuronModel;
+
+/// Parameters of the Hodgkin–Huxley model of the squid giant axon, with the membrane potential
+/// shifted to rest at -65 mV:
+///
+/// `C dV/dt = -g_Na m³h (V - E_Na) - g_K n⁴ (V - E_K) - g_L (V - E_L) + I`,
+///
+/// where each gate `x` of `m`, `h` and `n` follows `dx/dt = α_x(V) (1 - x) - β_x(V) x`. The
+/// current `I` in µA/cm² is the input of the network scaled by `input_gain`, as weights and noise
+/// are in the units of the Izhikevich model.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct HodgkinHuxleyParameters {
+    /// Membrane capacitance `C` in µF/cm².
+    pub capacitance: f64,
+    /// Maximal sodium conductance `g_Na` in mS/cm².
+    pub g_na: f64,
+    /// Maximal potassium conductance `g_K` in mS/cm².
+    pub g_k: f64,
+    /// Leak conductance `g_L` in mS/cm².
+    pub g_leak: f64,
+    /// Sodium reversal potential `E_Na` in mV.
+    pub e_na: f64,
+    /// Potassium reversal potential `E_K` in mV.
+    pub e_k: f64,
+    /// Leak reversal potential `E_L` in mV.
+    pub e_leak: f64,
+    /// Potential whose upward crossing counts as a spike in mV.
+    pub spike_threshold: f64,
+    /// Longest integration step in ms, as the sodium current is far too fast for the time step
+    /// of the network.
+    pub max_substep: f64,
+    /// Current in µA/cm² per unit of network input.
+    pub input_gain: f64,
+}
+
+impl Default for HodgkinHuxleyParameters {
+    fn default() -> Self {
+        HodgkinHuxleyParameters {
+            capacitance: 1.,
+            g_na: 120.,
+            g_k: 36.,
+            g_leak: 0.3,
+            e_na: 50.,
+            e_k: -77.,
+            e_leak: -54.387,
+            spike_threshold: 0.,
+            max_substep: 0.01,
+            // the thalamic noise alone then drives the neuron at about 5 Hz like a regular
+            // spiking Izhikevich neuron, as it is far more excitable by short pulses
+            input_gain: 0.35,
+        }
+    }
+}
+
+/// `x / (exp(x / y) - 1)`, continued through its removable singularity at `x = 0`.
+fn vtrap(x: f64, y: f64) -> f64 {
+    if (x / y).abs() < 1e-6 {
+        y * (1. - x / y / 2.)
+    } else {
+        x / ((x / y).exp() - 1.)
+    }
+}
+
+impl HodgkinHuxleyParameters {
+    /// Opening and closing rates per ms of the `m`, `h` and `n` gates at `v`.
+    fn rates(v: f64) -> [(f64, f64); 3] {
+        [
+            (0.1 * vtrap(-(v + 40.), 10.), 4. * (-(v + 65.) / 18.).exp()),
+            (
+                0.07 * (-(v + 65.) / 20.).exp(),
+                1. / (1. + (-(v + 35.) / 10.).exp()),
+            ),
+            (
+                0.01 * vtrap(-(v + 55.), 10.),
+                0.125 * (-(v + 65.) / 80.).exp(),
+            ),
+        ]
+    }
+
+    /// Neuron at its resting potential of -65 mV with the gates at their steady states.
+    pub fn resting_state(self) -> HodgkinHuxley {
+        let v = -65.;
+        let [m, h, n] = Self::rates(v).map(|(alpha, beta)| alpha / (alpha + beta));
+        HodgkinHuxley {
+            parameters: self,
+            gates: [m, h, n],
+            spiked: false,
+        }
+    }
+}
+
+/// A Hodgkin–Huxley neuron, the [`NeuronModel`] whose parameters carry the gating variables
+/// beyond `v`, while `u` is unused. It has no reset: a spike is the upward crossing of the
+/// threshold, after which the potassium current repolarizes the membrane on its own.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct HodgkinHuxley {
+    pub parameters: HodgkinHuxleyParameters,
+    /// The `m`, `h` and `n` gates.
+    pub gates: [f64; 3],
+    /// Whether the last step crossed the spike threshold.
+    spiked: bool,
+}
+
+impl NeuronModel for HodgkinHuxley {
+    fn integrate(&mut self, v: &mut f64, _u: &mut f64, i: f64, dt: f64) {
+        let p = &self.parameters;
+        let substeps = (dt / p.max_substep).ceil().max(1.);
+        let h = dt / substeps;
+        let current = p.input_gain * i;
+        self.spiked = false;
+        for _ in 0..substeps as usize {
+            let [m, gate_h, n] = self.gates;
+            let ionic = p.g_na * m * m * m * gate_h * (*v - p.e_na)
+                + p.g_k * n * n * n * n * (*v - p.e_k)
+                + p.g_leak * (*v - p.e_leak);
+            let previous = *v;
+            *v += h * (current - ionic) / p.capacitance;
+            // the gates relax exponentially towards their steady states, which stays stable
+            // for the fast m gate
+            for (gate, (alpha, beta)) in self
+                .gates
+                .iter_mut()
+                .zip(HodgkinHuxleyParameters::rates(previous))
+            {
+                let steady = alpha / (alpha + beta);
+                *gate = steady + (*gate - steady) * (-(alpha + beta) * h).exp();
+            }
+            self.spiked |= previous < p.spike_threshold && *v >= p.spike_threshold;
+        }
+    }
+
+    fn fired(&self, _v: f64) -> bool {
+        self.spiked
+    }
+
+    fn reset(&mut self, _v: &mut f64, _u: &mut f64) {}
+
+    /// Integrates the membrane without input, as the spike itself makes the neuron refractory.
+    fn hold(&mut self, v: &mut f64, u: &mut f64, dt: f64) {
+        self.integrate(v, u, 0., dt);
+    }
+}
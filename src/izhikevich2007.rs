@@ -0,0 +1,160 @@
+use serde::{Deserialize, Serialize};
+
+use crate::neuron::{interpolate_crossing, NeuronModel, SynapseType};
+
+/// Parameters of the dimensional "simple model" of Izhikevich (2007), Dynamical Systems in
+/// Neuroscience, chapter 8, a [`NeuronModel`] in place of the `0.04 v² + 5 v + 140` form:
+///
+/// `C dv/dt = k (v - v_r) (v - v_t) - u + I`,
+/// `du/dt = a (b (v - v_r) - u)`,
+///
+/// with `v ← c` and `u ← u + d` once `v` reaches `v_peak`. The current `I` in pA is the input of
+/// the network scaled by `input_gain`, as weights and noise are in the units of the 2003 model.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct Izhikevich2007Parameters {
+    /// Membrane capacitance `C` in pF.
+    pub capacitance: f64,
+    /// Scale `k` of the quadratic current in nS/mV.
+    pub k: f64,
+    /// Resting potential `v_r` in mV.
+    pub v_r: f64,
+    /// Instantaneous threshold potential `v_t` in mV.
+    pub v_t: f64,
+    /// Rate `a` of the recovery variable per ms.
+    pub a: f64,
+    /// Sensitivity `b` of the recovery variable to `v` in nS.
+    pub b: f64,
+    /// Reset potential `c` in mV.
+    pub c: f64,
+    /// Increment `d` of the recovery variable after a spike in pA.
+    pub d: f64,
+    /// Spike cutoff `v_peak` in mV.
+    pub v_peak: f64,
+    /// Potential `v_b` above which `u` relaxes to `b (v - v_b)³` instead of `b (v - v_r)`, and to
+    /// 0 below, as for fast spiking basket cells; `None` for the linear form.
+    pub v_b: Option<f64>,
+    /// Current in pA per unit of network input.
+    pub input_gain: f64,
+}
+
+/// Cortical cell types with the published parameters of the simple model.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Izhikevich2007Preset {
+    /// Regular spiking layer 5 pyramidal neuron.
+    Pyramidal,
+    /// Intrinsically bursting layer 5 pyramidal neuron.
+    IntrinsicallyBursting,
+    /// Chattering layer 2/3 pyramidal neuron.
+    Chattering,
+    /// Fast spiking basket interneuron.
+    Basket,
+}
+
+impl Izhikevich2007Preset {
+    pub fn params(&self) -> Izhikevich2007Parameters {
+        // C, k, v_r, v_t, a, b, c, d, v_peak
+        let (capacitance, k, v_r, v_t, a, b, c, d, v_peak) = match self {
+            Izhikevich2007Preset::Pyramidal => (100., 0.7, -60., -40., 0.03, -2., -50., 100., 35.),
+            Izhikevich2007Preset::IntrinsicallyBursting => {
+                (150., 1.2, -75., -45., 0.01, 5., -56., 130., 50.)
+            }
+            Izhikevich2007Preset::Chattering => (50., 1.5, -60., -40., 0.03, 1., -40., 150., 25.),
+            Izhikevich2007Preset::Basket => (20., 1., -55., -40., 0.2, 0.025, -45., 0., 25.),
+        };
+        let v_b = match self {
+            Izhikevich2007Preset::Basket => Some(-55.),
+            _ => None,
+        };
+        Izhikevich2007Parameters {
+            capacitance,
+            k,
+            v_r,
+            v_t,
+            a,
+            b,
+            c,
+            d,
+            v_peak,
+            v_b,
+            // white noise makes the potential of a resting regular spiking 2003 neuron fluctuate
+            // like a membrane with a time constant of about 1.25 ms, whose variance this matches
+            // given the slower time constant C / (k (v_t - v_r)) at rest
+            input_gain: (1.25 * capacitance * k * (v_t - v_r)).sqrt(),
+        }
+    }
+
+    /// Preset standing in for the randomized neurons of the 2003 paper: regular spiking
+    /// pyramidal excitatory and fast spiking basket inhibitory neurons.
+    pub fn for_synapse_type(synapse_type: &SynapseType) -> Self {
+        match synapse_type {
+            SynapseType::Excitatory => Izhikevich2007Preset::Pyramidal,
+            SynapseType::Inhibitory => Izhikevich2007Preset::Basket,
+        }
+    }
+}
+
+/// Longest integration step in ms.
+const MAX_SUBSTEP: f64 = 0.1;
+
+impl Izhikevich2007Parameters {
+    /// Value `u` relaxes to at `v`.
+    fn recovery_nullcline(&self, v: f64) -> f64 {
+        match self.v_b {
+            Some(v_b) if v < v_b => 0.,
+            Some(v_b) => self.b * (v - v_b).powi(3),
+            None => self.b * (v - self.v_r),
+        }
+    }
+}
+
+impl NeuronModel for Izhikevich2007Parameters {
+    fn integrate(&mut self, v: &mut f64, u: &mut f64, i: f64, dt: f64) {
+        // in sub-steps, as the small basket cells are too fast for steps of 1 ms, stopping at
+        // the spike cutoff so that an overshoot cannot blow up their cubic recovery
+        let substeps = (dt / MAX_SUBSTEP).ceil().max(1.);
+        let h = dt / substeps;
+        let i = self.input_gain * i;
+        for _ in 0..substeps as usize {
+            *v += h * (self.k * (*v - self.v_r) * (*v - self.v_t) - *u + i) / self.capacitance;
+            if *v >= self.v_peak {
+                *v = self.v_peak;
+                break;
+            }
+            *u += h * self.a * (self.recovery_nullcline(*v) - *u);
+        }
+    }
+
+    fn fired(&self, v: f64) -> bool {
+        v >= self.v_peak
+    }
+
+    fn crossing(&self, v_before: f64, v_after: f64) -> f64 {
+        interpolate_crossing(self.v_peak, v_before, v_after)
+    }
+
+    fn reset(&mut self, v: &mut f64, u: &mut f64) {
+        *v = self.c;
+        *u += self.d;
+    }
+
+    fn hold(&mut self, v: &mut f64, u: &mut f64, dt: f64) {
+        *v = self.c;
+        *u += dt * self.a * (self.recovery_nullcline(*v) - *u);
+    }
+
+    fn settle(&mut self, v: &mut f64, u: &mut f64, tolerance: f64) -> bool {
+        // v_r with u = 0 is the resting state of every preset, stable unless the recovery
+        // variable outweighs the quadratic current
+        let slope = match self.v_b {
+            Some(_) => 0.,
+            None => self.b,
+        };
+        let stable =
+            self.recovery_nullcline(self.v_r) == 0. && self.k * (self.v_t - self.v_r) + slope > 0.;
+        if !stable || (*v - self.v_r).abs() > tolerance || u.abs() > tolerance {
+            return false;
+        }
+        (*v, *u) = (self.v_r, 0.);
+        true
+    }
+}
@@ -0,0 +1,77 @@
+use std::str::FromStr;
+
+use serde::{Deserialize, Serialize};
+
+/// Virtual lesion of part of the network, applied with [`crate::WorldState::lesion`] or at a
+/// later time with [`crate::WorldState::schedule_lesion`].
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub enum Lesion {
+    /// Silences the neurons for the rest of the run: they receive no input, emit no spikes and
+    /// lose their outgoing synapses, but stay in the network so indices and rates still cover
+    /// them.
+    Neurons(Vec<usize>),
+    /// Removes every synapse from one of `pre` onto one of `post` for the rest of the run, also
+    /// against plasticity.
+    Synapses { pre: Vec<usize>, post: Vec<usize> },
+}
+
+impl Lesion {
+    /// Checks that the lesion only refers to the `neurons` neurons of a network.
+    pub fn check(&self, neurons: usize) -> Result<(), String> {
+        let sets = match self {
+            Lesion::Neurons(silenced) => vec![silenced],
+            Lesion::Synapses { pre, post } => vec![pre, post],
+        };
+        match sets.into_iter().flatten().find(|&&idx| idx >= neurons) {
+            Some(idx) => Err(format!(
+                "lesion of neuron {idx} out of range for {neurons} neurons"
+            )),
+            None => Ok(()),
+        }
+    }
+}
+
+impl FromStr for Lesion {
+    type Err = String;
+
+    /// Parses `neurons=SET` or `synapses=SET>SET` with pre- and postsynaptic sets, where a set
+    /// lists indices and inclusive ranges like `0-99,150`.
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let (kind, targets) = s
+            .split_once('=')
+            .ok_or_else(|| format!("expected `neurons=SET` or `synapses=SET>SET`, got `{s}`"))?;
+        match kind.trim() {
+            "neurons" => Ok(Lesion::Neurons(neuron_set(targets)?)),
+            "synapses" => {
+                let (pre, post) = targets
+                    .split_once('>')
+                    .ok_or_else(|| format!("expected `PRE>POST`, got `{targets}`"))?;
+                Ok(Lesion::Synapses {
+                    pre: neuron_set(pre)?,
+                    post: neuron_set(post)?,
+                })
+            }
+            _ => Err(format!(
+                "unknown lesion `{kind}`, expected neurons or synapses"
+            )),
+        }
+    }
+}
+
+/// Parses comma-separated indices and inclusive ranges like `0-99,150`.
+fn neuron_set(s: &str) -> Result<Vec<usize>, String> {
+    let index = |value: &str| {
+        value
+            .trim()
+            .parse::<usize>()
+            .map_err(|err| format!("invalid neuron `{value}`: {err}"))
+    };
+    let mut neurons = vec![];
+    for part in s.split(',') {
+        match part.split_once('-') {
+            Some((first, last)) => neurons.extend(index(first)?..=index(last)?),
+            None => neurons.push(index(part)?),
+        }
+    }
+    Ok(neurons)
+}
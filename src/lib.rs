@@ -1,2 +1,106 @@
+pub mod adex;
+pub mod analysis;
+pub mod batch;
+pub mod bifurcation;
+pub mod calibration;
+pub mod checkpoint;
+pub mod clamp;
+pub mod control;
+#[cfg(feature = "tui")]
+pub mod dashboard;
+pub mod decoding;
+#[cfg(feature = "mpi")]
+mod distributed;
+pub mod encoding;
+pub mod gap_junction;
+#[cfg(feature = "gpu")]
+mod gpu;
+pub mod graph;
+#[cfg(feature = "grpc")]
+pub mod grpc;
+#[cfg(feature = "gui")]
+pub mod gui;
+pub mod hodgkin_huxley;
+pub mod integrator;
+pub mod izhikevich2007;
+pub mod lesion;
+pub mod lif;
+#[cfg(feature = "live")]
+pub mod live;
+pub mod mnist;
+pub mod network;
+pub mod neuroml;
+pub mod neuromodulation;
 pub mod neuron;
+pub mod npy;
+pub mod output;
+pub mod overrides;
+pub mod plasticity;
+pub mod plot;
+pub mod profile;
+pub mod progress;
+#[cfg(feature = "python")]
+mod python;
+pub mod reservoir;
+mod simd;
 pub mod simulator;
+#[cfg(feature = "sonata")]
+pub mod sonata;
+pub mod spectrum;
+pub mod spike_record;
+pub mod stimulus;
+pub mod sweep;
+pub mod synapse;
+pub mod task;
+pub mod two_compartment;
+#[cfg(feature = "wasm")]
+mod wasm;
+pub mod weights;
+
+pub use adex::{AdExParameters, AdExPreset};
+pub use analysis::{
+    van_rossum_distance, victor_purpura_distance, Correlogram, Histogram, PolychronousGroup,
+    PopulationSynchrony, Psth, RateSummary, SpikeTrains, SpikeTriggeredAverage, SynchronySummary,
+    VoltageSynchrony, WeightHistory,
+};
+pub use batch::{Batch, BatchSummary, MeanStd, PopulationStatistics, TrialSummary};
+pub use bifurcation::{Bifurcation, BifurcationParameter, BifurcationPoint, FiringPattern};
+pub use calibration::{Calibrated, Calibration};
+pub use checkpoint::{load_checkpoint, save_checkpoint};
+pub use clamp::{ClampTrace, CurrentClamp, FiCurve, Segment, Shape, Waveform};
+pub use control::{Controller, RateClamp, StreamedInput};
+pub use decoding::Decoding;
+pub use encoding::Encoding;
+pub use gap_junction::GapJunctions;
+pub use hodgkin_huxley::{HodgkinHuxley, HodgkinHuxleyParameters};
+pub use integrator::Integrator;
+pub use izhikevich2007::{Izhikevich2007Parameters, Izhikevich2007Preset};
+pub use lesion::Lesion;
+pub use lif::LifParameters;
+pub use mnist::{Classification, Mnist, MnistBenchmark};
+pub use network::{
+    DistanceKernel, InstantiatedNetwork, NetworkBuilder, Population, Projection, Sheet, Synapse,
+    Topology, WeightDistribution,
+};
+pub use neuromodulation::{ModulatedVariable, Neuromodulator};
+pub use neuron::{
+    Neuron, NeuronDynamics, NeuronModel, NeuronParameters, NeuronType, Neurons, RefractoryPeriods,
+    SynapseType,
+};
+pub use overrides::NeuronOverride;
+pub use plasticity::{
+    DopamineStdpParameters, HomeostasisParameters, HomeostaticVariable, InhibitoryStdpParameters,
+    ShortTermPlasticity, StdpParameters, StpParameters,
+};
+pub use profile::{Phase, Profile};
+pub use reservoir::{MemoryCapacity, Readout, Reservoir};
+pub use simulator::{Backend, BackendError, SparseConnectivity, WorldState, WorldStateBuilder};
+pub use spectrum::{Band, PowerSpectrum};
+pub use spike_record::SpikeRecord;
+pub use stimulus::{
+    Electrode, NoiseModel, NoiseSource, PoissonInput, PulseTrain, Ramp, StepCurrent, Stimulus,
+};
+pub use sweep::{Sweep, SweepAxis, SweepParameter, SweepPoint};
+pub use synapse::{ConductanceParameters, ExponentialParameters, NmdaParameters, SynapseModel};
+pub use task::{RewardLearning, Task, Trial, TwoAlternativeChoice};
+pub use two_compartment::{TwoCompartment, TwoCompartmentParameters};
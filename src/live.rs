@@ -0,0 +1,50 @@
+use minifb::{Window, WindowOptions};
+use plotters::prelude::*;
+
+use crate::plot::recent_raster;
+use crate::simulator::WorldState;
+
+const WIDTH: usize = 800;
+const HEIGHT: usize = 600;
+
+/// Window showing a scrolling raster of the most recent spikes while the simulation runs.
+pub struct LiveRaster {
+    window: Window,
+    /// Length of the displayed time window in milliseconds.
+    window_ms: f64,
+    rgb: Vec<u8>,
+    pixels: Vec<u32>,
+}
+
+impl LiveRaster {
+    pub fn new(window_ms: f64) -> Result<Self, minifb::Error> {
+        let window = Window::new("izhikevich-rs", WIDTH, HEIGHT, WindowOptions::default())?;
+        Ok(LiveRaster {
+            window,
+            window_ms,
+            rgb: vec![0; WIDTH * HEIGHT * 3],
+            pixels: vec![0; WIDTH * HEIGHT],
+        })
+    }
+
+    /// Whether the window is still open; closing it does not stop the simulation.
+    pub fn is_open(&self) -> bool {
+        self.window.is_open()
+    }
+
+    /// Redraws the raster with the spikes of the last `window_ms` milliseconds.
+    pub fn update(&mut self, world: &WorldState) -> Result<(), Box<dyn std::error::Error>> {
+        {
+            let root = BitMapBackend::with_buffer(&mut self.rgb, (WIDTH as u32, HEIGHT as u32))
+                .into_drawing_area();
+            recent_raster(&root, world, self.window_ms)?;
+        }
+
+        for (pixel, rgb) in self.pixels.iter_mut().zip(self.rgb.chunks_exact(3)) {
+            *pixel = u32::from_be_bytes([0, rgb[0], rgb[1], rgb[2]]);
+        }
+        self.window
+            .update_with_buffer(&self.pixels, WIDTH, HEIGHT)?;
+        Ok(())
+    }
+}
@@ -1,69 +1,2749 @@
-mod neuron;
-mod simulator;
+use std::convert::Infallible;
+use std::ffi::OsString;
+use std::fs::{self, File};
+use std::io::{self, BufReader, BufWriter, Write};
+#[cfg(feature = "grpc")]
+use std::net::SocketAddr;
+use std::ops::Range;
+use std::path::{Path, PathBuf};
+use std::str::FromStr;
+use std::time::{Instant, SystemTime, UNIX_EPOCH};
 
-use clap::Parser;
-use plotters::prelude::*;
-use simulator::WorldState;
+use clap::error::ErrorKind;
+use clap::{Arg, ArgMatches, CommandFactory, FromArgMatches, Parser, Subcommand, ValueEnum};
+use izhikevich_rs::{
+    graph, neuroml, output, overrides, plot, plot::PlotFormat, progress, task, weights,
+    AdExParameters, AdExPreset, Backend, Batch, BatchSummary, Bifurcation, BifurcationParameter,
+    Calibrated, Calibration, CurrentClamp, ExponentialParameters, FiCurve, GapJunctions,
+    HodgkinHuxleyParameters, HomeostasisParameters, HomeostaticVariable, InhibitoryStdpParameters,
+    Integrator, Izhikevich2007Parameters, Izhikevich2007Preset, Lesion, LifParameters,
+    MemoryCapacity, Mnist, MnistBenchmark, ModulatedVariable, Neuromodulator, Neuron,
+    NeuronDynamics, NeuronParameters, NeuronType, NoiseModel, PoissonInput, PowerSpectrum,
+    PulseTrain, RateClamp, RateSummary, RefractoryPeriods, Reservoir, RewardLearning, Segment,
+    ShortTermPlasticity, SpikeTrains, StdpParameters, StpParameters, StreamedInput, Sweep,
+    SweepAxis, SweepParameter, SynapseModel, SynapseType, SynchronySummary, Topology,
+    TwoAlternativeChoice, TwoCompartmentParameters, VoltageSynchrony, Waveform, WeightDistribution,
+    WorldState, WorldStateBuilder,
+};
+
+/// Shortest time step in milliseconds the flags accept, as finer ones and the bins they allow
+/// would need more memory than any machine has.
+const MIN_DT: f64 = 1e-6;
+
+/// Help heading of the flags naming files the run writes, which go into its run directory.
+const OUTPUT: &str = "Output";
 
 #[derive(Parser, Debug)]
-#[command(author, version, about, long_about = None)]
+#[command(author, version, about, long_about = None, args_override_self = true)]
 struct Args {
+    #[command(subcommand)]
+    command: Option<Command>,
+
+    /// Read settings from this TOML file; flags given on the command line take precedence
+    #[arg(long, global = true)]
+    config: Option<PathBuf>,
+
+    /// Start from a published experiment, which replaces the connectivity, plasticity and input
+    /// flags
+    #[arg(long, value_enum, default_value_t = PresetArg::Paper, global = true)]
+    preset: PresetArg,
+
     /// Number of excitatory neurons
-    #[arg(short, long, default_value_t = 800)]
+    #[arg(short, long, default_value_t = 800, global = true)]
     excitatory: usize,
 
     /// Number of inhibitory neurons
-    #[arg(short, long, default_value_t = 200)]
+    #[arg(short, long, default_value_t = 200, global = true)]
     inhibitory: usize,
 
+    /// Neuron model of the default network
+    #[arg(long, value_enum, default_value_t = ModelArg::Izhikevich, global = true)]
+    model: ModelArg,
+
+    /// Neuron model of the inhibitory population, if different from --model
+    #[arg(long, value_enum, global = true)]
+    inhibitory_model: Option<ModelArg>,
+
+    /// AdEx firing pattern of the excitatory neurons with --model adex
+    #[arg(long, value_enum, default_value_t = AdExPresetArg::Adapting, global = true)]
+    adex_excitatory: AdExPresetArg,
+
+    /// AdEx firing pattern of the inhibitory neurons with --model adex
+    #[arg(long, value_enum, default_value_t = AdExPresetArg::Tonic, global = true)]
+    adex_inhibitory: AdExPresetArg,
+
+    /// Cell type of the excitatory neurons with --model izhikevich2007
+    #[arg(long, value_enum, default_value_t = Izhikevich2007PresetArg::Pyramidal, global = true)]
+    izhikevich2007_excitatory: Izhikevich2007PresetArg,
+
+    /// Cell type of the inhibitory neurons with --model izhikevich2007
+    #[arg(long, value_enum, default_value_t = Izhikevich2007PresetArg::Basket, global = true)]
+    izhikevich2007_inhibitory: Izhikevich2007PresetArg,
+
+    /// Factor on the input gain of the neurons of other models than Izhikevich, which matches the
+    /// noise response of the Izhikevich model at 1; goes before any command, since `memory` and
+    /// `mnist` have their own
+    #[arg(long, default_value_t = 1.)]
+    input_gain: f64,
+
+    /// Fixed excitatory neuron types with their fractions, e.g. `RS:0.7,IB:0.2,CH:0.1`, instead
+    /// of the randomized regular spiking/chattering neurons
+    #[arg(long, value_delimiter = ',', value_parser = neuron_fraction, global = true)]
+    excitatory_mix: Vec<(NeuronType, f64)>,
+
+    /// Fixed inhibitory neuron types with their fractions, e.g. `FS:0.8,LTS:0.2`
+    #[arg(long, value_delimiter = ',', value_parser = neuron_fraction, global = true)]
+    inhibitory_mix: Vec<(NeuronType, f64)>,
+
     /// Simulation duration in milliseconds
-    #[arg(short, long, default_value_t = 1000)]
+    #[arg(short, long, default_value_t = 1000, global = true)]
     milliseconds: usize,
+
+    /// Integration time step in milliseconds
+    #[arg(long, default_value_t = 1.0, global = true)]
+    dt: f64,
+
+    /// Scheme integrating the membranes of the Izhikevich neurons
+    #[arg(long, value_enum, default_value_t = IntegratorArg::Euler, global = true)]
+    integrator: IntegratorArg,
+
+    /// Largest local error in mV of a sub-step of the adaptive integrator
+    #[arg(long, default_value_t = 1e-3, global = true)]
+    integrator_tolerance: f64,
+
+    /// Maximum axonal conduction delay in milliseconds; delays are drawn uniformly from 1 ms;
+    /// goes before any command, since `memory` has its own
+    #[arg(long, default_value_t = 1)]
+    max_delay: u32,
+
+    /// Draw the weight magnitudes of the synapses from a population as `POPULATION=DIST`, with
+    /// DIST one of `constant:W`, `uniform:MIN,MAX`, `normal:MEAN,STD`, `lognormal:MEAN,STD` or
+    /// `gamma:SHAPE,SCALE`; repeat for both populations
+    #[arg(long = "weight-distribution", value_parser = weight_distribution, global = true)]
+    weight_distributions: Vec<(String, WeightDistribution)>,
+
+    /// Probability that each pair of neurons of the random topology is connected, with the
+    /// weights drawn as before
+    #[arg(long, default_value_t = 1., global = true)]
+    connection_prob: f64,
+
+    /// Which pairs of neurons are connected
+    #[arg(long, value_enum, default_value_t = TopologyArg::Random, global = true)]
+    topology: TopologyArg,
+
+    /// Number of nearest ring neighbors each neuron projects onto in the small-world topology
+    #[arg(long, default_value_t = 100, global = true)]
+    neighbors: usize,
+
+    /// Probability of rewiring each ring synapse of the small-world topology
+    #[arg(long, default_value_t = 0.1, global = true)]
+    rewire: f64,
+
+    /// Number of links each neuron forms when joining the scale-free topology
+    #[arg(long, default_value_t = 50, global = true)]
+    attachment: usize,
+
+    /// How arriving spikes are turned into synaptic current
+    #[arg(long, value_enum, default_value_t = SynapseModelArg::Current, global = true)]
+    synapse_model: SynapseModelArg,
+
+    /// Rise time constant of excitatory currents with `--synapse-model exponential` in ms
+    #[arg(long, default_value_t = 0.5, global = true)]
+    ampa_rise: f64,
+
+    /// Decay time constant of excitatory currents with `--synapse-model exponential` in ms
+    #[arg(long, default_value_t = 5., global = true)]
+    ampa_decay: f64,
+
+    /// Rise time constant of inhibitory currents with `--synapse-model exponential` in ms
+    #[arg(long, default_value_t = 1., global = true)]
+    gaba_rise: f64,
+
+    /// Decay time constant of inhibitory currents with `--synapse-model exponential` in ms
+    #[arg(long, default_value_t = 10., global = true)]
+    gaba_decay: f64,
+
+    /// Where the neurons are integrated; `simd` and `gpu` need one-step delays and current synapses,
+    /// `gpu` also the `gpu` feature
+    #[arg(long, value_enum, default_value_t = BackendArg::Cpu, global = true)]
+    backend: BackendArg,
+
+    /// Skip neurons at rest without input, counting a neuron as at rest once its state is within
+    /// this distance of the resting state, e.g. `1e-6`
+    #[arg(long, global = true)]
+    event_driven: Option<f64>,
+
+    /// Keep only the spikes of the last this many milliseconds in memory, for long runs; the
+    /// spike outputs and analyses then cover only that window
+    #[arg(long, global = true)]
+    spike_window: Option<f64>,
+
+    /// Time spikes within their step by interpolating where the membrane crossed the cutoff,
+    /// for the spike outputs, analyses and STDP
+    #[arg(long, global = true)]
+    precise_spike_times: bool,
+
+    /// Enable dopamine-modulated STDP of the excitatory synapses
+    #[arg(long, global = true)]
+    dopamine_stdp: bool,
+
+    /// Release dopamine at these times in milliseconds, e.g. `1000,2000`
+    #[arg(long, value_delimiter = ',', global = true)]
+    reward_at: Vec<f64>,
+
+    /// Amount of dopamine released per reward
+    #[arg(long, default_value_t = 0.5, global = true)]
+    reward_amount: f64,
+
+    /// Plot the distribution of the weights of the synapses from excitatory neurons at up to
+    /// five times of the run to this PNG, to follow plasticity
+    #[arg(long, global = true, help_heading = OUTPUT)]
+    weight_histogram_plot: Option<PathBuf>,
+
+    /// Plot the weights of a sample of the synapses from excitatory neurons over time to this PNG
+    #[arg(long, global = true, help_heading = OUTPUT)]
+    weight_traces_plot: Option<PathBuf>,
+
+    /// Milliseconds between the weight snapshots of the weight plots
+    #[arg(long, default_value_t = 100., global = true)]
+    weight_interval: f64,
+
+    /// Number of synapses in --weight-traces-plot
+    #[arg(long, default_value_t = 20, global = true)]
+    weight_samples: usize,
+
+    /// Lesion the network at a time in milliseconds as `neurons=SET@TIME`, silencing the neurons,
+    /// or `synapses=PRE>POST@TIME`, removing the synapses between the sets, where a set lists
+    /// indices and inclusive ranges like `0-99,150`; without `@TIME` from the start, repeat for
+    /// several
+    #[arg(long = "lesion", value_parser = timed_lesion, global = true)]
+    lesions: Vec<(f64, Lesion)>,
+
+    /// Hold the firing rate of a population at a target in Hz as `POPULATION=HZ`, injecting into
+    /// it the current of a closed-loop proportional-integral controller; repeat for several
+    #[arg(long = "rate-clamp", value_parser = rate_clamp, global = true)]
+    rate_clamps: Vec<(String, f64)>,
+
+    /// Before the run, tune --calibrate on a trial network until its excitatory neurons fire at
+    /// this mean rate in Hz, then run a fresh network with the value found
+    #[arg(long, global = true)]
+    calibrate_rate: Option<f64>,
+
+    /// Parameter tuned by --calibrate-rate: thalamic-sigma, excitatory-weight-scale or
+    /// inhibitory-weight-scale
+    #[arg(long, default_value_t = SweepParameter::ThalamicSigma, global = true)]
+    calibrate: SweepParameter,
+
+    /// Milliseconds the rate is measured over after every adjustment of --calibrate-rate
+    #[arg(long, default_value_t = 500., global = true)]
+    calibration_window: f64,
+
+    /// Inject the currents read from this file or named pipe while the simulation runs, or from
+    /// stdin for `-`, one line per step with a current per neuron or per population separated by
+    /// commas or spaces
+    #[arg(long, global = true)]
+    stream_input: Option<PathBuf>,
+
+    /// Scale the input gain, thalamic noise or adaptation of a population, or `all` neurons, by a
+    /// neuromodulator level interpolated between times in milliseconds, as
+    /// `POPULATION:gain|noise|adaptation@TIME=LEVEL,TIME=LEVEL,...`; repeat for several
+    #[arg(long = "neuromodulator", value_parser = neuromodulator, global = true)]
+    neuromodulators: Vec<NeuromodulatorArg>,
+
+    /// Let every neuron adapt its excitability until it fires at this rate in Hz
+    #[arg(long, global = true)]
+    homeostasis: Option<f64>,
+
+    /// Intrinsic parameter adapted by --homeostasis
+    #[arg(long, value_enum, default_value_t = HomeostaticVariableArg::Gain, global = true)]
+    homeostatic_variable: HomeostaticVariableArg,
+
+    /// Take this fraction of every STDP potentiation from the other plastic synapses onto the
+    /// same neuron, turning on STDP outside the polychronization preset
+    #[arg(long, global = true)]
+    heterosynaptic: Option<f64>,
+
+    /// Let the synapses from inhibitory onto excitatory neurons learn by the symmetric rule of
+    /// Vogels and Sprekeler until the excitatory neurons fire at this rate in Hz
+    #[arg(long, global = true)]
+    inhibitory_stdp: Option<f64>,
+
+    /// Tsodyks–Markram short-term plasticity of the synapses from PRE onto POST neurons as
+    /// `PRE:POST=KIND`, with PRE and POST `excitatory` or `inhibitory`, or `all=KIND` for every
+    /// class, and KIND `depressing`, `facilitating` or `U:TAU_REC:TAU_FACIL` in milliseconds;
+    /// repeat for several classes, the later overriding the earlier
+    #[arg(long = "stp", value_parser = stp_class, value_delimiter = ',', global = true)]
+    stp: Vec<StpArg>,
+
+    /// Couple the neuron pairs of this `a,b,conductance` CSV file by gap junctions
+    #[arg(long, global = true)]
+    gap_junctions: Option<PathBuf>,
+
+    /// Couple every pair of inhibitory neurons by a gap junction with this probability
+    #[arg(long, global = true)]
+    inhibitory_gap_junctions: Option<f64>,
+
+    /// Conductance of the junctions of --inhibitory-gap-junctions
+    #[arg(long, default_value_t = 0.01, global = true)]
+    gap_conductance: f64,
+
+    /// Absolute refractory period of excitatory neurons after each spike in milliseconds
+    #[arg(long, default_value_t = 0., global = true)]
+    refractory_excitatory: f64,
+
+    /// Absolute refractory period of inhibitory neurons after each spike in milliseconds
+    #[arg(long, default_value_t = 0., global = true)]
+    refractory_inhibitory: f64,
+
+    /// Add a voltage-dependent NMDA component to excitatory synapses
+    #[arg(long, global = true)]
+    nmda: bool,
+
+    /// Replace the white thalamic noise by an Ornstein–Uhlenbeck process with this correlation
+    /// time in milliseconds
+    #[arg(long, global = true)]
+    noise_tau: Option<f64>,
+
+    /// Mean of the Ornstein–Uhlenbeck thalamic noise relative to the paper's noise amplitude
+    #[arg(long, default_value_t = 0., global = true)]
+    noise_mean: f64,
+
+    /// Standard deviation of the Ornstein–Uhlenbeck thalamic noise relative to the paper's
+    #[arg(long, default_value_t = 1., global = true)]
+    noise_sigma: f64,
+
+    /// Drive the network by Poisson spike sources firing at this rate in Hz instead of
+    /// Gaussian thalamic noise
+    #[arg(long, global = true)]
+    poisson_rate: Option<f64>,
+
+    /// Number of Poisson sources, each connecting to a neuron with probability 0.1
+    #[arg(long, default_value_t = 100, global = true)]
+    poisson_sources: usize,
+
+    /// Weight of the Poisson source synapses
+    #[arg(long, default_value_t = 5.0, global = true)]
+    poisson_weight: f64,
+
+    /// Inject a pulse train of this amplitude into all neurons
+    #[arg(long, global = true)]
+    pulse_amplitude: Option<f64>,
+
+    /// Period of the injected pulse train in milliseconds
+    #[arg(long, default_value_t = 100.0, global = true)]
+    pulse_period: f64,
+
+    /// Width of each injected pulse in milliseconds
+    #[arg(long, default_value_t = 5.0, global = true)]
+    pulse_width: f64,
+
+    /// Seed for the random number generator, for reproducible runs
+    #[arg(long, global = true)]
+    seed: Option<u64>,
+
+    /// Run this many independent realizations in parallel, seeded consecutively from --seed,
+    /// and print the mean ± std of their rates, synchrony and spectra instead of a single run;
+    /// goes before any command, since `choice` has its own
+    #[arg(long)]
+    trials: Option<usize>,
+
+    /// Directory each trial's spikes and spectrum and the table of all trials are written to
+    #[arg(long, default_value = "trials", global = true, help_heading = OUTPUT)]
+    trials_out: PathBuf,
+
+    /// Write all files of the run, those of the output flags with relative paths, into a new
+    /// directory under this one, named after the start time and seed, together with a
+    /// `run.json` of the parameters, seed, version and timing
+    #[arg(long, default_value = "runs", global = true)]
+    run_dir: PathBuf,
+
+    /// Write the files of the run where their paths point instead of into a directory of its
+    /// own, overwriting those of earlier runs
+    #[arg(long, global = true)]
+    no_run_dir: bool,
+
+    /// Also write the spikes as `time,neuron_id` CSV to this file
+    #[arg(long, global = true, help_heading = OUTPUT)]
+    spikes_out: Option<PathBuf>,
+
+    /// Image format of every plot, replacing the extension of its file; otherwise each plot is
+    /// an SVG if its file ends in .svg and a PNG else
+    #[arg(long, value_enum, global = true)]
+    plot_format: Option<PlotFormatArg>,
+
+    /// Stream the spikes of every step as JSON lines to this file while the simulation runs, or
+    /// to stdout for `-`, which moves all other output to stderr
+    #[arg(long, global = true, help_heading = OUTPUT)]
+    stream_spikes: Option<PathBuf>,
+
+    /// Record the membrane potential of these neurons every step, e.g. `0,5,100`
+    #[arg(long, value_delimiter = ',', global = true)]
+    record_v: Vec<usize>,
+
+    /// File the recorded membrane potentials are written to as CSV
+    #[arg(long, default_value = "v_traces.csv", global = true, help_heading = OUTPUT)]
+    v_out: PathBuf,
+
+    /// Record the thalamic input onto these neurons every step, e.g. `0,5,100`, and write its
+    /// average before their spikes
+    #[arg(long, value_delimiter = ',', global = true)]
+    record_input: Vec<usize>,
+
+    /// Milliseconds before each spike the spike-triggered average spans
+    #[arg(long, default_value_t = 50.0, global = true)]
+    sta_window: f64,
+
+    /// File the spike-triggered averages are written to as CSV
+    #[arg(long, default_value = "sta.csv", global = true, help_heading = OUTPUT)]
+    sta_out: PathBuf,
+
+    /// Render the spike-triggered averages to this PNG
+    #[arg(long, global = true, help_heading = OUTPUT)]
+    sta_plot: Option<PathBuf>,
+
+    /// Write a proxy of the local field potential, the summed absolute synaptic currents into the
+    /// excitatory neurons, as a `time,lfp` CSV to this file
+    #[arg(long, global = true, help_heading = OUTPUT)]
+    lfp_out: Option<PathBuf>,
+
+    /// Render the local field potential proxy to this PNG
+    #[arg(long, global = true, help_heading = OUTPUT)]
+    lfp_plot: Option<PathBuf>,
+
+    /// Print the power in the delta to gamma bands of the population rate of all neurons or the
+    /// local field potential proxy
+    #[arg(long, value_enum, global = true)]
+    spectrum: Option<SpectrumSignalArg>,
+
+    /// Milliseconds of the overlapping segments averaged by the Welch power spectrum, setting
+    /// its frequency resolution
+    #[arg(long, default_value_t = 500.0, global = true)]
+    spectrum_segment: f64,
+
+    /// Write the power spectrum as a `frequency,power` CSV to this file
+    #[arg(long, global = true, help_heading = OUTPUT)]
+    spectrum_out: Option<PathBuf>,
+
+    /// Render the power spectrum to this PNG
+    #[arg(long, global = true, help_heading = OUTPUT)]
+    spectrum_plot: Option<PathBuf>,
+
+    /// Bin width in milliseconds of the population rates in the summary table
+    #[arg(long, default_value_t = 10.0, global = true)]
+    rate_bin: f64,
+
+    /// Print the spike-count correlation, Kuramoto order and Golomb–Rinzel index of the
+    /// membrane potentials of every population
+    #[arg(long, global = true)]
+    synchrony: bool,
+
+    /// Print the time spent drawing noise, in the synapses, the other input, the membrane
+    /// integration, plasticity and recording, over all steps and per step
+    #[arg(long, global = true)]
+    profile: bool,
+
+    /// Bin width in milliseconds of the spike counts and phase samples of the synchrony
+    /// measures
+    #[arg(long, default_value_t = 10.0, global = true)]
+    synchrony_bin: f64,
+
+    /// Write the rate of every population in spikes per ms per neuron as a `time,<population>...`
+    /// CSV time series to this file
+    #[arg(long, global = true, help_heading = OUTPUT)]
+    population_rate_out: Option<PathBuf>,
+
+    /// Bin width in milliseconds of the population rate time series
+    #[arg(long, default_value_t = 1.0, global = true)]
+    population_rate_bin: f64,
+
+    /// Plot the population rate time series under the raster
+    #[arg(long, global = true)]
+    raster_rates: bool,
+
+    /// Render the raster as a scrolling animation to this GIF, or MP4 through ffmpeg
+    #[arg(long, global = true, help_heading = OUTPUT)]
+    animation: Option<PathBuf>,
+
+    /// Milliseconds of activity shown in every frame of the animation
+    #[arg(long, default_value_t = 200.0, global = true)]
+    animation_window: f64,
+
+    /// Milliseconds the animation scrolls per frame
+    #[arg(long, default_value_t = 5.0, global = true)]
+    animation_step: f64,
+
+    /// Frames per second of the animation
+    #[arg(long, default_value_t = 20, global = true)]
+    animation_fps: u32,
+
+    /// Render the rates on the sheet the neurons are placed on as an animated heatmap to this GIF,
+    /// or MP4 through ffmpeg, with the window, step and frame rate of the raster animation
+    #[arg(long, global = true, help_heading = OUTPUT)]
+    heatmap_animation: Option<PathBuf>,
+
+    /// Columns and rows of the heatmap animation
+    #[arg(long, value_delimiter = ',', num_args = 2, default_values_t = [20, 20], global = true)]
+    heatmap_grid: Vec<usize>,
+
+    /// Render the pooled inter-spike interval histogram to this PNG
+    #[arg(long, global = true, help_heading = OUTPUT)]
+    isi_plot: Option<PathBuf>,
+
+    /// Bin width in milliseconds of the inter-spike interval histogram
+    #[arg(long, default_value_t = 2.0, global = true)]
+    isi_bin: f64,
+
+    /// Render the peri-stimulus time histogram around the stimulus onsets to this PNG
+    #[arg(long, global = true, help_heading = OUTPUT)]
+    psth_plot: Option<PathBuf>,
+
+    /// PSTH window before and after each onset in milliseconds
+    #[arg(long, value_delimiter = ',', num_args = 1..=2, default_values_t = [20.0, 80.0], global = true)]
+    psth_window: Vec<f64>,
+
+    /// Bin width of the PSTH in milliseconds
+    #[arg(long, default_value_t = 2.0, global = true)]
+    psth_bin: f64,
+
+    /// Cross-correlogram of the spikes of B around those of A as `A:B`, where each is a neuron
+    /// index or a population name, e.g. `0:1` or `excitatory:inhibitory`; repeat for several
+    #[arg(long = "correlogram", value_parser = correlogram_pair, global = true)]
+    correlograms: Vec<(String, String)>,
+
+    /// Correlogram lags from minus to plus this many milliseconds
+    #[arg(long, default_value_t = 50.0, global = true)]
+    correlogram_window: f64,
+
+    /// Bin width of the correlograms in milliseconds
+    #[arg(long, default_value_t = 1.0, global = true)]
+    correlogram_bin: f64,
+
+    /// File the correlograms are written to as CSV
+    #[arg(long, default_value = "correlograms.csv", global = true, help_heading = OUTPUT)]
+    correlogram_out: PathBuf,
+
+    /// Render the correlograms to this PNG
+    #[arg(long, global = true, help_heading = OUTPUT)]
+    correlogram_plot: Option<PathBuf>,
+
+    /// Compare the spikes of every neuron with those of another run written by --spikes-out, by
+    /// the mean Victor–Purpura and van Rossum distances of each population
+    #[arg(long, global = true)]
+    compare_spikes: Option<PathBuf>,
+
+    /// Cost per millisecond of moving a spike in the Victor–Purpura distance
+    #[arg(long, default_value_t = 0.1, global = true)]
+    vp_cost: f64,
+
+    /// Time constant of the van Rossum kernel in milliseconds
+    #[arg(long, default_value_t = 10.0, global = true)]
+    van_rossum_tau: f64,
+
+    /// Mine the polychronous groups from the spikes and print the most frequent ones
+    #[arg(long, global = true)]
+    polychronous_groups: bool,
+
+    /// Continue the run saved in this checkpoint instead of building a new network
+    #[arg(long, global = true)]
+    resume: Option<PathBuf>,
+
+    /// Save a checkpoint of the world to this file at the end of the run
+    #[arg(long, global = true, help_heading = OUTPUT)]
+    checkpoint_out: Option<PathBuf>,
+
+    /// Write the constructed network to this NeuroML file, with a LEMS simulation of the run
+    /// next to it as `LEMS_<name>.xml`
+    #[arg(long, global = true, help_heading = OUTPUT)]
+    neuroml_out: Option<PathBuf>,
+
+    /// Show the progress, population rates and activity of the run in the terminal instead of
+    /// the progress bar; `q` stops the run early
+    #[cfg(feature = "tui")]
+    #[arg(long, global = true)]
+    tui: bool,
+
+    /// Show a live raster window refreshed every this many steps
+    #[cfg(feature = "live")]
+    #[arg(long, global = true)]
+    live: Option<usize>,
+
+    /// Run the network in a window with sliders for the population sizes, noise and weight
+    /// scales, until it is closed, instead of for --milliseconds
+    #[cfg(feature = "gui")]
+    #[arg(long, global = true)]
+    gui: bool,
+
+    /// Replace the parameters of individual neurons by the rows of this CSV or JSON file with the
+    /// fields neuron, a, b, c, d and optionally synapse_type
+    #[arg(long, global = true)]
+    neuron_parameters: Option<PathBuf>,
+
+    /// Use the synapses of this `pre,post,weight,delay` CSV file written with `--weights-out`
+    /// instead of the drawn ones, keeping the neurons
+    #[arg(long, global = true)]
+    weights: Option<PathBuf>,
+
+    /// Use a square matrix of signed weights from this NumPy `.npy` or CSV file as the synapses,
+    /// with the weight from neuron `pre` onto `post` in row `pre` and column `post`, 0 for none,
+    /// and delays of 1 ms
+    #[arg(long, conflicts_with = "weights", global = true)]
+    weight_matrix: Option<PathBuf>,
+
+    /// Write every synapse of the network as built to this `pre,post,weight,delay` CSV file,
+    /// with delays in milliseconds
+    #[arg(long, global = true, help_heading = OUTPUT)]
+    weights_out: Option<PathBuf>,
+
+    /// Write the network graph to this GraphML file, or DOT file for a `.dot` or `.gv` extension,
+    /// with the population, synapse type, model and parameters of every neuron
+    #[arg(long, global = true, help_heading = OUTPUT)]
+    graph_out: Option<PathBuf>,
+
+    /// Leave the synapses weaker than this in magnitude out of the network graph
+    #[arg(long, default_value_t = 0., global = true)]
+    graph_threshold: f64,
+
+    /// Simulate the network of this SONATA circuit config instead of drawing one
+    #[cfg(feature = "sonata")]
+    #[arg(long, global = true)]
+    sonata: Option<PathBuf>,
+
+    /// Write the spikes as a 2×K array of times over neurons, the recorded membrane potentials
+    /// and the synapses as NumPy arrays to this `.npz` archive, or to `.npy` files in this
+    /// directory
+    #[arg(long, global = true, help_heading = OUTPUT)]
+    npy_out: Option<PathBuf>,
+
+    /// Write spikes, recorded membrane potentials and weights to this HDF5 file
+    #[cfg(feature = "hdf5")]
+    #[arg(long, global = true, help_heading = OUTPUT)]
+    hdf5_out: Option<PathBuf>,
 }
 
-fn main() -> Result<(), Box<dyn std::error::Error>> {
-    let args = Args::parse();
-    let total_neurons = args.excitatory + args.inhibitory;
+#[derive(Subcommand, Debug)]
+enum Command {
+    /// Run the network from the other flags and write the reports and data they ask for, but no
+    /// raster, ISI histogram or animation, which `plot` can draw from the spikes saved with
+    /// --spikes-out
+    Simulate,
+    /// Report on spikes saved with --spikes-out without simulating, with the rates, synchrony,
+    /// spectrum, distances, correlograms and population rates the other flags ask for
+    Analyze {
+        /// Spikes written with --spikes-out
+        spikes: PathBuf,
+    },
+    /// Draw the raster of spikes saved with --spikes-out without simulating, with the rates, ISI
+    /// histogram and animation the other flags ask for
+    Plot {
+        /// Spikes written with --spikes-out
+        spikes: PathBuf,
+
+        /// Where to write the raster
+        #[arg(long, default_value = "spikes.png", help_heading = OUTPUT)]
+        raster: PathBuf,
+    },
+    /// Serve the simulator over gRPC, building the networks requested by clients from the other
+    /// flags
+    #[cfg(feature = "grpc")]
+    Serve {
+        /// Address to listen on
+        #[arg(long, default_value = "127.0.0.1:50051")]
+        address: SocketAddr,
+    },
+    /// Run the network for every combination of parameter values in parallel and write the
+    /// population rates of each point as CSV; with --resume every point continues the saved run
+    Sweep(SweepArgs),
+    /// Simulate a single neuron under an injected current, without noise or synapses, for
+    /// --milliseconds in steps of --dt and plot v(t) and u(t)
+    Clamp(ClampArgs),
+    /// Measure the steady-state firing rate of a single neuron under constant currents, each for
+    /// --milliseconds in steps of --dt, and plot the f–I curve
+    Fi(FiArgs),
+    /// Classify the firing of a single neuron under a constant current, each for --milliseconds in
+    /// steps of --dt, along one of its parameters and plot the inter-spike intervals
+    Bifurcation(BifurcationArgs),
+    /// Train a network of two cue and two action populations with --excitatory / 4 neurons each
+    /// on a two-alternative choice task by dopamine-modulated STDP, printing the share of correct
+    /// choices as it learns
+    Choice(ChoiceArgs),
+    /// Measure the short-term memory capacity of the network as a liquid state machine: a random
+    /// input drives it, and ridge regression readouts of its filtered spike traces recall the
+    /// input of earlier samples
+    Memory(MemoryArgs),
+    /// Classify MNIST digits: every image drives the network as input currents proportional to
+    /// its pixels, and a ridge regression readout of the filtered spike traces is trained on the
+    /// training images and scored on the test images
+    Mnist(MnistArgs),
+}
+
+/// Flags of the `sweep` command.
+#[derive(clap::Args, Debug)]
+struct SweepArgs {
+    /// Values of one parameter as `name=start:stop:count` or `name=v1,v2,...`; repeat for a
+    /// grid over several parameters
+    #[arg(long = "axis", required = true)]
+    axes: Vec<SweepAxis>,
+
+    /// Where to write the table
+    #[arg(long, default_value = "sweep.csv", help_heading = OUTPUT)]
+    out: PathBuf,
+
+    /// Run the network this many milliseconds once before the points, which all continue
+    /// from its state
+    #[arg(long, default_value_t = 0.)]
+    warm_up: f64,
+
+    /// Save a checkpoint of the state the points start from to this file, for --resume in
+    /// later sweeps
+    #[arg(long, help_heading = OUTPUT)]
+    warm_up_out: Option<PathBuf>,
+}
+
+/// Flags of the `clamp` command.
+#[derive(clap::Args, Debug)]
+struct ClampArgs {
+    #[command(flatten)]
+    neuron: NeuronArgs,
+
+    /// Current segment `step:AMPLITUDE`, `ramp:FROM:TO`, `pulses:AMPLITUDE:PERIOD:WIDTH` or
+    /// `sine:AMPLITUDE:FREQUENCY` with an optional window `@START..END` in ms; repeat to add
+    /// segments
+    #[arg(long = "current", allow_hyphen_values = true)]
+    segments: Vec<Segment>,
+
+    /// CSV file with `time` and `current` columns, interpolated linearly and added to the
+    /// segments
+    #[arg(long)]
+    current_file: Option<PathBuf>,
+
+    /// Where to write the plot
+    #[arg(long, default_value = "clamp.png", help_heading = OUTPUT)]
+    plot: PathBuf,
+
+    /// Also plot the trajectory in the (v, u) plane over the nullclines of the neuron
+    #[arg(long, help_heading = OUTPUT)]
+    phase_plane: Option<PathBuf>,
+
+    /// Also write the trace as CSV
+    #[arg(long, help_heading = OUTPUT)]
+    out: Option<PathBuf>,
+}
+
+/// Flags of the `fi` command.
+#[derive(clap::Args, Debug)]
+struct FiArgs {
+    #[command(flatten)]
+    neuron: NeuronArgs,
+
+    /// Currents as `start:stop:count` or `i1,i2,...`, in the units of the network input
+    #[arg(long, default_value = "0:20:41", allow_hyphen_values = true)]
+    currents: Values,
+
+    /// Milliseconds at the start of each run left out of the rate
+    #[arg(long, default_value_t = 200.)]
+    transient: f64,
+
+    /// Where to write the plot
+    #[arg(long, default_value = "fi.png", help_heading = OUTPUT)]
+    plot: PathBuf,
+
+    /// Also write the curve as CSV
+    #[arg(long, help_heading = OUTPUT)]
+    out: Option<PathBuf>,
+}
+
+/// Flags of the `bifurcation` command.
+#[derive(clap::Args, Debug)]
+struct BifurcationArgs {
+    #[command(flatten)]
+    neuron: NeuronArgs,
+
+    /// Parameter to vary: a, b, c, d or current
+    #[arg(long)]
+    parameter: BifurcationParameter,
+
+    /// Values of the parameter as `start:stop:count` or `v1,v2,...`
+    #[arg(long, allow_hyphen_values = true)]
+    values: Values,
+
+    /// Constant current unless it is the varied parameter
+    #[arg(long, default_value_t = 10., allow_negative_numbers = true)]
+    current: f64,
+
+    /// Milliseconds at the start of each run left out of the classification
+    #[arg(long, default_value_t = 200.)]
+    transient: f64,
+
+    /// Where to write the plot
+    #[arg(long, default_value = "bifurcation.png", help_heading = OUTPUT)]
+    plot: PathBuf,
+
+    /// Also write the patterns as CSV
+    #[arg(long, help_heading = OUTPUT)]
+    out: Option<PathBuf>,
+}
+
+/// Flags of the `choice` command.
+#[derive(clap::Args, Debug)]
+struct ChoiceArgs {
+    /// Number of trials
+    #[arg(long, default_value_t = 300)]
+    trials: usize,
+
+    /// Trials per printed share of correct choices
+    #[arg(long, default_value_t = 50)]
+    block: usize,
+
+    /// Also write every trial as CSV
+    #[arg(long, help_heading = OUTPUT)]
+    out: Option<PathBuf>,
+}
+
+/// Flags of the `memory` command.
+#[derive(clap::Args, Debug)]
+struct MemoryArgs {
+    /// Samples used to fit the readouts
+    #[arg(long, default_value_t = 2000)]
+    train: usize,
+
+    /// Held-out samples the readouts are scored on
+    #[arg(long, default_value_t = 500)]
+    test: usize,
+
+    /// Longest recall delay in samples
+    #[arg(long, default_value_t = 10)]
+    max_delay: usize,
+
+    /// Milliseconds every input sample is held
+    #[arg(long, default_value_t = 10.)]
+    sample_duration: f64,
+
+    /// Time constant in milliseconds of the filtered spike traces
+    #[arg(long, default_value_t = 30.)]
+    tau: f64,
+
+    /// Share of the neurons receiving the input
+    #[arg(long, default_value_t = 0.3)]
+    input_fraction: f64,
+
+    /// Current into those neurons per unit of input, which lies in [0, 1]
+    #[arg(long, default_value_t = 5.)]
+    input_gain: f64,
+
+    /// Penalty of the squared readout weights
+    #[arg(long, default_value_t = 1000.)]
+    ridge: f64,
+
+    /// Also write the score of every delay as CSV
+    #[arg(long, help_heading = OUTPUT)]
+    out: Option<PathBuf>,
+}
+
+/// Flags of the `mnist` command.
+#[derive(clap::Args, Debug)]
+struct MnistArgs {
+    /// IDX file of the training images, e.g. `train-images-idx3-ubyte`, uncompressed
+    #[arg(long)]
+    train_images: PathBuf,
+
+    /// IDX file of the training labels
+    #[arg(long)]
+    train_labels: PathBuf,
+
+    /// IDX file of the test images, e.g. `t10k-images-idx3-ubyte`
+    #[arg(long)]
+    test_images: PathBuf,
+
+    /// IDX file of the test labels
+    #[arg(long)]
+    test_labels: PathBuf,
+
+    /// Number of training images used
+    #[arg(long, default_value_t = 1000)]
+    train: usize,
+
+    /// Number of test images used
+    #[arg(long, default_value_t = 500)]
+    test: usize,
+
+    /// Milliseconds every image is presented, followed by as long without input
+    #[arg(long, default_value_t = 50.)]
+    presentation: f64,
+
+    /// Time constant in milliseconds of the filtered spike traces
+    #[arg(long, default_value_t = 30.)]
+    tau: f64,
+
+    /// Share of the neurons receiving each pixel
+    #[arg(long, default_value_t = 0.1)]
+    input_fraction: f64,
+
+    /// Current per unit of pixel intensity, which lies in [0, 1]
+    #[arg(long, default_value_t = 2.)]
+    input_gain: f64,
+
+    /// Penalty of the squared readout weights
+    #[arg(long, default_value_t = 100.)]
+    ridge: f64,
+}
+
+/// The single neuron of the `clamp`, `fi` and `bifurcation` commands.
+#[derive(clap::Args, Debug)]
+struct NeuronArgs {
+    /// Neuron type: RS, IB, CH, FS, LTS, TC or RZ
+    #[arg(long = "type", default_value = "RS")]
+    neuron_type: NeuronType,
+
+    /// Parameter a, replacing that of the type
+    #[arg(short)]
+    a: Option<f64>,
+
+    /// Parameter b, replacing that of the type
+    #[arg(short)]
+    b: Option<f64>,
+
+    /// Parameter c, replacing that of the type
+    #[arg(short, allow_negative_numbers = true)]
+    c: Option<f64>,
+
+    /// Parameter d, replacing that of the type
+    #[arg(short, allow_negative_numbers = true)]
+    d: Option<f64>,
+
+    /// Initial membrane potential in mV with u at b v, e.g. -87 for the rebound burst of a TC
+    /// neuron
+    #[arg(long, allow_negative_numbers = true)]
+    v0: Option<f64>,
+}
+
+impl NeuronArgs {
+    fn neuron(&self) -> Neuron {
+        let defaults = self.neuron_type.params();
+        let parameters = NeuronParameters {
+            a: self.a.unwrap_or(defaults.a),
+            b: self.b.unwrap_or(defaults.b),
+            c: self.c.unwrap_or(defaults.c),
+            d: self.d.unwrap_or(defaults.d),
+        };
+        let v0 = self.v0.unwrap_or(self.neuron_type.initial_potential());
+        Neuron::new(parameters, self.neuron_type.into()).with_potential(v0)
+    }
+}
+
+#[derive(Clone, Copy, Debug, ValueEnum)]
+enum ModelArg {
+    Izhikevich,
+    /// Adaptive exponential integrate-and-fire
+    Adex,
+    /// Leaky integrate-and-fire
+    Lif,
+    /// Hodgkin–Huxley, much slower as it integrates in steps of 0.01 ms
+    HodgkinHuxley,
+    /// Dimensional simple model of Izhikevich (2007)
+    Izhikevich2007,
+    /// Izhikevich soma with a passive dendrite receiving the input
+    TwoCompartment,
+}
 
-    let mut world_state = WorldState::new(args.excitatory, args.inhibitory);
-    for t in 0..args.milliseconds {
-        if t % 100 == 0 {
-            println!("Time step: {}", t);
+#[derive(Clone, Copy, Debug, ValueEnum)]
+enum Izhikevich2007PresetArg {
+    Pyramidal,
+    IntrinsicallyBursting,
+    Chattering,
+    Basket,
+}
+
+impl From<Izhikevich2007PresetArg> for Izhikevich2007Preset {
+    fn from(preset: Izhikevich2007PresetArg) -> Self {
+        match preset {
+            Izhikevich2007PresetArg::Pyramidal => Izhikevich2007Preset::Pyramidal,
+            Izhikevich2007PresetArg::IntrinsicallyBursting => {
+                Izhikevich2007Preset::IntrinsicallyBursting
+            }
+            Izhikevich2007PresetArg::Chattering => Izhikevich2007Preset::Chattering,
+            Izhikevich2007PresetArg::Basket => Izhikevich2007Preset::Basket,
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, ValueEnum)]
+enum AdExPresetArg {
+    BretteGerstner,
+    Tonic,
+    Adapting,
+    InitialBursting,
+    Bursting,
+}
+
+impl From<AdExPresetArg> for AdExPreset {
+    fn from(preset: AdExPresetArg) -> Self {
+        match preset {
+            AdExPresetArg::BretteGerstner => AdExPreset::BretteGerstner,
+            AdExPresetArg::Tonic => AdExPreset::Tonic,
+            AdExPresetArg::Adapting => AdExPreset::Adapting,
+            AdExPresetArg::InitialBursting => AdExPreset::InitialBursting,
+            AdExPresetArg::Bursting => AdExPreset::Bursting,
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, ValueEnum)]
+enum PresetArg {
+    /// Randomly connected network of the 2003 paper
+    Paper,
+    /// STDP network with 1–20 ms delays of Izhikevich (2006)
+    Polychronization,
+}
+
+#[derive(Clone, Copy, Debug, ValueEnum)]
+enum SpectrumSignalArg {
+    /// Population rate of all neurons in Hz, in bins of one time step
+    Rate,
+    /// Local field potential proxy, see --lfp-out
+    Lfp,
+}
+
+#[derive(Clone, Copy, Debug, ValueEnum)]
+enum TopologyArg {
+    /// All pairs connected independently, as in the paper
+    Random,
+    /// Watts–Strogatz ring lattice with rewiring
+    SmallWorld,
+    /// Barabási–Albert preferential attachment
+    ScaleFree,
+}
+
+#[derive(Clone, Copy, Debug, ValueEnum)]
+enum SynapseModelArg {
+    /// Delta current pulses
+    Current,
+    /// Exponentially decaying conductances with AMPA/GABA_A kinetics
+    Conductance,
+    /// Currents rising and decaying exponentially with the --ampa-* and --gaba-* time constants
+    Exponential,
+}
+
+impl From<SynapseModelArg> for SynapseModel {
+    fn from(arg: SynapseModelArg) -> Self {
+        match arg {
+            SynapseModelArg::Current => SynapseModel::Current,
+            SynapseModelArg::Conductance => SynapseModel::Conductance(Default::default()),
+            SynapseModelArg::Exponential => SynapseModel::Exponential(Default::default()),
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, ValueEnum)]
+enum HomeostaticVariableArg {
+    /// Factor on all input current
+    Gain,
+    /// After-spike recovery increment
+    D,
+}
+
+#[derive(Clone, Copy, Debug, ValueEnum)]
+enum IntegratorArg {
+    /// Forward Euler in two half steps, as in the paper
+    Euler,
+    /// Explicit midpoint method
+    Rk2,
+    /// Classic fourth order Runge–Kutta
+    Rk4,
+    /// Third order Runge–Kutta in sub-steps within --integrator-tolerance
+    Adaptive,
+}
+
+#[derive(Clone, Copy, Debug, ValueEnum)]
+enum PlotFormatArg {
+    /// Bitmap
+    Png,
+    /// Vector graphics, for embedding in papers
+    Svg,
+}
+
+impl From<PlotFormatArg> for PlotFormat {
+    fn from(arg: PlotFormatArg) -> Self {
+        match arg {
+            PlotFormatArg::Png => PlotFormat::Png,
+            PlotFormatArg::Svg => PlotFormat::Svg,
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, ValueEnum)]
+enum BackendArg {
+    /// Double precision on all CPU cores
+    Cpu,
+    /// Single precision with explicit SIMD
+    Simd,
+    /// Single precision wgpu compute shader
+    Gpu,
+    /// Double precision with the neurons split across MPI processes
+    Mpi,
+}
+
+impl From<BackendArg> for Backend {
+    fn from(arg: BackendArg) -> Self {
+        match arg {
+            BackendArg::Cpu => Backend::Cpu,
+            BackendArg::Simd => Backend::Simd,
+            BackendArg::Gpu => Backend::Gpu,
+            BackendArg::Mpi => Backend::Mpi,
         }
-        world_state.step();
     }
+}
+
+/// Every argument of the run, including defaults, as `(name, value)` pairs.
+/// Parses `TYPE:FRACTION` of a neuron type mixture.
+/// Values of the `fi` and `bifurcation` commands, as a single argument holding all of them.
+#[derive(Clone, Debug)]
+struct Values(Vec<f64>);
 
-    let mut spikes = Vec::new();
-    for (time_step, step_spikes) in world_state.action_potentials.iter().enumerate() {
-        for (neuron_idx, &spiked) in step_spikes.iter().enumerate() {
-            if spiked {
-                spikes.push((time_step as i32, neuron_idx as i32));
+impl FromStr for Values {
+    type Err = String;
+
+    /// Parses `start:stop:count` evenly spaced values with both ends included, or `i1,i2,...`.
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let number = |value: &str| {
+            value
+                .trim()
+                .parse::<f64>()
+                .map_err(|err| format!("invalid value `{value}`: {err}"))
+        };
+        match s.split(':').collect::<Vec<_>>().as_slice() {
+            [start, stop, count] => {
+                let count: usize = count
+                    .trim()
+                    .parse()
+                    .map_err(|err| format!("invalid count `{count}`: {err}"))?;
+                let (start, stop) = (number(start)?, number(stop)?);
+                let step = (stop - start) / count.saturating_sub(1).max(1) as f64;
+                Ok(Values(
+                    (0..count).map(|k| start + k as f64 * step).collect(),
+                ))
             }
+            [_] => s
+                .split(',')
+                .map(number)
+                .collect::<Result<_, _>>()
+                .map(Values),
+            _ => Err(format!(
+                "expected `start:stop:count` or `i1,i2,...`, got `{s}`"
+            )),
+        }
+    }
+}
+
+fn correlogram_pair(s: &str) -> Result<(String, String), String> {
+    let (reference, target) = s
+        .split_once(':')
+        .ok_or_else(|| format!("expected `A:B`, got `{s}`"))?;
+    Ok((reference.to_string(), target.to_string()))
+}
+
+fn timed_lesion(s: &str) -> Result<(f64, Lesion), String> {
+    match s.rsplit_once('@') {
+        Some((lesion, time)) => {
+            let time: f64 = time
+                .parse()
+                .map_err(|err| format!("invalid time `{time}`: {err}"))?;
+            Ok((time, lesion.parse()?))
         }
+        None => Ok((0., s.parse()?)),
+    }
+}
+
+fn weight_distribution(s: &str) -> Result<(String, WeightDistribution), String> {
+    let (population, distribution) = s
+        .split_once('=')
+        .ok_or_else(|| format!("expected `POPULATION=DIST`, got `{s}`"))?;
+    match population {
+        "excitatory" | "inhibitory" => Ok((population.to_string(), distribution.parse()?)),
+        _ => Err(format!(
+            "unknown population `{population}`, expected excitatory or inhibitory"
+        )),
+    }
+}
+
+fn rate_clamp(s: &str) -> Result<(String, f64), String> {
+    let (population, rate) = s
+        .split_once('=')
+        .ok_or_else(|| format!("expected `POPULATION=HZ`, got `{s}`"))?;
+    let rate: f64 = rate
+        .parse()
+        .map_err(|err| format!("invalid rate `{rate}`: {err}"))?;
+    if !(rate >= 0. && rate.is_finite()) {
+        return Err(format!("rate `{rate}` must be non-negative"));
     }
+    Ok((population.to_string(), rate))
+}
 
-    let root = BitMapBackend::new("spikes.png", (800, 1200)).into_drawing_area();
-    root.fill(&WHITE)?;
+/// Population, variable and `(time, level)` schedule of `--neuromodulator`.
+type NeuromodulatorArg = (String, ModulatedVariable, Vec<(f64, f64)>);
 
-    let mut chart = ChartBuilder::on(&root)
-        .margin(20)
-        .x_label_area_size(35)
-        .y_label_area_size(35)
-        .build_cartesian_2d(0..args.milliseconds as i32, 0..total_neurons as i32)?;
+fn neuromodulator(s: &str) -> Result<NeuromodulatorArg, String> {
+    let expected = || format!("expected `POPULATION:VARIABLE@TIME=LEVEL,...`, got `{s}`");
+    let (target, schedule) = s.split_once('@').ok_or_else(expected)?;
+    let (population, variable) = target.split_once(':').ok_or_else(expected)?;
+    let schedule = schedule
+        .split(',')
+        .map(|point| {
+            let (time, level) = point.split_once('=').ok_or_else(expected)?;
+            let time: f64 = time
+                .parse()
+                .map_err(|err| format!("invalid time `{time}`: {err}"))?;
+            let level: f64 = level
+                .parse()
+                .map_err(|err| format!("invalid level `{level}`: {err}"))?;
+            Ok((time, level))
+        })
+        .collect::<Result<Vec<_>, String>>()?;
+    Ok((population.to_string(), variable.parse()?, schedule))
+}
 
-    chart
-        .configure_mesh()
-        .disable_mesh()
-        .x_desc("Time (ms)")
-        .y_desc("Neuron Index")
-        .draw()?;
+/// Connection classes and parameters of `--stp`.
+type StpArg = (Vec<(SynapseType, SynapseType)>, StpParameters);
 
-    chart.draw_series(
-        spikes
-            .iter()
-            .map(|&(x, y)| Circle::new((x, y), 1, RGBAColor(0, 0, 0, 0.3).filled())),
-    )?;
+fn stp_class(s: &str) -> Result<StpArg, String> {
+    let (class, params) = s
+        .split_once('=')
+        .ok_or_else(|| format!("expected `PRE:POST=KIND` or `all=KIND`, got `{s}`"))?;
+    let classes = match class.split_once(':') {
+        Some((pre, post)) => vec![(pre.parse()?, post.parse()?)],
+        None if class == "all" => {
+            let types = [SynapseType::Excitatory, SynapseType::Inhibitory];
+            types
+                .iter()
+                .flat_map(|pre| types.iter().map(|post| (pre.clone(), post.clone())))
+                .collect()
+        }
+        None => return Err(format!("expected `PRE:POST` or `all`, got `{class}`")),
+    };
+    Ok((classes, params.parse()?))
+}
 
-    root.present()?;
-    Ok(())
+fn neuron_fraction(s: &str) -> Result<(NeuronType, f64), String> {
+    let (neuron_type, fraction) = s
+        .split_once(':')
+        .ok_or_else(|| format!("expected `TYPE:FRACTION`, got `{s}`"))?;
+    let fraction: f64 = fraction
+        .parse()
+        .map_err(|err| format!("invalid fraction `{fraction}`: {err}"))?;
+    if !(fraction >= 0. && fraction.is_finite()) {
+        return Err(format!("fraction `{fraction}` must be non-negative"));
+    }
+    Ok((neuron_type.parse()?, fraction))
+}
+
+/// The command line with every flag under the [`OUTPUT`] heading placing its relative paths,
+/// given one or by default, into `dir`, and `-` for stdout where it stands.
+fn command_in(dir: &Path) -> clap::Command {
+    let place = |arg: Arg| {
+        if arg.get_help_heading() != Some(OUTPUT) {
+            return arg;
+        }
+        let dir = dir.to_path_buf();
+        arg.value_parser(move |path: &str| {
+            Ok::<_, Infallible>(match path {
+                "-" => PathBuf::from(path),
+                path => dir.join(path),
+            })
+        })
+    };
+    let command = Args::command().mut_args(place);
+    let names: Vec<String> = command
+        .get_subcommands()
+        .map(|subcommand| subcommand.get_name().to_string())
+        .collect();
+    names.iter().fold(command, |command, name| {
+        command.mut_subcommand(name, |subcommand| subcommand.mut_args(place))
+    })
+}
+
+/// `path` with the extension of `format`, if given.
+fn plot_path(path: impl AsRef<Path>, format: Option<PlotFormatArg>) -> PathBuf {
+    match format {
+        Some(format) => path
+            .as_ref()
+            .with_extension(PlotFormat::from(format).extension()),
+        None => path.as_ref().to_path_buf(),
+    }
+}
+
+/// Reports the rates, synchrony, spectrum and distances of `trains` and writes the correlograms
+/// and population rates the flags ask for, whether simulated or loaded. `populations` holds "all"
+/// followed by the populations of the network, `voltages` the membrane potential synchrony and
+/// `lfp` the local field potential proxy if recorded.
+fn analyze_spikes(
+    args: &Args,
+    trains: &SpikeTrains,
+    populations: &[(String, Range<usize>)],
+    voltages: &[VoltageSynchrony],
+    lfp: Option<&[f64]>,
+    dt: f64,
+    report: &mut dyn Write,
+) -> Result<(), Box<dyn std::error::Error>> {
+    write!(
+        report,
+        "{}",
+        RateSummary::new(trains, populations, args.rate_bin)
+    )?;
+    if args.synchrony {
+        let summary = SynchronySummary::new(trains, populations, voltages, args.synchrony_bin);
+        write!(report, "{summary}")?;
+    }
+    if let Some(signal) = args.spectrum {
+        let (name, samples) = match signal {
+            SpectrumSignalArg::Rate => (
+                "population rate",
+                trains.population_rate(0..trains.neurons(), dt),
+            ),
+            SpectrumSignalArg::Lfp => (
+                "local field potential proxy",
+                lfp.ok_or("the local field potential proxy was not recorded")?
+                    .to_vec(),
+            ),
+        };
+        let spectrum = PowerSpectrum::welch(&samples, dt, args.spectrum_segment);
+        writeln!(
+            report,
+            "Power spectrum of the {name} at {} Hz resolution",
+            spectrum.resolution()
+        )?;
+        write!(report, "{spectrum}")?;
+        if let Some(path) = &args.spectrum_out {
+            spectrum.write_csv(BufWriter::new(File::create(path)?))?;
+        }
+        if let Some(path) = &args.spectrum_plot {
+            plot::power_spectrum(plot_path(path, args.plot_format), &spectrum)?;
+        }
+    }
+
+    if let Some(path) = &args.compare_spikes {
+        let other = SpikeTrains::read_csv(path)?;
+        writeln!(
+            report,
+            "{:<12} {:>16} {:>16}",
+            "population",
+            format!("vp {}/ms", args.vp_cost),
+            format!("vr {}ms", args.van_rossum_tau)
+        )?;
+        for (name, neurons) in populations {
+            let mean =
+                |distances: Vec<f64>| distances.iter().sum::<f64>() / neurons.len().max(1) as f64;
+            writeln!(
+                report,
+                "{:<12} {:>16.3} {:>16.3}",
+                name,
+                mean(trains.victor_purpura(&other, neurons.clone(), args.vp_cost)),
+                mean(trains.van_rossum(&other, neurons.clone(), args.van_rossum_tau))
+            )?;
+        }
+    }
+
+    if !args.correlograms.is_empty() {
+        let neurons = |name: &str| -> Result<Range<usize>, String> {
+            match name.parse::<usize>() {
+                Ok(idx) if idx < trains.neurons() => Ok(idx..idx + 1),
+                Ok(idx) => Err(format!("neuron {idx} is not in the network")),
+                Err(_) => populations
+                    .iter()
+                    .find(|(population, _)| population == name)
+                    .map(|(_, range)| range.clone())
+                    .ok_or_else(|| format!("no population or neuron `{name}`")),
+            }
+        };
+        let correlograms = args
+            .correlograms
+            .iter()
+            .map(|(reference, target)| {
+                let correlogram = trains.cross_correlogram(
+                    neurons(reference)?,
+                    neurons(target)?,
+                    args.correlogram_window,
+                    args.correlogram_bin,
+                );
+                Ok((format!("{reference}:{target}"), correlogram))
+            })
+            .collect::<Result<Vec<_>, String>>()?;
+        let writer = BufWriter::new(File::create(&args.correlogram_out)?);
+        output::write_correlograms_csv(writer, &correlograms)?;
+        if let Some(path) = &args.correlogram_plot {
+            plot::correlograms(plot_path(path, args.plot_format), &correlograms)?;
+        }
+    }
+
+    if let Some(path) = &args.population_rate_out {
+        let writer = BufWriter::new(File::create(path)?);
+        output::write_population_rates_csv(
+            writer,
+            trains,
+            &populations[1..],
+            args.population_rate_bin,
+        )?;
+    }
+    Ok(())
+}
+
+/// Draws the inter-spike interval histogram and raster animation the flags ask for and the
+/// raster to `raster`, whether simulated or loaded.
+fn spike_plots(
+    args: &Args,
+    trains: &SpikeTrains,
+    populations: &[(String, Range<usize>)],
+    raster: &Path,
+) -> Result<(), Box<dyn std::error::Error>> {
+    if let Some(path) = &args.isi_plot {
+        let histogram = trains.isi_histogram(0..trains.neurons(), args.isi_bin);
+        plot::histogram(
+            plot_path(path, args.plot_format),
+            &histogram,
+            "Inter-spike interval (ms)",
+        )?;
+    }
+    if let Some(path) = &args.animation {
+        plot::animation(
+            path,
+            trains,
+            populations,
+            args.animation_window,
+            args.animation_step,
+            args.animation_fps,
+        )?;
+    }
+    let rate_bin = args.raster_rates.then_some(args.population_rate_bin);
+    plot::raster(
+        plot_path(raster, args.plot_format),
+        trains,
+        populations,
+        rate_bin,
+    )?;
+    Ok(())
+}
+
+fn run_parameters(matches: &ArgMatches) -> Vec<(String, String)> {
+    Args::command()
+        .get_arguments()
+        .filter_map(|arg| {
+            let id = arg.get_id().as_str();
+            let values: Vec<String> = matches
+                .get_raw(id)?
+                .map(|value| value.to_string_lossy().into_owned())
+                .collect();
+            Some((id.to_string(), values.join(",")))
+        })
+        .collect()
+}
+
+/// Command line arguments equivalent to a TOML config file. Every key is the long name of a flag,
+/// e.g. `max_delay = 5`; tables like `[connectivity]` only group them.
+fn config_args(path: &Path) -> Result<Vec<OsString>, Box<dyn std::error::Error>> {
+    fn scalar(value: &toml::Value) -> Result<String, String> {
+        match value {
+            toml::Value::String(s) => Ok(s.clone()),
+            toml::Value::Integer(i) => Ok(i.to_string()),
+            toml::Value::Float(f) => Ok(f.to_string()),
+            value => Err(format!("unsupported config value `{value}`")),
+        }
+    }
+
+    fn push_args(table: &toml::Table, args: &mut Vec<OsString>) -> Result<(), String> {
+        for (key, value) in table {
+            let flag = format!("--{}", key.replace('_', "-"));
+            match value {
+                toml::Value::Table(table) => push_args(table, args)?,
+                toml::Value::Boolean(true) => args.push(flag.into()),
+                toml::Value::Boolean(false) => {}
+                toml::Value::Array(values) => {
+                    if !values.is_empty() {
+                        let values: Vec<String> =
+                            values.iter().map(scalar).collect::<Result<_, _>>()?;
+                        args.push(format!("{flag}={}", values.join(",")).into());
+                    }
+                }
+                value => args.push(format!("{flag}={}", scalar(value)?).into()),
+            }
+        }
+        Ok(())
+    }
+
+    let table: toml::Table = std::fs::read_to_string(path)?.parse()?;
+    let mut args = vec![];
+    push_args(&table, &mut args)?;
+    Ok(args)
+}
+
+/// Writes the network to `path` and a LEMS simulation of `duration` milliseconds beside it.
+fn write_neuroml(path: &Path, world: &WorldState, duration: f64) -> std::io::Result<()> {
+    neuroml::write_neuroml(BufWriter::new(File::create(path)?), world)?;
+    let name = path.file_name().unwrap_or_default().to_string_lossy();
+    let stem = path.file_stem().unwrap_or_default().to_string_lossy();
+    let lems = path.with_file_name(format!("LEMS_{stem}.xml"));
+    neuroml::write_lems_simulation(BufWriter::new(File::create(lems)?), world, &name, duration)
+}
+
+fn main() -> Result<(), Box<dyn std::error::Error>> {
+    let mut argv: Vec<OsString> = std::env::args_os().collect();
+    let mut matches = Args::command().get_matches_from(&argv);
+    if let Some(path) = matches.get_one::<PathBuf>("config") {
+        // the file's settings come first, so the command line overrides them
+        argv.splice(1..1, config_args(path)?);
+        matches = Args::command().get_matches_from(&argv);
+    }
+    let args = Args::from_arg_matches(&matches)?;
+    if args.no_run_dir {
+        return run(args, &matches, Path::new(""));
+    }
+
+    let started = SystemTime::now();
+    let clock = Instant::now();
+    // a drawn seed makes the run reproducible from its metadata
+    let seed = args.seed.unwrap_or_else(rand::random);
+    let unix_time = started.duration_since(UNIX_EPOCH)?.as_secs();
+    let name = format!("{}-seed{seed}", utc_timestamp(unix_time));
+    let dir = create_run_dir(&args.run_dir, &name)?;
+    let matches = command_in(&dir).get_matches_from(&argv);
+    let mut args = Args::from_arg_matches(&matches)?;
+    args.seed = Some(seed);
+    let outcome = run(args, &matches, &dir);
+
+    let mut artifacts: Vec<String> = fs::read_dir(&dir)?
+        .map(|entry| Ok(entry?.file_name().to_string_lossy().into_owned()))
+        .collect::<io::Result<_>>()?;
+    artifacts.sort();
+    let parameters: serde_json::Map<String, serde_json::Value> = run_parameters(&matches)
+        .into_iter()
+        .map(|(key, value)| (key, value.into()))
+        .collect();
+    let metadata = serde_json::json!({
+        "version": env!("CARGO_PKG_VERSION"),
+        "command_line": std::env::args().collect::<Vec<_>>(),
+        "parameters": parameters,
+        "seed": seed,
+        "started": unix_time,
+        "wall_time_s": clock.elapsed().as_secs_f64(),
+        "error": outcome.as_ref().err().map(|err| err.to_string()),
+        "artifacts": artifacts,
+    });
+    let writer = BufWriter::new(File::create(dir.join("run.json"))?);
+    serde_json::to_writer_pretty(writer, &metadata)?;
+    eprintln!("Wrote the run to {}", dir.display());
+    outcome
+}
+
+/// Tunes `--calibrate` on a network of `builder` until its excitatory neurons, or all without
+/// such a population, fire at `rate`.
+fn calibrate(
+    args: &Args,
+    builder: WorldStateBuilder,
+    rate: f64,
+) -> Result<Calibrated, Box<dyn std::error::Error>> {
+    let mut world = builder.build();
+    if args.calibrate == SweepParameter::ThalamicSigma && !world.thalamic_noise {
+        return Err("--calibrate thalamic-sigma needs thalamic noise".into());
+    }
+    let neurons = world
+        .populations()
+        .into_iter()
+        .find(|(name, _)| name == "excitatory")
+        .map_or(0..world.neurons.len(), |(_, range)| range);
+    let mut calibration = Calibration::new(neurons, rate, args.calibrate);
+    calibration.window = args.calibration_window;
+    let calibrated = calibration.run(&mut world);
+    if calibrated.converged {
+        eprintln!(
+            "Calibrated {} to {:.4} in {} iterations, at {:.2} Hz",
+            calibrated.parameter, calibrated.value, calibrated.iterations, calibrated.rate
+        );
+    } else {
+        eprintln!(
+            "Calibration of {} stopped at {:.4} after {} iterations, at {:.2} Hz instead of {rate} Hz",
+            calibrated.parameter, calibrated.value, calibrated.iterations, calibrated.rate
+        );
+    }
+    Ok(calibrated)
+}
+
+/// Creates the directory `name` under `parent`, with a counter appended if it exists already.
+fn create_run_dir(parent: &Path, name: &str) -> io::Result<PathBuf> {
+    fs::create_dir_all(parent)?;
+    for attempt in 0.. {
+        let dir = match attempt {
+            0 => parent.join(name),
+            n => parent.join(format!("{name}-{n}")),
+        };
+        match fs::create_dir(&dir) {
+            Err(err) if err.kind() == io::ErrorKind::AlreadyExists => continue,
+            created => return created.map(|()| dir),
+        }
+    }
+    unreachable!("the attempts never run out")
+}
+
+/// `YYYYMMDD-HHMMSS` in UTC of `unix_time` seconds since the epoch.
+fn utc_timestamp(unix_time: u64) -> String {
+    let (days, seconds) = (unix_time / 86400, unix_time % 86400);
+    // civil date from the days since 1970-01-01, after Howard Hinnant's algorithm
+    let z = days as i64 + 719468;
+    let era = z.div_euclid(146097);
+    let day_of_era = z.rem_euclid(146097);
+    let year_of_era =
+        (day_of_era - day_of_era / 1460 + day_of_era / 36524 - day_of_era / 146096) / 365;
+    let day_of_year = day_of_era - (365 * year_of_era + year_of_era / 4 - year_of_era / 100);
+    let shifted_month = (5 * day_of_year + 2) / 153;
+    let day = day_of_year - (153 * shifted_month + 2) / 5 + 1;
+    let month = if shifted_month < 10 {
+        shifted_month + 3
+    } else {
+        shifted_month - 9
+    };
+    let year = year_of_era + era * 400 + i64::from(month <= 2);
+    format!(
+        "{year:04}{month:02}{day:02}-{:02}{:02}{:02}",
+        seconds / 3600,
+        seconds / 60 % 60,
+        seconds % 60
+    )
+}
+
+/// Exits with a usage error for flags that are out of range, on their own or together.
+fn validate(args: &Args) {
+    if args.psth_window.len() != 2 {
+        Args::command()
+            .error(
+                ErrorKind::WrongNumberOfValues,
+                "--psth-window takes the window before and after onset, e.g. `20,80`",
+            )
+            .exit();
+    }
+    if args
+        .psth_window
+        .iter()
+        .any(|&side| !(side >= 0. && side.is_finite()))
+    {
+        Args::command()
+            .error(
+                ErrorKind::ValueValidation,
+                "--psth-window must not be negative",
+            )
+            .exit();
+    }
+
+    for (flag, mix, synapse_type) in [
+        (
+            "--excitatory-mix",
+            &args.excitatory_mix,
+            SynapseType::Excitatory,
+        ),
+        (
+            "--inhibitory-mix",
+            &args.inhibitory_mix,
+            SynapseType::Inhibitory,
+        ),
+    ] {
+        if let Some((neuron_type, _)) = mix
+            .iter()
+            .find(|(neuron_type, _)| SynapseType::from(*neuron_type) != synapse_type)
+        {
+            Args::command()
+                .error(
+                    ErrorKind::ValueValidation,
+                    format!("{flag} cannot contain {}", neuron_type.abbreviation()),
+                )
+                .exit();
+        }
+    }
+
+    if args.heatmap_grid.contains(&0) {
+        Args::command()
+            .error(
+                ErrorKind::ValueValidation,
+                "--heatmap-grid must have at least one column and row",
+            )
+            .exit();
+    }
+    if args.calibrate == SweepParameter::ConnectionProbability {
+        Args::command()
+            .error(
+                ErrorKind::ValueValidation,
+                "--calibrate cannot tune the connection probability",
+            )
+            .exit();
+    }
+    if args.calibrate_rate.is_some_and(|rate| rate < 0.) || args.calibration_window <= 0. {
+        Args::command()
+            .error(
+                ErrorKind::ValueValidation,
+                "--calibrate-rate must not be negative and --calibration-window must be positive",
+            )
+            .exit();
+    }
+    if !(args.dt >= MIN_DT && args.dt.is_finite()) {
+        Args::command()
+            .error(
+                ErrorKind::ValueValidation,
+                format!("--dt must be finite and at least {MIN_DT:e} ms"),
+            )
+            .exit();
+    }
+    // bins finer than a step only split its spikes, at the cost of allocating them all
+    for (flag, value) in [
+        ("--rate-bin", args.rate_bin),
+        ("--synchrony-bin", args.synchrony_bin),
+        ("--population-rate-bin", args.population_rate_bin),
+        ("--correlogram-bin", args.correlogram_bin),
+        ("--isi-bin", args.isi_bin),
+        ("--psth-bin", args.psth_bin),
+    ] {
+        if !(value >= args.dt && value.is_finite()) {
+            Args::command()
+                .error(
+                    ErrorKind::ValueValidation,
+                    format!("{flag} must be finite and at least --dt, {} ms", args.dt),
+                )
+                .exit();
+        }
+    }
+    if !(args.sta_window > 0. && args.sta_window.is_finite()) {
+        Args::command()
+            .error(
+                ErrorKind::ValueValidation,
+                "--sta-window must be positive and finite",
+            )
+            .exit();
+    }
+    if args.max_delay < 1 {
+        Args::command()
+            .error(ErrorKind::ValueValidation, "--max-delay must be at least 1")
+            .exit();
+    }
+    if !(0. ..=1.).contains(&args.connection_prob) {
+        Args::command()
+            .error(
+                ErrorKind::ValueValidation,
+                "--connection-prob must lie in [0, 1]",
+            )
+            .exit();
+    }
+    if args.animation_window <= 0. || args.animation_step <= 0. || args.animation_fps == 0 {
+        Args::command()
+            .error(
+                ErrorKind::ValueValidation,
+                "--animation-window, --animation-step and --animation-fps must be positive",
+            )
+            .exit();
+    }
+}
+
+/// The synapse model of the flags, exiting with a usage error on invalid time constants.
+fn synapse_model(args: &Args) -> SynapseModel {
+    match args.synapse_model {
+        SynapseModelArg::Exponential => {
+            let params = ExponentialParameters {
+                tau_rise_exc: args.ampa_rise,
+                tau_decay_exc: args.ampa_decay,
+                tau_rise_inh: args.gaba_rise,
+                tau_decay_inh: args.gaba_decay,
+            };
+            if !params.is_valid() {
+                Args::command()
+                    .error(
+                        ErrorKind::ValueValidation,
+                        "synaptic rise time constants must be non-negative and shorter than the \
+                         decay",
+                    )
+                    .exit();
+            }
+            SynapseModel::Exponential(params)
+        }
+        model => model.into(),
+    }
+}
+
+/// The integrator of the flags, exiting with a usage error on an invalid tolerance.
+fn integrator(args: &Args) -> Integrator {
+    let integrator = match args.integrator {
+        IntegratorArg::Euler => Integrator::Euler,
+        IntegratorArg::Rk2 => Integrator::Rk2,
+        IntegratorArg::Rk4 => Integrator::Rk4,
+        IntegratorArg::Adaptive => Integrator::Adaptive {
+            tolerance: args.integrator_tolerance,
+        },
+    };
+    if !integrator.is_valid() {
+        Args::command()
+            .error(
+                ErrorKind::ValueValidation,
+                "--integrator-tolerance must be positive",
+            )
+            .exit();
+    }
+    integrator
+}
+
+/// Dynamics of `model` for neurons of `synapse_type`, or `None` for the Izhikevich neurons the
+/// network is built with.
+fn neuron_dynamics(
+    args: &Args,
+    model: ModelArg,
+    synapse_type: SynapseType,
+) -> Option<NeuronDynamics> {
+    match model {
+        ModelArg::Izhikevich => None,
+        ModelArg::Adex => {
+            let preset = match synapse_type {
+                SynapseType::Excitatory => args.adex_excitatory,
+                SynapseType::Inhibitory => args.adex_inhibitory,
+            };
+            let params = AdExPreset::from(preset).params();
+            Some(NeuronDynamics::AdEx(AdExParameters {
+                input_gain: args.input_gain * params.input_gain,
+                ..params
+            }))
+        }
+        ModelArg::Lif => {
+            let params = LifParameters::for_synapse_type(&synapse_type);
+            Some(NeuronDynamics::Lif(LifParameters {
+                input_gain: args.input_gain * params.input_gain,
+                ..params
+            }))
+        }
+        ModelArg::HodgkinHuxley => {
+            let params = HodgkinHuxleyParameters::default();
+            let params = HodgkinHuxleyParameters {
+                input_gain: args.input_gain * params.input_gain,
+                ..params
+            };
+            Some(NeuronDynamics::HodgkinHuxley(params.resting_state()))
+        }
+        ModelArg::Izhikevich2007 => {
+            let preset = match synapse_type {
+                SynapseType::Excitatory => args.izhikevich2007_excitatory,
+                SynapseType::Inhibitory => args.izhikevich2007_inhibitory,
+            };
+            let params = Izhikevich2007Preset::from(preset).params();
+            Some(NeuronDynamics::Izhikevich2007(Izhikevich2007Parameters {
+                input_gain: args.input_gain * params.input_gain,
+                ..params
+            }))
+        }
+        ModelArg::TwoCompartment => {
+            let params = TwoCompartmentParameters::for_synapse_type(&synapse_type);
+            let params = TwoCompartmentParameters {
+                input_gain: args.input_gain * params.input_gain,
+                ..params
+            };
+            Some(NeuronDynamics::TwoCompartment(params.resting_state()))
+        }
+    }
+}
+
+/// Adds the plasticity rules the flags ask for to `builder`, after any preset, whose STDP
+/// `--heterosynaptic` replaces.
+fn with_plasticity(args: &Args, mut builder: WorldStateBuilder) -> WorldStateBuilder {
+    if args.dopamine_stdp {
+        builder = builder.dopamine_stdp(Default::default());
+    }
+    if let Some(target_rate) = args.homeostasis {
+        let params = HomeostasisParameters {
+            target_rate,
+            ..Default::default()
+        };
+        builder = builder.homeostasis(match args.homeostatic_variable {
+            HomeostaticVariableArg::Gain => params,
+            HomeostaticVariableArg::D => HomeostasisParameters {
+                variable: HomeostaticVariable::RecoveryIncrement,
+                min: 0.,
+                ..params
+            },
+        });
+    }
+    if let Some(heterosynaptic) = args.heterosynaptic {
+        builder = builder.stdp(StdpParameters {
+            heterosynaptic,
+            ..Default::default()
+        });
+    }
+    if let Some(target_rate) = args.inhibitory_stdp {
+        builder = builder.inhibitory_stdp(InhibitoryStdpParameters {
+            target_rate,
+            ..Default::default()
+        });
+    }
+    if !args.stp.is_empty() {
+        let mut config = ShortTermPlasticity::default();
+        for (classes, params) in &args.stp {
+            for (pre, post) in classes {
+                config.set(pre, post, params.clone());
+            }
+        }
+        builder = builder.short_term_plasticity(config);
+    }
+    builder
+}
+
+/// The network the flags describe, with its neurons, synapses, inputs and plasticity.
+fn world_builder(args: &Args) -> Result<WorldStateBuilder, Box<dyn std::error::Error>> {
+    let mut builder = WorldState::builder()
+        .excitatory(args.excitatory)
+        .inhibitory(args.inhibitory)
+        .excitatory_types(args.excitatory_mix.clone())
+        .inhibitory_types(args.inhibitory_mix.clone())
+        .dt(args.dt)
+        .integrator(integrator(args))
+        .delay_range(1, args.max_delay)
+        .connection_probability(args.connection_prob)
+        .topology(match args.topology {
+            TopologyArg::Random => Topology::Random,
+            TopologyArg::SmallWorld => Topology::SmallWorld {
+                neighbors: args.neighbors,
+                rewire: args.rewire,
+            },
+            TopologyArg::ScaleFree => Topology::ScaleFree {
+                attachment: args.attachment,
+            },
+        })
+        .synapse_model(synapse_model(args));
+    for (population, weight) in &args.weight_distributions {
+        builder = builder.weight_distribution(population, weight.clone());
+    }
+    if args.nmda {
+        builder = builder.nmda(Default::default());
+    }
+    if let Some(tau) = args.noise_tau {
+        builder = builder.noise_model(NoiseModel::OrnsteinUhlenbeck {
+            mean: args.noise_mean,
+            sigma: args.noise_sigma,
+            tau,
+        });
+    }
+    if let Some(model) = neuron_dynamics(args, args.model, SynapseType::Excitatory) {
+        builder = builder.excitatory_dynamics(model);
+    }
+    let inhibitory_model = args.inhibitory_model.unwrap_or(args.model);
+    if let Some(model) = neuron_dynamics(args, inhibitory_model, SynapseType::Inhibitory) {
+        builder = builder.inhibitory_dynamics(model);
+    }
+    if args.refractory_excitatory > 0. || args.refractory_inhibitory > 0. {
+        builder = builder.refractory_periods(RefractoryPeriods {
+            excitatory: args.refractory_excitatory,
+            inhibitory: args.refractory_inhibitory,
+            by_type: vec![],
+        });
+    }
+    if let Some(rate) = args.poisson_rate {
+        builder = builder.thalamic_noise(false).poisson_input(PoissonInput {
+            sources: args.poisson_sources,
+            rate,
+            weight: args.poisson_weight,
+            connection_probability: 0.1,
+            targets: 0..args.excitatory + args.inhibitory,
+        });
+    }
+    if let PresetArg::Polychronization = args.preset {
+        builder = builder.polychronization();
+    }
+    builder = with_plasticity(args, builder);
+    #[cfg(feature = "sonata")]
+    if let Some(path) = &args.sonata {
+        builder = builder.instantiated_network(izhikevich_rs::sonata::load_sonata(path)?);
+    }
+    if let Some(path) = &args.neuron_parameters {
+        builder = builder.neuron_overrides(overrides::read_overrides(path)?);
+    }
+    if let Some(path) = &args.weights {
+        builder = builder.synapses(weights::read_weights_csv(path)?);
+    }
+    if let Some(path) = &args.weight_matrix {
+        let (neurons, synapses) = weights::read_weight_matrix(path, 1.)?;
+        let n = args.excitatory + args.inhibitory;
+        if neurons != n {
+            return Err(format!("the weight matrix is {neurons}×{neurons} for {n} neurons").into());
+        }
+        builder = builder.synapses(synapses);
+    }
+    if let Some(seed) = args.seed {
+        builder = builder.seed(seed);
+    }
+    Ok(builder)
+}
+
+/// Reports on the spikes saved to `spikes` for the `analyze` command.
+fn run_analyze(args: &Args, spikes: &Path) -> Result<(), Box<dyn std::error::Error>> {
+    let (trains, mut populations) = SpikeTrains::read_csv_populations(spikes)?;
+    populations.insert(0, ("all".to_string(), 0..trains.neurons()));
+    analyze_spikes(
+        args,
+        &trains,
+        &populations,
+        &[],
+        None,
+        args.dt,
+        &mut io::stdout(),
+    )
+}
+
+/// Draws the spikes saved to `spikes` for the `plot` command.
+fn run_plot(args: &Args, spikes: &Path, raster: &Path) -> Result<(), Box<dyn std::error::Error>> {
+    let (trains, mut populations) = SpikeTrains::read_csv_populations(spikes)?;
+    if populations.is_empty() {
+        populations.push(("all".to_string(), 0..trains.neurons()));
+    }
+    spike_plots(args, &trains, &populations, raster)
+}
+
+/// Plots and writes the trace of the clamped neuron.
+fn run_clamp(args: &Args, clamp: &ClampArgs) -> Result<(), Box<dyn std::error::Error>> {
+    let neuron = clamp.neuron.neuron();
+    let mut waveform = Waveform {
+        segments: clamp.segments.clone(),
+    };
+    if let Some(path) = &clamp.current_file {
+        waveform.segments.extend(Waveform::read_csv(path)?.segments);
+    }
+    let duration = args.milliseconds as f64;
+    let trace = CurrentClamp {
+        neuron: neuron.clone(),
+        waveform,
+        dt: args.dt,
+    }
+    .run(duration);
+    println!(
+        "{} spikes in {duration} ms ({:.2} Hz)",
+        trace.spikes.len(),
+        trace.spikes.len() as f64 * 1000. / duration
+    );
+    plot::clamp_trace(plot_path(&clamp.plot, args.plot_format), &trace)?;
+    if let Some(path) = &clamp.phase_plane {
+        plot::phase_plane(
+            plot_path(path, args.plot_format),
+            &trace,
+            neuron.parameters(),
+        )?;
+    }
+    if let Some(path) = &clamp.out {
+        trace.write_csv(BufWriter::new(File::create(path)?))?;
+    }
+    Ok(())
+}
+
+/// Measures, plots and writes the f–I curve of the neuron.
+fn run_fi(args: &Args, fi: &FiArgs) -> Result<(), Box<dyn std::error::Error>> {
+    let neuron = fi.neuron.neuron();
+    let duration = args.milliseconds as f64;
+    let curve = FiCurve::measure(
+        &neuron,
+        fi.currents.0.clone(),
+        duration,
+        fi.transient,
+        args.dt,
+    );
+    match curve.rheobase() {
+        Some(rheobase) => println!("Fires from a current of {rheobase}"),
+        None => println!("Silent at every current"),
+    }
+    // the noise of the network is white, so its scale depends on the step
+    println!(
+        "The thalamic noise onto this neuron has a standard deviation of {} per step of {} ms",
+        neuron.thalamic_current(1., args.dt),
+        args.dt
+    );
+    plot::fi_curve(plot_path(&fi.plot, args.plot_format), &curve)?;
+    if let Some(path) = &fi.out {
+        curve.write_csv(BufWriter::new(File::create(path)?))?;
+    }
+    Ok(())
+}
+
+/// Classifies, plots and writes the firing of the neuron along the parameter.
+fn run_bifurcation(
+    args: &Args,
+    bifurcation: &BifurcationArgs,
+) -> Result<(), Box<dyn std::error::Error>> {
+    let parameter = bifurcation.parameter;
+    let measured = Bifurcation::measure(
+        &bifurcation.neuron.neuron(),
+        parameter,
+        &bifurcation.values.0,
+        bifurcation.current,
+        args.milliseconds as f64,
+        bifurcation.transient,
+        args.dt,
+    );
+    for point in &measured.points {
+        println!(
+            "{parameter} = {}: {} at {:.2} Hz",
+            point.value, point.pattern, point.rate
+        );
+    }
+    plot::bifurcation(plot_path(&bifurcation.plot, args.plot_format), &measured)?;
+    if let Some(path) = &bifurcation.out {
+        measured.write_csv(BufWriter::new(File::create(path)?))?;
+    }
+    Ok(())
+}
+
+/// Runs `trials` trials of the network and writes their spikes, spectra and summary.
+fn run_trials(
+    args: &Args,
+    builder: &WorldStateBuilder,
+    trials: usize,
+) -> Result<(), Box<dyn std::error::Error>> {
+    let batch = Batch {
+        trials,
+        seed: args.seed.unwrap_or_else(rand::random),
+        duration: args.milliseconds as f64,
+        rate_bin: args.rate_bin,
+        synchrony_bin: args.synchrony_bin,
+        spectrum_segment: args.spectrum_segment,
+    };
+    let summaries = batch.run(builder);
+    std::fs::create_dir_all(&args.trials_out)?;
+    let file = |name: String| File::create(args.trials_out.join(name)).map(BufWriter::new);
+    for (trial, summary) in summaries.iter().enumerate() {
+        summary
+            .trains
+            .write_csv(file(format!("trial_{trial}_spikes.csv"))?)?;
+        summary
+            .spectrum
+            .write_csv(file(format!("trial_{trial}_spectrum.csv"))?)?;
+    }
+    batch.write_csv(file("trials.csv".into())?, &summaries)?;
+    print!("{}", BatchSummary::new(&batch, &summaries));
+    Ok(())
+}
+
+/// Runs the points of the sweep, from a warmed-up or resumed network if asked, and writes
+/// their table.
+fn run_sweep(
+    args: &Args,
+    sweep_args: &SweepArgs,
+    builder: &WorldStateBuilder,
+) -> Result<(), Box<dyn std::error::Error>> {
+    let SweepArgs {
+        axes,
+        out,
+        warm_up,
+        warm_up_out,
+    } = sweep_args;
+    let sweep = Sweep {
+        axes: axes.clone(),
+        duration: args.milliseconds as f64,
+        rate_bin: args.rate_bin,
+    };
+    let warm = args.resume.is_some() || *warm_up > 0. || warm_up_out.is_some();
+    let points = if warm {
+        if axes
+            .iter()
+            .any(|axis| axis.parameter == SweepParameter::ConnectionProbability)
+        {
+            return Err(
+                "connection-probability cannot vary from a warmed-up or resumed network".into(),
+            );
+        }
+        let mut world = match &args.resume {
+            Some(path) => izhikevich_rs::load_checkpoint(path)?,
+            None => builder.clone().build(),
+        };
+        world.run_for(*warm_up);
+        if let Some(path) = warm_up_out {
+            izhikevich_rs::save_checkpoint(path, &mut world)?;
+        }
+        sweep.run_from(&mut world)
+    } else {
+        sweep.run(builder)
+    };
+    sweep.write_csv(BufWriter::new(File::create(out)?), &points)?;
+    Ok(())
+}
+
+/// Trains the choice network block by block, printing the share of correct choices.
+fn run_choice(
+    args: &Args,
+    choice: &ChoiceArgs,
+    builder: WorldStateBuilder,
+) -> Result<(), Box<dyn std::error::Error>> {
+    let mut world = builder.two_alternative_choice().build();
+    let learning =
+        RewardLearning::for_populations(&world, &["cue_0", "cue_1"], &["action_0", "action_1"]);
+    let mut task = TwoAlternativeChoice::new(args.seed.unwrap_or_else(rand::random));
+    let mut results = vec![];
+    while results.len() < choice.trials {
+        let first = results.len();
+        let block = choice.block.min(choice.trials - first);
+        let outcomes = learning.run(&mut world, &mut task, first, block);
+        let correct = outcomes
+            .iter()
+            .filter(|trial| trial.action == Some(trial.cue))
+            .count();
+        println!(
+            "trials {}-{}: {:.0}% correct",
+            first + 1,
+            first + outcomes.len(),
+            100. * correct as f64 / outcomes.len() as f64
+        );
+        results.extend(outcomes);
+    }
+    if let Some(path) = &choice.out {
+        task::write_trials_csv(BufWriter::new(File::create(path)?), &results, 0)?;
+    }
+    Ok(())
+}
+
+/// Measures the memory capacity of the network and prints its score by delay.
+fn run_memory(
+    args: &Args,
+    memory: &MemoryArgs,
+    builder: WorldStateBuilder,
+) -> Result<(), Box<dyn std::error::Error>> {
+    let seed = args.seed.unwrap_or_else(rand::random);
+    let mut world = builder.build();
+    let mut reservoir =
+        Reservoir::random(&world, 1, memory.input_fraction, memory.input_gain, seed);
+    reservoir.sample_duration = memory.sample_duration;
+    reservoir.tau = memory.tau;
+    let benchmark = MemoryCapacity {
+        train: memory.train,
+        test: memory.test,
+        washout: 50,
+        max_delay: memory.max_delay,
+        ridge: memory.ridge,
+        seed,
+    };
+    let scores = benchmark.run(&mut world, &mut reservoir);
+    println!("delay  score");
+    for (idx, score) in scores.iter().enumerate() {
+        println!("{:>5}  {score:.3}", idx + 1);
+    }
+    println!("memory capacity {:.3}", scores.iter().sum::<f64>());
+    if let Some(path) = &memory.out {
+        benchmark.write_csv(BufWriter::new(File::create(path)?), &scores)?;
+    }
+    Ok(())
+}
+
+/// Trains and scores the readout of the digits and prints its confusion matrix.
+fn run_mnist(
+    args: &Args,
+    mnist: &MnistArgs,
+    builder: WorldStateBuilder,
+) -> Result<(), Box<dyn std::error::Error>> {
+    let mut train_set = Mnist::read(&mnist.train_images, &mnist.train_labels)?;
+    train_set.truncate(mnist.train);
+    let mut test_set = Mnist::read(&mnist.test_images, &mnist.test_labels)?;
+    test_set.truncate(mnist.test);
+    let pixels = train_set.images.first().map_or(0, Vec::len);
+    let mut world = builder.build();
+    let seed = args.seed.unwrap_or_else(rand::random);
+    let mut reservoir =
+        Reservoir::random(&world, pixels, mnist.input_fraction, mnist.input_gain, seed);
+    reservoir.sample_duration = mnist.presentation;
+    reservoir.tau = mnist.tau;
+    let classification = MnistBenchmark { ridge: mnist.ridge }.run(
+        &mut world,
+        &mut reservoir,
+        &train_set,
+        &test_set,
+    );
+    println!("label  predicted");
+    for (label, row) in classification.confusion.iter().enumerate() {
+        let counts: Vec<String> = row.iter().map(|count| format!("{count:>4}")).collect();
+        println!("{label:>5}  {}", counts.join(""));
+    }
+    println!(
+        "accuracy {:.1}% on {} test images",
+        100. * classification.accuracy,
+        test_set.labels.len()
+    );
+    Ok(())
+}
+
+/// The network of `builder`, calibrated and recording the probes the flags ask for, or the
+/// resumed one.
+fn build_world(
+    args: &Args,
+    builder: WorldStateBuilder,
+) -> Result<WorldState, Box<dyn std::error::Error>> {
+    let calibrated = match args.calibrate_rate {
+        Some(_) if args.resume.is_some() => {
+            return Err("--calibrate-rate cannot tune a resumed network".into());
+        }
+        Some(rate) => Some(calibrate(args, builder.clone(), rate)?),
+        None => None,
+    };
+    // recorded probes are part of the checkpoint
+    if let Some(path) = &args.resume {
+        return Ok(izhikevich_rs::load_checkpoint(path)?);
+    }
+    let mut world_state = builder.build();
+    if let Some(calibrated) = &calibrated {
+        calibrated.apply(&mut world_state);
+    }
+    let n = world_state.neurons.len();
+    if let Some(idx) = args.record_v.iter().find(|&&idx| idx >= n) {
+        Args::command()
+            .error(
+                ErrorKind::ValueValidation,
+                format!("--record-v: neuron {idx} is not in the network of {n} neurons"),
+            )
+            .exit();
+    }
+    if let Some(idx) = args.record_input.iter().find(|&&idx| idx >= n) {
+        Args::command()
+            .error(
+                ErrorKind::ValueValidation,
+                format!("--record-input: neuron {idx} is not in the network of {n} neurons"),
+            )
+            .exit();
+    }
+    world_state.record_v(args.record_v.iter().copied());
+    world_state.record_input(args.record_input.iter().copied());
+    let lfp_spectrum = matches!(args.spectrum, Some(SpectrumSignalArg::Lfp));
+    if args.lfp_out.is_some() || args.lfp_plot.is_some() || lfp_spectrum {
+        world_state.record_lfp();
+    }
+    if args.synchrony {
+        world_state.track_voltage_synchrony(0..world_state.neurons.len());
+        for (_, neurons) in world_state.populations() {
+            world_state.track_voltage_synchrony(neurons);
+        }
+    }
+    Ok(world_state)
+}
+
+/// Writes the network before the run in the formats the flags ask for.
+fn write_network(args: &Args, world_state: &WorldState) -> Result<(), Box<dyn std::error::Error>> {
+    if let Some(path) = &args.neuroml_out {
+        write_neuroml(path, world_state, args.milliseconds as f64)?;
+    }
+    if let Some(path) = &args.weights_out {
+        weights::write_weights_csv(
+            BufWriter::new(File::create(path)?),
+            &world_state.connectivity,
+            world_state.dt,
+        )?;
+    }
+    if let Some(path) = &args.graph_out {
+        graph::write_graph(
+            BufWriter::new(File::create(path)?),
+            world_state,
+            graph::GraphFormat::from_path(path),
+            args.graph_threshold,
+        )?;
+    }
+    Ok(())
+}
+
+/// Sets how `world_state` steps and what it records during the run.
+fn configure_run(
+    args: &Args,
+    world_state: &mut WorldState,
+) -> Result<(), Box<dyn std::error::Error>> {
+    if args.heatmap_animation.is_some() && world_state.positions.is_none() {
+        return Err("--heatmap-animation needs neurons placed on a sheet".into());
+    }
+    if args.event_driven.is_some() {
+        world_state.set_event_driven(args.event_driven);
+    }
+    if args.spike_window.is_some() {
+        world_state.set_spike_window(args.spike_window);
+    }
+    if args.precise_spike_times {
+        world_state.set_precise_spike_times(true);
+    }
+    let weight_plots = args.weight_histogram_plot.is_some() || args.weight_traces_plot.is_some();
+    if weight_plots && world_state.weight_history.is_none() {
+        world_state.record_weights(args.weight_interval, args.weight_samples);
+    }
+    if let Err(err) = world_state.set_backend(args.backend.into()) {
+        Args::command()
+            .error(ErrorKind::ArgumentConflict, format!("--backend: {err}"))
+            .exit();
+    }
+    if args.profile {
+        world_state.start_profiling();
+    }
+    Ok(())
+}
+
+/// Schedules the lesions, neuromodulation, rewards and pulses of the run and adds its gap
+/// junctions and controllers.
+fn add_interventions(
+    args: &Args,
+    world_state: &mut WorldState,
+) -> Result<(), Box<dyn std::error::Error>> {
+    let total_neurons = world_state.neurons.len();
+    let start = world_state.time();
+    for (time, lesion) in &args.lesions {
+        lesion.check(total_neurons)?;
+        world_state.schedule_lesion(*time, lesion.clone());
+    }
+    match (&args.gap_junctions, args.inhibitory_gap_junctions) {
+        (Some(_), Some(_)) => {
+            return Err("--gap-junctions and --inhibitory-gap-junctions exclude each other".into())
+        }
+        (Some(path), None) => {
+            world_state.gap_junctions = Some(GapJunctions::read_csv(path, total_neurons)?);
+        }
+        (None, Some(probability)) => {
+            let inhibitory: Vec<usize> = (0..total_neurons)
+                .filter(|&idx| world_state.neurons.synapse_types[idx] == SynapseType::Inhibitory)
+                .collect();
+            world_state.gap_junctions = Some(GapJunctions::random(
+                total_neurons,
+                &inhibitory,
+                probability,
+                args.gap_conductance,
+                args.seed.unwrap_or_else(rand::random),
+            ));
+        }
+        (None, None) => {}
+    }
+    for (population, variable, schedule) in &args.neuromodulators {
+        let neurons = if population == "all" {
+            0..total_neurons
+        } else {
+            world_state
+                .populations()
+                .into_iter()
+                .find(|(name, _)| name == population)
+                .map(|(_, range)| range)
+                .ok_or_else(|| format!("no population `{population}`"))?
+        };
+        world_state.add_neuromodulator(Neuromodulator::new(neurons, *variable, schedule.clone()));
+    }
+    for (population, rate) in &args.rate_clamps {
+        let neurons = world_state
+            .populations()
+            .into_iter()
+            .find(|(name, _)| name == population)
+            .map(|(_, range)| range)
+            .ok_or_else(|| format!("no population `{population}`"))?;
+        world_state.add_controller(RateClamp::new(neurons, *rate));
+    }
+    match args.stream_input.as_deref() {
+        Some(path) if path == Path::new("-") => {
+            world_state.add_controller(StreamedInput::new(BufReader::new(io::stdin())));
+        }
+        Some(path) => {
+            world_state.add_controller(StreamedInput::new(BufReader::new(File::open(path)?)));
+        }
+        None => {}
+    }
+    if let Some(stdp) = &mut world_state.dopamine_stdp {
+        for &time in &args.reward_at {
+            stdp.schedule(time, args.reward_amount);
+        }
+    }
+    if let Some(amplitude) = args.pulse_amplitude {
+        world_state.add_stimulus(
+            PulseTrain {
+                amplitude,
+                period: args.pulse_period,
+                width: args.pulse_width,
+            },
+            0..total_neurons,
+            start..start + args.milliseconds as f64,
+        );
+    }
+    Ok(())
+}
+
+/// Steps `world_state` for --milliseconds, streaming the spikes and showing the progress, live
+/// raster or dashboard the flags ask for.
+fn simulate(args: &Args, world_state: &mut WorldState) -> Result<(), Box<dyn std::error::Error>> {
+    let steps = (args.milliseconds as f64 / args.dt).round() as usize;
+    let root = world_state.rank() == 0;
+    let to_stdout = args.stream_spikes.as_deref() == Some(Path::new("-"));
+    let mut stream = match &args.stream_spikes {
+        Some(_) if !root => None,
+        Some(_) if to_stdout => Some(output::SpikeStream::new(
+            Box::new(io::stdout()) as Box<dyn Write>
+        )),
+        Some(path) => Some(output::SpikeStream::new(
+            Box::new(BufWriter::new(File::create(path)?)) as Box<dyn Write>,
+        )),
+        None => None,
+    };
+    #[cfg(feature = "live")]
+    let mut live = match args.live {
+        Some(_) => Some(izhikevich_rs::live::LiveRaster::new(500.)?),
+        None => None,
+    };
+    #[cfg(feature = "tui")]
+    if args.tui && to_stdout {
+        return Err("--tui draws on stdout, so stream the spikes to a file instead".into());
+    }
+    #[cfg(feature = "tui")]
+    let mut dashboard = args
+        .tui
+        .then(|| izhikevich_rs::dashboard::Dashboard::new(world_state, steps));
+    #[cfg(feature = "tui")]
+    let mut progress_bar =
+        (root && dashboard.is_none()).then(|| progress::Progress::new(steps, args.dt));
+    #[cfg(not(feature = "tui"))]
+    let mut progress_bar = root.then(|| progress::Progress::new(steps, args.dt));
+    for t in 0..steps {
+        world_state.step();
+        if let Some(bar) = &mut progress_bar {
+            bar.update(t + 1)?;
+        }
+        if let Some(stream) = &mut stream {
+            stream.write_step(world_state)?;
+        }
+        #[cfg(feature = "tui")]
+        if let Some(dashboard) = &mut dashboard {
+            if dashboard.update(world_state)? {
+                break;
+            }
+        }
+
+        #[cfg(feature = "live")]
+        if let (Some(window), Some(every)) = (live.as_mut(), args.live) {
+            if (t + 1) % every.max(1) == 0 && window.is_open() {
+                window.update(world_state)?;
+            }
+        }
+    }
+
+    // only the dashboard stops runs early
+    if let Some(bar) = &mut progress_bar {
+        bar.finish(steps)?;
+    }
+    // restores the terminal before the reports
+    #[cfg(feature = "tui")]
+    drop(dashboard);
+    Ok(())
+}
+
+/// Reports the polychronous groups of at least 3 layers among the strong synapses.
+fn report_polychronous_groups(
+    trains: &SpikeTrains,
+    world_state: &WorldState,
+    report: &mut dyn Write,
+) -> io::Result<()> {
+    // strong synapses are those near the largest weight, which STDP drives them to
+    let strongest = world_state
+        .connectivity
+        .weights()
+        .iter()
+        .copied()
+        .fold(0., f64::max);
+    let groups = trains.polychronous_groups(
+        &world_state.connectivity,
+        world_state.dt,
+        0.95 * strongest,
+        4.,
+        2,
+    );
+    let groups: Vec<_> = groups
+        .into_iter()
+        .filter(|group| group.layers >= 3)
+        .collect();
+    writeln!(
+        report,
+        "{} polychronous groups with at least 3 layers",
+        groups.len()
+    )?;
+    for group in groups.iter().take(10) {
+        let firings: Vec<String> = group
+            .firings
+            .iter()
+            .take(8)
+            .map(|(neuron, time)| format!("{neuron}@{time}"))
+            .collect();
+        let more = if group.firings.len() > 8 { " ..." } else { "" };
+        writeln!(
+            report,
+            "{:>4} activations, {:>2} layers, {:>4} firings: {}{more}",
+            group.activations,
+            group.layers,
+            group.firings.len(),
+            firings.join(" ")
+        )?;
+    }
+    Ok(())
+}
+
+/// Reports and writes the spike-triggered averages of the recorded inputs.
+fn spike_triggered_averages(
+    args: &Args,
+    trains: &SpikeTrains,
+    world_state: &WorldState,
+    report: &mut dyn Write,
+) -> Result<(), Box<dyn std::error::Error>> {
+    let dt = world_state.dt;
+    let stas: Vec<_> = world_state
+        .input_traces
+        .iter()
+        .map(|(idx, trace)| {
+            // the last sample is over the step just simulated
+            let first = (world_state.time_step + 1 - trace.len()) as f64 * dt;
+            let sta = trains.spike_triggered_average(*idx, trace, first, dt, args.sta_window);
+            (*idx, sta)
+        })
+        .collect();
+    for (idx, sta) in &stas {
+        writeln!(
+            report,
+            "Spike-triggered average of the input onto neuron {idx} over {} spikes",
+            sta.spikes
+        )?;
+    }
+    output::write_stas_csv(BufWriter::new(File::create(&args.sta_out)?), &stas)?;
+    if let Some(path) = &args.sta_plot {
+        plot::spike_triggered_averages(plot_path(path, args.plot_format), &stas)?;
+    }
+    Ok(())
+}
+
+/// Writes the reports, data and plots of the finished run, the raster into `run_dir`.
+fn write_outputs(
+    args: &Args,
+    matches: &ArgMatches,
+    run_dir: &Path,
+    world_state: &mut WorldState,
+) -> Result<(), Box<dyn std::error::Error>> {
+    // with the spikes streamed to stdout, everything else goes to stderr
+    let to_stdout = args.stream_spikes.as_deref() == Some(Path::new("-"));
+    let mut report: Box<dyn Write> = if to_stdout {
+        Box::new(io::stderr())
+    } else {
+        Box::new(io::stdout())
+    };
+    if let Some(profile) = &world_state.profile {
+        write!(report, "{profile}")?;
+    }
+
+    let total_neurons = world_state.neurons.len();
+    let trains = SpikeTrains::from_world(world_state);
+    let mut populations = vec![("all".to_string(), 0..total_neurons)];
+    populations.extend(world_state.populations());
+    analyze_spikes(
+        args,
+        &trains,
+        &populations,
+        &world_state.voltage_synchrony,
+        world_state.lfp.as_deref(),
+        world_state.dt,
+        &mut report,
+    )?;
+
+    if args.polychronous_groups {
+        report_polychronous_groups(&trains, world_state, &mut report)?;
+    }
+
+    if let Some(path) = &args.psth_plot {
+        let (before, after) = (args.psth_window[0], args.psth_window[1]);
+        let onsets = world_state.stimulus_onsets();
+        let psth = trains.psth(0..total_neurons, &onsets, before, after, args.psth_bin);
+        plot::psth(plot_path(path, args.plot_format), &psth)?;
+    }
+
+    if let Some(path) = &args.spikes_out {
+        let writer = BufWriter::new(File::create(path)?);
+        output::write_spikes_csv(writer, world_state, &run_parameters(matches))?;
+    }
+
+    if !world_state.v_traces.is_empty() {
+        let writer = BufWriter::new(File::create(&args.v_out)?);
+        output::write_v_traces_csv(writer, world_state)?;
+    }
+
+    if !world_state.input_traces.is_empty() {
+        spike_triggered_averages(args, &trains, world_state, &mut report)?;
+    }
+
+    if let Some(path) = &args.lfp_out {
+        output::write_lfp_csv(BufWriter::new(File::create(path)?), world_state)?;
+    }
+    if let Some(path) = &args.lfp_plot {
+        plot::lfp(plot_path(path, args.plot_format), world_state)?;
+    }
+    if let Some(history) = &world_state.weight_history {
+        if let Some(path) = &args.weight_histogram_plot {
+            plot::weight_distributions(plot_path(path, args.plot_format), history)?;
+        }
+        if let Some(path) = &args.weight_traces_plot {
+            plot::weight_traces(plot_path(path, args.plot_format), history)?;
+        }
+    }
+
+    if let Some(path) = &args.npy_out {
+        output::write_numpy(path, world_state)?;
+    }
+    #[cfg(feature = "hdf5")]
+    if let Some(path) = &args.hdf5_out {
+        output::write_hdf5(path, world_state)?;
+    }
+
+    if let Some(path) = &args.checkpoint_out {
+        izhikevich_rs::save_checkpoint(path, world_state)?;
+    }
+
+    if let (Some(path), Some(positions)) = (&args.heatmap_animation, &world_state.positions) {
+        plot::spatial_animation(
+            path,
+            &trains,
+            positions,
+            (args.heatmap_grid[0], args.heatmap_grid[1]),
+            args.animation_window,
+            args.animation_step,
+            args.animation_fps,
+        )?;
+    }
+
+    if !matches!(args.command, Some(Command::Simulate)) {
+        spike_plots(
+            args,
+            &trains,
+            &populations[1..],
+            &run_dir.join("spikes.png"),
+        )?;
+    }
+    Ok(())
+}
+
+fn run(args: Args, matches: &ArgMatches, run_dir: &Path) -> Result<(), Box<dyn std::error::Error>> {
+    validate(&args);
+    let builder = world_builder(&args)?;
+    match &args.command {
+        Some(Command::Analyze { spikes }) => return run_analyze(&args, spikes),
+        Some(Command::Plot { spikes, raster }) => return run_plot(&args, spikes, raster),
+        Some(Command::Clamp(clamp)) => return run_clamp(&args, clamp),
+        Some(Command::Fi(fi)) => return run_fi(&args, fi),
+        Some(Command::Bifurcation(bifurcation)) => return run_bifurcation(&args, bifurcation),
+        #[cfg(feature = "grpc")]
+        Some(Command::Serve { address }) => {
+            println!("Serving on {address}");
+            return izhikevich_rs::grpc::serve(*address, builder);
+        }
+        _ => {}
+    }
+    if let Some(trials) = args.trials {
+        return run_trials(&args, &builder, trials);
+    }
+    match &args.command {
+        Some(Command::Sweep(sweep)) => return run_sweep(&args, sweep, &builder),
+        Some(Command::Choice(choice)) => return run_choice(&args, choice, builder),
+        Some(Command::Memory(memory)) => return run_memory(&args, memory, builder),
+        Some(Command::Mnist(mnist)) => return run_mnist(&args, mnist, builder),
+        _ => {}
+    }
+    #[cfg(feature = "gui")]
+    if args.gui {
+        izhikevich_rs::gui::Gui::new(builder).run()?;
+        return Ok(());
+    }
+
+    let mut world_state = build_world(&args, builder)?;
+    write_network(&args, &world_state)?;
+    configure_run(&args, &mut world_state)?;
+    add_interventions(&args, &mut world_state)?;
+    simulate(&args, &mut world_state)?;
+    // every MPI process holds the whole network, so only the first writes anything
+    if world_state.rank() != 0 {
+        return Ok(());
+    }
+    write_outputs(&args, matches, run_dir, &mut world_state)
 }
@@ -0,0 +1,163 @@
+use std::error::Error;
+use std::fs;
+use std::path::Path;
+
+use crate::reservoir::{Readout, Reservoir};
+use crate::simulator::WorldState;
+
+/// Images and labels of the MNIST handwritten digits, with pixels scaled to `[0, 1]`.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct Mnist {
+    /// Pixels of every image row by row.
+    pub images: Vec<Vec<f64>>,
+    pub labels: Vec<usize>,
+}
+
+impl Mnist {
+    /// Reads the images and labels from the IDX files of the MNIST distribution, e.g.
+    /// `train-images-idx3-ubyte` and `train-labels-idx1-ubyte`, uncompressed.
+    pub fn read(images: &Path, labels: &Path) -> Result<Self, Box<dyn Error>> {
+        let image_bytes = fs::read(images)?;
+        let (dimensions, pixels) = idx(&image_bytes, 0x0803, 3)?;
+        let size = dimensions[1] * dimensions[2];
+        let label_bytes = fs::read(labels)?;
+        let (label_dimensions, labels) = idx(&label_bytes, 0x0801, 1)?;
+        if label_dimensions[0] != dimensions[0] {
+            return Err(format!(
+                "{} images but {} labels",
+                dimensions[0], label_dimensions[0]
+            )
+            .into());
+        }
+        Ok(Mnist {
+            images: pixels
+                .chunks(size)
+                .map(|image| image.iter().map(|&pixel| pixel as f64 / 255.).collect())
+                .collect(),
+            labels: labels.iter().map(|&label| label as usize).collect(),
+        })
+    }
+
+    /// Keeps the first `count` images.
+    pub fn truncate(&mut self, count: usize) {
+        self.images.truncate(count);
+        self.labels.truncate(count);
+    }
+}
+
+/// Dimensions and data of an IDX file of unsigned bytes with `magic` and `rank` dimensions.
+fn idx(bytes: &[u8], magic: u32, rank: usize) -> Result<(Vec<usize>, &[u8]), String> {
+    let word = |idx: usize| {
+        bytes
+            .get(4 * idx..4 * idx + 4)
+            .map(|word| u32::from_be_bytes(word.try_into().unwrap()))
+            .ok_or("truncated IDX header")
+    };
+    if word(0)? != magic {
+        return Err(format!(
+            "expected IDX magic number {magic:#010x}, got {:#010x}",
+            word(0)?
+        ));
+    }
+    let dimensions = (1..=rank)
+        .map(|idx| word(idx).map(|size| size as usize))
+        .collect::<Result<Vec<_>, _>>()?;
+    let data = &bytes[4 * (rank + 1)..];
+    let expected: usize = dimensions.iter().product();
+    if data.len() < expected {
+        return Err(format!(
+            "expected {expected} bytes of data, got {}",
+            data.len()
+        ));
+    }
+    Ok((dimensions, &data[..expected]))
+}
+
+/// Classification of MNIST digits by a network as a [`Reservoir`]: every image is presented as
+/// the currents of one sample, with one input channel per pixel, followed by a blank sample in
+/// which the activity decays. A [`Readout`] fitted with one-hot targets on the states at the end
+/// of the training images classifies the test images by its largest output.
+#[derive(Clone, Debug, PartialEq)]
+pub struct MnistBenchmark {
+    pub ridge: f64,
+}
+
+/// Outcome of a [`MnistBenchmark`].
+#[derive(Clone, Debug, PartialEq)]
+pub struct Classification {
+    /// Share of the test images classified correctly.
+    pub accuracy: f64,
+    /// Number of test images of every label, indexed `[label][predicted]`.
+    pub confusion: Vec<Vec<usize>>,
+}
+
+impl MnistBenchmark {
+    /// Trains on `train` and scores on `test`.
+    ///
+    /// # Panics
+    ///
+    /// If `reservoir` does not have one input channel per pixel, or `train` is empty.
+    pub fn run(
+        &self,
+        world: &mut WorldState,
+        reservoir: &mut Reservoir,
+        train: &Mnist,
+        test: &Mnist,
+    ) -> Classification {
+        let classes = train
+            .labels
+            .iter()
+            .chain(&test.labels)
+            .max()
+            .map_or(0, |&max| max + 1);
+        let train_states = present(world, reservoir, &train.images);
+        let targets: Vec<Vec<f64>> = train
+            .labels
+            .iter()
+            .map(|&label| {
+                let mut target = vec![0.; classes];
+                target[label] = 1.;
+                target
+            })
+            .collect();
+        let readout = Readout::fit(&train_states, &targets, self.ridge);
+
+        let mut confusion = vec![vec![0; classes]; classes];
+        for (state, &label) in present(world, reservoir, &test.images)
+            .iter()
+            .zip(&test.labels)
+        {
+            let outputs = readout.predict(state);
+            let predicted = (0..classes)
+                .max_by(|&a, &b| outputs[a].total_cmp(&outputs[b]))
+                .unwrap_or(0);
+            confusion[label][predicted] += 1;
+        }
+        let correct: usize = (0..classes).map(|label| confusion[label][label]).sum();
+        Classification {
+            accuracy: correct as f64 / test.labels.len().max(1) as f64,
+            confusion,
+        }
+    }
+}
+
+/// States at the end of every image, each followed by a blank sample.
+fn present(
+    world: &mut WorldState,
+    reservoir: &mut Reservoir,
+    images: &[Vec<f64>],
+) -> Vec<Vec<f64>> {
+    let Some(first) = images.first() else {
+        return vec![];
+    };
+    let blank = vec![0.; first.len()];
+    let inputs: Vec<Vec<f64>> = images
+        .iter()
+        .flat_map(|image| [image.clone(), blank.clone()])
+        .collect();
+    reservoir
+        .run(world, &inputs)
+        .into_iter()
+        .step_by(2)
+        .collect()
+}
@@ -0,0 +1,869 @@
+use std::ops::Range;
+use std::str::FromStr;
+
+use rand::seq::SliceRandom;
+use rand::Rng;
+use rand_distr::{Distribution, Gamma, Geometric, LogNormal, Normal};
+
+use crate::neuron::{Neuron, NeuronDynamics, NeuronType, Neurons, RefractoryPeriods, SynapseType};
+use crate::simulator::SparseConnectivity;
+use crate::synapse::SynapseModel;
+
+/// A named group of neurons sharing a neuron type and the sign of their outgoing synapses.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Population {
+    pub name: String,
+    pub size: usize,
+    /// Fixed neuron types with their relative fractions of the population, or empty for the
+    /// randomized regular spiking/chattering (excitatory) or fast/low-threshold spiking
+    /// (inhibitory) mixture of the paper.
+    pub neuron_types: Vec<(NeuronType, f64)>,
+    /// Sign of the synapses from this population.
+    pub synapse_type: SynapseType,
+    /// Model the neurons follow instead of the Izhikevich model, if any.
+    pub dynamics: Option<NeuronDynamics>,
+}
+
+impl Population {
+    /// Population of randomized neurons as in the paper.
+    pub fn new(name: impl Into<String>, size: usize, synapse_type: SynapseType) -> Self {
+        Population {
+            name: name.into(),
+            size,
+            neuron_types: vec![],
+            synapse_type,
+            dynamics: None,
+        }
+    }
+
+    /// Population of identical neurons of `neuron_type`, whose synapse sign follows the type.
+    pub fn of_type(name: impl Into<String>, size: usize, neuron_type: NeuronType) -> Self {
+        Population {
+            name: name.into(),
+            size,
+            neuron_types: vec![(neuron_type, 1.)],
+            synapse_type: neuron_type.into(),
+            dynamics: None,
+        }
+    }
+
+    /// Population of neurons of several fixed types in proportion to their relative fractions,
+    /// whose synapses all have the sign of `synapse_type`.
+    pub fn mixed(
+        name: impl Into<String>,
+        size: usize,
+        synapse_type: SynapseType,
+        neuron_types: Vec<(NeuronType, f64)>,
+    ) -> Self {
+        Population {
+            name: name.into(),
+            size,
+            neuron_types,
+            synapse_type,
+            dynamics: None,
+        }
+    }
+
+    /// Fixed type of every neuron, or `None` for the randomized ones. The counts of the types
+    /// follow their fractions up to rounding, in random order.
+    fn draw_types<R: Rng + ?Sized>(&self, rng: &mut R) -> Vec<Option<NeuronType>> {
+        let total: f64 = self
+            .neuron_types
+            .iter()
+            .map(|&(_, fraction)| fraction)
+            .sum();
+        if self.neuron_types.is_empty() || total <= 0. {
+            return vec![None; self.size];
+        }
+        // largest remainder rounding, so the counts add up to the size
+        let quotas: Vec<f64> = self
+            .neuron_types
+            .iter()
+            .map(|&(_, fraction)| fraction / total * self.size as f64)
+            .collect();
+        let mut counts: Vec<usize> = quotas.iter().map(|quota| quota.floor() as usize).collect();
+        let mut by_remainder: Vec<usize> = (0..quotas.len()).collect();
+        by_remainder.sort_by(|&i, &j| {
+            (quotas[j] - quotas[j].floor()).total_cmp(&(quotas[i] - quotas[i].floor()))
+        });
+        let missing = self.size - counts.iter().sum::<usize>();
+        for &k in by_remainder.iter().take(missing) {
+            counts[k] += 1;
+        }
+        let mut types: Vec<Option<NeuronType>> = self
+            .neuron_types
+            .iter()
+            .zip(counts)
+            .flat_map(|(&(neuron_type, _), count)| std::iter::repeat_n(Some(neuron_type), count))
+            .collect();
+        if self.neuron_types.len() > 1 {
+            types.shuffle(rng);
+        }
+        types
+    }
+}
+
+/// Distribution of synaptic weight magnitudes; the sign comes from the presynaptic population.
+#[derive(Clone, Debug, PartialEq)]
+pub enum WeightDistribution {
+    Constant(f64),
+    Uniform {
+        min: f64,
+        max: f64,
+    },
+    /// Normally distributed, clamped at zero.
+    Normal {
+        mean: f64,
+        std: f64,
+    },
+    /// Log-normally distributed with the given mean and standard deviation of the weights
+    /// themselves, like measured EPSP amplitudes: most synapses weak and a long tail of strong
+    /// ones.
+    LogNormal {
+        mean: f64,
+        std: f64,
+    },
+    /// Gamma distributed with mean `shape * scale`.
+    Gamma {
+        shape: f64,
+        scale: f64,
+    },
+}
+
+impl WeightDistribution {
+    /// Checks that the parameters describe a distribution: finite, with `min <= max`, a
+    /// non-negative standard deviation of normal weights, a positive mean and standard deviation
+    /// of log-normal ones and a positive shape and scale of gamma ones.
+    pub fn check(&self) -> Result<(), String> {
+        let valid = match *self {
+            WeightDistribution::Constant(weight) => weight.is_finite(),
+            WeightDistribution::Uniform { min, max } => {
+                min.is_finite() && max.is_finite() && min <= max
+            }
+            WeightDistribution::Normal { mean, std } => {
+                mean.is_finite() && std.is_finite() && std >= 0.
+            }
+            WeightDistribution::LogNormal { mean, std } => {
+                mean.is_finite() && std.is_finite() && mean > 0. && std > 0.
+            }
+            WeightDistribution::Gamma { shape, scale } => {
+                shape.is_finite() && scale.is_finite() && shape > 0. && scale > 0.
+            }
+        };
+        if valid {
+            Ok(())
+        } else {
+            Err(format!("invalid weight distribution {self:?}"))
+        }
+    }
+
+    /// # Panics
+    ///
+    /// If the parameters fail [`WeightDistribution::check`].
+    pub fn sample<R: Rng + ?Sized>(&self, rng: &mut R) -> f64 {
+        match *self {
+            WeightDistribution::Constant(weight) => weight,
+            WeightDistribution::Uniform { min, max } => min + (max - min) * rng.gen::<f64>(),
+            WeightDistribution::Normal { mean, std } => Normal::new(mean, std)
+                .expect("weight standard deviation must be finite")
+                .sample(rng)
+                .max(0.),
+            WeightDistribution::LogNormal { mean, std } => {
+                LogNormal::from_mean_cv(mean, std / mean)
+                    .expect(
+                        "log-normal weights need a positive mean and a finite standard deviation",
+                    )
+                    .sample(rng)
+            }
+            WeightDistribution::Gamma { shape, scale } => Gamma::new(shape, scale)
+                .expect("gamma weights need a positive shape and scale")
+                .sample(rng),
+        }
+    }
+}
+
+impl FromStr for WeightDistribution {
+    type Err = String;
+
+    /// Parses `constant:W`, `uniform:MIN,MAX`, `normal:MEAN,STD`, `lognormal:MEAN,STD` or
+    /// `gamma:SHAPE,SCALE`.
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let (kind, parameters) = s
+            .split_once(':')
+            .ok_or_else(|| format!("expected `KIND:PARAMETERS`, got `{s}`"))?;
+        let parameters = parameters
+            .split(',')
+            .map(|value| {
+                value
+                    .trim()
+                    .parse::<f64>()
+                    .map_err(|err| format!("invalid parameter `{value}`: {err}"))
+            })
+            .collect::<Result<Vec<_>, _>>()?;
+        let distribution = match (kind.trim(), &parameters[..]) {
+            ("constant", &[weight]) => WeightDistribution::Constant(weight),
+            ("uniform", &[min, max]) => WeightDistribution::Uniform { min, max },
+            ("normal", &[mean, std]) => WeightDistribution::Normal { mean, std },
+            ("lognormal", &[mean, std]) => WeightDistribution::LogNormal { mean, std },
+            ("gamma", &[shape, scale]) => WeightDistribution::Gamma { shape, scale },
+            ("constant" | "uniform" | "normal" | "lognormal" | "gamma", _) => {
+                return Err(format!("wrong number of parameters in `{s}`"))
+            }
+            _ => {
+                return Err(format!(
+                    "unknown distribution `{kind}`, expected constant, uniform, normal, lognormal \
+                     or gamma"
+                ))
+            }
+        };
+        distribution.check()?;
+        Ok(distribution)
+    }
+}
+
+/// How a connection property falls off with the distance between two neurons, from 1 at
+/// distance 0.
+#[derive(Clone, Debug, PartialEq)]
+pub enum DistanceKernel {
+    /// `exp(-d² / (2 sigma²))`
+    Gaussian { sigma: f64 },
+    /// `exp(-d / length)`
+    Exponential { length: f64 },
+}
+
+impl DistanceKernel {
+    pub fn factor(&self, distance: f64) -> f64 {
+        match *self {
+            DistanceKernel::Gaussian { sigma } => {
+                (-distance * distance / (2. * sigma * sigma)).exp()
+            }
+            DistanceKernel::Exponential { length } => (-distance / length).exp(),
+        }
+    }
+}
+
+/// Rectangular sheet the neurons are scattered on uniformly at random.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Sheet {
+    pub width: f64,
+    pub height: f64,
+    /// Wrap distances around the edges, so there are no boundary effects.
+    pub periodic: bool,
+}
+
+impl Sheet {
+    pub fn distance(&self, a: [f64; 2], b: [f64; 2]) -> f64 {
+        let mut dx = (a[0] - b[0]).abs();
+        let mut dy = (a[1] - b[1]).abs();
+        if self.periodic {
+            dx = dx.min(self.width - dx);
+            dy = dy.min(self.height - dy);
+        }
+        dx.hypot(dy)
+    }
+}
+
+/// Which pairs of neurons are connected. Structured topologies span all neurons in index order
+/// and only take the weights from the projections: pairs whose populations have no projection
+/// stay unconnected, and the projection probabilities are ignored.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub enum Topology {
+    /// Every pair is connected independently with the probability of its projection.
+    #[default]
+    Random,
+    /// Watts–Strogatz ring lattice: every neuron projects onto its `neighbors / 2` nearest
+    /// neighbors on either side, and each synapse is rewired to a random target with probability
+    /// `rewire`.
+    SmallWorld { neighbors: usize, rewire: f64 },
+    /// Barabási–Albert preferential attachment: neurons join one by one and each links to
+    /// `attachment` existing ones with probability proportional to their degree. Links are
+    /// synapses in both directions, and neurons join in random order so hubs can be of any
+    /// population.
+    ScaleFree { attachment: usize },
+}
+
+impl Topology {
+    /// Sorted targets of every neuron, or `None` for [`Topology::Random`].
+    fn graph<R: Rng + ?Sized>(&self, n: usize, rng: &mut R) -> Option<Vec<Vec<usize>>> {
+        match *self {
+            Topology::Random => None,
+            Topology::SmallWorld { neighbors, rewire } => {
+                let half = (neighbors / 2).min(n.saturating_sub(1) / 2);
+                Some(
+                    (0..n)
+                        .map(|pre| {
+                            let mut targets: Vec<usize> = (1..=half)
+                                .flat_map(|offset| [(pre + offset) % n, (pre + n - offset) % n])
+                                .collect();
+                            for k in 0..targets.len() {
+                                // rewiring needs a target that is neither `pre` nor taken
+                                if targets.len() + 1 < n && rng.gen::<f64>() < rewire {
+                                    targets[k] = loop {
+                                        let post = rng.gen_range(0..n);
+                                        if post != pre && !targets.contains(&post) {
+                                            break post;
+                                        }
+                                    };
+                                }
+                            }
+                            targets.sort_unstable();
+                            targets
+                        })
+                        .collect(),
+                )
+            }
+            Topology::ScaleFree { attachment } => {
+                let m = attachment.min(n.saturating_sub(1));
+                let mut order: Vec<usize> = (0..n).collect();
+                order.shuffle(rng);
+                let mut targets = vec![vec![]; n];
+                // every neuron appears once per link, so uniform draws are degree-proportional
+                let mut endpoints = vec![];
+                let mut link = |a: usize, b: usize, endpoints: &mut Vec<usize>| {
+                    targets[a].push(b);
+                    targets[b].push(a);
+                    endpoints.extend([a, b]);
+                };
+                // fully connected seed of `m + 1` neurons
+                for (k, &a) in order.iter().enumerate().take(m + 1) {
+                    for &b in &order[..k] {
+                        link(a, b, &mut endpoints);
+                    }
+                }
+                for &new in order.iter().skip(m + 1) {
+                    let mut chosen: Vec<usize> = Vec::with_capacity(m);
+                    while chosen.len() < m {
+                        let old = endpoints[rng.gen_range(0..endpoints.len())];
+                        if !chosen.contains(&old) {
+                            chosen.push(old);
+                        }
+                    }
+                    for old in chosen {
+                        link(new, old, &mut endpoints);
+                    }
+                }
+                for row in &mut targets {
+                    row.sort_unstable();
+                }
+                Some(targets)
+            }
+        }
+    }
+}
+
+/// How one population connects onto another.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Projection {
+    /// Probability that any given pair of distinct neurons is connected, at distance 0 if
+    /// `probability_kernel` is set.
+    pub probability: f64,
+    pub weight: WeightDistribution,
+    /// Fall-off of the connection probability with distance on the [`Sheet`].
+    pub probability_kernel: Option<DistanceKernel>,
+    /// Fall-off of the weight magnitude with distance on the [`Sheet`].
+    pub weight_kernel: Option<DistanceKernel>,
+    /// Range of whole milliseconds the delays are drawn from, overriding the one of the world.
+    pub delay_range: Option<(u32, u32)>,
+    /// How spikes along this projection turn into synaptic current, overriding the model of the
+    /// world, e.g. for conductance-based inhibition alongside current-based excitation.
+    pub synapse_model: Option<SynapseModel>,
+}
+
+impl Projection {
+    /// Distance-independent projection.
+    ///
+    /// # Panics
+    ///
+    /// If `probability` does not lie in `[0, 1]`.
+    pub fn new(probability: f64, weight: WeightDistribution) -> Self {
+        assert!(
+            (0.0..=1.0).contains(&probability),
+            "the connection probability must lie in [0, 1]"
+        );
+        Projection {
+            probability,
+            weight,
+            probability_kernel: None,
+            weight_kernel: None,
+            delay_range: None,
+            synapse_model: None,
+        }
+    }
+
+    pub fn probability_kernel(mut self, kernel: DistanceKernel) -> Self {
+        self.probability_kernel = Some(kernel);
+        self
+    }
+
+    pub fn weight_kernel(mut self, kernel: DistanceKernel) -> Self {
+        self.weight_kernel = Some(kernel);
+        self
+    }
+
+    /// Draws the delays of this projection uniformly from `min..=max` whole milliseconds.
+    ///
+    /// # Panics
+    ///
+    /// If `min` is below 1 ms or above `max`.
+    pub fn delay_range(mut self, min: u32, max: u32) -> Self {
+        assert!(
+            min >= 1 && min <= max,
+            "the delays must lie in a range of at least 1 ms"
+        );
+        self.delay_range = Some((min, max));
+        self
+    }
+
+    /// Makes this projection follow `synapse_model` instead of the model of the world.
+    ///
+    /// # Panics
+    ///
+    /// If the time constants of [`SynapseModel::Exponential`] are not valid, see
+    /// [`crate::ExponentialParameters::is_valid`].
+    pub fn synapse_model(mut self, synapse_model: SynapseModel) -> Self {
+        if let SynapseModel::Exponential(params) = &synapse_model {
+            assert!(
+                params.is_valid(),
+                "synaptic rise time constants must be non-negative and shorter than the decay"
+            );
+        }
+        self.synapse_model = Some(synapse_model);
+        self
+    }
+}
+
+/// Describes the populations of a network and the projections between them, for
+/// [`crate::WorldStateBuilder::network`]. Neurons are numbered population by population in the
+/// order they were added.
+#[derive(Clone, Debug, Default)]
+pub struct NetworkBuilder {
+    populations: Vec<Population>,
+    /// Projection from population `pre` onto population `post`, indexed `[pre][post]`.
+    projections: Vec<Vec<Option<Projection>>>,
+    sheet: Option<Sheet>,
+    /// Conduction velocity in sheet units per millisecond, deriving delays from distances.
+    conduction_velocity: Option<f64>,
+    topology: Topology,
+    refractory_periods: RefractoryPeriods,
+}
+
+impl NetworkBuilder {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// The network of the paper: all pairs connected with `connection_probability`, excitatory
+    /// weights uniform in `[0, 0.5)` and inhibitory ones in `(-1, 0]`.
+    pub fn paper(excitatory: usize, inhibitory: usize, connection_probability: f64) -> Self {
+        let from_excitatory = Projection::new(
+            connection_probability,
+            WeightDistribution::Uniform { min: 0., max: 0.5 },
+        );
+        let from_inhibitory = Projection::new(
+            connection_probability,
+            WeightDistribution::Uniform { min: 0., max: 1. },
+        );
+        NetworkBuilder::new()
+            .population(Population::new(
+                "excitatory",
+                excitatory,
+                SynapseType::Excitatory,
+            ))
+            .population(Population::new(
+                "inhibitory",
+                inhibitory,
+                SynapseType::Inhibitory,
+            ))
+            .connect("excitatory", "excitatory", from_excitatory.clone())
+            .connect("excitatory", "inhibitory", from_excitatory)
+            .connect("inhibitory", "excitatory", from_inhibitory.clone())
+            .connect("inhibitory", "inhibitory", from_inhibitory)
+    }
+
+    /// The network of Izhikevich (2006), "Polychronization: computation with spikes": every
+    /// neuron projects onto 100 others on average, with excitatory weights of 6 and delays of
+    /// 1–20 ms onto any neuron, and inhibitory weights of 5 and delays of 1 ms onto excitatory
+    /// neurons only. The inhibitory neurons are fast spiking.
+    pub fn polychronization(excitatory: usize, inhibitory: usize) -> Self {
+        let n = excitatory + inhibitory;
+        let from_excitatory = Projection::new(
+            (100. / n.saturating_sub(1) as f64).min(1.),
+            WeightDistribution::Constant(6.),
+        )
+        .delay_range(1, 20);
+        let from_inhibitory = Projection::new(
+            (100. / excitatory as f64).min(1.),
+            WeightDistribution::Constant(5.),
+        )
+        .delay_range(1, 1);
+        NetworkBuilder::new()
+            .population(Population::new(
+                "excitatory",
+                excitatory,
+                SynapseType::Excitatory,
+            ))
+            .population(Population::of_type(
+                "inhibitory",
+                inhibitory,
+                NeuronType::FastSpiking,
+            ))
+            .connect("excitatory", "excitatory", from_excitatory.clone())
+            .connect("excitatory", "inhibitory", from_excitatory)
+            .connect("inhibitory", "excitatory", from_inhibitory)
+    }
+
+    /// The network of [`crate::TwoAlternativeChoice`]: two cue and two action populations of
+    /// `group` excitatory neurons each, every cue projecting onto both actions, and `inhibitory`
+    /// neurons driven by both actions and inhibiting them strongly so that one action wins. Every
+    /// neuron receives 25 synapses on average from each population projecting onto it.
+    pub fn two_alternative_choice(group: usize, inhibitory: usize) -> Self {
+        let projection = |size: usize, max: f64| {
+            Projection::new(
+                (25. / size.max(1) as f64).min(1.),
+                WeightDistribution::Uniform { min: 0., max },
+            )
+        };
+        let mut network = NetworkBuilder::new();
+        for name in ["cue_0", "cue_1", "action_0", "action_1"] {
+            network = network.population(Population::new(name, group, SynapseType::Excitatory));
+        }
+        network = network.population(Population::new(
+            "inhibitory",
+            inhibitory,
+            SynapseType::Inhibitory,
+        ));
+        for action in ["action_0", "action_1"] {
+            network = network
+                .connect("cue_0", action, projection(group, 0.5))
+                .connect("cue_1", action, projection(group, 0.5))
+                .connect(action, "inhibitory", projection(group, 0.5))
+                .connect("inhibitory", action, projection(inhibitory, 8.));
+        }
+        network
+    }
+
+    /// Adds a population, initially unconnected.
+    pub fn population(mut self, population: Population) -> Self {
+        self.populations.push(population);
+        for row in &mut self.projections {
+            row.push(None);
+        }
+        self.projections.push(vec![None; self.populations.len()]);
+        self
+    }
+
+    /// Makes the population named `population` a mixture of fixed neuron types, see
+    /// [`Population::mixed`].
+    pub fn neuron_types(mut self, population: &str, neuron_types: Vec<(NeuronType, f64)>) -> Self {
+        let idx = self.index(population);
+        self.populations[idx].neuron_types = neuron_types;
+        self
+    }
+
+    /// Makes the neurons of the population named `population` follow another model than the
+    /// Izhikevich one, such as AdEx or leaky integrate-and-fire. They are drawn as before
+    /// otherwise, so a seeded network keeps its synapses.
+    pub fn dynamics(mut self, population: &str, dynamics: NeuronDynamics) -> Self {
+        let idx = self.index(population);
+        self.populations[idx].dynamics = Some(dynamics);
+        self
+    }
+
+    /// Connects the population named `pre` onto the one named `post`, replacing any previous
+    /// projection between them.
+    ///
+    /// # Panics
+    ///
+    /// If the weights of `projection` fail [`WeightDistribution::check`].
+    pub fn connect(mut self, pre: &str, post: &str, projection: Projection) -> Self {
+        if let Err(err) = projection.weight.check() {
+            panic!("{err}");
+        }
+        let (pre, post) = (self.index(pre), self.index(post));
+        self.projections[pre][post] = Some(projection);
+        self
+    }
+
+    /// Draws the weights of every projection from the population named `pre` from `weight`.
+    ///
+    /// # Panics
+    ///
+    /// If `weight` fails [`WeightDistribution::check`].
+    pub fn weights(mut self, pre: &str, weight: WeightDistribution) -> Self {
+        if let Err(err) = weight.check() {
+            panic!("{err}");
+        }
+        let pre = self.index(pre);
+        for projection in self.projections[pre].iter_mut().flatten() {
+            projection.weight = weight.clone();
+        }
+        self
+    }
+
+    /// Chooses which pairs of neurons are connected, see [`Topology`].
+    pub fn topology(mut self, topology: Topology) -> Self {
+        self.topology = topology;
+        self
+    }
+
+    /// Gives the neurons absolute refractory periods according to their type.
+    pub fn refractory_periods(mut self, refractory_periods: RefractoryPeriods) -> Self {
+        self.refractory_periods = refractory_periods;
+        self
+    }
+
+    /// Gives every neuron a position on `sheet`, needed by distance kernels.
+    pub fn sheet(mut self, sheet: Sheet) -> Self {
+        self.sheet = Some(sheet);
+        self
+    }
+
+    /// Derives every synaptic delay from the distance between the neurons on the sheet, as
+    /// `distance / velocity` milliseconds, instead of drawing it from the delay range.
+    pub fn conduction_velocity(mut self, velocity: f64) -> Self {
+        self.conduction_velocity = Some(velocity);
+        self
+    }
+
+    pub fn populations(&self) -> &[Population] {
+        &self.populations
+    }
+
+    fn index(&self, name: &str) -> usize {
+        self.populations
+            .iter()
+            .position(|population| population.name == name)
+            .unwrap_or_else(|| panic!("no population named `{name}`"))
+    }
+
+    /// Named index ranges of the populations.
+    pub fn ranges(&self) -> Vec<(String, Range<usize>)> {
+        let mut start = 0;
+        self.populations
+            .iter()
+            .map(|population| {
+                let range = start..start + population.size;
+                start = range.end;
+                (population.name.clone(), range)
+            })
+            .collect()
+    }
+
+    /// Distinct models of the projections that have their own, and for every synapse of
+    /// `connectivity` the index of its model plus one, or 0 if it follows the model of the world.
+    /// Both are empty if every projection follows the model of the world.
+    pub(crate) fn synapse_channels(
+        &self,
+        connectivity: &SparseConnectivity,
+    ) -> (Vec<SynapseModel>, Vec<usize>) {
+        let mut models: Vec<SynapseModel> = vec![];
+        let channels: Vec<Vec<usize>> = self
+            .projections
+            .iter()
+            .map(|row| {
+                row.iter()
+                    .map(|projection| {
+                        let Some(model) =
+                            projection.as_ref().and_then(|p| p.synapse_model.as_ref())
+                        else {
+                            return 0;
+                        };
+                        match models.iter().position(|known| known == model) {
+                            Some(index) => index + 1,
+                            None => {
+                                models.push(model.clone());
+                                models.len()
+                            }
+                        }
+                    })
+                    .collect()
+            })
+            .collect();
+        if models.is_empty() {
+            return (models, vec![]);
+        }
+        let population_of: Vec<usize> = self
+            .ranges()
+            .iter()
+            .enumerate()
+            .flat_map(|(i, (_, range))| range.clone().map(move |_| i))
+            .collect();
+        let mut synapse_channels = vec![0; connectivity.synapses()];
+        for (pre, &i) in population_of.iter().enumerate() {
+            for synapse in connectivity.row(pre) {
+                let post = connectivity.targets()[synapse];
+                synapse_channels[synapse] = channels[i][population_of[post]];
+            }
+        }
+        (models, synapse_channels)
+    }
+
+    /// Draws the neurons, their positions if there is a sheet, and the synapses, with delays
+    /// drawn uniformly from `delay_range` whole milliseconds, unless the projection has its own
+    /// range or they follow from the conduction velocity, and converted to steps of `dt`.
+    pub(crate) fn build<R: Rng + ?Sized>(
+        &self,
+        dt: f64,
+        delay_range: (u32, u32),
+        rng: &mut R,
+    ) -> (Neurons, Option<Vec<[f64; 2]>>, SparseConnectivity) {
+        let mut neurons = Neurons::new();
+        for population in &self.populations {
+            for neuron_type in population.draw_types(rng) {
+                let synapse_type = population.synapse_type.clone();
+                let mut neuron = match neuron_type {
+                    Some(neuron_type) => Neuron::new(neuron_type.params(), synapse_type.clone())
+                        .with_potential(neuron_type.initial_potential()),
+                    None => Neuron::from_synapse_type_with_rng(synapse_type.clone(), rng),
+                };
+                if let Some(dynamics) = &population.dynamics {
+                    neuron = dynamics.clone().neuron(synapse_type);
+                }
+                let period = self
+                    .refractory_periods
+                    .period(neuron_type, &population.synapse_type)
+                    .max(neuron.refractory_period);
+                neurons.push(neuron.with_refractory_period(period));
+            }
+        }
+
+        let positions: Option<Vec<[f64; 2]>> = self.sheet.as_ref().map(|sheet| {
+            (0..neurons.len())
+                .map(|_| {
+                    [
+                        rng.gen::<f64>() * sheet.width,
+                        rng.gen::<f64>() * sheet.height,
+                    ]
+                })
+                .collect()
+        });
+        let distance = |pre: usize, post: usize| {
+            let (sheet, positions) = self
+                .sheet
+                .as_ref()
+                .zip(positions.as_ref())
+                .expect("distance kernels need the neurons placed on a sheet");
+            sheet.distance(positions[pre], positions[post])
+        };
+
+        let ranges = self.ranges();
+        let expected_synapses: f64 = ranges
+            .iter()
+            .enumerate()
+            .flat_map(|(i, (_, pre))| {
+                ranges.iter().enumerate().filter_map(move |(j, (_, post))| {
+                    let projection = self.projections[i][j].as_ref()?;
+                    Some(pre.len() as f64 * post.len() as f64 * projection.probability)
+                })
+            })
+            .sum();
+        let mut connectivity =
+            SparseConnectivity::with_capacity(neurons.len(), expected_synapses as usize);
+
+        // inhibitory connections actually inhibit
+        let signs: Vec<f64> = self
+            .populations
+            .iter()
+            .map(|population| match population.synapse_type {
+                SynapseType::Excitatory => 1.,
+                SynapseType::Inhibitory => -1.,
+            })
+            .collect();
+        let synapse = |rng: &mut R, pre: usize, post: usize, i: usize, projection: &Projection| {
+            let mut weight = signs[i] * projection.weight.sample(rng);
+            if let Some(kernel) = &projection.weight_kernel {
+                weight *= kernel.factor(distance(pre, post));
+            }
+            let delay_ms = match self.conduction_velocity {
+                Some(velocity) => distance(pre, post) / velocity,
+                None => {
+                    let (min, max) = projection.delay_range.unwrap_or(delay_range);
+                    rng.gen_range(min..=max) as f64
+                }
+            };
+            // a spike can arrive at the next step at the earliest
+            let delay = ((delay_ms / dt).round() as u32).max(1);
+            (weight, delay)
+        };
+
+        if let Some(graph) = self.topology.graph(neurons.len(), rng) {
+            let population_of: Vec<usize> = ranges
+                .iter()
+                .enumerate()
+                .flat_map(|(i, (_, range))| range.clone().map(move |_| i))
+                .collect();
+            for (pre, targets) in graph.iter().enumerate() {
+                let i = population_of[pre];
+                for &post in targets {
+                    if let Some(projection) = &self.projections[i][population_of[post]] {
+                        let (weight, delay) = synapse(rng, pre, post, i, projection);
+                        connectivity.push(post, weight, delay);
+                    }
+                }
+                connectivity.finish_row();
+            }
+            return (neurons, positions, connectivity);
+        }
+
+        for (i, (_, pre_range)) in ranges.iter().enumerate() {
+            for pre in pre_range.clone() {
+                // skipping ahead by geometrically distributed gaps only visits the synapses that
+                // exist; gaps are memoryless, so one carries over into the next population when
+                // the probability stays the same
+                let mut post = 0;
+                let mut carried: Option<f64> = None;
+                for (j, (_, post_range)) in ranges.iter().enumerate() {
+                    let Some(projection) = &self.projections[i][j] else {
+                        carried = None;
+                        continue;
+                    };
+                    let gaps = Geometric::new(projection.probability)
+                        .expect("connection probability must lie in [0, 1]");
+                    if carried != Some(projection.probability) {
+                        post = post_range.start.saturating_add(gaps.sample(rng) as usize);
+                    }
+                    while post < post_range.end {
+                        // thinning the gaps by the kernel yields the distance-dependent probability
+                        let connected = match &projection.probability_kernel {
+                            Some(kernel) => rng.gen::<f64>() < kernel.factor(distance(pre, post)),
+                            None => true,
+                        };
+                        // don't allow self-connections
+                        if connected && post != pre {
+                            let (weight, delay) = synapse(rng, pre, post, i, projection);
+                            connectivity.push(post, weight, delay);
+                        }
+                        post = post.saturating_add(1 + gaps.sample(rng) as usize);
+                    }
+                    carried = Some(projection.probability);
+                }
+                connectivity.finish_row();
+            }
+        }
+        (neurons, positions, connectivity)
+    }
+}
+
+/// `(pre, post, weight, delay)` of a synapse, with a signed weight and the delay in
+/// milliseconds.
+pub type Synapse = (usize, usize, f64, f64);
+
+/// A network given neuron by neuron and synapse by synapse, e.g. read from the files of another
+/// simulator, for [`crate::WorldStateBuilder::instantiated_network`].
+#[derive(Clone, Debug, Default)]
+pub struct InstantiatedNetwork {
+    /// Named index ranges of the populations.
+    pub populations: Vec<(String, Range<usize>)>,
+    pub neurons: Neurons,
+    pub positions: Option<Vec<[f64; 2]>>,
+    pub synapses: Vec<Synapse>,
+}
+
+impl InstantiatedNetwork {
+    /// Sorts the synapses into rows by presynaptic neuron, converting the delays to steps of
+    /// `dt`.
+    pub(crate) fn build(self, dt: f64) -> (Neurons, Option<Vec<[f64; 2]>>, SparseConnectivity) {
+        let connectivity = SparseConnectivity::from_synapses(self.neurons.len(), self.synapses, dt);
+        (self.neurons, self.positions, connectivity)
+    }
+}
@@ -0,0 +1,216 @@
+use std::collections::{BTreeMap, HashMap};
+use std::io::{self, Write};
+
+use crate::neuron::NeuronParameters;
+use crate::simulator::WorldState;
+use crate::synapse::SynapseModel;
+
+/// NeuroML populations are homogeneous, so neurons of a population of the network are split
+/// into one NeuroML population per distinct parameter set.
+struct Layout {
+    /// Id, cell parameters and neurons of every NeuroML population.
+    populations: Vec<(String, NeuronParameters, Vec<usize>)>,
+    /// NeuroML population and index within it of every neuron.
+    location: Vec<(usize, usize)>,
+}
+
+impl Layout {
+    fn new(world: &WorldState) -> Self {
+        let mut populations: Vec<(String, NeuronParameters, Vec<usize>)> = vec![];
+        let mut location = vec![(0, 0); world.neurons.len()];
+        for (name, range) in world.populations() {
+            let name = nml_id(&name);
+            let mut by_parameters = HashMap::new();
+            let first = populations.len();
+            for idx in range {
+                let parameters = world.neurons.parameters(idx);
+                let key =
+                    [parameters.a, parameters.b, parameters.c, parameters.d].map(f64::to_bits);
+                let population = *by_parameters.entry(key).or_insert_with(|| {
+                    populations.push((String::new(), parameters, vec![]));
+                    populations.len() - 1
+                });
+                location[idx] = (population, populations[population].2.len());
+                populations[population].2.push(idx);
+            }
+            let split = populations.len() - first > 1;
+            for (k, population) in populations[first..].iter_mut().enumerate() {
+                population.0 = if split {
+                    format!("{name}_{k}")
+                } else {
+                    name.clone()
+                };
+            }
+        }
+        Layout {
+            populations,
+            location,
+        }
+    }
+
+    /// LEMS path of neuron `idx`.
+    fn path(&self, idx: usize) -> String {
+        let (population, local) = self.location[idx];
+        format!("{}[{local}]", self.populations[population].0)
+    }
+}
+
+/// Makes a population name a valid NeuroML id.
+fn nml_id(name: &str) -> String {
+    let id: String = name
+        .chars()
+        .map(|c| if c.is_ascii_alphanumeric() { c } else { '_' })
+        .collect();
+    match id.chars().next() {
+        Some(c) if !c.is_ascii_digit() => id,
+        _ => format!("_{id}"),
+    }
+}
+
+/// Writes the neurons and current weights of `world` as a NeuroML 2 document, with neurons as
+/// `izhikevichCell`s starting from rest at -65 mV and synapses injecting their weight as a current
+/// pulse over one time step, the custom `deltaCurrentSynapseDL`. Thalamic noise, stimuli,
+/// plasticity and refractory periods are not part of the export, and only current-based synapses
+/// are supported.
+pub fn write_neuroml<W: Write>(mut writer: W, world: &WorldState) -> io::Result<()> {
+    if world.synapse_model != SynapseModel::Current
+        || world.synaptic_state.projection_models().next().is_some()
+        || world.synaptic_state.nmda().is_some()
+    {
+        return Err(io::Error::new(
+            io::ErrorKind::InvalidInput,
+            "NeuroML export supports current-based synapses only",
+        ));
+    }
+    if world.neurons.has_dynamics() {
+        return Err(io::Error::new(
+            io::ErrorKind::InvalidInput,
+            "NeuroML export supports Izhikevich neurons only",
+        ));
+    }
+    let layout = Layout::new(world);
+
+    writeln!(writer, r#"<?xml version="1.0" encoding="UTF-8"?>"#)?;
+    writeln!(
+        writer,
+        r#"<neuroml xmlns="http://www.neuroml.org/schema/neuroml2" id="izhikevich_rs">"#
+    )?;
+    writeln!(
+        writer,
+        r#"    <ComponentType name="deltaCurrentSynapseDL" extends="basePointCurrentDL" description="Injects its weight as a current pulse of the given duration">
+        <Property name="weight" dimension="none" defaultValue="1"/>
+        <Parameter name="duration" dimension="time"/>
+        <Constant name="tscale" dimension="time" value="1ms"/>
+        <EventPort name="in" direction="in"/>
+        <Dynamics>
+            <StateVariable name="I" dimension="none" exposure="I"/>
+            <StateVariable name="tsince" dimension="time"/>
+            <TimeDerivative variable="tsince" value="1"/>
+            <OnEvent port="in">
+                <StateAssignment variable="I" value="weight * tscale / duration"/>
+                <StateAssignment variable="tsince" value="0"/>
+            </OnEvent>
+            <OnCondition test="tsince .geq. duration">
+                <StateAssignment variable="I" value="0"/>
+            </OnCondition>
+        </Dynamics>
+    </ComponentType>"#
+    )?;
+    writeln!(
+        writer,
+        r#"    <deltaCurrentSynapseDL id="synapse" duration="{}ms"/>"#,
+        world.dt
+    )?;
+    for (id, parameters, _) in &layout.populations {
+        writeln!(
+            writer,
+            r#"    <izhikevichCell id="{id}_cell" v0="-65mV" thresh="30mV" a="{}" b="{}" c="{}" d="{}"/>"#,
+            parameters.a, parameters.b, parameters.c, parameters.d
+        )?;
+    }
+
+    writeln!(writer, r#"    <network id="network">"#)?;
+    for (id, _, neurons) in &layout.populations {
+        writeln!(
+            writer,
+            r#"        <population id="{id}" component="{id}_cell" size="{}"/>"#,
+            neurons.len()
+        )?;
+    }
+    let connectivity = &world.connectivity;
+    let mut projections: BTreeMap<(usize, usize), Vec<usize>> = BTreeMap::new();
+    for pre in 0..connectivity.neurons() {
+        for synapse in connectivity.row(pre) {
+            let post = connectivity.targets()[synapse];
+            projections
+                .entry((layout.location[pre].0, layout.location[post].0))
+                .or_default()
+                .push(synapse);
+        }
+    }
+    // the presynaptic neuron of each synapse, as rows only give the reverse mapping
+    let pre_of: Vec<usize> = (0..connectivity.neurons())
+        .flat_map(|pre| connectivity.row(pre).map(move |_| pre))
+        .collect();
+    for ((pre_population, post_population), synapses) in projections {
+        let pre_id = &layout.populations[pre_population].0;
+        let post_id = &layout.populations[post_population].0;
+        writeln!(
+            writer,
+            r#"        <projection id="{pre_id}_to_{post_id}" presynapticPopulation="{pre_id}" postsynapticPopulation="{post_id}" synapse="synapse">"#
+        )?;
+        for (k, synapse) in synapses.into_iter().enumerate() {
+            let post = connectivity.targets()[synapse];
+            writeln!(
+                writer,
+                r#"            <connectionWD id="{k}" preCellId="../{}" postCellId="../{}" weight="{}" delay="{}ms"/>"#,
+                layout.path(pre_of[synapse]),
+                layout.path(post),
+                connectivity.weights()[synapse],
+                connectivity.delays()[synapse] as f64 * world.dt
+            )?;
+        }
+        writeln!(writer, "        </projection>")?;
+    }
+    writeln!(writer, "    </network>")?;
+    writeln!(writer, "</neuroml>")?;
+    writer.flush()
+}
+
+/// Writes a LEMS simulation of the document written by [`write_neuroml`] to `neuroml_file` that
+/// runs for `duration` milliseconds with the time step of `world`, e.g. with `jnml`. The spikes
+/// are written to `spikes.dat` as `time id` rows with times in seconds and the neuron indices of
+/// `world` as ids.
+pub fn write_lems_simulation<W: Write>(
+    mut writer: W,
+    world: &WorldState,
+    neuroml_file: &str,
+    duration: f64,
+) -> io::Result<()> {
+    let layout = Layout::new(world);
+    writeln!(writer, "<Lems>")?;
+    writeln!(writer, r#"    <Target component="simulation"/>"#)?;
+    for include in ["Cells.xml", "Networks.xml", "Simulation.xml", neuroml_file] {
+        writeln!(writer, r#"    <Include file="{include}"/>"#)?;
+    }
+    writeln!(
+        writer,
+        r#"    <Simulation id="simulation" length="{duration}ms" step="{}ms" target="network">"#,
+        world.dt
+    )?;
+    writeln!(
+        writer,
+        r#"        <EventOutputFile id="spikes" fileName="spikes.dat" format="TIME_ID">"#
+    )?;
+    for idx in 0..world.neurons.len() {
+        writeln!(
+            writer,
+            r#"            <EventSelection id="{idx}" select="{}" eventPort="spike"/>"#,
+            layout.path(idx)
+        )?;
+    }
+    writeln!(writer, "        </EventOutputFile>")?;
+    writeln!(writer, "    </Simulation>")?;
+    writeln!(writer, "</Lems>")?;
+    writer.flush()
+}
@@ -0,0 +1,119 @@
+use std::ops::Range;
+use std::str::FromStr;
+
+use serde::{Deserialize, Serialize};
+
+/// Property of the neurons a [`Neuromodulator`] scales.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub enum ModulatedVariable {
+    /// Synaptic and stimulus input, like acetylcholine raising the responsiveness to afferents.
+    InputGain,
+    /// Amplitude of the thalamic noise, like the arousal drive of noradrenaline.
+    NoiseAmplitude,
+    /// After-spike increment `d` of the recovery variable, i.e. spike frequency adaptation,
+    /// which acetylcholine and noradrenaline reduce during waking. Only Izhikevich neurons
+    /// adapt.
+    Adaptation,
+}
+
+impl FromStr for ModulatedVariable {
+    type Err = String;
+
+    /// Parses `gain`, `noise` or `adaptation`, ignoring case.
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_ascii_lowercase().as_str() {
+            "gain" => Ok(ModulatedVariable::InputGain),
+            "noise" => Ok(ModulatedVariable::NoiseAmplitude),
+            "adaptation" => Ok(ModulatedVariable::Adaptation),
+            _ => Err(format!(
+                "unknown modulated variable `{s}`, expected gain, noise or adaptation"
+            )),
+        }
+    }
+}
+
+/// Neuromodulatory signal such as acetylcholine or noradrenaline, whose level over time scales
+/// one property of a group of neurons, so that brain-state transitions like those between sleep
+/// and waking can be scheduled. Several modulators of the input or noise of the same neurons
+/// multiply, while the adaptation follows the last one added.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct Neuromodulator {
+    /// Neurons the signal reaches.
+    pub neurons: Range<usize>,
+    pub variable: ModulatedVariable,
+    /// `(time, level)` points in milliseconds, sorted by time, between which the level is
+    /// interpolated linearly, holding the first level before and the last after them.
+    pub schedule: Vec<(f64, f64)>,
+    /// Unmodulated `d` of the neurons, taken when the modulator is attached.
+    baseline: Vec<f64>,
+}
+
+impl Neuromodulator {
+    /// Modulator of `variable` of `neurons` following `schedule`, in any order.
+    ///
+    /// # Panics
+    ///
+    /// If `schedule` is empty.
+    pub fn new(
+        neurons: Range<usize>,
+        variable: ModulatedVariable,
+        mut schedule: Vec<(f64, f64)>,
+    ) -> Self {
+        assert!(!schedule.is_empty(), "a neuromodulator needs a schedule");
+        schedule.sort_by(|a, b| a.0.total_cmp(&b.0));
+        Neuromodulator {
+            neurons,
+            variable,
+            schedule,
+            baseline: vec![],
+        }
+    }
+
+    /// Level of the signal at `time` milliseconds.
+    pub fn level(&self, time: f64) -> f64 {
+        let after = self.schedule.partition_point(|&(t, _)| t <= time);
+        match (after.checked_sub(1), self.schedule.get(after)) {
+            (None, _) => self.schedule[0].1,
+            (Some(last), None) => self.schedule[last].1,
+            (Some(before), Some(&(t1, l1))) => {
+                let (t0, l0) = self.schedule[before];
+                l0 + (l1 - l0) * (time - t0) / (t1 - t0)
+            }
+        }
+    }
+
+    /// Takes the unmodulated `d` of the neurons from `d`, the increments of the whole network.
+    pub(crate) fn attach(&mut self, d: &[f64]) {
+        self.baseline = d[self.neurons.clone()].to_vec();
+    }
+
+    /// Scales the `thalamic_input` and `input_current` of this step at `time`, or sets the
+    /// adaptation `d`, of the network.
+    pub(crate) fn apply(
+        &self,
+        time: f64,
+        thalamic_input: &mut [f64],
+        input_current: &mut [f64],
+        d: &mut [f64],
+    ) {
+        let level = self.level(time);
+        let neurons = self.neurons.clone();
+        match self.variable {
+            ModulatedVariable::InputGain => {
+                for current in &mut input_current[neurons] {
+                    *current *= level;
+                }
+            }
+            ModulatedVariable::NoiseAmplitude => {
+                for input in &mut thalamic_input[neurons] {
+                    *input *= level;
+                }
+            }
+            ModulatedVariable::Adaptation => {
+                for (d, baseline) in d[neurons].iter_mut().zip(&self.baseline) {
+                    *d = baseline * level;
+                }
+            }
+        }
+    }
+}
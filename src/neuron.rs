@@ -1,6 +1,18 @@
+use std::ops::Range;
+use std::str::FromStr;
+
 use rand::Rng;
+use rayon::prelude::*;
+use serde::{Deserialize, Serialize};
+
+use crate::adex::AdExParameters;
+use crate::hodgkin_huxley::{HodgkinHuxley, HodgkinHuxleyParameters};
+use crate::integrator::{Integrator, Izhikevich};
+use crate::izhikevich2007::Izhikevich2007Parameters;
+use crate::lif::LifParameters;
+use crate::two_compartment::{TwoCompartment, TwoCompartmentParameters};
 
-#[derive(Clone)]
+#[derive(Clone, Debug, PartialEq)]
 pub struct NeuronParameters {
     /// Represents the time scale of the recovery variable u.
     pub a: f64,
@@ -12,28 +24,80 @@ pub struct NeuronParameters {
     pub d: f64,
 }
 
-#[derive(Clone, Eq, PartialEq)]
+#[derive(Clone, Debug, Eq, PartialEq, Serialize, Deserialize)]
 pub enum SynapseType {
     Excitatory,
     Inhibitory,
 }
+
+impl FromStr for SynapseType {
+    type Err = String;
+
+    /// Parses `excitatory` or `inhibitory`, or their initials, ignoring case.
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_ascii_lowercase().as_str() {
+            "excitatory" | "e" => Ok(SynapseType::Excitatory),
+            "inhibitory" | "i" => Ok(SynapseType::Inhibitory),
+            _ => Err(format!(
+                "unknown synapse type `{s}`, expected excitatory or inhibitory"
+            )),
+        }
+    }
+}
+
+impl SynapseType {
+    /// Strength of the thalamic input onto neurons of this type.
+    fn thalamic_gain(&self) -> f64 {
+        // excitatory synapses have stronger strengths to the input
+        match self {
+            SynapseType::Excitatory => 5.,
+            SynapseType::Inhibitory => 2.,
+        }
+    }
+}
+
+#[derive(Clone, Debug)]
 pub struct Neuron {
     // Izhikevich morphology parameters
     parameters: NeuronParameters,
     /// Excitatory or inhibitory, used for scaling thalamic input
     pub synapse_type: SynapseType,
-    /// Pairwise weights to other neurons
-    connection_weights: Vec<f64>,
     /// Membrane potential.
     pub v: f64,
     /// Membrane recovery variable.
     pub u: f64,
+    /// Absolute refractory period after each spike in milliseconds, zero for none.
+    pub refractory_period: f64,
+    /// Time left of the current refractory period in milliseconds.
+    pub refractory: f64,
+    /// Model the neuron follows instead of the Izhikevich model, whose parameters are then
+    /// ignored.
+    pub dynamics: Option<Box<NeuronDynamics>>,
 }
 
 impl Neuron {
+    /// Neuron with explicit `parameters`, starting at rest at -65 mV.
+    pub fn new(parameters: NeuronParameters, synapse_type: SynapseType) -> Self {
+        Neuron {
+            u: parameters.b * -65.,
+            parameters,
+            synapse_type,
+            v: -65.,
+            refractory_period: 0.,
+            refractory: 0.,
+            dynamics: None,
+        }
+    }
+
     pub fn from_synapse_type(synapse_type: SynapseType) -> Self {
-        let mut rng = rand::thread_rng();
+        Self::from_synapse_type_with_rng(synapse_type, &mut rand::thread_rng())
+    }
 
+    /// Like [`Neuron::from_synapse_type`], drawing the randomized parameters from `rng`.
+    pub fn from_synapse_type_with_rng<R: Rng + ?Sized>(
+        synapse_type: SynapseType,
+        rng: &mut R,
+    ) -> Self {
         let params = match synapse_type {
             SynapseType::Excitatory => {
                 let r = rng.gen::<f64>();
@@ -61,9 +125,11 @@ impl Neuron {
         Neuron {
             parameters: params.clone(),
             synapse_type,
-            connection_weights: vec![],
             v: -65.,
             u: params.b * -65.,
+            refractory_period: 0.,
+            refractory: 0.,
+            dynamics: None,
         }
     }
 
@@ -72,55 +138,718 @@ impl Neuron {
 
         let synapse_type = neuron_type.into();
 
+        Neuron::new(parameters, synapse_type).with_potential(neuron_type.initial_potential())
+    }
+
+    /// AdEx neuron starting at rest at the leak reversal potential without adaptation, see
+    /// [`NeuronDynamics::neuron`].
+    pub fn adex(parameters: AdExParameters, synapse_type: SynapseType) -> Self {
+        NeuronDynamics::AdEx(parameters).neuron(synapse_type)
+    }
+
+    /// Leaky integrate-and-fire neuron starting at rest with the refractory period of its
+    /// parameters, see [`NeuronDynamics::neuron`].
+    pub fn lif(parameters: LifParameters, synapse_type: SynapseType) -> Self {
+        NeuronDynamics::Lif(parameters).neuron(synapse_type)
+    }
+
+    /// Hodgkin–Huxley neuron at rest with its gates at their steady states, see
+    /// [`NeuronDynamics::neuron`].
+    pub fn hodgkin_huxley(parameters: HodgkinHuxleyParameters, synapse_type: SynapseType) -> Self {
+        NeuronDynamics::HodgkinHuxley(parameters.resting_state()).neuron(synapse_type)
+    }
+
+    /// Neuron of the 2007 simple model at its resting potential without recovery, see
+    /// [`NeuronDynamics::neuron`].
+    pub fn izhikevich2007(parameters: Izhikevich2007Parameters, synapse_type: SynapseType) -> Self {
+        NeuronDynamics::Izhikevich2007(parameters).neuron(synapse_type)
+    }
+
+    /// Two-compartment neuron with soma and dendrite at rest, see [`NeuronDynamics::neuron`].
+    pub fn two_compartment(
+        parameters: TwoCompartmentParameters,
+        synapse_type: SynapseType,
+    ) -> Self {
+        NeuronDynamics::TwoCompartment(parameters.resting_state()).neuron(synapse_type)
+    }
+
+    pub fn parameters(&self) -> &NeuronParameters {
+        &self.parameters
+    }
+
+    /// Starts the neuron at the membrane potential `v` with the recovery variable at `b v`, its
+    /// steady state there. A thalamo-cortical neuron started hyperpolarized, e.g. at -87 mV,
+    /// fires a rebound burst.
+    pub fn with_potential(mut self, v: f64) -> Self {
+        self.v = v;
+        self.u = self.parameters.b * v;
+        self
+    }
+
+    /// Sets the absolute refractory period of the neuron in milliseconds.
+    pub fn with_refractory_period(mut self, refractory_period: f64) -> Self {
+        self.refractory_period = refractory_period;
+        self
+    }
+
+    /// Current resulting from a unit-variance thalamic noise sample over a step of `dt`.
+    pub fn thalamic_current(&self, thalamic_input: f64, dt: f64) -> f64 {
+        thalamic_input * self.synapse_type.thalamic_gain() / dt.sqrt()
+    }
+
+    /// Advances the neuron by `dt` milliseconds given the synaptic and stimulus current over the
+    /// step. The thalamic input is white noise, so it is scaled by `1 / sqrt(dt)`.
+    pub fn step(&mut self, thalamic_input: f64, input_current: f64, dt: f64) -> bool {
+        let i = self.thalamic_current(thalamic_input, dt) + input_current;
+        let (v, u, period) = (&mut self.v, &mut self.u, self.refractory_period);
+        let offset = match &mut self.dynamics {
+            Some(model) => step_refractory(&mut **model, v, u, i, dt, period, &mut self.refractory),
+            None => step_refractory(
+                &mut self.parameters,
+                v,
+                u,
+                i,
+                dt,
+                period,
+                &mut self.refractory,
+            ),
+        };
+        offset.is_some()
+    }
+}
+
+/// Dynamics of a point neuron with a membrane potential `v` and a recovery variable `u`, which
+/// [`Neuron`] and [`Neurons`] advance through this trait. Implemented by the parameters of one
+/// neuron, [`NeuronParameters`] for the Izhikevich model, which also hold any further state
+/// variables of a model.
+pub trait NeuronModel {
+    /// Advances `v` and `u` by `dt` milliseconds under the total input current `i`, without
+    /// testing for a spike.
+    fn integrate(&mut self, v: &mut f64, u: &mut f64, i: f64, dt: f64);
+
+    /// Whether `v` reached the spike cutoff.
+    fn fired(&self, v: f64) -> bool;
+
+    /// Resets the state after a spike.
+    fn reset(&mut self, v: &mut f64, u: &mut f64);
+
+    /// Holds `v` at the reset potential for a step of `dt` of an absolute refractory period,
+    /// while `u` keeps recovering.
+    fn hold(&mut self, v: &mut f64, u: &mut f64, dt: f64);
+
+    /// Moves a state without input that came within `tolerance` of the stable resting state onto
+    /// it, returning whether it did. The resting state must be a fixed point of [`integrate`], so
+    /// that event-driven stepping can skip the neuron. By default no state settles.
+    ///
+    /// [`integrate`]: NeuronModel::integrate
+    fn settle(&mut self, _v: &mut f64, _u: &mut f64, _tolerance: f64) -> bool {
+        false
+    }
+
+    /// Offset within a step that took `v` from `v_before` to `v_after` at which it crossed the
+    /// spike cutoff, as a fraction of the step. By default the end of the step, for models
+    /// without a fixed cutoff.
+    fn crossing(&self, _v_before: f64, _v_after: f64) -> f64 {
+        1.
+    }
+
+    /// Advances the state by `dt` milliseconds under the total input current `i`, returning
+    /// whether the neuron fired and was reset.
+    fn step(&mut self, v: &mut f64, u: &mut f64, i: f64, dt: f64) -> bool {
+        self.integrate(v, u, i, dt);
+        let fired = self.fired(*v);
+        if fired {
+            self.reset(v, u);
+        }
+        fired
+    }
+}
+
+impl NeuronModel for NeuronParameters {
+    fn integrate(&mut self, v: &mut f64, u: &mut f64, i: f64, dt: f64) {
+        // in 2 half steps for numerical stability
+        let half_dt = 0.5 * dt;
+        *v += half_dt * ((0.04 * *v * *v) + (5. * *v) + 140. - *u + i);
+        *v += half_dt * ((0.04 * *v * *v) + (5. * *v) + 140. - *u + i);
+        *u += dt * self.a * ((self.b * *v) - *u);
+    }
+
+    fn fired(&self, v: f64) -> bool {
+        // action potential
+        v >= 30.
+    }
+
+    fn crossing(&self, v_before: f64, v_after: f64) -> f64 {
+        interpolate_crossing(30., v_before, v_after)
+    }
+
+    fn reset(&mut self, v: &mut f64, u: &mut f64) {
+        *v = self.c;
+        *u += self.d;
+    }
+
+    fn hold(&mut self, v: &mut f64, u: &mut f64, dt: f64) {
+        *v = self.c;
+        *u += dt * self.a * ((self.b * *v) - *u);
+    }
+
+    fn settle(&mut self, v: &mut f64, u: &mut f64, tolerance: f64) -> bool {
+        // u = b v holds at rest, which is cheaper to check than the resting state
+        if (*u - self.b * *v).abs() > (1. + self.b) * tolerance {
+            return false;
+        }
+        match self.resting_potential() {
+            Some(rest)
+                if (*v - rest).abs() <= tolerance && (*u - self.b * rest).abs() <= tolerance =>
+            {
+                (*v, *u) = (rest, self.b * rest);
+                true
+            }
+            _ => false,
+        }
+    }
+}
+
+impl NeuronParameters {
+    /// Stable membrane potential without input, where `dv/dt` and `du/dt` vanish, or `None` if
+    /// the neuron has no resting state and fires tonically.
+    pub fn resting_potential(&self) -> Option<f64> {
+        // 0.04 v² + (5 - b) v + 140 = 0, whose lower root is stable
+        let slope = 5. - self.b;
+        let discriminant = slope * slope - 4. * 0.04 * 140.;
+        (discriminant >= 0.).then(|| (-slope - discriminant.sqrt()) / (2. * 0.04))
+    }
+    /// Recovery variable at which `dv/dt` vanishes at `v` under the current `i`, the parabola
+    /// whose knee the trajectory passes when the neuron fires.
+    pub fn v_nullcline(&self, v: f64, i: f64) -> f64 {
+        0.04 * v * v + 5. * v + 140. + i
+    }
+
+    /// Recovery variable at which `du/dt` vanishes at `v`.
+    pub fn u_nullcline(&self, v: f64) -> f64 {
+        self.b * v
+    }
+}
+
+/// Neuron models other than the Izhikevich one, see [`Neuron::dynamics`].
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub enum NeuronDynamics {
+    /// Adaptive exponential integrate-and-fire neuron.
+    AdEx(AdExParameters),
+    /// Leaky integrate-and-fire neuron.
+    Lif(LifParameters),
+    /// Hodgkin–Huxley neuron with its gating variables.
+    HodgkinHuxley(HodgkinHuxley),
+    /// Dimensional simple model of Izhikevich (2007).
+    Izhikevich2007(Izhikevich2007Parameters),
+    /// Izhikevich soma with a passive dendrite receiving the input, with its dendritic potential.
+    TwoCompartment(TwoCompartment),
+}
+
+impl NeuronDynamics {
+    /// Absolute refractory period the model requires in milliseconds, zero for none.
+    pub fn refractory_period(&self) -> f64 {
+        match self {
+            NeuronDynamics::AdEx(_) => 0.,
+            NeuronDynamics::Lif(parameters) => parameters.refractory,
+            NeuronDynamics::HodgkinHuxley(_) => 0.,
+            NeuronDynamics::Izhikevich2007(_) => 0.,
+            NeuronDynamics::TwoCompartment(_) => 0.,
+        }
+    }
+
+    /// Neuron following this model, starting at rest without adaptation. Its Izhikevich
+    /// parameters are those of the regular or fast spiking type of its synapse sign.
+    pub fn neuron(self, synapse_type: SynapseType) -> Neuron {
+        let neuron_type = match synapse_type {
+            SynapseType::Excitatory => NeuronType::Regular,
+            SynapseType::Inhibitory => NeuronType::FastSpiking,
+        };
+        let v = match &self {
+            NeuronDynamics::AdEx(parameters) => parameters.leak_reversal,
+            NeuronDynamics::Lif(parameters) => parameters.v_rest,
+            NeuronDynamics::HodgkinHuxley(_) => -65.,
+            NeuronDynamics::Izhikevich2007(parameters) => parameters.v_r,
+            NeuronDynamics::TwoCompartment(neuron) => neuron.parameters.resting_potential(),
+        };
+        let u = match &self {
+            NeuronDynamics::TwoCompartment(neuron) => neuron.parameters.b * v,
+            _ => 0.,
+        };
         Neuron {
-            parameters: parameters.clone(),
+            v,
+            u,
+            refractory_period: self.refractory_period(),
+            dynamics: Some(Box::new(self)),
+            ..Neuron::new(neuron_type.params(), synapse_type)
+        }
+    }
+}
+
+impl NeuronModel for NeuronDynamics {
+    fn integrate(&mut self, v: &mut f64, u: &mut f64, i: f64, dt: f64) {
+        match self {
+            NeuronDynamics::AdEx(parameters) => parameters.integrate(v, u, i, dt),
+            NeuronDynamics::Lif(parameters) => parameters.integrate(v, u, i, dt),
+            NeuronDynamics::HodgkinHuxley(neuron) => neuron.integrate(v, u, i, dt),
+            NeuronDynamics::Izhikevich2007(parameters) => parameters.integrate(v, u, i, dt),
+            NeuronDynamics::TwoCompartment(neuron) => neuron.integrate(v, u, i, dt),
+        }
+    }
+
+    fn fired(&self, v: f64) -> bool {
+        match self {
+            NeuronDynamics::AdEx(parameters) => parameters.fired(v),
+            NeuronDynamics::Lif(parameters) => parameters.fired(v),
+            NeuronDynamics::HodgkinHuxley(neuron) => neuron.fired(v),
+            NeuronDynamics::Izhikevich2007(parameters) => parameters.fired(v),
+            NeuronDynamics::TwoCompartment(neuron) => neuron.fired(v),
+        }
+    }
+
+    fn reset(&mut self, v: &mut f64, u: &mut f64) {
+        match self {
+            NeuronDynamics::AdEx(parameters) => parameters.reset(v, u),
+            NeuronDynamics::Lif(parameters) => parameters.reset(v, u),
+            NeuronDynamics::HodgkinHuxley(neuron) => neuron.reset(v, u),
+            NeuronDynamics::Izhikevich2007(parameters) => parameters.reset(v, u),
+            NeuronDynamics::TwoCompartment(neuron) => neuron.reset(v, u),
+        }
+    }
+
+    fn hold(&mut self, v: &mut f64, u: &mut f64, dt: f64) {
+        match self {
+            NeuronDynamics::AdEx(parameters) => parameters.hold(v, u, dt),
+            NeuronDynamics::Lif(parameters) => parameters.hold(v, u, dt),
+            NeuronDynamics::HodgkinHuxley(neuron) => neuron.hold(v, u, dt),
+            NeuronDynamics::Izhikevich2007(parameters) => parameters.hold(v, u, dt),
+            NeuronDynamics::TwoCompartment(neuron) => neuron.hold(v, u, dt),
+        }
+    }
+
+    fn settle(&mut self, v: &mut f64, u: &mut f64, tolerance: f64) -> bool {
+        match self {
+            NeuronDynamics::AdEx(parameters) => parameters.settle(v, u, tolerance),
+            NeuronDynamics::Lif(parameters) => parameters.settle(v, u, tolerance),
+            NeuronDynamics::HodgkinHuxley(neuron) => neuron.settle(v, u, tolerance),
+            NeuronDynamics::Izhikevich2007(parameters) => parameters.settle(v, u, tolerance),
+            NeuronDynamics::TwoCompartment(neuron) => neuron.settle(v, u, tolerance),
+        }
+    }
+
+    fn crossing(&self, v_before: f64, v_after: f64) -> f64 {
+        match self {
+            NeuronDynamics::AdEx(parameters) => parameters.crossing(v_before, v_after),
+            NeuronDynamics::Lif(parameters) => parameters.crossing(v_before, v_after),
+            NeuronDynamics::HodgkinHuxley(neuron) => neuron.crossing(v_before, v_after),
+            NeuronDynamics::Izhikevich2007(parameters) => parameters.crossing(v_before, v_after),
+            NeuronDynamics::TwoCompartment(neuron) => neuron.crossing(v_before, v_after),
+        }
+    }
+}
+
+/// Fraction of a step from `before` to `after` at which `v` reached `cutoff`, assuming it rose
+/// linearly.
+pub(crate) fn interpolate_crossing(cutoff: f64, before: f64, after: f64) -> f64 {
+    if after > before {
+        ((cutoff - before) / (after - before)).clamp(0., 1.)
+    } else {
+        1.
+    }
+}
+
+/// Like [`NeuronModel::step`], but for the `refractory` milliseconds left after a spike `v` is
+/// held at the reset potential and the input ignored, see [`NeuronModel::hold`]. A spike starts a
+/// new period of `refractory_period`, which ends on the step closest to its duration. Returns the
+/// offset of a spike within the step, see [`NeuronModel::crossing`].
+fn step_refractory<M: NeuronModel + ?Sized>(
+    model: &mut M,
+    v: &mut f64,
+    u: &mut f64,
+    i: f64,
+    dt: f64,
+    refractory_period: f64,
+    refractory: &mut f64,
+) -> Option<f64> {
+    if *refractory > 0.5 * dt {
+        *refractory -= dt;
+        model.hold(v, u, dt);
+        return None;
+    }
+    let before = *v;
+    model.integrate(v, u, i, dt);
+    let offset = model.fired(*v).then(|| model.crossing(before, *v));
+    if offset.is_some() {
+        model.reset(v, u);
+    }
+    *refractory = if offset.is_some() {
+        refractory_period
+    } else {
+        0.
+    };
+    offset
+}
+
+/// Like [`step_refractory`] for event-driven stepping, also returning whether the neuron
+/// settled at rest, see [`NeuronModel::settle`].
+fn step_settling<M: NeuronModel + ?Sized>(
+    model: &mut M,
+    (v, u, refractory): (&mut f64, &mut f64, &mut f64),
+    i: f64,
+    dt: f64,
+    refractory_period: f64,
+    tolerance: f64,
+) -> (Option<f64>, bool) {
+    let offset = step_refractory(model, v, u, i, dt, refractory_period, refractory);
+    let settled = i == 0. && offset.is_none() && *refractory == 0. && model.settle(v, u, tolerance);
+    (offset, settled)
+}
+
+/// Neurons stored as a structure of arrays, so the update loop runs over contiguous slices.
+/// [`Neuron`] values are views of one index, read with [`Neurons::get`].
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+pub struct Neurons {
+    /// Membrane potentials.
+    pub v: Vec<f64>,
+    /// Membrane recovery variables.
+    pub u: Vec<f64>,
+    pub a: Vec<f64>,
+    pub b: Vec<f64>,
+    pub c: Vec<f64>,
+    pub d: Vec<f64>,
+    pub synapse_types: Vec<SynapseType>,
+    /// Absolute refractory periods in milliseconds.
+    pub refractory_period: Vec<f64>,
+    /// Time left of the current refractory periods in milliseconds.
+    pub refractory: Vec<f64>,
+    /// Models of the neurons that do not follow the Izhikevich model, see [`Neuron::dynamics`].
+    pub dynamics: Vec<Option<Box<NeuronDynamics>>>,
+    /// Offset of the last spike of every neuron within its step as a fraction of the step, see
+    /// [`NeuronModel::crossing`].
+    pub spike_offset: Vec<f64>,
+}
+
+impl Neurons {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn len(&self) -> usize {
+        self.v.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.v.is_empty()
+    }
+
+    pub fn push(&mut self, neuron: Neuron) {
+        let Neuron {
+            parameters,
             synapse_type,
-            connection_weights: vec![],
-            v: -65.,
-            u: parameters.b * -65.,
+            v,
+            u,
+            refractory_period,
+            refractory,
+            dynamics,
+        } = neuron;
+        self.v.push(v);
+        self.u.push(u);
+        self.a.push(parameters.a);
+        self.b.push(parameters.b);
+        self.c.push(parameters.c);
+        self.d.push(parameters.d);
+        self.synapse_types.push(synapse_type);
+        self.refractory_period.push(refractory_period);
+        self.refractory.push(refractory);
+        self.dynamics.push(dynamics);
+        self.spike_offset.push(1.);
+    }
+
+    /// Whether any neuron follows a model other than the Izhikevich one.
+    pub fn has_dynamics(&self) -> bool {
+        self.dynamics.iter().any(Option::is_some)
+    }
+
+    pub fn parameters(&self, idx: usize) -> NeuronParameters {
+        NeuronParameters {
+            a: self.a[idx],
+            b: self.b[idx],
+            c: self.c[idx],
+            d: self.d[idx],
         }
     }
 
-    pub fn connect(&mut self, connection_weights: Vec<f64>) {
-        self.connection_weights = connection_weights;
+    /// Copy of the state and parameters of neuron `idx`.
+    pub fn get(&self, idx: usize) -> Neuron {
+        Neuron {
+            parameters: self.parameters(idx),
+            synapse_type: self.synapse_types[idx].clone(),
+            v: self.v[idx],
+            u: self.u[idx],
+            refractory_period: self.refractory_period[idx],
+            refractory: self.refractory[idx],
+            dynamics: self.dynamics[idx].clone(),
+        }
     }
 
-    pub fn step(&mut self, thalamic_input: f64, synaptic_input: &[bool]) -> bool {
-        // excitatory synapses have stronger strengths to the input
-        let mut i = match self.synapse_type {
-            SynapseType::Excitatory => thalamic_input * 5.,
-            SynapseType::Inhibitory => thalamic_input * 2.,
-        };
+    pub fn iter(&self) -> impl Iterator<Item = Neuron> + '_ {
+        (0..self.len()).map(|idx| self.get(idx))
+    }
+
+    /// Current resulting from a unit-variance thalamic noise sample onto neuron `idx`, see
+    /// [`Neuron::thalamic_current`].
+    pub fn thalamic_current(&self, idx: usize, thalamic_input: f64, dt: f64) -> f64 {
+        thalamic_input * self.synapse_types[idx].thalamic_gain() / dt.sqrt()
+    }
 
-        i += synaptic_input
-            .iter()
-            .zip(&self.connection_weights)
-            .filter(|&(&spike, _)| spike)
-            .map(|(_, weight)| weight)
-            .sum::<f64>();
+    /// Advances every neuron in parallel like [`Neuron::step`], the Izhikevich ones with
+    /// `integrator`, returning which of them fired.
+    pub fn step(
+        &mut self,
+        thalamic_input: &[f64],
+        input_current: &[f64],
+        dt: f64,
+        integrator: Integrator,
+    ) -> Vec<bool> {
+        self.step_range(0..self.len(), thalamic_input, input_current, dt, integrator)
+    }
 
-        // in 2 time steps for numerical stability
-        self.v += 0.5 * ((0.04 * self.v * self.v) + (5. * self.v) + 140. - self.u + i);
-        self.v += 0.5 * ((0.04 * self.v * self.v) + (5. * self.v) + 140. - self.u + i);
-        self.u += self.parameters.a * ((self.parameters.b * self.v) - self.u);
+    /// Like [`Neurons::step`], but only advances `neurons`, whose spikes it returns, leaving the
+    /// others as they are. The inputs cover all neurons.
+    pub fn step_range(
+        &mut self,
+        neurons: Range<usize>,
+        thalamic_input: &[f64],
+        input_current: &[f64],
+        dt: f64,
+        integrator: Integrator,
+    ) -> Vec<bool> {
+        (
+            self.v[neurons.clone()].par_iter_mut(),
+            self.u[neurons.clone()].par_iter_mut(),
+            self.a[neurons.clone()].par_iter(),
+            self.b[neurons.clone()].par_iter(),
+            self.c[neurons.clone()].par_iter(),
+            self.d[neurons.clone()].par_iter(),
+            self.synapse_types[neurons.clone()].par_iter(),
+            thalamic_input[neurons.clone()].par_iter(),
+            input_current[neurons.clone()].par_iter(),
+            (
+                self.refractory_period[neurons.clone()].par_iter(),
+                self.refractory[neurons.clone()].par_iter_mut(),
+                self.dynamics[neurons.clone()].par_iter_mut(),
+                self.spike_offset[neurons].par_iter_mut(),
+            ),
+        )
+            .into_par_iter()
+            .map(
+                |(
+                    v,
+                    u,
+                    &a,
+                    &b,
+                    &c,
+                    &d,
+                    synapse_type,
+                    thalamic,
+                    current,
+                    (&period, refractory, dynamics, spike_offset),
+                )| {
+                    let i = thalamic * synapse_type.thalamic_gain() / dt.sqrt() + current;
+                    let offset = match dynamics {
+                        Some(model) => {
+                            step_refractory(&mut **model, v, u, i, dt, period, refractory)
+                        }
+                        None => {
+                            let parameters = NeuronParameters { a, b, c, d };
+                            let mut model = Izhikevich::new(parameters, integrator);
+                            step_refractory(&mut model, v, u, i, dt, period, refractory)
+                        }
+                    };
+                    if let Some(offset) = offset {
+                        *spike_offset = offset;
+                    }
+                    offset.is_some()
+                },
+            )
+            .collect()
+    }
 
-        // action potential
-        if self.v >= 30. {
-            self.v = self.parameters.c;
-            self.u += self.parameters.d;
-            true
-        } else {
-            false
+    /// Like [`Neurons::step`], but skips the neurons flagged in `settled` while they receive no
+    /// input. A neuron without input that came within `tolerance` of its resting state is moved
+    /// onto it and flagged; the resting state is a fixed point of the integration, so skipping it
+    /// changes nothing until input clears the flag.
+    pub fn step_event_driven(
+        &mut self,
+        thalamic_input: &[f64],
+        input_current: &[f64],
+        dt: f64,
+        integrator: Integrator,
+        tolerance: f64,
+        settled: &mut [bool],
+    ) -> Vec<bool> {
+        // plain loops over chunks, so checking a skipped neuron costs next to nothing
+        const CHUNK: usize = 1024;
+        let mut spikes = vec![false; self.len()];
+        let sqrt_dt = dt.sqrt();
+        (
+            (
+                spikes.par_chunks_mut(CHUNK),
+                self.v.par_chunks_mut(CHUNK),
+                self.u.par_chunks_mut(CHUNK),
+                settled.par_chunks_mut(CHUNK),
+            ),
+            (
+                self.a.par_chunks(CHUNK),
+                self.b.par_chunks(CHUNK),
+                self.c.par_chunks(CHUNK),
+                self.d.par_chunks(CHUNK),
+            ),
+            (
+                self.refractory_period.par_chunks(CHUNK),
+                self.refractory.par_chunks_mut(CHUNK),
+                self.dynamics.par_chunks_mut(CHUNK),
+                self.spike_offset.par_chunks_mut(CHUNK),
+            ),
+            self.synapse_types.par_chunks(CHUNK),
+            thalamic_input.par_chunks(CHUNK),
+            input_current.par_chunks(CHUNK),
+        )
+            .into_par_iter()
+            .for_each(
+                |(
+                    (spikes, v, u, settled),
+                    (a, b, c, d),
+                    (periods, refractory, dynamics, offsets),
+                    types,
+                    thalamic,
+                    current,
+                )| {
+                    if settled.iter().all(|&settled| settled)
+                        && thalamic.iter().all(|&i| i == 0.)
+                        && current.iter().all(|&i| i == 0.)
+                    {
+                        return;
+                    }
+                    let state = spikes.iter_mut().zip(v).zip(u).zip(settled.iter_mut());
+                    let parameters = a.iter().zip(b).zip(c).zip(d).zip(types);
+                    let inputs = thalamic.iter().zip(current);
+                    let refractory = periods
+                        .iter()
+                        .zip(refractory.iter_mut())
+                        .zip(dynamics.iter_mut())
+                        .zip(offsets.iter_mut());
+                    for (
+                        ((state, parameters), (&thalamic, &current)),
+                        (((&period, refractory), dynamics), spike_offset),
+                    ) in state.zip(parameters).zip(inputs).zip(refractory)
+                    {
+                        let (((fired, v), u), settled) = state;
+                        let ((((&a, &b), &c), &d), synapse_type) = parameters;
+                        if *settled && thalamic == 0. && current == 0. {
+                            continue;
+                        }
+                        let i = thalamic * synapse_type.thalamic_gain() / sqrt_dt + current;
+                        let state = (v, u, refractory);
+                        let offset;
+                        (offset, *settled) = match dynamics {
+                            Some(model) => {
+                                step_settling(&mut **model, state, i, dt, period, tolerance)
+                            }
+                            None => {
+                                let parameters = NeuronParameters { a, b, c, d };
+                                let mut model = Izhikevich::new(parameters, integrator);
+                                step_settling(&mut model, state, i, dt, period, tolerance)
+                            }
+                        };
+                        if let Some(offset) = offset {
+                            *spike_offset = offset;
+                        }
+                        *fired = offset.is_some();
+                    }
+                },
+            );
+        spikes
+    }
+}
+
+impl FromIterator<Neuron> for Neurons {
+    fn from_iter<I: IntoIterator<Item = Neuron>>(iter: I) -> Self {
+        let mut neurons = Neurons::new();
+        for neuron in iter {
+            neurons.push(neuron);
         }
+        neurons
     }
 }
 
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
 pub enum NeuronType {
     Regular,
     IntrinsicallyBursting,
     Chattering,
     FastSpiking,
     LowThresholdSpiking,
+    /// Thalamo-cortical relay neuron, firing tonically when depolarized from rest and a rebound
+    /// burst when released from hyperpolarization, see [`Neuron::with_potential`].
+    ThalamoCortical,
+    /// Resonator with damped subthreshold oscillations, preferring inputs at their frequency.
+    Resonator,
+}
+
+impl NeuronType {
+    pub const ALL: [NeuronType; 7] = [
+        NeuronType::Regular,
+        NeuronType::IntrinsicallyBursting,
+        NeuronType::Chattering,
+        NeuronType::FastSpiking,
+        NeuronType::LowThresholdSpiking,
+        NeuronType::ThalamoCortical,
+        NeuronType::Resonator,
+    ];
+
+    /// Usual abbreviation of the type, e.g. `RS` for regular spiking.
+    pub fn abbreviation(&self) -> &'static str {
+        match self {
+            NeuronType::Regular => "RS",
+            NeuronType::IntrinsicallyBursting => "IB",
+            NeuronType::Chattering => "CH",
+            NeuronType::FastSpiking => "FS",
+            NeuronType::LowThresholdSpiking => "LTS",
+            NeuronType::ThalamoCortical => "TC",
+            NeuronType::Resonator => "RZ",
+        }
+    }
+
+    /// Membrane potential neurons of this type start at: -65 mV as in the paper, except for the
+    /// thalamo-cortical and resonator neurons, which start at rest as a resonator would fire
+    /// from -65 mV.
+    pub fn initial_potential(&self) -> f64 {
+        match self {
+            NeuronType::ThalamoCortical | NeuronType::Resonator => {
+                self.params().resting_potential().unwrap_or(-65.)
+            }
+            _ => -65.,
+        }
+    }
+}
+
+impl FromStr for NeuronType {
+    type Err = String;
+
+    /// Parses the abbreviation of a type, ignoring case.
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        NeuronType::ALL
+            .into_iter()
+            .find(|neuron_type| neuron_type.abbreviation().eq_ignore_ascii_case(s))
+            .ok_or_else(|| {
+                let names: Vec<_> = NeuronType::ALL.iter().map(|t| t.abbreviation()).collect();
+                format!(
+                    "unknown neuron type `{s}`, expected one of {}",
+                    names.join(", ")
+                )
+            })
+    }
 }
 
 impl From<NeuronType> for SynapseType {
@@ -131,10 +860,43 @@ impl From<NeuronType> for SynapseType {
             NeuronType::Chattering => SynapseType::Excitatory,
             NeuronType::FastSpiking => SynapseType::Inhibitory,
             NeuronType::LowThresholdSpiking => SynapseType::Inhibitory,
+            NeuronType::ThalamoCortical => SynapseType::Excitatory,
+            // fast recovering like the interneuron types
+            NeuronType::Resonator => SynapseType::Inhibitory,
         }
     }
 }
 
+/// Absolute refractory periods in milliseconds by neuron type, see
+/// [`crate::network::NetworkBuilder::refractory_periods`]. All zero by default, leaving the
+/// model of the paper unchanged.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct RefractoryPeriods {
+    /// Periods of the randomized excitatory neurons and of excitatory types not in `by_type`.
+    pub excitatory: f64,
+    /// Periods of the randomized inhibitory neurons and of inhibitory types not in `by_type`.
+    pub inhibitory: f64,
+    /// Periods of neurons of a fixed type.
+    pub by_type: Vec<(NeuronType, f64)>,
+}
+
+impl RefractoryPeriods {
+    /// Period of a neuron of `neuron_type`, or of the randomized mixture of `synapse_type`.
+    pub fn period(&self, neuron_type: Option<NeuronType>, synapse_type: &SynapseType) -> f64 {
+        neuron_type
+            .and_then(|neuron_type| {
+                self.by_type
+                    .iter()
+                    .find(|(fixed, _)| *fixed == neuron_type)
+                    .map(|&(_, period)| period)
+            })
+            .unwrap_or(match synapse_type {
+                SynapseType::Excitatory => self.excitatory,
+                SynapseType::Inhibitory => self.inhibitory,
+            })
+    }
+}
+
 const REGULAR_PARAMS: NeuronParameters = NeuronParameters {
     a: 0.02,
     b: 0.2,
@@ -170,6 +932,20 @@ const LOW_THRESHOLD_SPIKING_PARAMS: NeuronParameters = NeuronParameters {
     d: 2.0,
 };
 
+const THALAMO_CORTICAL_PARAMS: NeuronParameters = NeuronParameters {
+    a: 0.02,
+    b: 0.25,
+    c: -65.0,
+    d: 0.05,
+};
+
+const RESONATOR_PARAMS: NeuronParameters = NeuronParameters {
+    a: 0.1,
+    b: 0.26,
+    c: -65.0,
+    d: 2.0,
+};
+
 impl NeuronType {
     pub fn params(&self) -> NeuronParameters {
         match self {
@@ -178,6 +954,8 @@ impl NeuronType {
             NeuronType::Chattering => CHATTERING_PARAMS,
             NeuronType::FastSpiking => FAST_SPIKING_PARAMS,
             NeuronType::LowThresholdSpiking => LOW_THRESHOLD_SPIKING_PARAMS,
+            NeuronType::ThalamoCortical => THALAMO_CORTICAL_PARAMS,
+            NeuronType::Resonator => RESONATOR_PARAMS,
         }
     }
 }
@@ -0,0 +1,187 @@
+use std::error::Error;
+use std::fs;
+use std::io::{self, Write};
+use std::path::Path;
+
+/// Numeric array of a NumPy `.npy` file, converted to `f64`.
+#[derive(Clone, Debug, PartialEq)]
+pub struct NpyArray {
+    pub shape: Vec<usize>,
+    /// Elements in row-major order, whatever the order of the file.
+    pub data: Vec<f64>,
+}
+
+/// Reads a `.npy` file of little-endian floats, integers or booleans, as written by `np.save`.
+pub fn read_npy(path: &Path) -> Result<NpyArray, Box<dyn Error>> {
+    let bytes = fs::read(path)?;
+    Ok(parse_npy(&bytes).map_err(|err| format!("{}: {err}", path.display()))?)
+}
+
+fn parse_npy(bytes: &[u8]) -> Result<NpyArray, String> {
+    let rest = bytes.strip_prefix(b"\x93NUMPY").ok_or("not a .npy file")?;
+    let (header_len, rest) = match rest {
+        [1, _, a, b, rest @ ..] => (u16::from_le_bytes([*a, *b]) as usize, rest),
+        [2 | 3, _, a, b, c, d, rest @ ..] => (u32::from_le_bytes([*a, *b, *c, *d]) as usize, rest),
+        _ => return Err("unsupported .npy version".into()),
+    };
+    if rest.len() < header_len {
+        return Err("truncated header".into());
+    }
+    let (header, data) = rest.split_at(header_len);
+    let header = std::str::from_utf8(header).map_err(|_| "header is not text")?;
+
+    let value = |key: &str| {
+        let start = header
+            .find(&format!("'{key}'"))
+            .ok_or_else(|| format!("no `{key}` in the header"))?;
+        let value = header[start + key.len() + 2..].trim_start();
+        Ok::<_, String>(value.trim_start_matches(':').trim_start())
+    };
+    let descr = value("descr")?
+        .strip_prefix('\'')
+        .and_then(|descr| descr.split('\'').next())
+        .ok_or("malformed `descr`")?;
+    let fortran_order = value("fortran_order")?.starts_with("True");
+    let shape: Vec<usize> = value("shape")?
+        .strip_prefix('(')
+        .and_then(|shape| shape.split(')').next())
+        .ok_or("malformed `shape`")?
+        .split(',')
+        .map(str::trim)
+        .filter(|dim| !dim.is_empty())
+        .map(|dim| {
+            dim.parse()
+                .map_err(|_| format!("malformed dimension `{dim}`"))
+        })
+        .collect::<Result<_, _>>()?;
+
+    let (size, convert): (usize, fn(&[u8]) -> f64) = match descr {
+        "<f8" => (8, |b| f64::from_le_bytes(b.try_into().unwrap())),
+        "<f4" => (4, |b| f32::from_le_bytes(b.try_into().unwrap()) as f64),
+        "<i8" => (8, |b| i64::from_le_bytes(b.try_into().unwrap()) as f64),
+        "<i4" => (4, |b| i32::from_le_bytes(b.try_into().unwrap()) as f64),
+        "<i2" => (2, |b| i16::from_le_bytes(b.try_into().unwrap()) as f64),
+        "|i1" => (1, |b| b[0] as i8 as f64),
+        "|u1" | "|b1" => (1, |b| b[0] as f64),
+        _ => return Err(format!("unsupported element type `{descr}`")),
+    };
+    let len: usize = shape.iter().product();
+    if data.len() < len * size {
+        return Err(format!("expected {len} elements of {size} bytes"));
+    }
+    let stored: Vec<f64> = data[..len * size].chunks_exact(size).map(convert).collect();
+    let data = if fortran_order && shape.len() > 1 {
+        // column-major strides, the first index varying fastest
+        let mut strides = vec![1; shape.len()];
+        for axis in 1..shape.len() {
+            strides[axis] = strides[axis - 1] * shape[axis - 1];
+        }
+        (0..len)
+            .map(|mut idx| {
+                let mut offset = 0;
+                for axis in (0..shape.len()).rev() {
+                    offset += idx % shape[axis] * strides[axis];
+                    idx /= shape[axis];
+                }
+                stored[offset]
+            })
+            .collect()
+    } else {
+        stored
+    };
+    Ok(NpyArray { shape, data })
+}
+
+/// Writes `data` in row-major order as a `.npy` array of `f64` of the given shape.
+///
+/// # Panics
+///
+/// If `data` does not have as many elements as the shape.
+pub fn write_npy<W: Write>(mut writer: W, shape: &[usize], data: &[f64]) -> io::Result<()> {
+    assert_eq!(
+        shape.iter().product::<usize>(),
+        data.len(),
+        "data does not fill the shape"
+    );
+    let dims: Vec<String> = shape.iter().map(usize::to_string).collect();
+    let shape = match &dims[..] {
+        [dim] => format!("({dim},)"),
+        dims => format!("({})", dims.join(", ")),
+    };
+    let mut header = format!("{{'descr': '<f8', 'fortran_order': False, 'shape': {shape}, }}");
+    // the data starts at a multiple of 64 bytes after the magic, version and length
+    let padding = (64 - (10 + header.len() + 1) % 64) % 64;
+    header.extend(std::iter::repeat_n(' ', padding));
+    header.push('\n');
+    writer.write_all(b"\x93NUMPY\x01\x00")?;
+    writer.write_all(&(header.len() as u16).to_le_bytes())?;
+    writer.write_all(header.as_bytes())?;
+    for value in data {
+        writer.write_all(&value.to_le_bytes())?;
+    }
+    Ok(())
+}
+
+/// Writes `(name, shape, data)` arrays as the `.npy` members of an uncompressed `.npz` archive,
+/// as written by `np.savez`.
+pub fn write_npz<W: Write>(mut writer: W, arrays: &[(&str, &[usize], &[f64])]) -> io::Result<()> {
+    let mut central_directory = vec![];
+    let mut offset = 0;
+    for &(name, shape, data) in arrays {
+        let mut member = vec![];
+        write_npy(&mut member, shape, data)?;
+        let name = format!("{name}.npy");
+        let crc = crc32(&member);
+        // stored without compression, dated 1980-01-01
+        let mut fields = vec![];
+        for value in [20u16, 0, 0, 0, 0x21] {
+            fields.extend(value.to_le_bytes());
+        }
+        for value in [crc, member.len() as u32, member.len() as u32] {
+            fields.extend(value.to_le_bytes());
+        }
+        fields.extend((name.len() as u16).to_le_bytes());
+        fields.extend(0u16.to_le_bytes());
+
+        writer.write_all(&0x04034b50u32.to_le_bytes())?;
+        writer.write_all(&fields)?;
+        writer.write_all(name.as_bytes())?;
+        writer.write_all(&member)?;
+
+        central_directory.extend(0x02014b50u32.to_le_bytes());
+        central_directory.extend(20u16.to_le_bytes());
+        central_directory.extend(&fields);
+        // comment length, disk, internal and external attributes
+        central_directory.extend([0; 10]);
+        central_directory.extend((offset as u32).to_le_bytes());
+        central_directory.extend(name.as_bytes());
+        offset += 4 + fields.len() + name.len() + member.len();
+    }
+    writer.write_all(&central_directory)?;
+    writer.write_all(&0x06054b50u32.to_le_bytes())?;
+    writer.write_all(&[0; 4])?;
+    writer.write_all(&(arrays.len() as u16).to_le_bytes())?;
+    writer.write_all(&(arrays.len() as u16).to_le_bytes())?;
+    writer.write_all(&(central_directory.len() as u32).to_le_bytes())?;
+    writer.write_all(&(offset as u32).to_le_bytes())?;
+    writer.write_all(&0u16.to_le_bytes())?;
+    writer.flush()
+}
+
+/// CRC-32 of the zip format.
+fn crc32(bytes: &[u8]) -> u32 {
+    let table: Vec<u32> = (0..256)
+        .map(|byte| {
+            (0..8).fold(byte, |crc, _| {
+                if crc & 1 == 1 {
+                    0xedb88320 ^ (crc >> 1)
+                } else {
+                    crc >> 1
+                }
+            })
+        })
+        .collect();
+    !bytes.iter().fold(!0, |crc, &byte| {
+        table[((crc ^ byte as u32) & 0xff) as usize] ^ (crc >> 8)
+    })
+}
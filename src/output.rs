@@ -0,0 +1,309 @@
+use std::fs::{self, File};
+use std::io::{self, BufWriter, Write};
+use std::path::Path;
+
+use std::ops::Range;
+
+use serde::Serialize;
+
+use crate::analysis::{Correlogram, SpikeTrains, SpikeTriggeredAverage};
+use crate::npy::{write_npy, write_npz};
+use crate::simulator::WorldState;
+
+/// Writes all recorded spikes as `time,neuron_id` rows, with time in milliseconds, see
+/// [`WorldState::spike_times`]. The run
+/// parameters are written first as `# key=value` comment lines, so e.g. pandas can skip them
+/// with `comment="#"`, followed by the number of neurons, the recorded time and the populations
+/// that [`SpikeTrains::read_csv_populations`] reads back.
+pub fn write_spikes_csv<W: Write>(
+    mut writer: W,
+    world: &WorldState,
+    parameters: &[(String, String)],
+) -> io::Result<()> {
+    for (key, value) in parameters {
+        writeln!(writer, "# {key}={value}")?;
+    }
+    let trains = SpikeTrains::from_world(world);
+    writeln!(writer, "# neurons={}", trains.neurons())?;
+    writeln!(writer, "# start={}", trains.start)?;
+    writeln!(writer, "# duration={}", trains.duration)?;
+    for (name, neurons) in world.populations() {
+        writeln!(
+            writer,
+            "# population={name}:{}..{}",
+            neurons.start, neurons.end
+        )?;
+    }
+    writeln!(writer, "time,neuron_id")?;
+    for (time, neuron_idx) in world.spike_times() {
+        writeln!(writer, "{time},{neuron_idx}")?;
+    }
+    writer.flush()
+}
+
+/// Writes the spikes of every step as one JSON line while the simulation runs, e.g.
+/// `{"time_step":12,"time":12.0,"spikes":[3,41]}` with the time in milliseconds at the end of the
+/// step, flushing each line so that readers see it at once.
+pub struct SpikeStream<W: Write> {
+    writer: W,
+}
+
+#[derive(Serialize)]
+struct StreamedStep<'a> {
+    time_step: usize,
+    time: f64,
+    spikes: &'a [usize],
+}
+
+impl<W: Write> SpikeStream<W> {
+    pub fn new(writer: W) -> Self {
+        SpikeStream { writer }
+    }
+
+    /// Writes the spikes of the step `world` has just simulated.
+    pub fn write_step(&mut self, world: &WorldState) -> io::Result<()> {
+        let spikes: Vec<usize> = world
+            .last_spikes()
+            .iter()
+            .enumerate()
+            .filter(|&(_, &spiked)| spiked)
+            .map(|(neuron_idx, _)| neuron_idx)
+            .collect();
+        let step = StreamedStep {
+            time_step: world.time_step,
+            time: world.time(),
+            spikes: &spikes,
+        };
+        serde_json::to_writer(&mut self.writer, &step)?;
+        writeln!(self.writer)?;
+        self.writer.flush()
+    }
+}
+
+/// Writes the recorded membrane potentials with one `time,v_<neuron_idx>...` row per sample.
+pub fn write_v_traces_csv<W: Write>(mut writer: W, world: &WorldState) -> io::Result<()> {
+    write!(writer, "time")?;
+    for (idx, _) in &world.v_traces {
+        write!(writer, ",v_{idx}")?;
+    }
+    writeln!(writer)?;
+
+    // probes registered later have shorter traces, aligned at the end
+    let samples = world
+        .v_traces
+        .iter()
+        .map(|(_, v)| v.len())
+        .max()
+        .unwrap_or(0);
+    let start_step = world.time_step + 1 - samples;
+    for k in 0..samples {
+        write!(writer, "{}", (start_step + k) as f64 * world.dt)?;
+        for (_, trace) in &world.v_traces {
+            match (k + trace.len()).checked_sub(samples) {
+                Some(i) => write!(writer, ",{}", trace[i])?,
+                None => write!(writer, ",")?,
+            }
+        }
+        writeln!(writer)?;
+    }
+    writer.flush()
+}
+
+/// Writes the recorded local field potential proxy, one `time,lfp` row per step with the time at
+/// its end, or only the header if it was not recorded.
+pub fn write_lfp_csv<W: Write>(mut writer: W, world: &WorldState) -> io::Result<()> {
+    writeln!(writer, "time,lfp")?;
+    let lfp = world.lfp.as_deref().unwrap_or_default();
+    let start_step = world.time_step + 1 - lfp.len();
+    for (k, sample) in lfp.iter().enumerate() {
+        writeln!(writer, "{},{sample}", (start_step + k) as f64 * world.dt)?;
+    }
+    writer.flush()
+}
+
+/// Writes the population rates of `populations` in spikes per ms per neuron in bins of `bin`
+/// milliseconds, one `time,<population>...` row per bin with the time at its start.
+pub fn write_population_rates_csv<W: Write>(
+    mut writer: W,
+    trains: &SpikeTrains,
+    populations: &[(String, Range<usize>)],
+    bin: f64,
+) -> io::Result<()> {
+    write!(writer, "time")?;
+    for (name, _) in populations {
+        write!(writer, ",{name}")?;
+    }
+    writeln!(writer)?;
+    let rates: Vec<Vec<f64>> = populations
+        .iter()
+        .map(|(_, neurons)| trains.population_rate(neurons.clone(), bin))
+        .collect();
+    let bins = rates.first().map_or(0, Vec::len);
+    for k in 0..bins {
+        write!(writer, "{}", trains.start + k as f64 * bin)?;
+        for rates in &rates {
+            write!(writer, ",{}", rates[k] / 1000.)?;
+        }
+        writeln!(writer)?;
+    }
+    writer.flush()
+}
+
+/// Writes spike-triggered averages of several neurons, over windows of the same sampling
+/// interval, with one `lag,sta_<neuron_idx>...` row per sample and the spike at lag 0.
+pub fn write_stas_csv<W: Write>(
+    mut writer: W,
+    stas: &[(usize, SpikeTriggeredAverage)],
+) -> io::Result<()> {
+    write!(writer, "lag")?;
+    for (idx, _) in stas {
+        write!(writer, ",sta_{idx}")?;
+    }
+    writeln!(writer)?;
+    if let Some((_, sta)) = stas.first() {
+        for (k, lag) in sta.lags().enumerate() {
+            write!(writer, "{lag}")?;
+            for (_, sta) in stas {
+                write!(writer, ",{}", sta.average[k])?;
+            }
+            writeln!(writer)?;
+        }
+    }
+    writer.flush()
+}
+
+/// Writes cross-correlograms with the same bins, one `lag,<name>...` row per bin with the lag at
+/// its lower edge.
+pub fn write_correlograms_csv<W: Write>(
+    mut writer: W,
+    correlograms: &[(String, Correlogram)],
+) -> io::Result<()> {
+    write!(writer, "lag")?;
+    for (name, _) in correlograms {
+        write!(writer, ",{name}")?;
+    }
+    writeln!(writer)?;
+    if let Some((_, correlogram)) = correlograms.first() {
+        for (k, lag) in correlogram.bin_starts().enumerate() {
+            write!(writer, "{lag}")?;
+            for (_, correlogram) in correlograms {
+                write!(writer, ",{}", correlogram.rates[k])?;
+            }
+            writeln!(writer)?;
+        }
+    }
+    writer.flush()
+}
+
+/// Writes the spikes, the recorded membrane traces and the synapses as NumPy arrays of `f64`:
+/// `spikes` with a row of times in milliseconds over a row of neurons, `v` with one row of
+/// potentials per neuron recorded, `v_neurons` with the indices of those neurons and `synapses`
+/// with rows of the presynaptic neurons, postsynaptic neurons, weights and delays in
+/// milliseconds. A path ending in `.npz` gets an archive of them for `np.load`, any other a
+/// directory of one `.npy` file per array.
+pub fn write_numpy(path: &Path, world: &WorldState) -> io::Result<()> {
+    let (times, neurons): (Vec<f64>, Vec<f64>) = world
+        .spike_times()
+        .map(|(time, neuron_idx)| (time, neuron_idx as f64))
+        .unzip();
+    let spike_count = times.len();
+    let spikes = [times, neurons].concat();
+
+    let traces = &world.v_traces;
+    let steps = traces.first().map_or(0, |(_, v)| v.len());
+    let v: Vec<f64> = traces.iter().flat_map(|(_, v)| v.iter().copied()).collect();
+    let v_neurons: Vec<f64> = traces.iter().map(|&(idx, _)| idx as f64).collect();
+
+    let connectivity = &world.connectivity;
+    let mut synapses = vec![0.; 4 * connectivity.synapses()];
+    let (pre, rest) = synapses.split_at_mut(connectivity.synapses());
+    let (post, rest) = rest.split_at_mut(connectivity.synapses());
+    let (weights, delays) = rest.split_at_mut(connectivity.synapses());
+    for neuron in 0..connectivity.neurons() {
+        pre[connectivity.row(neuron)].fill(neuron as f64);
+    }
+    for (synapse, &target) in connectivity.targets().iter().enumerate() {
+        post[synapse] = target as f64;
+        weights[synapse] = connectivity.weights()[synapse];
+        delays[synapse] = connectivity.delays()[synapse] as f64 * world.dt;
+    }
+
+    let arrays: [(&str, &[usize], &[f64]); 4] = [
+        ("spikes", &[2, spike_count], &spikes),
+        ("v", &[traces.len(), steps], &v),
+        ("v_neurons", &[traces.len()], &v_neurons),
+        ("synapses", &[4, connectivity.synapses()], &synapses),
+    ];
+    if path.extension().is_some_and(|ext| ext == "npz") {
+        return write_npz(BufWriter::new(File::create(path)?), &arrays);
+    }
+    fs::create_dir_all(path)?;
+    for (name, shape, data) in arrays {
+        let mut writer = BufWriter::new(File::create(path.join(format!("{name}.npy")))?);
+        write_npy(&mut writer, shape, data)?;
+        writer.flush()?;
+    }
+    Ok(())
+}
+
+/// Writes spike times, the recorded membrane traces and the weights to an HDF5 file. Weights are stored in their sparse row-per-presynaptic-neuron form under
+/// `weights/`, since the dense matrix does not fit in memory for large networks.
+#[cfg(feature = "hdf5")]
+pub fn write_hdf5<P: AsRef<Path>>(path: P, world: &WorldState) -> hdf5::Result<()> {
+    let file = hdf5::File::create(path)?;
+
+    let (times, neurons): (Vec<f64>, Vec<u64>) = world
+        .spike_times()
+        .map(|(time, neuron_idx)| (time, neuron_idx as u64))
+        .unzip();
+    let spikes = file.create_group("spikes")?;
+    spikes
+        .new_dataset_builder()
+        .with_data(times.as_slice())
+        .create("times")?;
+    spikes
+        .new_dataset_builder()
+        .with_data(neurons.as_slice())
+        .create("neurons")?;
+
+    let traces = &world.v_traces;
+    let trace_group = file.create_group("traces")?;
+    let trace_neurons: Vec<u64> = traces.iter().map(|&(idx, _)| idx as u64).collect();
+    trace_group
+        .new_dataset_builder()
+        .with_data(trace_neurons.as_slice())
+        .create("neurons")?;
+    let steps = traces.first().map_or(0, |(_, v)| v.len());
+    let v: Vec<f64> = traces.iter().flat_map(|(_, v)| v.iter().copied()).collect();
+    trace_group
+        .new_dataset::<f64>()
+        .shape((traces.len(), steps))
+        .create("v")?
+        .write_raw(&v)?;
+
+    let connectivity = &world.connectivity;
+    let row_offsets: Vec<u64> = (0..connectivity.neurons())
+        .map(|pre| connectivity.row(pre).start as u64)
+        .chain(std::iter::once(connectivity.synapses() as u64))
+        .collect();
+    let targets: Vec<u64> = connectivity.targets().iter().map(|&t| t as u64).collect();
+    let weights = file.create_group("weights")?;
+    weights
+        .new_dataset_builder()
+        .with_data(row_offsets.as_slice())
+        .create("row_offsets")?;
+    weights
+        .new_dataset_builder()
+        .with_data(targets.as_slice())
+        .create("targets")?;
+    weights
+        .new_dataset_builder()
+        .with_data(connectivity.weights())
+        .create("values")?;
+    weights
+        .new_dataset_builder()
+        .with_data(connectivity.delays())
+        .create("delays")?;
+
+    Ok(())
+}
@@ -0,0 +1,168 @@
+use std::error::Error;
+use std::fs;
+use std::path::Path;
+
+use serde::Deserialize;
+
+use crate::neuron::{NeuronParameters, Neurons, SynapseType};
+use crate::simulator::SparseConnectivity;
+
+/// Explicit parameters of one neuron of the network, replacing the drawn ones, e.g. from a fit to
+/// recordings.
+#[derive(Clone, Debug, PartialEq)]
+pub struct NeuronOverride {
+    /// Index of the neuron in the network.
+    pub neuron: usize,
+    pub parameters: NeuronParameters,
+    /// New sign of the synapses of the neuron, or `None` to keep it.
+    pub synapse_type: Option<SynapseType>,
+}
+
+/// One row of an overrides file.
+#[derive(Deserialize)]
+struct Row {
+    neuron: usize,
+    a: f64,
+    b: f64,
+    c: f64,
+    d: f64,
+    #[serde(default)]
+    synapse_type: Option<String>,
+}
+
+impl TryFrom<Row> for NeuronOverride {
+    type Error = String;
+
+    fn try_from(row: Row) -> Result<Self, Self::Error> {
+        Ok(NeuronOverride {
+            neuron: row.neuron,
+            parameters: NeuronParameters {
+                a: row.a,
+                b: row.b,
+                c: row.c,
+                d: row.d,
+            },
+            synapse_type: row
+                .synapse_type
+                .filter(|s| !s.is_empty())
+                .map(|s| s.parse())
+                .transpose()?,
+        })
+    }
+}
+
+/// Reads per-neuron parameters from a `.json` file holding an array of objects, or otherwise from
+/// a CSV file with a header, both with the fields `neuron`, `a`, `b`, `c`, `d` and optionally
+/// `synapse_type` as `excitatory` or `inhibitory`.
+pub fn read_overrides(path: &Path) -> Result<Vec<NeuronOverride>, Box<dyn Error>> {
+    let text = fs::read_to_string(path)?;
+    let rows: Vec<Row> = if path.extension().is_some_and(|ext| ext == "json") {
+        serde_json::from_str(&text)?
+    } else {
+        parse_csv(&text)?
+    };
+    Ok(rows
+        .into_iter()
+        .map(NeuronOverride::try_from)
+        .collect::<Result<_, _>>()?)
+}
+
+/// Reads the rows of a CSV file by the column names of its header, so columns may come in any
+/// order and unknown ones are ignored.
+fn parse_csv(text: &str) -> Result<Vec<Row>, String> {
+    let mut lines = text
+        .lines()
+        .enumerate()
+        .filter(|(_, line)| !line.trim().is_empty());
+    let header: Vec<&str> = match lines.next() {
+        Some((_, header)) => header.split(',').map(str::trim).collect(),
+        None => return Ok(vec![]),
+    };
+    let column = |name: &str| {
+        header
+            .iter()
+            .position(|&column| column == name)
+            .ok_or_else(|| format!("missing column `{name}`"))
+    };
+    let (neuron, a, b, c, d) = (
+        column("neuron")?,
+        column("a")?,
+        column("b")?,
+        column("c")?,
+        column("d")?,
+    );
+    let synapse_type = column("synapse_type").ok();
+
+    lines
+        .map(|(number, line)| {
+            let fields: Vec<&str> = line.split(',').map(str::trim).collect();
+            let field = |idx: usize| {
+                fields
+                    .get(idx)
+                    .copied()
+                    .ok_or_else(|| format!("line {}: missing field `{}`", number + 1, header[idx]))
+            };
+            let number_at = |idx: usize| {
+                field(idx)?
+                    .parse::<f64>()
+                    .map_err(|err| format!("line {}: `{}`: {err}", number + 1, header[idx]))
+            };
+            Ok(Row {
+                neuron: field(neuron)?
+                    .parse()
+                    .map_err(|err| format!("line {}: `neuron`: {err}", number + 1))?,
+                a: number_at(a)?,
+                b: number_at(b)?,
+                c: number_at(c)?,
+                d: number_at(d)?,
+                synapse_type: match synapse_type {
+                    Some(idx) => Some(field(idx)?.to_string()),
+                    None => None,
+                },
+            })
+        })
+        .collect()
+}
+
+/// Sets the parameters of the overridden neurons, which follow the Izhikevich model even if
+/// they followed another one, and starts them at rest at -65 mV. A neuron
+/// whose synapse type changes has the signs of its outgoing weights flipped.
+///
+/// # Panics
+///
+/// If an override refers to a neuron outside the network.
+pub(crate) fn apply_overrides(
+    overrides: &[NeuronOverride],
+    neurons: &mut Neurons,
+    connectivity: &mut SparseConnectivity,
+) {
+    let n = neurons.len();
+    for NeuronOverride {
+        neuron,
+        parameters,
+        synapse_type,
+    } in overrides
+    {
+        let idx = *neuron;
+        assert!(
+            idx < n,
+            "override of neuron {idx} out of range for {n} neurons"
+        );
+        neurons.a[idx] = parameters.a;
+        neurons.b[idx] = parameters.b;
+        neurons.c[idx] = parameters.c;
+        neurons.d[idx] = parameters.d;
+        neurons.v[idx] = -65.;
+        neurons.u[idx] = parameters.b * -65.;
+        neurons.dynamics[idx] = None;
+        if let Some(synapse_type) = synapse_type {
+            if *synapse_type != neurons.synapse_types[idx] {
+                let row = connectivity.row(idx);
+                for weight in &mut connectivity.weights_mut()[row] {
+                    *weight = -*weight;
+                }
+                neurons.synapse_types[idx] = synapse_type.clone();
+            }
+        }
+    }
+}
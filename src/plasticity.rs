@@ -0,0 +1,735 @@
+use std::str::FromStr;
+
+use serde::{Deserialize, Serialize};
+
+use crate::neuron::{Neurons, SynapseType};
+use crate::simulator::SparseConnectivity;
+
+/// Tsodyks–Markram short-term plasticity parameters of one connection class.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct StpParameters {
+    /// Baseline utilization of synaptic resources per spike, `U`.
+    pub u: f64,
+    /// Time constant for recovery from depression in milliseconds.
+    pub tau_rec: f64,
+    /// Time constant for decay of facilitation in milliseconds, 0 disables facilitation.
+    pub tau_facil: f64,
+}
+
+impl StpParameters {
+    /// Depressing synapse, typical of excitatory connections between pyramidal cells.
+    pub fn depressing() -> Self {
+        StpParameters {
+            u: 0.5,
+            tau_rec: 800.,
+            tau_facil: 0.,
+        }
+    }
+
+    /// Facilitating synapse, typical of excitatory input onto some interneurons.
+    pub fn facilitating() -> Self {
+        StpParameters {
+            u: 0.15,
+            tau_rec: 130.,
+            tau_facil: 670.,
+        }
+    }
+
+    /// Checks that the parameters are finite with `0 < U <= 1`, a positive recovery time
+    /// constant and a non-negative facilitation one.
+    pub fn check(&self) -> Result<(), String> {
+        let valid = self.u > 0.
+            && self.u <= 1.
+            && self.tau_rec > 0.
+            && self.tau_rec.is_finite()
+            && self.tau_facil >= 0.
+            && self.tau_facil.is_finite();
+        if valid {
+            Ok(())
+        } else {
+            Err(format!("invalid short-term plasticity {self:?}"))
+        }
+    }
+}
+
+impl FromStr for StpParameters {
+    type Err = String;
+
+    /// Parses `depressing`, `facilitating` or `U:TAU_REC:TAU_FACIL` with the time constants in
+    /// milliseconds.
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let params = match s.trim() {
+            "depressing" => StpParameters::depressing(),
+            "facilitating" => StpParameters::facilitating(),
+            s => {
+                let values = s
+                    .split(':')
+                    .map(|value| {
+                        value
+                            .trim()
+                            .parse::<f64>()
+                            .map_err(|err| format!("invalid parameter `{value}`: {err}"))
+                    })
+                    .collect::<Result<Vec<_>, _>>()
+                    .map_err(|err| {
+                        format!("{err}, expected depressing, facilitating or U:TAU_REC:TAU_FACIL")
+                    })?;
+                let &[u, tau_rec, tau_facil] = &values[..] else {
+                    return Err(format!(
+                        "expected depressing, facilitating or U:TAU_REC:TAU_FACIL, got `{s}`"
+                    ));
+                };
+                StpParameters {
+                    u,
+                    tau_rec,
+                    tau_facil,
+                }
+            }
+        };
+        params.check()?;
+        Ok(params)
+    }
+}
+
+/// Per connection class short-term plasticity, `None` keeps a class static.
+#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
+pub struct ShortTermPlasticity {
+    pub excitatory_to_excitatory: Option<StpParameters>,
+    pub excitatory_to_inhibitory: Option<StpParameters>,
+    pub inhibitory_to_excitatory: Option<StpParameters>,
+    pub inhibitory_to_inhibitory: Option<StpParameters>,
+}
+
+impl ShortTermPlasticity {
+    /// Makes the synapses from `pre` onto `post` neurons follow `params`.
+    pub fn set(&mut self, pre: &SynapseType, post: &SynapseType, params: StpParameters) {
+        let class = match (pre, post) {
+            (SynapseType::Excitatory, SynapseType::Excitatory) => {
+                &mut self.excitatory_to_excitatory
+            }
+            (SynapseType::Excitatory, SynapseType::Inhibitory) => {
+                &mut self.excitatory_to_inhibitory
+            }
+            (SynapseType::Inhibitory, SynapseType::Excitatory) => {
+                &mut self.inhibitory_to_excitatory
+            }
+            (SynapseType::Inhibitory, SynapseType::Inhibitory) => {
+                &mut self.inhibitory_to_inhibitory
+            }
+        };
+        *class = Some(params);
+    }
+
+    pub fn params(&self, pre: &SynapseType, post: &SynapseType) -> Option<&StpParameters> {
+        match (pre, post) {
+            (SynapseType::Excitatory, SynapseType::Excitatory) => {
+                self.excitatory_to_excitatory.as_ref()
+            }
+            (SynapseType::Excitatory, SynapseType::Inhibitory) => {
+                self.excitatory_to_inhibitory.as_ref()
+            }
+            (SynapseType::Inhibitory, SynapseType::Excitatory) => {
+                self.inhibitory_to_excitatory.as_ref()
+            }
+            (SynapseType::Inhibitory, SynapseType::Inhibitory) => {
+                self.inhibitory_to_inhibitory.as_ref()
+            }
+        }
+    }
+}
+
+/// Depression and facilitation state of every synapse, aligned with [`SparseConnectivity`].
+#[derive(Serialize, Deserialize)]
+pub struct StpState {
+    pub config: ShortTermPlasticity,
+    /// Utilization at the last presynaptic spike.
+    u: Vec<f64>,
+    /// Fraction of resources left after the last presynaptic spike.
+    x: Vec<f64>,
+    /// Time of the last spike of each presynaptic neuron in milliseconds.
+    last_spike: Vec<f64>,
+    synapse_types: Vec<SynapseType>,
+}
+
+impl StpState {
+    pub fn new(
+        config: ShortTermPlasticity,
+        connectivity: &SparseConnectivity,
+        synapse_types: Vec<SynapseType>,
+    ) -> Self {
+        StpState {
+            config,
+            u: vec![0.; connectivity.synapses()],
+            x: vec![1.; connectivity.synapses()],
+            last_spike: vec![f64::NEG_INFINITY; connectivity.neurons()],
+            synapse_types,
+        }
+    }
+
+    /// Registers a spike of `pre` at `time` and returns the efficacy of each of its outgoing
+    /// synapses, normalized so a fully rested synapse transmits its nominal weight.
+    pub fn on_spike(
+        &mut self,
+        pre: usize,
+        time: f64,
+        connectivity: &SparseConnectivity,
+    ) -> Vec<f64> {
+        let elapsed = time - self.last_spike[pre];
+        self.last_spike[pre] = time;
+
+        connectivity
+            .row(pre)
+            .map(|synapse| {
+                let post = connectivity.targets()[synapse];
+                let Some(params) = self
+                    .config
+                    .params(&self.synapse_types[pre], &self.synapse_types[post])
+                else {
+                    return 1.;
+                };
+
+                let facilitation = if params.tau_facil > 0. {
+                    (-elapsed / params.tau_facil).exp()
+                } else {
+                    0.
+                };
+                let u = params.u + self.u[synapse] * (1. - params.u) * facilitation;
+                let x = 1. - (1. - self.x[synapse]) * (-elapsed / params.tau_rec).exp();
+
+                self.u[synapse] = u;
+                self.x[synapse] = x - u * x;
+                u * x / params.u
+            })
+            .collect()
+    }
+}
+
+/// Parameters of dopamine-modulated STDP after Izhikevich (2007), "Solving the distal reward
+/// problem through linkage of STDP and dopamine signaling".
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct DopamineStdpParameters {
+    /// Eligibility added by a presynaptic spike followed by a postsynaptic one.
+    pub a_plus: f64,
+    /// Eligibility removed by a postsynaptic spike followed by a presynaptic one.
+    pub a_minus: f64,
+    /// Time constant of the potentiation window in milliseconds.
+    pub tau_plus: f64,
+    /// Time constant of the depression window in milliseconds.
+    pub tau_minus: f64,
+    /// Decay time constant of the eligibility traces in milliseconds.
+    pub tau_eligibility: f64,
+    /// Decay time constant of the dopamine concentration in milliseconds.
+    pub tau_dopamine: f64,
+    /// Weight change per millisecond per unit of eligibility and dopamine.
+    pub learning_rate: f64,
+    /// Plastic weights are kept in `0..=w_max`.
+    pub w_max: f64,
+}
+
+impl Default for DopamineStdpParameters {
+    /// The time constants of the paper, with the learning rate and bound scaled to the
+    /// excitatory weights of this network.
+    fn default() -> Self {
+        DopamineStdpParameters {
+            a_plus: 1.,
+            a_minus: 1.5,
+            tau_plus: 20.,
+            tau_minus: 20.,
+            tau_eligibility: 1000.,
+            tau_dopamine: 200.,
+            learning_rate: 0.002,
+            w_max: 1.,
+        }
+    }
+}
+
+/// Eligibility traces of the synapses from excitatory neurons, turned into weight changes only
+/// while dopamine is present.
+#[derive(Serialize, Deserialize)]
+pub struct DopamineStdp {
+    pub params: DopamineStdpParameters,
+    /// Eligibility trace of every synapse, aligned with [`SparseConnectivity`].
+    pub eligibility: Vec<f64>,
+    /// Extracellular dopamine concentration.
+    pub dopamine: f64,
+    /// Pending `(time, amount)` dopamine injections, sorted by time.
+    schedule: Vec<(f64, f64)>,
+    /// Exponentially decaying trace of the spikes of every neuron as presynaptic partner.
+    pre_trace: Vec<f64>,
+    /// Exponentially decaying trace of the spikes of every neuron as postsynaptic partner.
+    post_trace: Vec<f64>,
+    /// `(synapse, pre)` of the plastic synapses onto every neuron.
+    incoming: Vec<Vec<(usize, usize)>>,
+    /// Whether each neuron's outgoing synapses are plastic.
+    plastic: Vec<bool>,
+}
+
+impl DopamineStdp {
+    pub fn new(
+        params: DopamineStdpParameters,
+        connectivity: &SparseConnectivity,
+        synapse_types: &[SynapseType],
+    ) -> Self {
+        let n = connectivity.neurons();
+        let (plastic, incoming) = excitatory_synapses(connectivity, synapse_types);
+        DopamineStdp {
+            params,
+            eligibility: vec![0.; connectivity.synapses()],
+            dopamine: 0.,
+            schedule: vec![],
+            pre_trace: vec![0.; n],
+            post_trace: vec![0.; n],
+            incoming,
+            plastic,
+        }
+    }
+
+    /// Releases `amount` of dopamine immediately.
+    pub fn inject(&mut self, amount: f64) {
+        self.dopamine += amount;
+    }
+
+    /// Releases `amount` of dopamine once the simulation reaches `time` milliseconds.
+    pub fn schedule(&mut self, time: f64, amount: f64) {
+        let idx = self.schedule.partition_point(|&(t, _)| t <= time);
+        self.schedule.insert(idx, (time, amount));
+    }
+
+    /// Advances the traces by a step of `dt` ending at `time`, in which `spikes` were emitted at
+    /// the `offsets` of precise spike times or else at the end, and applies the dopamine-gated
+    /// weight changes.
+    pub fn on_step(
+        &mut self,
+        spikes: &[bool],
+        offsets: Option<&[f64]>,
+        time: f64,
+        dt: f64,
+        connectivity: &mut SparseConnectivity,
+    ) {
+        let params = &self.params;
+        let plus_decay = (-dt / params.tau_plus).exp();
+        let minus_decay = (-dt / params.tau_minus).exp();
+        for (pre, post) in self.pre_trace.iter_mut().zip(&mut self.post_trace) {
+            *pre *= plus_decay;
+            *post *= minus_decay;
+        }
+        let eligibility_decay = (-dt / params.tau_eligibility).exp();
+        for c in &mut self.eligibility {
+            *c *= eligibility_decay;
+        }
+        self.dopamine *= (-dt / params.tau_dopamine).exp();
+        let due = self.schedule.partition_point(|&(t, _)| t <= time);
+        for (_, amount) in self.schedule.drain(..due) {
+            self.dopamine += amount;
+        }
+
+        // the traces are those at the end of the step, so they are moved back to the spike
+        let before_end = |idx: usize| (1. - offsets.map_or(1., |o| o[idx])) * dt;
+        for (idx, _) in spikes.iter().enumerate().filter(|&(_, &spiked)| spiked) {
+            let back = before_end(idx);
+            // potentiate after earlier presynaptic spikes
+            let plus = (back / params.tau_plus).exp();
+            for &(synapse, pre) in &self.incoming[idx] {
+                self.eligibility[synapse] += params.a_plus * self.pre_trace[pre] * plus;
+            }
+            // depress after earlier postsynaptic spikes
+            if self.plastic[idx] {
+                let minus = (back / params.tau_minus).exp();
+                for synapse in connectivity.row(idx) {
+                    let post = connectivity.targets()[synapse];
+                    self.eligibility[synapse] -= params.a_minus * self.post_trace[post] * minus;
+                }
+            }
+        }
+        for (idx, _) in spikes.iter().enumerate().filter(|&(_, &spiked)| spiked) {
+            let back = before_end(idx);
+            self.pre_trace[idx] += (-back / params.tau_plus).exp();
+            self.post_trace[idx] += (-back / params.tau_minus).exp();
+        }
+
+        if self.dopamine != 0. {
+            let rate = dt * params.learning_rate * self.dopamine;
+            let weights = connectivity.weights_mut();
+            for &(synapse, _) in self.incoming.iter().flatten() {
+                let w = &mut weights[synapse];
+                *w = (*w + rate * self.eligibility[synapse]).clamp(0., params.w_max);
+            }
+        }
+    }
+}
+
+/// Whether each neuron is excitatory, and the `(synapse, pre)` of the synapses from excitatory
+/// neurons onto every neuron.
+fn excitatory_synapses(
+    connectivity: &SparseConnectivity,
+    synapse_types: &[SynapseType],
+) -> (Vec<bool>, Vec<Vec<(usize, usize)>>) {
+    let n = connectivity.neurons();
+    let plastic: Vec<bool> = synapse_types
+        .iter()
+        .map(|synapse_type| *synapse_type == SynapseType::Excitatory)
+        .collect();
+    let mut incoming = vec![vec![]; n];
+    for pre in (0..n).filter(|&pre| plastic[pre]) {
+        for synapse in connectivity.row(pre) {
+            incoming[connectivity.targets()[synapse]].push((synapse, pre));
+        }
+    }
+    (plastic, incoming)
+}
+
+/// Parameters of the additive STDP of Izhikevich (2006), "Polychronization: computation with
+/// spikes", where spike timing is taken at the arrival of the presynaptic spike.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct StdpParameters {
+    /// Weight change of an arrival followed by a postsynaptic spike.
+    pub a_plus: f64,
+    /// Weight change of a postsynaptic spike followed by an arrival.
+    pub a_minus: f64,
+    /// Time constant of the potentiation window in milliseconds.
+    pub tau_plus: f64,
+    /// Time constant of the depression window in milliseconds.
+    pub tau_minus: f64,
+    /// Plastic weights are kept in `0..=w_max`.
+    pub w_max: f64,
+    /// The accumulated changes are applied every this many milliseconds.
+    pub update_interval: f64,
+    /// Added to every plastic weight at each update, so silent synapses can grow.
+    pub bias: f64,
+    /// Fraction of the accumulated changes kept after each update.
+    pub derivative_decay: f64,
+    /// Fraction of every potentiation taken at the same update from the other plastic synapses
+    /// onto the neuron, spread evenly over them, as if they competed for a fixed resource.
+    pub heterosynaptic: f64,
+}
+
+impl Default for StdpParameters {
+    /// The parameters of the paper.
+    fn default() -> Self {
+        StdpParameters {
+            a_plus: 0.1,
+            a_minus: 0.12,
+            tau_plus: 20.,
+            tau_minus: 20.,
+            w_max: 10.,
+            update_interval: 1000.,
+            bias: 0.01,
+            derivative_decay: 0.9,
+            heterosynaptic: 0.,
+        }
+    }
+}
+
+/// Spike-timing-dependent plasticity of the synapses from excitatory neurons. Each arrival is
+/// paired with the postsynaptic spikes before it, and each postsynaptic spike with the latest
+/// arrival at every synapse. Without precise spike times, spikes fall at the end of their step
+/// and arrive at the start of a later one.
+#[derive(Serialize, Deserialize)]
+pub struct Stdp {
+    pub params: StdpParameters,
+    /// Weight change of every synapse accumulated for the next update, aligned with
+    /// [`SparseConnectivity`].
+    pub derivatives: Vec<f64>,
+    /// Plastic synapses whose spikes are in flight with the offset of their arrival within its
+    /// step, indexed by arrival step modulo its length.
+    arrivals: Vec<Vec<(usize, f64)>>,
+    /// Time of the latest arrival at every synapse in milliseconds.
+    last_arrival: Vec<f64>,
+    /// Exponentially decaying trace of the spikes of every neuron.
+    post_trace: Vec<f64>,
+    /// `(synapse, pre)` of the plastic synapses onto every neuron.
+    incoming: Vec<Vec<(usize, usize)>>,
+    /// Whether each neuron's outgoing synapses are plastic.
+    plastic: Vec<bool>,
+    /// Time of the next weight update in milliseconds.
+    next_update: f64,
+}
+
+impl Stdp {
+    pub fn new(
+        params: StdpParameters,
+        connectivity: &SparseConnectivity,
+        synapse_types: &[SynapseType],
+    ) -> Self {
+        let max_delay = connectivity.delays().iter().copied().max().unwrap_or(1) as usize;
+        let (plastic, incoming) = excitatory_synapses(connectivity, synapse_types);
+        Stdp {
+            next_update: params.update_interval,
+            params,
+            derivatives: vec![0.; connectivity.synapses()],
+            arrivals: vec![vec![]; max_delay],
+            last_arrival: vec![f64::NEG_INFINITY; connectivity.synapses()],
+            post_trace: vec![0.; connectivity.neurons()],
+            incoming,
+            plastic,
+        }
+    }
+
+    /// Accounts for step `time_step` of `dt`, in which `spikes` were emitted at the `offsets` of
+    /// precise spike times or else at the end, and applies the accumulated changes when an
+    /// update is due.
+    pub fn on_step(
+        &mut self,
+        spikes: &[bool],
+        offsets: Option<&[f64]>,
+        time_step: usize,
+        dt: f64,
+        connectivity: &mut SparseConnectivity,
+    ) {
+        let params = &self.params;
+        let len = self.arrivals.len();
+        let start = time_step as f64 * dt;
+        // offset of the postsynaptic spike of every neuron in this step, if any
+        let spike_offset = |idx: usize| spikes[idx].then(|| offsets.map_or(1., |o| o[idx]));
+        // spikes arriving during this step, after the postsynaptic spikes before them, and
+        // paired with later ones
+        let mut late = vec![];
+        for (synapse, offset) in std::mem::take(&mut self.arrivals[time_step % len]) {
+            let post = connectivity.targets()[synapse];
+            let mut trace = self.post_trace[post] * (-offset * dt / params.tau_minus).exp();
+            match spike_offset(post) {
+                Some(spiked) if spiked < offset => {
+                    trace += (-(offset - spiked) * dt / params.tau_minus).exp();
+                    late.push((synapse, offset));
+                }
+                _ => self.last_arrival[synapse] = start + offset * dt,
+            }
+            self.derivatives[synapse] -= params.a_minus * trace;
+        }
+
+        let decay = (-dt / params.tau_minus).exp();
+        for (idx, trace) in self.post_trace.iter_mut().enumerate() {
+            *trace = *trace * decay
+                + spike_offset(idx)
+                    .map_or(0., |offset| (-(1. - offset) * dt / params.tau_minus).exp());
+        }
+
+        let end = start + dt;
+        for (idx, _) in spikes.iter().enumerate().filter(|&(_, &spiked)| spiked) {
+            let offset = spike_offset(idx).unwrap_or(1.);
+            let spike_time = start + offset * dt;
+            for &(synapse, _) in &self.incoming[idx] {
+                let elapsed = spike_time - self.last_arrival[synapse];
+                self.derivatives[synapse] += params.a_plus * (-elapsed / params.tau_plus).exp();
+            }
+            if self.plastic[idx] {
+                // the delays are whole steps, keeping the offset of the spike
+                let arrival_offset = if offsets.is_some() { offset } else { 0. };
+                for synapse in connectivity.row(idx) {
+                    let arrival = time_step + connectivity.delays()[synapse] as usize;
+                    self.arrivals[arrival % len].push((synapse, arrival_offset));
+                }
+            }
+        }
+        for (synapse, offset) in late {
+            self.last_arrival[synapse] = start + offset * dt;
+        }
+
+        if end >= self.next_update {
+            self.next_update += params.update_interval;
+            let weights = connectivity.weights_mut();
+            for incoming in &self.incoming {
+                // potentiation elsewhere onto the same neuron, shared by the other synapses
+                let potentiation: f64 = if params.heterosynaptic > 0. {
+                    incoming
+                        .iter()
+                        .map(|&(synapse, _)| (params.bias + self.derivatives[synapse]).max(0.))
+                        .sum()
+                } else {
+                    0.
+                };
+                let others = incoming.len().saturating_sub(1).max(1) as f64;
+                for &(synapse, _) in incoming {
+                    let change = params.bias + self.derivatives[synapse];
+                    let heterosynaptic =
+                        params.heterosynaptic * (potentiation - change.max(0.)) / others;
+                    let w = &mut weights[synapse];
+                    *w = (*w + change - heterosynaptic).clamp(0., params.w_max);
+                    self.derivatives[synapse] *= params.derivative_decay;
+                }
+            }
+        }
+    }
+}
+
+/// Parameters of the inhibitory plasticity of Vogels et al. (2011), "Inhibitory plasticity
+/// balances excitation and inhibition in sensory pathways and memory networks".
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct InhibitoryStdpParameters {
+    /// Change of the weight magnitude per pair of coincident spikes.
+    pub eta: f64,
+    /// Time constant of the symmetric window in milliseconds.
+    pub tau: f64,
+    /// Rate in Hz the postsynaptic neurons are driven to.
+    pub target_rate: f64,
+    /// Plastic weight magnitudes are kept in `0..=w_max`.
+    pub w_max: f64,
+}
+
+impl Default for InhibitoryStdpParameters {
+    fn default() -> Self {
+        InhibitoryStdpParameters {
+            eta: 0.01,
+            tau: 20.,
+            target_rate: 5.,
+            w_max: 5.,
+        }
+    }
+}
+
+/// Symmetric STDP of the synapses from inhibitory onto excitatory neurons: every pair of
+/// spikes close in time strengthens the inhibition, while every presynaptic spike also weakens
+/// it by a constant, so that postsynaptic neurons firing above the target rate receive more
+/// inhibition and those below less, balancing their excitation. Spike timing is taken at the
+/// presynaptic spike rather than its arrival, and changes apply immediately.
+#[derive(Serialize, Deserialize)]
+pub struct InhibitoryStdp {
+    pub params: InhibitoryStdpParameters,
+    /// Exponentially decaying trace of the spikes of every neuron.
+    trace: Vec<f64>,
+    /// `(synapse, pre)` of the plastic synapses onto every neuron.
+    incoming: Vec<Vec<(usize, usize)>>,
+    /// `(synapse, post)` of the plastic synapses from every neuron.
+    outgoing: Vec<Vec<(usize, usize)>>,
+}
+
+impl InhibitoryStdp {
+    pub fn new(
+        params: InhibitoryStdpParameters,
+        connectivity: &SparseConnectivity,
+        synapse_types: &[SynapseType],
+    ) -> Self {
+        let n = connectivity.neurons();
+        let mut incoming = vec![vec![]; n];
+        let mut outgoing = vec![vec![]; n];
+        for pre in (0..n).filter(|&pre| synapse_types[pre] == SynapseType::Inhibitory) {
+            for synapse in connectivity.row(pre) {
+                let post = connectivity.targets()[synapse];
+                if synapse_types[post] == SynapseType::Excitatory {
+                    incoming[post].push((synapse, pre));
+                    outgoing[pre].push((synapse, post));
+                }
+            }
+        }
+        InhibitoryStdp {
+            params,
+            trace: vec![0.; n],
+            incoming,
+            outgoing,
+        }
+    }
+
+    /// Accounts for the `spikes` of a step of `dt`.
+    pub fn on_step(&mut self, spikes: &[bool], dt: f64, connectivity: &mut SparseConnectivity) {
+        let params = &self.params;
+        // depression per presynaptic spike, which balances the potentiation at the target rate
+        let alpha = 2. * params.target_rate / 1000. * params.tau;
+        let decay = (-dt / params.tau).exp();
+        for trace in &mut self.trace {
+            *trace *= decay;
+        }
+        let weights = connectivity.weights_mut();
+        let mut change = |synapse: usize, amount: f64| {
+            // inhibitory weights are negative
+            let w = &mut weights[synapse];
+            *w = -(-*w + params.eta * amount).clamp(0., params.w_max);
+        };
+        for (idx, _) in spikes.iter().enumerate().filter(|&(_, &spiked)| spiked) {
+            for &(synapse, post) in &self.outgoing[idx] {
+                change(synapse, self.trace[post] - alpha);
+            }
+            for &(synapse, pre) in &self.incoming[idx] {
+                change(synapse, self.trace[pre]);
+            }
+        }
+        for (trace, _) in self
+            .trace
+            .iter_mut()
+            .zip(spikes)
+            .filter(|(_, &spiked)| spiked)
+        {
+            *trace += 1.;
+        }
+    }
+}
+
+/// Intrinsic parameter adapted by [`Homeostasis`].
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub enum HomeostaticVariable {
+    /// Factor on all input current of the neuron, raised while it fires below the target rate.
+    InputGain,
+    /// After-spike increment `d` of the recovery variable, lowered while the neuron fires below
+    /// the target rate. AdEx neurons ignore `d`, so they do not adapt.
+    RecoveryIncrement,
+}
+
+/// Parameters of intrinsic homeostatic plasticity, which drives the running firing rate of every
+/// neuron towards a target by slowly adapting one of its own parameters.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct HomeostasisParameters {
+    /// Firing rate every neuron converges to, in Hz.
+    pub target_rate: f64,
+    /// Time constant of the running rate estimate in milliseconds.
+    pub tau: f64,
+    /// Change of the adapted parameter per millisecond and Hz of rate error.
+    pub learning_rate: f64,
+    pub variable: HomeostaticVariable,
+    /// The adapted parameter is kept in `min..=max`.
+    pub min: f64,
+    pub max: f64,
+}
+
+impl Default for HomeostasisParameters {
+    /// An input gain between 0.1 and 10 settling at 5 Hz within about ten seconds.
+    fn default() -> Self {
+        HomeostasisParameters {
+            target_rate: 5.,
+            tau: 1000.,
+            learning_rate: 1e-5,
+            variable: HomeostaticVariable::InputGain,
+            min: 0.1,
+            max: 10.,
+        }
+    }
+}
+
+/// Running firing rate estimate and input gain of every neuron.
+#[derive(Serialize, Deserialize)]
+pub struct Homeostasis {
+    pub params: HomeostasisParameters,
+    /// Exponentially weighted firing rate of every neuron in Hz, starting at the target.
+    pub rates: Vec<f64>,
+    /// Factor on the input of every neuron, fixed at 1 unless it is the adapted variable.
+    pub gains: Vec<f64>,
+}
+
+impl Homeostasis {
+    pub fn new(params: HomeostasisParameters, neurons: usize) -> Self {
+        Homeostasis {
+            rates: vec![params.target_rate; neurons],
+            gains: vec![1.; neurons],
+            params,
+        }
+    }
+
+    /// Updates the rate estimates with the `spikes` of a step of `dt` milliseconds and adapts
+    /// the parameters.
+    pub fn on_step(&mut self, spikes: &[bool], dt: f64, neurons: &mut Neurons) {
+        let params = &self.params;
+        let decay = (-dt / params.tau).exp();
+        // a spike adds its share of a rate averaged over tau
+        let spike_rate = 1000. / params.tau;
+        for (i, &spiked) in spikes.iter().enumerate() {
+            self.rates[i] = self.rates[i] * decay + if spiked { spike_rate } else { 0. };
+            let change = params.learning_rate * dt * (params.target_rate - self.rates[i]);
+            match params.variable {
+                HomeostaticVariable::InputGain => {
+                    self.gains[i] = (self.gains[i] + change).clamp(params.min, params.max);
+                }
+                HomeostaticVariable::RecoveryIncrement => {
+                    neurons.d[i] = (neurons.d[i] - change).clamp(params.min, params.max);
+                }
+            }
+        }
+    }
+}
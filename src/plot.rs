@@ -0,0 +1,1127 @@
+#[cfg(not(target_arch = "wasm32"))]
+use std::io::Write;
+#[cfg(not(target_arch = "wasm32"))]
+use std::ops::Range;
+#[cfg(not(target_arch = "wasm32"))]
+use std::path::Path;
+#[cfg(not(target_arch = "wasm32"))]
+use std::process::{Command, Stdio};
+
+use plotters::coord::Shift;
+use plotters::prelude::*;
+
+// plotters cannot write image files on wasm32, where only `recent_raster` is available
+#[cfg(not(target_arch = "wasm32"))]
+use crate::analysis::{
+    Correlogram, Histogram, Psth, SpikeTrains, SpikeTriggeredAverage, WeightHistory,
+};
+#[cfg(not(target_arch = "wasm32"))]
+use crate::bifurcation::{Bifurcation, FiringPattern};
+#[cfg(not(target_arch = "wasm32"))]
+use crate::clamp::{ClampTrace, FiCurve};
+#[cfg(not(target_arch = "wasm32"))]
+use crate::neuron::NeuronParameters;
+use crate::simulator::WorldState;
+#[cfg(not(target_arch = "wasm32"))]
+use crate::spectrum::{Band, PowerSpectrum};
+
+/// Image format of a plot, chosen by the extension of its path.
+#[cfg(not(target_arch = "wasm32"))]
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum PlotFormat {
+    Png,
+    /// Scalable vector graphics, for embedding in papers.
+    Svg,
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+impl PlotFormat {
+    /// SVG for a path ending in `.svg` in any case, PNG otherwise.
+    pub fn from_path(path: &Path) -> Self {
+        match path.extension().and_then(|extension| extension.to_str()) {
+            Some(extension) if extension.eq_ignore_ascii_case("svg") => PlotFormat::Svg,
+            _ => PlotFormat::Png,
+        }
+    }
+
+    pub fn extension(self) -> &'static str {
+        match self {
+            PlotFormat::Png => "png",
+            PlotFormat::Svg => "svg",
+        }
+    }
+}
+
+/// Evaluates `$body` with `$root` the drawing area of an image of `$size` at `$path`, a bitmap or
+/// an SVG by the [`PlotFormat`] of the path.
+#[cfg(not(target_arch = "wasm32"))]
+macro_rules! render {
+    ($path:expr, $size:expr, |$root:ident| $body:block) => {
+        match PlotFormat::from_path($path.as_ref()) {
+            PlotFormat::Png => {
+                let $root = BitMapBackend::new($path.as_ref(), $size).into_drawing_area();
+                $body
+            }
+            PlotFormat::Svg => {
+                let $root = SVGBackend::new($path.as_ref(), $size).into_drawing_area();
+                $body
+            }
+        }
+    };
+}
+
+/// Color of the `idx`th population, keeping the classic black for a single population.
+pub fn population_color(idx: usize) -> RGBColor {
+    const COLORS: [RGBColor; 6] = [
+        RGBColor(0, 0, 0),
+        RGBColor(214, 39, 40),
+        RGBColor(31, 119, 180),
+        RGBColor(44, 160, 44),
+        RGBColor(255, 127, 14),
+        RGBColor(148, 103, 189),
+    ];
+    COLORS[idx % COLORS.len()]
+}
+
+/// Renders the spike raster to a PNG or SVG at `path`, coloring each population differently, with
+/// the population rates in bins of `rate_bin` milliseconds under it if given.
+#[cfg(not(target_arch = "wasm32"))]
+pub fn raster<P: AsRef<Path>>(
+    path: P,
+    trains: &SpikeTrains,
+    populations: &[(String, Range<usize>)],
+    rate_bin: Option<f64>,
+) -> Result<(), Box<dyn std::error::Error>> {
+    let height = if rate_bin.is_some() { 1500 } else { 1200 };
+    render!(path, (800, height), |root| {
+        root.fill(&WHITE)?;
+        let (raster_area, rate_area) = root.split_vertically(1200);
+
+        let mut chart = ChartBuilder::on(&raster_area)
+            .margin(20)
+            .x_label_area_size(35)
+            .y_label_area_size(35)
+            .build_cartesian_2d(
+                trains.start..trains.start + trains.duration,
+                0..trains.neurons() as i32,
+            )?;
+
+        chart
+            .configure_mesh()
+            .disable_mesh()
+            .x_desc("Time (ms)")
+            .y_desc("Neuron Index")
+            .draw()?;
+
+        for (idx, (name, neurons)) in populations.iter().enumerate() {
+            let color = population_color(idx);
+            chart
+                .draw_series(neurons.clone().flat_map(|neuron_idx| {
+                    trains.trains[neuron_idx].iter().map(move |&time| {
+                        Circle::new((time, neuron_idx as i32), 1, color.mix(0.3).filled())
+                    })
+                }))?
+                .label(name)
+                .legend(move |(x, y)| Circle::new((x, y), 3, color.filled()));
+        }
+
+        chart
+            .configure_series_labels()
+            .background_style(WHITE.mix(0.8))
+            .border_style(BLACK)
+            .draw()?;
+
+        if let Some(bin) = rate_bin {
+            let rates: Vec<Vec<f64>> = populations
+                .iter()
+                .map(|(_, neurons)| trains.population_rate(neurons.clone(), bin))
+                .collect();
+            let y_max = rates.iter().flatten().copied().fold(0., f64::max).max(1.);
+            let mut chart = ChartBuilder::on(&rate_area)
+                .margin(20)
+                .x_label_area_size(35)
+                .y_label_area_size(35)
+                .build_cartesian_2d(
+                    trains.start..trains.start + trains.duration,
+                    0.0..y_max * 1.05,
+                )?;
+
+            chart
+                .configure_mesh()
+                .disable_mesh()
+                .x_desc("Time (ms)")
+                .y_desc("Hz")
+                .draw()?;
+
+            for (idx, rates) in rates.iter().enumerate() {
+                chart.draw_series(LineSeries::new(
+                    rates
+                        .iter()
+                        .enumerate()
+                        .map(|(k, &rate)| (trains.start + (k as f64 + 0.5) * bin, rate)),
+                    population_color(idx),
+                ))?;
+            }
+        }
+
+        root.present()?;
+        Ok(())
+    })
+}
+
+/// Draws the raster of the spikes of `world` in the last `window_ms` milliseconds onto `root`,
+/// for displays refreshed while the simulation runs.
+pub fn recent_raster<DB: DrawingBackend>(
+    root: &DrawingArea<DB, Shift>,
+    world: &WorldState,
+    window_ms: f64,
+) -> Result<(), Box<dyn std::error::Error>>
+where
+    DB::ErrorType: 'static,
+{
+    let end = world.time();
+    let start = (end - window_ms).max(0.);
+    let first_step = (start / world.dt) as usize;
+
+    root.fill(&WHITE)?;
+    let mut chart = ChartBuilder::on(root)
+        .margin(10)
+        .x_label_area_size(30)
+        .y_label_area_size(40)
+        .build_cartesian_2d(start..start + window_ms, 0..world.neurons.len() as i32)?;
+    chart
+        .configure_mesh()
+        .disable_mesh()
+        .x_desc("Time (ms)")
+        .y_desc("Neuron Index")
+        .draw()?;
+
+    for (idx, (_, neurons)) in world.populations().into_iter().enumerate() {
+        let color = population_color(idx);
+        chart.draw_series(
+            world
+                .spike_record()
+                .since(first_step)
+                .filter(|(_, i)| neurons.contains(i))
+                .map(|(time_step, i)| Pixel::new((time_step as f64 * world.dt, i as i32), color)),
+        )?;
+    }
+    root.present()?;
+    Ok(())
+}
+
+/// Renders the spike raster as an animation at `path` of a window of `window` milliseconds
+/// scrolling `frame_step` milliseconds per frame through the recording, played at `fps` frames
+/// per second: an animated GIF, or an MP4 for a path ending in `.mp4`, encoded by the `ffmpeg`
+/// executable, which must be on the `PATH`.
+///
+/// # Panics
+///
+/// If `window` or `frame_step` is not positive or `fps` is 0.
+#[cfg(not(target_arch = "wasm32"))]
+pub fn animation<P: AsRef<Path>>(
+    path: P,
+    trains: &SpikeTrains,
+    populations: &[(String, Range<usize>)],
+    window: f64,
+    frame_step: f64,
+    fps: u32,
+) -> Result<(), Box<dyn std::error::Error>> {
+    assert!(window > 0., "the window must be positive");
+    let ends = frame_ends(trains, frame_step);
+    encode_animation(path.as_ref(), (800, 600), ends.len(), fps, |root, frame| {
+        raster_frame(root, trains, populations, ends[frame], window)
+    })
+}
+
+/// Renders the activity of neurons at `positions` as an animation at `path` of heatmaps of
+/// `grid` columns and rows spanning the positions, each cell colored by the rate of its neurons
+/// in the `window` milliseconds up to a frame, `frame_step` milliseconds apart and played at
+/// `fps` frames per second. The colors scale to the highest rate of any cell in any frame, so
+/// that frames compare. The animation is a GIF or an MP4 as for [`animation`].
+///
+/// # Panics
+///
+/// If `positions` does not match the neurons of `trains`, the grid is empty, `window` or
+/// `frame_step` is not positive or `fps` is 0.
+#[cfg(not(target_arch = "wasm32"))]
+pub fn spatial_animation<P: AsRef<Path>>(
+    path: P,
+    trains: &SpikeTrains,
+    positions: &[[f64; 2]],
+    grid: (usize, usize),
+    window: f64,
+    frame_step: f64,
+    fps: u32,
+) -> Result<(), Box<dyn std::error::Error>> {
+    assert_eq!(positions.len(), trains.neurons(), "one position per neuron");
+    assert!(grid.0 > 0 && grid.1 > 0, "the grid must have cells");
+    assert!(window > 0., "the window must be positive");
+
+    let (columns, rows) = grid;
+    let extent = |axis: usize| {
+        let values = positions.iter().map(|position| position[axis]);
+        let min = values.clone().fold(f64::INFINITY, f64::min);
+        let max = values.fold(f64::NEG_INFINITY, f64::max);
+        if min < max {
+            min..max
+        } else {
+            min - 0.5..min + 0.5
+        }
+    };
+    let (x, y) = (extent(0), extent(1));
+    let index = |value: f64, range: &Range<f64>, cells: usize| {
+        (((value - range.start) / (range.end - range.start) * cells as f64) as usize).min(cells - 1)
+    };
+    let cells: Vec<usize> = positions
+        .iter()
+        .map(|&[px, py]| index(py, &y, rows) * columns + index(px, &x, columns))
+        .collect();
+    let mut occupancy = vec![0; columns * rows];
+    for &cell in &cells {
+        occupancy[cell] += 1;
+    }
+
+    let ends = frame_ends(trains, frame_step);
+    let rates: Vec<Vec<f64>> = ends
+        .iter()
+        .map(|&end| {
+            let start = (end - window).max(trains.start);
+            let mut counts = vec![0.; columns * rows];
+            for (train, &cell) in trains.trains.iter().zip(&cells) {
+                counts[cell] += (train.partition_point(|&time| time <= end)
+                    - train.partition_point(|&time| time <= start))
+                    as f64;
+            }
+            let span = (end - start).max(f64::MIN_POSITIVE);
+            counts
+                .iter()
+                .zip(&occupancy)
+                .map(|(&count, &neurons)| 1e3 * count / (neurons.max(1) as f64 * span))
+                .collect()
+        })
+        .collect();
+    let peak = rates.iter().flatten().copied().fold(0., f64::max).max(1.);
+
+    let (width, height) = (x.end - x.start, y.end - y.start);
+    encode_animation(path.as_ref(), (800, 800), ends.len(), fps, |root, frame| {
+        root.fill(&WHITE)?;
+        let mut chart = ChartBuilder::on(root)
+            .caption(
+                format!("{:.0} ms, peak {peak:.1} Hz", ends[frame]),
+                ("sans-serif", 20),
+            )
+            .margin(10)
+            .x_label_area_size(30)
+            .y_label_area_size(40)
+            .build_cartesian_2d(x.clone(), y.clone())?;
+        chart.configure_mesh().disable_mesh().draw()?;
+        chart.draw_series(rates[frame].iter().enumerate().map(|(cell, &rate)| {
+            let (column, row) = ((cell % columns) as f64, (cell / columns) as f64);
+            let corner = |column: f64, row: f64| {
+                (
+                    x.start + column * width / columns as f64,
+                    y.start + row * height / rows as f64,
+                )
+            };
+            Rectangle::new(
+                [corner(column, row), corner(column + 1., row + 1.)],
+                ViridisRGB::get_color(rate / peak).filled(),
+            )
+        }))?;
+        root.present()?;
+        Ok(())
+    })
+}
+
+/// Ends of the frames of an animation `frame_step` milliseconds apart, the last at the end of
+/// the recording.
+///
+/// # Panics
+///
+/// If `frame_step` is not positive.
+#[cfg(not(target_arch = "wasm32"))]
+fn frame_ends(trains: &SpikeTrains, frame_step: f64) -> Vec<f64> {
+    assert!(frame_step > 0., "the frame step must be positive");
+    let frames = ((trains.duration / frame_step).ceil() as usize).max(1);
+    let end = trains.start + trains.duration;
+    (1..=frames)
+        .map(|frame| (trains.start + frame as f64 * frame_step).min(end))
+        .collect()
+}
+
+/// Encodes `frames` frames of `size` drawn by `draw` from their index into an animated GIF at
+/// `path` played at `fps` frames per second, or an MP4 through `ffmpeg` for a path ending in
+/// `.mp4`.
+///
+/// # Panics
+///
+/// If `fps` is 0.
+#[cfg(not(target_arch = "wasm32"))]
+fn encode_animation(
+    path: &Path,
+    size: (u32, u32),
+    frames: usize,
+    fps: u32,
+    mut draw: impl FnMut(
+        &DrawingArea<BitMapBackend, Shift>,
+        usize,
+    ) -> Result<(), Box<dyn std::error::Error>>,
+) -> Result<(), Box<dyn std::error::Error>> {
+    assert!(fps > 0, "the frame rate must be positive");
+    let mp4 = path
+        .extension()
+        .and_then(|extension| extension.to_str())
+        .is_some_and(|extension| extension.eq_ignore_ascii_case("mp4"));
+    if !mp4 {
+        let root = BitMapBackend::gif(path, size, 1000 / fps)?.into_drawing_area();
+        for frame in 0..frames {
+            draw(&root, frame)?;
+        }
+        return Ok(());
+    }
+
+    let mut ffmpeg = Command::new("ffmpeg")
+        .args([
+            "-y",
+            "-loglevel",
+            "error",
+            "-f",
+            "rawvideo",
+            "-pixel_format",
+            "rgb24",
+        ])
+        .args(["-video_size", &format!("{}x{}", size.0, size.1)])
+        .args([
+            "-framerate",
+            &fps.to_string(),
+            "-i",
+            "-",
+            "-pix_fmt",
+            "yuv420p",
+        ])
+        .arg(path)
+        .stdin(Stdio::piped())
+        .spawn()
+        .map_err(|e| format!("cannot run ffmpeg to encode {}: {e}", path.display()))?;
+    let mut stdin = ffmpeg.stdin.take().expect("stdin of ffmpeg is piped");
+    let mut buffer = vec![0; (size.0 * size.1 * 3) as usize];
+    for frame in 0..frames {
+        {
+            let root = BitMapBackend::with_buffer(&mut buffer, size).into_drawing_area();
+            draw(&root, frame)?;
+        }
+        stdin.write_all(&buffer)?;
+    }
+    drop(stdin);
+    let status = ffmpeg.wait()?;
+    if !status.success() {
+        return Err(format!("ffmpeg failed to encode {}: {status}", path.display()).into());
+    }
+    Ok(())
+}
+
+/// Draws the raster of `trains` in the `window` milliseconds up to `end` onto `root` as a frame
+/// of an [`animation`], the window starting with the recording until it is filled.
+#[cfg(not(target_arch = "wasm32"))]
+fn raster_frame<DB: DrawingBackend>(
+    root: &DrawingArea<DB, Shift>,
+    trains: &SpikeTrains,
+    populations: &[(String, Range<usize>)],
+    end: f64,
+    window: f64,
+) -> Result<(), Box<dyn std::error::Error>>
+where
+    DB::ErrorType: 'static,
+{
+    let start = (end - window).max(trains.start);
+
+    root.fill(&WHITE)?;
+    let mut chart = ChartBuilder::on(root)
+        .margin(10)
+        .x_label_area_size(30)
+        .y_label_area_size(40)
+        .build_cartesian_2d(start..start + window, 0..trains.neurons() as i32)?;
+    chart
+        .configure_mesh()
+        .disable_mesh()
+        .x_desc("Time (ms)")
+        .y_desc("Neuron Index")
+        .draw()?;
+
+    for (idx, (_, neurons)) in populations.iter().enumerate() {
+        let color = population_color(idx);
+        chart.draw_series(neurons.clone().flat_map(|neuron_idx| {
+            let train = &trains.trains[neuron_idx];
+            let first = train.partition_point(|&time| time < start);
+            let last = train.partition_point(|&time| time <= end);
+            train[first..last]
+                .iter()
+                .map(move |&time| Circle::new((time, neuron_idx as i32), 1, color.filled()))
+        }))?;
+    }
+    root.present()?;
+    Ok(())
+}
+
+/// Renders `histogram` as a bar chart to a PNG or SVG at `path`.
+#[cfg(not(target_arch = "wasm32"))]
+pub fn histogram<P: AsRef<Path>>(
+    path: P,
+    histogram: &Histogram,
+    x_desc: &str,
+) -> Result<(), Box<dyn std::error::Error>> {
+    render!(path, (800, 600), |root| {
+        root.fill(&WHITE)?;
+
+        let x_max = histogram.counts.len() as f64 * histogram.bin_width;
+        let y_max = histogram.counts.iter().copied().max().unwrap_or(0).max(1);
+        let mut chart = ChartBuilder::on(&root)
+            .margin(20)
+            .x_label_area_size(35)
+            .y_label_area_size(50)
+            .build_cartesian_2d(0.0..x_max, 0..y_max)?;
+
+        chart
+            .configure_mesh()
+            .disable_mesh()
+            .x_desc(x_desc)
+            .y_desc("Count")
+            .draw()?;
+
+        chart.draw_series(histogram.bin_starts().zip(&histogram.counts).map(
+            |(start, &count)| {
+                Rectangle::new(
+                    [(start, 0), (start + histogram.bin_width, count)],
+                    BLACK.mix(0.6).filled(),
+                )
+            },
+        ))?;
+
+        root.present()?;
+        Ok(())
+    })
+}
+
+/// Most snapshots drawn by [`weight_distributions`].
+#[cfg(not(target_arch = "wasm32"))]
+const DISTRIBUTIONS: usize = 5;
+
+/// Renders the weight distributions of up to five snapshots evenly spread over `history`, including
+/// the first and the last, as outlined histograms to a PNG or SVG at `path`.
+#[cfg(not(target_arch = "wasm32"))]
+pub fn weight_distributions<P: AsRef<Path>>(
+    path: P,
+    history: &WeightHistory,
+) -> Result<(), Box<dyn std::error::Error>> {
+    render!(path, (800, 600), |root| {
+        root.fill(&WHITE)?;
+
+        let snapshots = history.distributions.len();
+        let mut shown: Vec<usize> = (0..DISTRIBUTIONS.min(snapshots))
+            .map(|k| k * (snapshots - 1) / (DISTRIBUTIONS.min(snapshots) - 1).max(1))
+            .collect();
+        shown.dedup();
+        let bins = shown
+            .iter()
+            .map(|&k| history.distributions[k].counts.len())
+            .max()
+            .unwrap_or(1);
+        let x_max = bins as f64 * history.bin_width;
+        let y_max = shown
+            .iter()
+            .flat_map(|&k| history.distributions[k].counts.iter().copied())
+            .max()
+            .unwrap_or(0)
+            .max(1);
+        let mut chart = ChartBuilder::on(&root)
+            .margin(20)
+            .x_label_area_size(35)
+            .y_label_area_size(60)
+            .build_cartesian_2d(0.0..x_max, 0..y_max)?;
+
+        chart
+            .configure_mesh()
+            .disable_mesh()
+            .x_desc("Weight")
+            .y_desc("Synapses")
+            .draw()?;
+
+        for (idx, &k) in shown.iter().enumerate() {
+            let histogram = &history.distributions[k];
+            let color = population_color(idx);
+            let outline = histogram
+                .bin_starts()
+                .zip(&histogram.counts)
+                .flat_map(|(start, &count)| [(start, count), (start + histogram.bin_width, count)]);
+            chart
+                .draw_series(LineSeries::new(outline, color))?
+                .label(format!("{:.0} ms", history.times[k]))
+                .legend(move |(x, y)| PathElement::new([(x, y), (x + 20, y)], color));
+        }
+
+        chart
+            .configure_series_labels()
+            .background_style(WHITE.mix(0.8))
+            .border_style(BLACK)
+            .draw()?;
+
+        root.present()?;
+        Ok(())
+    })
+}
+
+/// Renders the weights of the sampled synapses of `history` over time to a PNG or SVG at `path`.
+#[cfg(not(target_arch = "wasm32"))]
+pub fn weight_traces<P: AsRef<Path>>(
+    path: P,
+    history: &WeightHistory,
+) -> Result<(), Box<dyn std::error::Error>> {
+    render!(path, (1200, 600), |root| {
+        root.fill(&WHITE)?;
+
+        let start = history.times.first().copied().unwrap_or(0.);
+        let end = history.times.last().copied().unwrap_or(0.).max(start + 1.);
+        let weights: Vec<f64> = history.traces.iter().flatten().copied().collect();
+        let mut chart = ChartBuilder::on(&root)
+            .margin(20)
+            .caption(
+                format!("{} sampled synapses", history.sampled.len()),
+                ("sans-serif", 20),
+            )
+            .x_label_area_size(35)
+            .y_label_area_size(60)
+            .build_cartesian_2d(start..end, padded_range(&weights))?;
+
+        chart
+            .configure_mesh()
+            .disable_mesh()
+            .x_desc("Time (ms)")
+            .y_desc("Weight")
+            .draw()?;
+
+        for (idx, trace) in history.traces.iter().enumerate() {
+            chart.draw_series(LineSeries::new(
+                history.times.iter().copied().zip(trace.iter().copied()),
+                population_color(idx),
+            ))?;
+        }
+
+        root.present()?;
+        Ok(())
+    })
+}
+
+/// Renders a peri-stimulus time histogram to a PNG or SVG at `path`, with the onset at 0.
+#[cfg(not(target_arch = "wasm32"))]
+pub fn psth<P: AsRef<Path>>(path: P, psth: &Psth) -> Result<(), Box<dyn std::error::Error>> {
+    render!(path, (800, 600), |root| {
+        root.fill(&WHITE)?;
+
+        let x_end = psth.start + psth.rates.len() as f64 * psth.bin;
+        let y_max = psth.rates.iter().copied().fold(0., f64::max).max(1.);
+        let mut chart = ChartBuilder::on(&root)
+            .margin(20)
+            .caption(
+                format!("PSTH over {} trials", psth.trials),
+                ("sans-serif", 20),
+            )
+            .x_label_area_size(35)
+            .y_label_area_size(50)
+            .build_cartesian_2d(psth.start..x_end, 0.0..y_max * 1.05)?;
+
+        chart
+            .configure_mesh()
+            .disable_mesh()
+            .x_desc("Time from onset (ms)")
+            .y_desc("Rate (Hz)")
+            .draw()?;
+
+        chart.draw_series(psth.bin_starts().zip(&psth.rates).map(|(start, &rate)| {
+            Rectangle::new(
+                [(start, 0.), (start + psth.bin, rate)],
+                BLACK.mix(0.6).filled(),
+            )
+        }))?;
+        chart.draw_series(LineSeries::new(
+            [(0., 0.), (0., y_max * 1.05)],
+            RGBColor(214, 39, 40),
+        ))?;
+
+        root.present()?;
+        Ok(())
+    })
+}
+
+/// Renders cross-correlograms as stacked bar charts to a PNG or SVG at `path`, each titled by its
+/// name.
+#[cfg(not(target_arch = "wasm32"))]
+pub fn correlograms<P: AsRef<Path>>(
+    path: P,
+    correlograms: &[(String, Correlogram)],
+) -> Result<(), Box<dyn std::error::Error>> {
+    let height = 300 * correlograms.len().max(1) as u32;
+    render!(path, (800, height), |root| {
+        root.fill(&WHITE)?;
+
+        let areas = root.split_evenly((correlograms.len().max(1), 1));
+        for (area, (name, correlogram)) in areas.iter().zip(correlograms) {
+            let end = correlogram.start + correlogram.rates.len() as f64 * correlogram.bin;
+            let y_max = correlogram.rates.iter().copied().fold(0., f64::max).max(1.);
+            let mut chart = ChartBuilder::on(area)
+                .margin(15)
+                .caption(
+                    format!("{name} over {} spikes", correlogram.references),
+                    ("sans-serif", 18),
+                )
+                .x_label_area_size(35)
+                .y_label_area_size(50)
+                .build_cartesian_2d(correlogram.start..end, 0.0..y_max * 1.05)?;
+
+            chart
+                .configure_mesh()
+                .disable_mesh()
+                .x_desc("Lag (ms)")
+                .y_desc("Rate (Hz)")
+                .draw()?;
+
+            chart.draw_series(correlogram.bin_starts().zip(&correlogram.rates).map(
+                |(start, &rate)| {
+                    Rectangle::new(
+                        [(start, 0.), (start + correlogram.bin, rate)],
+                        BLACK.mix(0.6).filled(),
+                    )
+                },
+            ))?;
+        }
+
+        root.present()?;
+        Ok(())
+    })
+}
+
+/// Renders the spike-triggered averages of the thalamic input onto several neurons to a PNG or SVG
+/// at `path`, one line per neuron, with the spike at lag 0.
+#[cfg(not(target_arch = "wasm32"))]
+pub fn spike_triggered_averages<P: AsRef<Path>>(
+    path: P,
+    stas: &[(usize, SpikeTriggeredAverage)],
+) -> Result<(), Box<dyn std::error::Error>> {
+    render!(path, (800, 600), |root| {
+        root.fill(&WHITE)?;
+
+        let start = stas
+            .iter()
+            .filter_map(|(_, sta)| sta.lags().next())
+            .fold(0., f64::min);
+        let values: Vec<f64> = stas
+            .iter()
+            .flat_map(|(_, sta)| sta.average.iter().copied())
+            .collect();
+        let mut chart = ChartBuilder::on(&root)
+            .margin(20)
+            .x_label_area_size(35)
+            .y_label_area_size(50)
+            .build_cartesian_2d(start.min(-1.)..0., padded_range(&values))?;
+
+        chart
+            .configure_mesh()
+            .disable_mesh()
+            .x_desc("Time before spike (ms)")
+            .y_desc("Thalamic input")
+            .draw()?;
+
+        for (k, (idx, sta)) in stas.iter().enumerate() {
+            let color = population_color(k);
+            chart
+                .draw_series(LineSeries::new(
+                    sta.lags().zip(sta.average.iter().copied()),
+                    color,
+                ))?
+                .label(format!("neuron {idx} ({} spikes)", sta.spikes))
+                .legend(move |(x, y)| PathElement::new([(x, y), (x + 20, y)], color));
+        }
+
+        chart
+            .configure_series_labels()
+            .background_style(WHITE.mix(0.8))
+            .border_style(BLACK)
+            .draw()?;
+
+        root.present()?;
+        Ok(())
+    })
+}
+
+/// Renders the recorded local field potential proxy of `world` over time to a PNG or SVG at `path`.
+#[cfg(not(target_arch = "wasm32"))]
+pub fn lfp<P: AsRef<Path>>(path: P, world: &WorldState) -> Result<(), Box<dyn std::error::Error>> {
+    render!(path, (1200, 400), |root| {
+        root.fill(&WHITE)?;
+
+        let lfp = world.lfp.as_deref().unwrap_or_default();
+        // the samples are over the last steps, timed at their ends
+        let start_step = world.time_step + 1 - lfp.len();
+        let times = (start_step..).map(|step| step as f64 * world.dt);
+        let end = world.time_step as f64 * world.dt;
+        let mut chart = ChartBuilder::on(&root)
+            .margin(20)
+            .x_label_area_size(35)
+            .y_label_area_size(60)
+            .build_cartesian_2d(start_step as f64 * world.dt..end.max(1.), padded_range(lfp))?;
+
+        chart
+            .configure_mesh()
+            .disable_mesh()
+            .x_desc("Time (ms)")
+            .y_desc("LFP proxy")
+            .draw()?;
+
+        chart.draw_series(LineSeries::new(times.zip(lfp.iter().copied()), BLACK))?;
+
+        root.present()?;
+        Ok(())
+    })
+}
+
+/// Renders a power spectrum on a logarithmic scale up to the end of the gamma band or the Nyquist
+/// frequency to a PNG or SVG at `path`, over the shaded frequency bands.
+#[cfg(not(target_arch = "wasm32"))]
+pub fn power_spectrum<P: AsRef<Path>>(
+    path: P,
+    spectrum: &PowerSpectrum,
+) -> Result<(), Box<dyn std::error::Error>> {
+    render!(path, (800, 600), |root| {
+        root.fill(&WHITE)?;
+
+        let nyquist = spectrum.frequencies.last().copied().unwrap_or(0.);
+        let end = nyquist.min(Band::Gamma.range().end).max(1.);
+        // the log scale leaves out frequencies without power, including the removed mean
+        let points: Vec<(f64, f64)> = spectrum
+            .frequencies
+            .iter()
+            .copied()
+            .zip(spectrum.power.iter().copied())
+            .filter(|&(frequency, power)| frequency > 0. && frequency <= end && power > 0.)
+            .collect();
+        let (min, max) = points.iter().fold((f64::INFINITY, 0f64), |(min, max), p| {
+            (min.min(p.1), max.max(p.1))
+        });
+        let (min, max) = if points.is_empty() {
+            (0.1, 1.)
+        } else {
+            (min / 2., max * 2.)
+        };
+        let mut chart = ChartBuilder::on(&root)
+            .margin(20)
+            .x_label_area_size(35)
+            .y_label_area_size(60)
+            .build_cartesian_2d(0.0..end, (min..max).log_scale())?;
+
+        chart
+            .configure_mesh()
+            .disable_mesh()
+            .x_desc("Frequency (Hz)")
+            .y_desc("Power per Hz")
+            .draw()?;
+
+        for (k, band) in Band::ALL.into_iter().enumerate() {
+            let range = band.range();
+            if range.start >= end {
+                continue;
+            }
+            let color = population_color(k + 1).mix(0.15);
+            chart
+                .draw_series(std::iter::once(Rectangle::new(
+                    [(range.start, min), (range.end.min(end), max)],
+                    color.filled(),
+                )))?
+                .label(band.name())
+                .legend(move |(x, y)| {
+                    Rectangle::new([(x, y - 5), (x + 20, y + 5)], color.filled())
+                });
+        }
+
+        chart.draw_series(LineSeries::new(points, BLACK))?;
+
+        chart
+            .configure_series_labels()
+            .background_style(WHITE.mix(0.8))
+            .border_style(BLACK)
+            .draw()?;
+
+        root.present()?;
+        Ok(())
+    })
+}
+
+/// Range of `values` padded by 5% on either side, or around the value if they are all equal.
+#[cfg(not(target_arch = "wasm32"))]
+fn padded_range(values: &[f64]) -> Range<f64> {
+    let (min, max) = values
+        .iter()
+        .fold((f64::INFINITY, f64::NEG_INFINITY), |(min, max), &value| {
+            (min.min(value), max.max(value))
+        });
+    if !min.is_finite() || !max.is_finite() {
+        return -1.0..1.0;
+    }
+    let pad = if max > min { 0.05 * (max - min) } else { 1. };
+    min - pad..max + pad
+}
+
+/// Renders the membrane potential, recovery variable and injected current of a current-clamp
+/// experiment as stacked panels to a PNG or SVG at `path`.
+#[cfg(not(target_arch = "wasm32"))]
+pub fn clamp_trace<P: AsRef<Path>>(
+    path: P,
+    trace: &ClampTrace,
+) -> Result<(), Box<dyn std::error::Error>> {
+    render!(path, (800, 900), |root| {
+        root.fill(&WHITE)?;
+
+        let end = trace.time.last().copied().unwrap_or(1.);
+        let panels = [
+            (&trace.v, "v (mV)"),
+            (&trace.u, "u"),
+            (&trace.current, "Current"),
+        ];
+        for (area, (values, y_desc)) in root.split_evenly((3, 1)).iter().zip(panels) {
+            let mut chart = ChartBuilder::on(area)
+                .margin(15)
+                .x_label_area_size(35)
+                .y_label_area_size(50)
+                .build_cartesian_2d(0.0..end, padded_range(values))?;
+
+            chart
+                .configure_mesh()
+                .disable_mesh()
+                .x_desc("Time (ms)")
+                .y_desc(y_desc)
+                .draw()?;
+
+            chart.draw_series(LineSeries::new(
+                trace.time.iter().copied().zip(values.iter().copied()),
+                BLACK,
+            ))?;
+        }
+
+        root.present()?;
+        Ok(())
+    })
+}
+
+/// Renders the trajectory of a current-clamp experiment in the `(v, u)` plane to a PNG or SVG at
+/// `path`, over the nullclines of the Izhikevich neuron with `parameters`: the `u` nullcline, the
+/// `v` nullclines at the smallest and largest injected current, and the reset potential `c`.
+#[cfg(not(target_arch = "wasm32"))]
+pub fn phase_plane<P: AsRef<Path>>(
+    path: P,
+    trace: &ClampTrace,
+    parameters: &NeuronParameters,
+) -> Result<(), Box<dyn std::error::Error>> {
+    render!(path, (800, 800), |root| {
+        root.fill(&WHITE)?;
+
+        let (i_min, i_max) = trace
+            .current
+            .iter()
+            .fold((0f64, 0f64), |(min, max), &i| (min.min(i), max.max(i)));
+        let v_range = padded_range(&[trace.v.as_slice(), &[parameters.c, 30.]].concat());
+        // keep the knees of the v nullclines in view, the parabolae leave it on either side
+        const KNEE: f64 = -62.5;
+        let knee = parameters.v_nullcline(KNEE, i_min);
+        let u_range = padded_range(&[trace.u.as_slice(), &[knee]].concat());
+        let mut chart = ChartBuilder::on(&root)
+            .margin(20)
+            .x_label_area_size(35)
+            .y_label_area_size(50)
+            .build_cartesian_2d(v_range.clone(), u_range.clone())?;
+
+        chart
+            .configure_mesh()
+            .disable_mesh()
+            .x_desc("v (mV)")
+            .y_desc("u")
+            .draw()?;
+
+        // plotters moves points outside the chart onto its edge, so curves are cut where they
+        // leave it, and the two branches of a parabola are drawn apart
+        let samples = 400;
+        let curve = |vs: Range<f64>, u: &dyn Fn(f64) -> f64| -> Vec<(f64, f64)> {
+            let step = (vs.end - vs.start) / samples as f64;
+            (0..=samples)
+                .map(|k| vs.start + k as f64 * step)
+                .map(|v| (v, u(v)))
+                .filter(|(_, u)| u_range.contains(u))
+                .collect()
+        };
+        let mut currents = vec![i_min];
+        if i_max > i_min {
+            currents.push(i_max);
+        }
+        let blue = RGBColor(31, 119, 180);
+        for (idx, &i) in currents.iter().enumerate() {
+            let color = if idx == 0 {
+                blue.mix(0.5)
+            } else {
+                blue.mix(1.)
+            };
+            let nullcline = |v| parameters.v_nullcline(v, i);
+            chart.draw_series(LineSeries::new(
+                curve(v_range.start..KNEE, &nullcline),
+                color.stroke_width(2),
+            ))?;
+            chart
+                .draw_series(LineSeries::new(
+                    curve(KNEE..v_range.end, &nullcline),
+                    color.stroke_width(2),
+                ))?
+                .label(format!("v nullcline at I = {i}"))
+                .legend(move |(x, y)| {
+                    PathElement::new([(x, y), (x + 20, y)], color.stroke_width(2))
+                });
+        }
+        let green = RGBColor(44, 160, 44);
+        chart
+            .draw_series(LineSeries::new(
+                curve(v_range.clone(), &|v| parameters.u_nullcline(v)),
+                green.stroke_width(2),
+            ))?
+            .label("u nullcline")
+            .legend(move |(x, y)| PathElement::new([(x, y), (x + 20, y)], green.stroke_width(2)));
+        let gray = BLACK.mix(0.4);
+        chart
+            .draw_series(LineSeries::new(
+                [(parameters.c, u_range.start), (parameters.c, u_range.end)],
+                gray,
+            ))?
+            .label("reset v = c")
+            .legend(move |(x, y)| PathElement::new([(x, y), (x + 20, y)], gray));
+
+        chart
+            .draw_series(LineSeries::new(
+                trace.v.iter().copied().zip(trace.u.iter().copied()),
+                BLACK,
+            ))?
+            .label("trajectory")
+            .legend(|(x, y)| PathElement::new([(x, y), (x + 20, y)], BLACK));
+        if let (Some(&v), Some(&u)) = (trace.v.first(), trace.u.first()) {
+            chart.draw_series([Circle::new((v, u), 4, BLACK.filled())])?;
+        }
+
+        chart
+            .configure_series_labels()
+            .background_style(WHITE.mix(0.8))
+            .border_style(BLACK)
+            .draw()?;
+
+        root.present()?;
+        Ok(())
+    })
+}
+
+/// Renders the firing rate against the injected current of an f–I curve to a PNG or SVG at `path`.
+#[cfg(not(target_arch = "wasm32"))]
+pub fn fi_curve<P: AsRef<Path>>(
+    path: P,
+    curve: &FiCurve,
+) -> Result<(), Box<dyn std::error::Error>> {
+    render!(path, (800, 600), |root| {
+        root.fill(&WHITE)?;
+
+        let y_max = curve.rates.iter().copied().fold(0., f64::max).max(1.);
+        let mut chart = ChartBuilder::on(&root)
+            .margin(20)
+            .x_label_area_size(35)
+            .y_label_area_size(50)
+            .build_cartesian_2d(padded_range(&curve.currents), 0.0..y_max * 1.05)?;
+
+        chart
+            .configure_mesh()
+            .disable_mesh()
+            .x_desc("Current")
+            .y_desc("Rate (Hz)")
+            .draw()?;
+
+        let points = || {
+            curve
+                .currents
+                .iter()
+                .copied()
+                .zip(curve.rates.iter().copied())
+        };
+        chart.draw_series(LineSeries::new(points(), BLACK))?;
+        chart.draw_series(points().map(|point| Circle::new(point, 3, BLACK.filled())))?;
+
+        root.present()?;
+        Ok(())
+    })
+}
+
+/// Renders the inter-spike intervals of a [`Bifurcation`] against the varied parameter to a PNG
+/// at `path`, colored by firing pattern, with quiescent values marked at zero.
+#[cfg(not(target_arch = "wasm32"))]
+pub fn bifurcation<P: AsRef<Path>>(
+    path: P,
+    bifurcation: &Bifurcation,
+) -> Result<(), Box<dyn std::error::Error>> {
+    render!(path, (800, 600), |root| {
+        root.fill(&WHITE)?;
+
+        let values: Vec<f64> = bifurcation.points.iter().map(|point| point.value).collect();
+        let y_max = bifurcation
+            .points
+            .iter()
+            .flat_map(|point| point.isis.iter().copied())
+            .fold(0., f64::max)
+            .max(1.);
+        let mut chart = ChartBuilder::on(&root)
+            .margin(20)
+            .x_label_area_size(35)
+            .y_label_area_size(50)
+            .build_cartesian_2d(padded_range(&values), 0.0..y_max * 1.05)?;
+
+        chart
+            .configure_mesh()
+            .disable_mesh()
+            .x_desc(bifurcation.parameter.name())
+            .y_desc("Inter-spike interval (ms)")
+            .draw()?;
+
+        for pattern in FiringPattern::ALL {
+            let color = match pattern {
+                FiringPattern::Quiescent => BLACK.mix(0.4),
+                FiringPattern::Tonic => RGBColor(31, 119, 180).mix(1.),
+                FiringPattern::Bursting => RGBColor(214, 39, 40).mix(1.),
+            };
+            let points = bifurcation
+                .points
+                .iter()
+                .filter(|point| point.pattern == pattern);
+            let series = match pattern {
+                FiringPattern::Quiescent => chart.draw_series(
+                    points.map(|point| Cross::new((point.value, 0.), 4, color.stroke_width(2))),
+                )?,
+                _ => chart.draw_series(points.flat_map(|point| {
+                    point
+                        .isis
+                        .iter()
+                        .map(move |&isi| Circle::new((point.value, isi), 2, color.filled()))
+                }))?,
+            };
+            series
+                .label(pattern.name())
+                .legend(move |(x, y)| Circle::new((x, y), 3, color.filled()));
+        }
+
+        chart
+            .configure_series_labels()
+            .background_style(WHITE.mix(0.8))
+            .border_style(BLACK)
+            .draw()?;
+
+        root.present()?;
+        Ok(())
+    })
+}
@@ -0,0 +1,100 @@
+use std::fmt;
+use std::time::{Duration, Instant};
+
+/// Part of a step of the simulation timed by a [`Profile`].
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Phase {
+    /// Drawing the thalamic noise.
+    Noise,
+    /// Delivering the spikes due at the step, summing the synaptic and gap junction currents, and
+    /// scheduling the new spikes along their synapses.
+    Synapses,
+    /// Stimuli, controllers, homeostatic gains, neuromodulators and lesions.
+    Input,
+    /// Integrating the membrane potentials, on whichever backend.
+    Membrane,
+    /// Spike-timing dependent and homeostatic plasticity.
+    Plasticity,
+    /// Traces, local field potential, synchrony, weight snapshots and the spike record.
+    Recording,
+}
+
+impl Phase {
+    pub const ALL: [Phase; 6] = [
+        Phase::Noise,
+        Phase::Synapses,
+        Phase::Input,
+        Phase::Membrane,
+        Phase::Plasticity,
+        Phase::Recording,
+    ];
+
+    pub fn name(self) -> &'static str {
+        match self {
+            Phase::Noise => "noise",
+            Phase::Synapses => "synapses",
+            Phase::Input => "input",
+            Phase::Membrane => "membrane",
+            Phase::Plasticity => "plasticity",
+            Phase::Recording => "recording",
+        }
+    }
+}
+
+/// Wall-clock time spent in every [`Phase`] of the steps since profiling started, see
+/// [`WorldState::start_profiling`](crate::WorldState::start_profiling).
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct Profile {
+    /// Number of steps timed.
+    pub steps: usize,
+    /// Time of every phase, in the order of [`Phase::ALL`].
+    pub totals: [Duration; 6],
+}
+
+impl Profile {
+    /// Total time spent in `phase`.
+    pub fn total(&self, phase: Phase) -> Duration {
+        self.totals[phase as usize]
+    }
+
+    /// Time spent in all phases.
+    pub fn elapsed(&self) -> Duration {
+        self.totals.iter().sum()
+    }
+
+    /// Adds the time since `lap` to `phase` and restarts it.
+    pub(crate) fn lap(&mut self, phase: Phase, lap: &mut Instant) {
+        let now = Instant::now();
+        self.totals[phase as usize] += now - *lap;
+        *lap = now;
+    }
+}
+
+/// Table of the total time, time per step and share of every phase.
+impl fmt::Display for Profile {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let elapsed = self.elapsed().as_secs_f64();
+        writeln!(f, "Profile of {} steps", self.steps)?;
+        writeln!(
+            f,
+            "{:<12} {:>12} {:>14} {:>10}",
+            "phase", "total (ms)", "per step (µs)", "share (%)"
+        )?;
+        let rows = Phase::ALL
+            .iter()
+            .map(|&phase| (phase.name(), self.total(phase)))
+            .chain([("step", self.elapsed())]);
+        for (name, total) in rows {
+            let total = total.as_secs_f64();
+            writeln!(
+                f,
+                "{:<12} {:>12.2} {:>14.2} {:>10.1}",
+                name,
+                1e3 * total,
+                1e6 * total / self.steps.max(1) as f64,
+                100. * total / elapsed.max(f64::MIN_POSITIVE)
+            )?;
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,107 @@
+use std::io::{self, IsTerminal, Write};
+use std::time::{Duration, Instant};
+
+/// Wall-clock time between redraws of the bar.
+const REDRAW: Duration = Duration::from_millis(200);
+/// Width of the bar in characters.
+const WIDTH: usize = 30;
+
+/// Progress of a run on stderr: a bar redrawn in place on a terminal, or a line for every tenth
+/// of the run otherwise, with the speed in steps per second, the ratio of simulated to
+/// wall-clock time and the estimated time to completion.
+pub struct Progress {
+    steps: usize,
+    /// Integration time step in milliseconds.
+    dt: f64,
+    started: Instant,
+    last_draw: Option<Instant>,
+    terminal: bool,
+    /// Tenths of the run reported so far without a terminal.
+    tenths: usize,
+}
+
+impl Progress {
+    pub fn new(steps: usize, dt: f64) -> Self {
+        Progress {
+            steps,
+            dt,
+            started: Instant::now(),
+            last_draw: None,
+            terminal: io::stderr().is_terminal(),
+            tenths: 0,
+        }
+    }
+
+    /// Reports that `done` of the steps have been simulated.
+    pub fn update(&mut self, done: usize) -> io::Result<()> {
+        let mut stderr = io::stderr().lock();
+        if self.terminal {
+            if self.last_draw.is_some_and(|time| time.elapsed() < REDRAW) {
+                return Ok(());
+            }
+            self.last_draw = Some(Instant::now());
+            let filled = WIDTH * done / self.steps.max(1);
+            write!(
+                stderr,
+                "\r[{}{}] {}\x1b[K",
+                "#".repeat(filled),
+                " ".repeat(WIDTH - filled),
+                self.status(done)
+            )?;
+            stderr.flush()
+        } else {
+            let tenths = 10 * done / self.steps.max(1);
+            if tenths <= self.tenths {
+                return Ok(());
+            }
+            self.tenths = tenths;
+            writeln!(stderr, "{}", self.status(done))
+        }
+    }
+
+    /// Ends the bar with the total speed of the run.
+    pub fn finish(&mut self, done: usize) -> io::Result<()> {
+        let elapsed = self.started.elapsed().as_secs_f64();
+        let mut stderr = io::stderr().lock();
+        if self.terminal {
+            write!(stderr, "\r\x1b[K")?;
+        }
+        writeln!(
+            stderr,
+            "Simulated {:.0} ms in {}, {:.0} steps/s, {:.2}x real time",
+            done as f64 * self.dt,
+            format_duration(elapsed),
+            done as f64 / elapsed,
+            done as f64 * self.dt / (1000. * elapsed)
+        )
+    }
+
+    fn status(&self, done: usize) -> String {
+        let elapsed = self.started.elapsed().as_secs_f64();
+        let speed = done as f64 / elapsed;
+        let eta = if done == 0 {
+            "-".to_string()
+        } else {
+            format_duration(self.steps.saturating_sub(done) as f64 / speed)
+        };
+        format!(
+            "{:5.1}% {:.0}/{:.0} ms, {speed:.0} steps/s, {:.2}x real time, ETA {eta}",
+            100. * done as f64 / self.steps.max(1) as f64,
+            done as f64 * self.dt,
+            self.steps as f64 * self.dt,
+            done as f64 * self.dt / (1000. * elapsed)
+        )
+    }
+}
+
+/// `seconds` as e.g. `4.2 s`, `3m 05s` or `1h 02m`.
+fn format_duration(seconds: f64) -> String {
+    let whole = seconds.round() as u64;
+    if seconds < 60. {
+        format!("{seconds:.1} s")
+    } else if whole < 3600 {
+        format!("{}m {:02}s", whole / 60, whole % 60)
+    } else {
+        format!("{}h {:02}m", whole / 3600, whole % 3600 / 60)
+    }
+}
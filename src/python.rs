@@ -0,0 +1,309 @@
+use std::collections::HashMap;
+
+use numpy::{PyArray1, PyArray2, PyReadonlyArray1};
+use pyo3::exceptions::PyValueError;
+use pyo3::prelude::*;
+
+use crate::analysis::SpikeTrains;
+use crate::simulator::{Backend, WorldState};
+use crate::stimulus::{PulseTrain, StepCurrent};
+
+/// Python handle of a [`WorldState`]. State is copied into fresh numpy arrays on every access,
+/// so arrays held on the Python side never alias the simulation.
+#[pyclass(name = "WorldState", module = "izhikevich_rs", unsendable)]
+struct PyWorldState {
+    world: WorldState,
+}
+
+#[pymethods]
+impl PyWorldState {
+    /// Builds the network of the paper, or the `"polychronization"` preset.
+    #[new]
+    #[pyo3(signature = (
+        excitatory = 800,
+        inhibitory = 200,
+        *,
+        dt = 1.0,
+        seed = None,
+        connection_probability = 1.0,
+        max_delay = 1,
+        thalamic_noise = true,
+        preset = None,
+    ))]
+    #[allow(clippy::too_many_arguments)]
+    fn new(
+        excitatory: usize,
+        inhibitory: usize,
+        dt: f64,
+        seed: Option<u64>,
+        connection_probability: f64,
+        max_delay: u32,
+        thalamic_noise: bool,
+        preset: Option<&str>,
+    ) -> PyResult<Self> {
+        if max_delay < 1 {
+            return Err(PyValueError::new_err("max_delay must be at least 1"));
+        }
+        if !(0.0..=1.0).contains(&connection_probability) {
+            return Err(PyValueError::new_err(
+                "connection_probability must lie in [0, 1]",
+            ));
+        }
+        let mut builder = WorldState::builder()
+            .excitatory(excitatory)
+            .inhibitory(inhibitory)
+            .dt(dt)
+            .connection_probability(connection_probability)
+            .delay_range(1, max_delay)
+            .thalamic_noise(thalamic_noise);
+        match preset {
+            None | Some("paper") => {}
+            Some("polychronization") => builder = builder.polychronization(),
+            Some(preset) => {
+                return Err(PyValueError::new_err(format!("unknown preset `{preset}`")));
+            }
+        }
+        if let Some(seed) = seed {
+            builder = builder.seed(seed);
+        }
+        Ok(PyWorldState {
+            world: builder.build(),
+        })
+    }
+
+    /// Continues a run saved by `save_checkpoint`.
+    #[staticmethod]
+    fn load_checkpoint(path: &str) -> PyResult<Self> {
+        Ok(PyWorldState {
+            world: crate::checkpoint::load_checkpoint(path)?,
+        })
+    }
+
+    fn save_checkpoint(&mut self, path: &str) -> PyResult<()> {
+        Ok(crate::checkpoint::save_checkpoint(path, &mut self.world)?)
+    }
+
+    /// Selects `"cpu"`, `"simd"` or `"gpu"` for the following steps.
+    fn set_backend(&mut self, backend: &str) -> PyResult<()> {
+        let backend = match backend {
+            "cpu" => Backend::Cpu,
+            "simd" => Backend::Simd,
+            "gpu" => Backend::Gpu,
+            backend => {
+                return Err(PyValueError::new_err(format!(
+                    "unknown backend `{backend}`"
+                )));
+            }
+        };
+        self.world
+            .set_backend(backend)
+            .map_err(|err| PyValueError::new_err(err.to_string()))
+    }
+
+    fn step(&mut self) {
+        self.world.step();
+    }
+
+    /// Advances the simulation by `steps` time steps.
+    fn run(&mut self, steps: usize) {
+        self.world.run(steps);
+    }
+
+    /// Advances the simulation by `milliseconds` of simulated time.
+    fn run_for(&mut self, milliseconds: f64) {
+        self.world.run_for(milliseconds);
+    }
+
+    /// Simulated time in milliseconds.
+    #[getter]
+    fn time(&self) -> f64 {
+        self.world.time()
+    }
+
+    /// Integration time step in milliseconds.
+    #[getter]
+    fn dt(&self) -> f64 {
+        self.world.dt
+    }
+
+    fn __len__(&self) -> usize {
+        self.world.neurons.len()
+    }
+
+    /// `(name, start, stop)` of every population.
+    fn populations(&self) -> Vec<(String, usize, usize)> {
+        self.world
+            .populations()
+            .into_iter()
+            .map(|(name, range)| (name, range.start, range.end))
+            .collect()
+    }
+
+    /// Membrane potentials.
+    #[getter]
+    fn v<'py>(&self, py: Python<'py>) -> Bound<'py, PyArray1<f64>> {
+        PyArray1::from_slice(py, &self.world.neurons.v)
+    }
+
+    /// Membrane recovery variables.
+    #[getter]
+    fn u<'py>(&self, py: Python<'py>) -> Bound<'py, PyArray1<f64>> {
+        PyArray1::from_slice(py, &self.world.neurons.u)
+    }
+
+    /// Records the membrane potential of `neurons` at every step from now on.
+    fn record_v(&mut self, neurons: Vec<usize>) -> PyResult<()> {
+        if let Some(&idx) = neurons.iter().max() {
+            self.check_neurons(idx + 1)?;
+        }
+        self.world.record_v(neurons);
+        Ok(())
+    }
+
+    /// Recorded membrane potential traces by neuron.
+    fn v_traces<'py>(&self, py: Python<'py>) -> HashMap<usize, Bound<'py, PyArray1<f64>>> {
+        self.world
+            .v_traces
+            .iter()
+            .map(|(idx, trace)| (*idx, PyArray1::from_slice(py, trace)))
+            .collect()
+    }
+
+    /// All recorded spikes as `(times, neurons)` arrays, with times in milliseconds.
+    fn spikes<'py>(
+        &self,
+        py: Python<'py>,
+    ) -> (Bound<'py, PyArray1<f64>>, Bound<'py, PyArray1<usize>>) {
+        let (times, neurons): (Vec<f64>, Vec<usize>) = self
+            .world
+            .spikes()
+            .map(|(time_step, idx)| (time_step as f64 * self.world.dt, idx))
+            .unzip();
+        (
+            PyArray1::from_vec(py, times),
+            PyArray1::from_vec(py, neurons),
+        )
+    }
+
+    /// Sorted spike times in milliseconds of every neuron, e.g. for `neo.SpikeTrain`.
+    fn spike_trains<'py>(&self, py: Python<'py>) -> Vec<Bound<'py, PyArray1<f64>>> {
+        SpikeTrains::from_world(&self.world)
+            .trains
+            .into_iter()
+            .map(|train| PyArray1::from_vec(py, train))
+            .collect()
+    }
+
+    /// The synapses as `(data, indices, indptr)` arrays of weights, postsynaptic neurons and row
+    /// offsets by presynaptic neuron, as taken by `scipy.sparse.csr_matrix`.
+    #[allow(clippy::type_complexity)]
+    fn weights<'py>(
+        &self,
+        py: Python<'py>,
+    ) -> (
+        Bound<'py, PyArray1<f64>>,
+        Bound<'py, PyArray1<usize>>,
+        Bound<'py, PyArray1<usize>>,
+    ) {
+        let connectivity = &self.world.connectivity;
+        let indptr: Vec<usize> = (0..connectivity.neurons())
+            .map(|pre| connectivity.row(pre).start)
+            .chain([connectivity.synapses()])
+            .collect();
+        (
+            PyArray1::from_slice(py, connectivity.weights()),
+            PyArray1::from_slice(py, connectivity.targets()),
+            PyArray1::from_vec(py, indptr),
+        )
+    }
+
+    /// Conduction delay of every synapse in steps, aligned with the `weights` data.
+    fn delays<'py>(&self, py: Python<'py>) -> Bound<'py, PyArray1<u32>> {
+        PyArray1::from_slice(py, self.world.connectivity.delays())
+    }
+
+    /// The weights as a dense `[pre, post]` matrix.
+    fn dense_weights<'py>(&self, py: Python<'py>) -> PyResult<Bound<'py, PyArray2<f64>>> {
+        Ok(PyArray2::from_vec2(
+            py,
+            &self.world.connectivity.to_dense(),
+        )?)
+    }
+
+    /// Replaces the weight of every synapse, in the order of the `weights` data.
+    fn set_weights(&mut self, weights: PyReadonlyArray1<f64>) -> PyResult<()> {
+        let weights = weights.as_slice()?;
+        let target = self.world.connectivity.weights_mut();
+        if weights.len() != target.len() {
+            return Err(PyValueError::new_err(format!(
+                "expected {} weights, got {}",
+                target.len(),
+                weights.len()
+            )));
+        }
+        target.copy_from_slice(weights);
+        Ok(())
+    }
+
+    /// Injects a constant current into neurons `start..stop` from `onset` to `offset`
+    /// milliseconds.
+    fn add_step_current(
+        &mut self,
+        amplitude: f64,
+        start: usize,
+        stop: usize,
+        onset: f64,
+        offset: f64,
+    ) -> PyResult<()> {
+        self.check_neurons(stop)?;
+        self.world
+            .add_stimulus(StepCurrent { amplitude }, start..stop, onset..offset);
+        Ok(())
+    }
+
+    /// Injects pulses of `width` every `period` milliseconds into neurons `start..stop` from
+    /// `onset` to `offset` milliseconds.
+    #[allow(clippy::too_many_arguments)]
+    fn add_pulse_train(
+        &mut self,
+        amplitude: f64,
+        period: f64,
+        width: f64,
+        start: usize,
+        stop: usize,
+        onset: f64,
+        offset: f64,
+    ) -> PyResult<()> {
+        self.check_neurons(stop)?;
+        self.world.add_stimulus(
+            PulseTrain {
+                amplitude,
+                period,
+                width,
+            },
+            start..stop,
+            onset..offset,
+        );
+        Ok(())
+    }
+}
+
+impl PyWorldState {
+    /// Fails unless the network has at least `count` neurons.
+    fn check_neurons(&self, count: usize) -> PyResult<()> {
+        let n = self.world.neurons.len();
+        if count > n {
+            return Err(PyValueError::new_err(format!(
+                "neuron {} out of range for {n} neurons",
+                count - 1
+            )));
+        }
+        Ok(())
+    }
+}
+
+#[pymodule]
+fn izhikevich_rs(m: &Bound<'_, PyModule>) -> PyResult<()> {
+    m.add_class::<PyWorldState>()?;
+    Ok(())
+}
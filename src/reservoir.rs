@@ -0,0 +1,308 @@
+use std::io::{self, Write};
+use std::ops::Range;
+
+use rand::{Rng, SeedableRng};
+use rand_chacha::ChaCha12Rng;
+use rayon::prelude::*;
+
+use crate::simulator::WorldState;
+use crate::stimulus::{AttachedStimulus, StepCurrent};
+
+/// Liquid state machine view of a network: presents input streams as currents, one sample per
+/// `sample_duration`, and reads out the exponentially filtered spike traces of its neurons as
+/// the state at the end of every sample, for a [`Readout`].
+#[derive(Clone, Debug, PartialEq)]
+pub struct Reservoir {
+    /// Current into every neuron per unit of each input channel, indexed `[channel][neuron]`.
+    pub input_weights: Vec<Vec<f64>>,
+    /// Milliseconds every input sample is held.
+    pub sample_duration: f64,
+    /// Time constant of the filtered spike traces in milliseconds.
+    pub tau: f64,
+    /// Neurons whose traces make up the state.
+    pub readout_neurons: Range<usize>,
+    /// Filtered spike trace of every neuron, carried over between calls of [`Reservoir::run`].
+    traces: Vec<f64>,
+}
+
+impl Reservoir {
+    /// Reservoir of `world` with `channels` inputs, each reaching a random `fraction` of its
+    /// neurons with `gain` current per unit, read out from all neurons.
+    pub fn random(
+        world: &WorldState,
+        channels: usize,
+        fraction: f64,
+        gain: f64,
+        seed: u64,
+    ) -> Self {
+        let n = world.neurons.len();
+        let mut rng = ChaCha12Rng::seed_from_u64(seed);
+        let input_weights = (0..channels)
+            .map(|_| {
+                (0..n)
+                    .map(|_| {
+                        if rng.gen::<f64>() < fraction {
+                            gain
+                        } else {
+                            0.
+                        }
+                    })
+                    .collect()
+            })
+            .collect();
+        Reservoir {
+            input_weights,
+            sample_duration: 10.,
+            tau: 30.,
+            readout_neurons: 0..n,
+            traces: vec![0.; n],
+        }
+    }
+
+    /// Presents every row of `inputs`, one value per channel, for `sample_duration` and returns
+    /// the traces of the readout neurons at the end of each.
+    ///
+    /// # Panics
+    ///
+    /// If a row does not have one value per channel.
+    pub fn run(&mut self, world: &mut WorldState, inputs: &[Vec<f64>]) -> Vec<Vec<f64>> {
+        let n = world.neurons.len();
+        self.traces.resize(n, 0.);
+        let decay = (-world.dt / self.tau).exp();
+        let steps = (self.sample_duration / world.dt).round() as usize;
+        inputs
+            .iter()
+            .map(|sample| {
+                assert_eq!(
+                    sample.len(),
+                    self.input_weights.len(),
+                    "expected one input per channel"
+                );
+                let mut currents = vec![0.; n];
+                for (&value, weights) in sample.iter().zip(&self.input_weights) {
+                    if value != 0. {
+                        for (current, weight) in currents.iter_mut().zip(weights) {
+                            *current += value * weight;
+                        }
+                    }
+                }
+                let start = world.time();
+                let stimuli = world.stimuli.len();
+                world.stimuli.push(
+                    AttachedStimulus::new(
+                        StepCurrent { amplitude: 1. },
+                        0..n,
+                        start..start + self.sample_duration,
+                    )
+                    .with_gains(currents),
+                );
+                for _ in 0..steps {
+                    world.step();
+                    for (trace, &spiked) in self.traces.iter_mut().zip(world.last_spikes()) {
+                        *trace = *trace * decay + if spiked { 1. } else { 0. };
+                    }
+                }
+                world.stimuli.truncate(stimuli);
+                self.traces[self.readout_neurons.clone()].to_vec()
+            })
+            .collect()
+    }
+}
+
+/// Linear readout of reservoir states, fitted by ridge regression.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Readout {
+    /// Weight of every state component for each output, indexed `[output][component]`.
+    pub weights: Vec<Vec<f64>>,
+    pub bias: Vec<f64>,
+}
+
+impl Readout {
+    /// Fits the readout mapping every state to its row of `targets` with the squared weights
+    /// penalized by `ridge`, which must be positive, leaving the bias unpenalized.
+    ///
+    /// # Panics
+    ///
+    /// If there are no states, or not one row of targets per state.
+    pub fn fit(states: &[Vec<f64>], targets: &[Vec<f64>], ridge: f64) -> Self {
+        assert!(!states.is_empty(), "cannot fit a readout without states");
+        assert_eq!(states.len(), targets.len(), "expected one target per state");
+        let samples = states.len() as f64;
+        let (features, outputs) = (states[0].len(), targets[0].len());
+        let mean = |rows: &[Vec<f64>], width: usize| {
+            let mut mean = vec![0.; width];
+            for row in rows {
+                for (mean, value) in mean.iter_mut().zip(row) {
+                    *mean += value / samples;
+                }
+            }
+            mean
+        };
+        let state_mean = mean(states, features);
+        let target_mean = mean(targets, outputs);
+        let centered = |rows: &[Vec<f64>], mean: &[f64]| -> Vec<Vec<f64>> {
+            rows.iter()
+                .map(|row| {
+                    row.iter()
+                        .zip(mean)
+                        .map(|(value, mean)| value - mean)
+                        .collect()
+                })
+                .collect()
+        };
+        let x = centered(states, &state_mean);
+        let y = centered(targets, &target_mean);
+
+        // Normal equations (XᵀX + ridge I) W = XᵀY, by rows of XᵀX
+        let mut gram: Vec<Vec<f64>> = (0..features)
+            .into_par_iter()
+            .map(|i| {
+                let mut row = vec![0.; features];
+                for sample in &x {
+                    let value = sample[i];
+                    if value != 0. {
+                        for (entry, other) in row.iter_mut().zip(sample) {
+                            *entry += value * other;
+                        }
+                    }
+                }
+                row[i] += ridge;
+                row
+            })
+            .collect();
+        let mut weights: Vec<Vec<f64>> = (0..outputs)
+            .map(|output| {
+                (0..features)
+                    .map(|i| x.iter().zip(&y).map(|(x, y)| x[i] * y[output]).sum())
+                    .collect()
+            })
+            .collect();
+        cholesky(&mut gram);
+        for rhs in &mut weights {
+            cholesky_solve(&gram, rhs);
+        }
+        let bias = weights
+            .iter()
+            .zip(&target_mean)
+            .map(|(weights, target)| target - dot(weights, &state_mean))
+            .collect();
+        Readout { weights, bias }
+    }
+
+    /// Outputs for `state`.
+    pub fn predict(&self, state: &[f64]) -> Vec<f64> {
+        self.weights
+            .iter()
+            .zip(&self.bias)
+            .map(|(weights, bias)| dot(weights, state) + bias)
+            .collect()
+    }
+}
+
+fn dot(a: &[f64], b: &[f64]) -> f64 {
+    a.iter().zip(b).map(|(a, b)| a * b).sum()
+}
+
+/// Replaces the lower triangle of the symmetric positive definite `matrix` with its Cholesky
+/// factor `L`, where `matrix = L Lᵀ`.
+fn cholesky(matrix: &mut [Vec<f64>]) {
+    for j in 0..matrix.len() {
+        let diagonal = (matrix[j][j] - dot(&matrix[j][..j], &matrix[j][..j])).sqrt();
+        matrix[j][j] = diagonal;
+        for i in j + 1..matrix.len() {
+            let value = (matrix[i][j] - dot(&matrix[i][..j], &matrix[j][..j])) / diagonal;
+            matrix[i][j] = value;
+        }
+    }
+}
+
+/// Solves `L Lᵀ x = rhs` in place for the factor of [`cholesky`].
+fn cholesky_solve(factor: &[Vec<f64>], rhs: &mut [f64]) {
+    let n = rhs.len();
+    for i in 0..n {
+        rhs[i] = (rhs[i] - dot(&factor[i][..i], &rhs[..i])) / factor[i][i];
+    }
+    for i in (0..n).rev() {
+        let later: f64 = (i + 1..n).map(|k| factor[k][i] * rhs[k]).sum();
+        rhs[i] = (rhs[i] - later) / factor[i][i];
+    }
+}
+
+/// Squared correlation between `predicted` and `target`, 1 for a perfect linear fit and 0 for
+/// none.
+pub fn squared_correlation(predicted: &[f64], target: &[f64]) -> f64 {
+    let n = predicted.len().min(target.len()) as f64;
+    let mean = |values: &[f64]| values.iter().sum::<f64>() / n;
+    let (mean_p, mean_t) = (mean(predicted), mean(target));
+    let (mut covariance, mut var_p, mut var_t) = (0., 0., 0.);
+    for (p, t) in predicted.iter().zip(target) {
+        covariance += (p - mean_p) * (t - mean_t);
+        var_p += (p - mean_p).powi(2);
+        var_t += (t - mean_t).powi(2);
+    }
+    if var_p == 0. || var_t == 0. {
+        0.
+    } else {
+        covariance * covariance / (var_p * var_t)
+    }
+}
+
+/// Short-term memory benchmark of Jaeger (2002): a random input in `[0, 1]` drives the
+/// reservoir, and one readout per delay `k` recalls the input of `k` samples earlier. The
+/// memory capacity is the sum over delays of the squared correlation of recall and input on
+/// held-out samples.
+#[derive(Clone, Debug, PartialEq)]
+pub struct MemoryCapacity {
+    /// Samples used to fit the readouts.
+    pub train: usize,
+    /// Held-out samples the readouts are scored on.
+    pub test: usize,
+    /// Initial samples discarded while the network settles.
+    pub washout: usize,
+    pub max_delay: usize,
+    pub ridge: f64,
+    pub seed: u64,
+}
+
+impl MemoryCapacity {
+    /// Squared correlation of recall and input for every delay from 1 to `max_delay`.
+    pub fn run(&self, world: &mut WorldState, reservoir: &mut Reservoir) -> Vec<f64> {
+        let mut rng = ChaCha12Rng::seed_from_u64(self.seed);
+        let samples = self.washout + self.max_delay + self.train + self.test;
+        let input: Vec<f64> = (0..samples).map(|_| rng.gen()).collect();
+        let inputs: Vec<Vec<f64>> = input.iter().map(|&value| vec![value; 1]).collect();
+        let states = reservoir.run(world, &inputs);
+
+        let first = self.washout + self.max_delay;
+        let targets: Vec<Vec<f64>> = (first..samples)
+            .map(|idx| {
+                (1..=self.max_delay)
+                    .map(|delay| input[idx - delay])
+                    .collect()
+            })
+            .collect();
+        let (train_states, test_states) = states[first..].split_at(self.train);
+        let (train_targets, test_targets) = targets.split_at(self.train);
+        let readout = Readout::fit(train_states, train_targets, self.ridge);
+        let predictions: Vec<Vec<f64>> = test_states
+            .iter()
+            .map(|state| readout.predict(state))
+            .collect();
+        (0..self.max_delay)
+            .map(|delay| {
+                let predicted: Vec<f64> = predictions.iter().map(|row| row[delay]).collect();
+                let target: Vec<f64> = test_targets.iter().map(|row| row[delay]).collect();
+                squared_correlation(&predicted, &target)
+            })
+            .collect()
+    }
+
+    /// Writes one `delay,score` CSV row per delay of the `scores` of [`MemoryCapacity::run`].
+    pub fn write_csv<W: Write>(&self, mut writer: W, scores: &[f64]) -> io::Result<()> {
+        writeln!(writer, "delay,score")?;
+        for (idx, score) in scores.iter().enumerate() {
+            writeln!(writer, "{},{score}", idx + 1)?;
+        }
+        writer.flush()
+    }
+}
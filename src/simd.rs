@@ -0,0 +1,121 @@
+use wide::{f32x8, CmpGe};
+
+use crate::neuron::Neurons;
+use crate::simulator::WorldState;
+
+const LANES: usize = 8;
+
+/// Integrates the network in single precision eight neurons at a time. Neuron state is kept in
+/// lanes of [`f32x8`], padded with silent neurons, and the weights as a dense `[pre][post]`
+/// matrix so the spike sum adds whole rows. This needs `n²` floats, so it suits the dense networks
+/// of the paper rather than large sparse ones. Only one-step delays and plain current synapses are
+/// supported, which the caller checks.
+pub struct SimdBackend {
+    v: Vec<f32x8>,
+    u: Vec<f32x8>,
+    a: Vec<f32x8>,
+    b: Vec<f32x8>,
+    c: Vec<f32x8>,
+    d: Vec<f32x8>,
+    /// Row `pre` holds the weights onto every postsynaptic neuron.
+    weights: Vec<f32x8>,
+    /// Spikes of the previous step, whose rows are summed into the synaptic input.
+    last_spikes: Vec<bool>,
+    dt: f32,
+}
+
+impl SimdBackend {
+    pub fn new(world: &WorldState) -> Self {
+        let neurons = &world.neurons;
+        let n = neurons.len();
+        let chunks = n.div_ceil(LANES);
+        // padding lanes sit at rest and never receive input
+        let lanes = |values: &[f64], pad: f32| -> Vec<f32x8> {
+            values
+                .chunks(LANES)
+                .map(|chunk| {
+                    let mut lane = [pad; LANES];
+                    for (lane, &value) in lane.iter_mut().zip(chunk) {
+                        *lane = value as f32;
+                    }
+                    f32x8::new(lane)
+                })
+                .collect()
+        };
+
+        let mut dense = vec![0f32; n * chunks * LANES];
+        for pre in 0..n {
+            for (post, weight) in world.connectivity.outgoing(pre) {
+                dense[pre * chunks * LANES + post] += weight as f32;
+            }
+        }
+        let weights = dense
+            .chunks_exact(LANES)
+            .map(|lane| f32x8::new(lane.try_into().unwrap()))
+            .collect();
+
+        SimdBackend {
+            v: lanes(&neurons.v, -65.),
+            u: lanes(&neurons.u, -13.),
+            a: lanes(&neurons.a, 0.02),
+            b: lanes(&neurons.b, 0.2),
+            c: lanes(&neurons.c, -65.),
+            d: lanes(&neurons.d, 0.),
+            weights,
+            last_spikes: world.last_spikes().to_vec(),
+            dt: world.dt as f32,
+        }
+    }
+
+    /// Advances every neuron by one step given the non-recurrent input current, copies the new
+    /// membrane state back into `neurons` and returns the spikes.
+    pub fn step(&mut self, external: &[f32], neurons: &mut Neurons) -> Vec<bool> {
+        let n = neurons.len();
+        let chunks = self.v.len();
+
+        let mut current = vec![f32x8::ZERO; chunks];
+        for (pre, _) in self.last_spikes.iter().enumerate().filter(|&(_, &s)| s) {
+            let row = &self.weights[pre * chunks..(pre + 1) * chunks];
+            for (acc, &weight) in current.iter_mut().zip(row) {
+                *acc += weight;
+            }
+        }
+
+        let inv_dt = f32x8::splat(1. / self.dt);
+        let dt = f32x8::splat(self.dt);
+        let half_dt = f32x8::splat(0.5 * self.dt);
+        let threshold = f32x8::splat(30.);
+        let mut spikes = vec![false; n];
+        for (chunk, synaptic) in current.iter().enumerate() {
+            let mut drive = [0f32; LANES];
+            let start = chunk * LANES;
+            let end = (start + LANES).min(n);
+            drive[..end - start].copy_from_slice(&external[start..end]);
+            let i = *synaptic * inv_dt + f32x8::new(drive);
+
+            let (mut v, mut u) = (self.v[chunk], self.u[chunk]);
+            // in 2 half steps for numerical stability
+            for _ in 0..2 {
+                let dv = (0.04 * v + 5.) * v + 140. - u + i;
+                v = dv.mul_add(half_dt, v);
+            }
+            u += dt * self.a[chunk] * (self.b[chunk] * v - u);
+
+            // action potential
+            let fired = v.cmp_ge(threshold);
+            self.v[chunk] = fired.blend(self.c[chunk], v);
+            self.u[chunk] = fired.blend(u + self.d[chunk], u);
+
+            let mask = fired.move_mask();
+            let (v, u) = (self.v[chunk].to_array(), self.u[chunk].to_array());
+            for k in 0..end - start {
+                spikes[start + k] = mask & (1 << k) != 0;
+                neurons.v[start + k] = v[k] as f64;
+                neurons.u[start + k] = u[k] as f64;
+            }
+        }
+
+        self.last_spikes.clone_from(&spikes);
+        spikes
+    }
+}
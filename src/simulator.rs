@@ -1,73 +1,1582 @@
-use rand::{thread_rng, Rng};
-use rand_distr::{Distribution, Normal};
-use rayon::prelude::*;
+use std::fmt;
+use std::ops::Range;
+use std::time::Instant;
 
-use crate::neuron::{Neuron, SynapseType};
+use rand::SeedableRng;
+use rand_chacha::ChaCha12Rng;
+use serde::{Deserialize, Serialize};
 
+use crate::analysis::{VoltageSynchrony, WeightHistory};
+use crate::control::Controller;
+#[cfg(feature = "mpi")]
+use crate::distributed::MpiBackend;
+use crate::encoding::Encoding;
+use crate::gap_junction::GapJunctions;
+#[cfg(feature = "gpu")]
+use crate::gpu::GpuBackend;
+use crate::integrator::Integrator;
+use crate::lesion::Lesion;
+use crate::network::{InstantiatedNetwork, NetworkBuilder, Synapse, Topology, WeightDistribution};
+use crate::neuromodulation::Neuromodulator;
+use crate::neuron::{NeuronDynamics, NeuronType, Neurons, RefractoryPeriods, SynapseType};
+use crate::overrides::{apply_overrides, NeuronOverride};
+use crate::plasticity::{
+    DopamineStdp, DopamineStdpParameters, Homeostasis, HomeostasisParameters, InhibitoryStdp,
+    InhibitoryStdpParameters, ShortTermPlasticity, Stdp, StdpParameters, StpState,
+};
+use crate::profile::{Phase, Profile};
+use crate::simd::SimdBackend;
+use crate::spike_record::SpikeRecord;
+use crate::stimulus::{
+    AttachedStimulus, Electrode, NoiseModel, NoiseSource, PoissonInput, PoissonSource, StepCurrent,
+    Stimulus,
+};
+use crate::synapse::{NmdaParameters, SpikeInput, SynapseModel, SynapticState};
+
+/// Builder for [`WorldState`], defaulting to the 800/200 network from the paper.
+#[derive(Clone)]
+pub struct WorldStateBuilder {
+    pub(crate) excitatory: usize,
+    pub(crate) inhibitory: usize,
+    dt: f64,
+    integrator: Integrator,
+    seed: Option<u64>,
+    connection_probability: f64,
+    excitatory_types: Vec<(NeuronType, f64)>,
+    weight_distributions: Vec<(String, WeightDistribution)>,
+    inhibitory_types: Vec<(NeuronType, f64)>,
+    excitatory_dynamics: Option<NeuronDynamics>,
+    inhibitory_dynamics: Option<NeuronDynamics>,
+    delay_range: (u32, u32),
+    short_term_plasticity: Option<ShortTermPlasticity>,
+    dopamine_stdp: Option<DopamineStdpParameters>,
+    stdp: Option<StdpParameters>,
+    inhibitory_stdp: Option<InhibitoryStdpParameters>,
+    homeostasis: Option<HomeostasisParameters>,
+    gap_junctions: Option<GapJunctions>,
+    refractory_periods: Option<RefractoryPeriods>,
+    synapse_model: SynapseModel,
+    nmda: Option<NmdaParameters>,
+    thalamic_noise: bool,
+    thalamic_sigma: f64,
+    noise_model: NoiseModel,
+    poisson_inputs: Vec<PoissonInput>,
+    network: Option<NetworkBuilder>,
+    instantiated_network: Option<InstantiatedNetwork>,
+    neuron_overrides: Vec<NeuronOverride>,
+    synapses: Option<Vec<Synapse>>,
+    topology: Topology,
+    event_driven: Option<f64>,
+    spike_window: Option<f64>,
+    precise_spike_times: bool,
+}
+
+impl Default for WorldStateBuilder {
+    fn default() -> Self {
+        WorldStateBuilder {
+            excitatory: 800,
+            inhibitory: 200,
+            dt: 1.,
+            integrator: Integrator::Euler,
+            seed: None,
+            connection_probability: 1.,
+            excitatory_types: vec![],
+            weight_distributions: vec![],
+            inhibitory_types: vec![],
+            excitatory_dynamics: None,
+            inhibitory_dynamics: None,
+            delay_range: (1, 1),
+            short_term_plasticity: None,
+            dopamine_stdp: None,
+            stdp: None,
+            inhibitory_stdp: None,
+            homeostasis: None,
+            gap_junctions: None,
+            refractory_periods: None,
+            synapse_model: SynapseModel::Current,
+            nmda: None,
+            thalamic_noise: true,
+            thalamic_sigma: 1.,
+            noise_model: NoiseModel::White,
+            poisson_inputs: vec![],
+            network: None,
+            instantiated_network: None,
+            neuron_overrides: vec![],
+            synapses: None,
+            topology: Topology::Random,
+            event_driven: None,
+            spike_window: None,
+            precise_spike_times: false,
+        }
+    }
+}
+
+impl WorldStateBuilder {
+    /// Number of excitatory neurons of the default network
+    pub fn excitatory(mut self, excitatory: usize) -> Self {
+        self.excitatory = excitatory;
+        self
+    }
+
+    /// Number of inhibitory neurons of the default network
+    pub fn inhibitory(mut self, inhibitory: usize) -> Self {
+        self.inhibitory = inhibitory;
+        self
+    }
+
+    /// Integration time step in milliseconds
+    ///
+    /// # Panics
+    ///
+    /// If `dt` is not positive and finite.
+    pub fn dt(mut self, dt: f64) -> Self {
+        assert!(
+            dt > 0. && dt.is_finite(),
+            "the time step must be positive and finite"
+        );
+        self.dt = dt;
+        self
+    }
+
+    /// Scheme integrating the membranes of the Izhikevich neurons
+    ///
+    /// # Panics
+    ///
+    /// If the parameters of the integrator are not valid, see [`Integrator::is_valid`].
+    pub fn integrator(mut self, integrator: Integrator) -> Self {
+        assert!(
+            integrator.is_valid(),
+            "the tolerance of the adaptive integrator must be positive"
+        );
+        self.integrator = integrator;
+        self
+    }
+
+    /// Seed for all random number generation, making runs reproducible
+    pub fn seed(mut self, seed: u64) -> Self {
+        self.seed = Some(seed);
+        self
+    }
+
+    /// Probability that any given pair of distinct neurons of the default network is connected
+    ///
+    /// # Panics
+    ///
+    /// If `connection_probability` does not lie in `[0, 1]`.
+    pub fn connection_probability(mut self, connection_probability: f64) -> Self {
+        assert!(
+            (0.0..=1.0).contains(&connection_probability),
+            "the connection probability must lie in [0, 1]"
+        );
+        self.connection_probability = connection_probability;
+        self
+    }
+
+    /// Makes the excitatory population of the default network a mixture of fixed neuron types in
+    /// proportion to their relative fractions instead of the randomized neurons of the paper
+    pub fn excitatory_types(mut self, neuron_types: Vec<(NeuronType, f64)>) -> Self {
+        self.excitatory_types = neuron_types;
+        self
+    }
+
+    /// Like [`WorldStateBuilder::excitatory_types`] for the inhibitory population
+    pub fn inhibitory_types(mut self, neuron_types: Vec<(NeuronType, f64)>) -> Self {
+        self.inhibitory_types = neuron_types;
+        self
+    }
+
+    /// Draws the weights of all synapses from the population named `population` of the network
+    /// from `weight` instead, e.g. a log-normal distribution for `excitatory`
+    pub fn weight_distribution(mut self, population: &str, weight: WeightDistribution) -> Self {
+        self.weight_distributions
+            .push((population.to_string(), weight));
+        self
+    }
+
+    /// Lets the excitatory neurons of the default network follow another model than the
+    /// Izhikevich one, keeping the synapses of a seeded network
+    pub fn excitatory_dynamics(mut self, dynamics: NeuronDynamics) -> Self {
+        self.excitatory_dynamics = Some(dynamics);
+        self
+    }
+
+    /// Like [`WorldStateBuilder::excitatory_dynamics`] for the inhibitory population
+    pub fn inhibitory_dynamics(mut self, dynamics: NeuronDynamics) -> Self {
+        self.inhibitory_dynamics = Some(dynamics);
+        self
+    }
+
+    /// Axonal conduction delays are drawn uniformly from `min..=max` whole milliseconds, unless
+    /// the network derives them from distances
+    ///
+    /// # Panics
+    ///
+    /// If `min` is below 1 ms or above `max`.
+    pub fn delay_range(mut self, min: u32, max: u32) -> Self {
+        assert!(
+            min >= 1 && min <= max,
+            "the delays must lie in a range of at least 1 ms"
+        );
+        self.delay_range = (min, max);
+        self
+    }
+
+    /// Enables Tsodyks–Markram short-term plasticity for the configured connection classes
+    ///
+    /// # Panics
+    ///
+    /// If the parameters of a class are not valid, see [`crate::StpParameters::check`].
+    pub fn short_term_plasticity(mut self, config: ShortTermPlasticity) -> Self {
+        for params in [
+            &config.excitatory_to_excitatory,
+            &config.excitatory_to_inhibitory,
+            &config.inhibitory_to_excitatory,
+            &config.inhibitory_to_inhibitory,
+        ]
+        .into_iter()
+        .flatten()
+        {
+            if let Err(err) = params.check() {
+                panic!("{err}");
+            }
+        }
+        self.short_term_plasticity = Some(config);
+        self
+    }
+
+    /// Enables dopamine-modulated STDP of the synapses from excitatory neurons
+    pub fn dopamine_stdp(mut self, params: DopamineStdpParameters) -> Self {
+        self.dopamine_stdp = Some(params);
+        self
+    }
+
+    /// Enables spike-timing-dependent plasticity of the synapses from excitatory neurons
+    pub fn stdp(mut self, params: StdpParameters) -> Self {
+        self.stdp = Some(params);
+        self
+    }
+
+    /// Enables the inhibitory plasticity of the synapses from inhibitory onto excitatory neurons
+    pub fn inhibitory_stdp(mut self, params: InhibitoryStdpParameters) -> Self {
+        self.inhibitory_stdp = Some(params);
+        self
+    }
+
+    /// Couples neurons by electrical synapses
+    pub fn gap_junctions(mut self, gap_junctions: GapJunctions) -> Self {
+        self.gap_junctions = Some(gap_junctions);
+        self
+    }
+
+    /// Lets every neuron adapt its excitability towards a target firing rate
+    pub fn homeostasis(mut self, params: HomeostasisParameters) -> Self {
+        self.homeostasis = Some(params);
+        self
+    }
+
+    /// Clamps every neuron at its reset potential for an absolute refractory period after each
+    /// spike, overriding the periods of a custom network
+    pub fn refractory_periods(mut self, periods: RefractoryPeriods) -> Self {
+        self.refractory_periods = Some(periods);
+        self
+    }
+
+    /// How arriving spikes are turned into synaptic current
+    ///
+    /// # Panics
+    ///
+    /// If the time constants of [`SynapseModel::Exponential`] are not valid, see
+    /// [`crate::ExponentialParameters::is_valid`].
+    pub fn synapse_model(mut self, synapse_model: SynapseModel) -> Self {
+        if let SynapseModel::Exponential(params) = &synapse_model {
+            assert!(
+                params.is_valid(),
+                "synaptic rise time constants must be non-negative and shorter than the decay"
+            );
+        }
+        self.synapse_model = synapse_model;
+        self
+    }
+
+    /// Adds a voltage-dependent NMDA component to excitatory synapses
+    pub fn nmda(mut self, nmda: NmdaParameters) -> Self {
+        self.nmda = Some(nmda);
+        self
+    }
+
+    /// Whether neurons receive the Gaussian thalamic noise, e.g. to drive them by stimuli only
+    pub fn thalamic_noise(mut self, thalamic_noise: bool) -> Self {
+        self.thalamic_noise = thalamic_noise;
+        self
+    }
+
+    /// Scale of the thalamic noise fluctuations relative to the paper's, at least 0
+    pub fn thalamic_sigma(mut self, thalamic_sigma: f64) -> Self {
+        self.thalamic_sigma = thalamic_sigma;
+        self
+    }
+
+    /// Temporal structure of the thalamic noise, white as in the paper by default
+    pub fn noise_model(mut self, noise_model: NoiseModel) -> Self {
+        self.noise_model = noise_model;
+        self
+    }
+
+    /// Which pairs of neurons of the default network are connected
+    pub fn topology(mut self, topology: Topology) -> Self {
+        self.topology = topology;
+        self
+    }
+
+    /// Replaces the default two-population network of the paper, ignoring the neuron counts and
+    /// the connection probability and topology set on this builder
+    pub fn network(mut self, network: NetworkBuilder) -> Self {
+        self.network = Some(network);
+        self
+    }
+
+    /// Uses the given neurons and synapses instead of drawing a network, ignoring the other
+    /// network settings of this builder
+    pub fn instantiated_network(mut self, network: InstantiatedNetwork) -> Self {
+        self.instantiated_network = Some(network);
+        self
+    }
+
+    /// Replaces the parameters, and possibly the synapse type, of individual neurons of the
+    /// drawn network, see [`crate::overrides::read_overrides`]. Building panics if an override
+    /// refers to a neuron outside the network
+    pub fn neuron_overrides(mut self, overrides: Vec<NeuronOverride>) -> Self {
+        self.neuron_overrides = overrides;
+        self
+    }
+
+    /// Replaces the drawn synapses, after any neuron overrides, with `(pre, post, weight, delay)`
+    /// ones with delays in milliseconds, e.g. from [`crate::weights::read_weights_csv`] to rerun
+    /// a network with the same seed. Building panics if a synapse connects a neuron outside the
+    /// network
+    pub fn synapses(mut self, synapses: Vec<Synapse>) -> Self {
+        self.synapses = Some(synapses);
+        self
+    }
+
+    /// Skips neurons at rest without input, see [`WorldState::set_event_driven`]
+    pub fn event_driven(mut self, tolerance: f64) -> Self {
+        self.event_driven = Some(tolerance);
+        self
+    }
+
+    /// Keeps only the spikes of the last `milliseconds` instead of the whole run, see
+    /// [`SpikeRecord`]
+    pub fn spike_window(mut self, milliseconds: f64) -> Self {
+        self.spike_window = Some(milliseconds);
+        self
+    }
+
+    /// Times spikes within their step, see [`WorldState::set_precise_spike_times`]
+    pub fn precise_spike_times(mut self) -> Self {
+        self.precise_spike_times = true;
+        self
+    }
+
+    /// Adds an external Poisson spike source population
+    pub fn poisson_input(mut self, input: PoissonInput) -> Self {
+        self.poisson_inputs.push(input);
+        self
+    }
+
+    /// The polychronization experiment of Izhikevich (2006) with the configured neuron counts:
+    /// the network of [`NetworkBuilder::polychronization`] with STDP, driven by a 20 mV input
+    /// into one random neuron per millisecond on average instead of Gaussian thalamic noise.
+    pub fn polychronization(self) -> Self {
+        let n = self.excitatory + self.inhibitory;
+        let network = NetworkBuilder::polychronization(self.excitatory, self.inhibitory);
+        self.network(network)
+            .stdp(StdpParameters::default())
+            .thalamic_noise(false)
+            .poisson_input(PoissonInput {
+                sources: n,
+                rate: 1.,
+                weight: 20.,
+                connection_probability: 1. / n.max(1) as f64,
+                targets: 0..n,
+            })
+    }
+
+    /// The network of [`NetworkBuilder::two_alternative_choice`] for a
+    /// [`crate::TwoAlternativeChoice`] task, with groups of a quarter of the configured excitatory
+    /// neurons and dopamine-modulated STDP whose short eligibility traces credit rewards to the
+    /// trial that earned them.
+    pub fn two_alternative_choice(self) -> Self {
+        let network = NetworkBuilder::two_alternative_choice(self.excitatory / 4, self.inhibitory);
+        self.network(network).dopamine_stdp(DopamineStdpParameters {
+            a_minus: 1.,
+            tau_eligibility: 50.,
+            learning_rate: 0.001,
+            ..Default::default()
+        })
+    }
+
+    pub fn build(self) -> WorldState {
+        let mut rng = match self.seed {
+            Some(seed) => ChaCha12Rng::seed_from_u64(seed),
+            None => ChaCha12Rng::from_entropy(),
+        };
+        let instantiated = self.instantiated_network.is_some();
+        let mut projection_models = (vec![], vec![]);
+        let (populations, (mut neurons, positions, mut connectivity)) =
+            match self.instantiated_network {
+                Some(network) => (network.populations.clone(), network.build(self.dt)),
+                None => {
+                    let mut network = self.network.unwrap_or_else(|| {
+                        NetworkBuilder::paper(
+                            self.excitatory,
+                            self.inhibitory,
+                            self.connection_probability,
+                        )
+                        .topology(self.topology)
+                        .neuron_types("excitatory", self.excitatory_types)
+                        .neuron_types("inhibitory", self.inhibitory_types)
+                    });
+                    if let Some(dynamics) = self.excitatory_dynamics {
+                        network = network.dynamics("excitatory", dynamics);
+                    }
+                    if let Some(dynamics) = self.inhibitory_dynamics {
+                        network = network.dynamics("inhibitory", dynamics);
+                    }
+                    if let Some(periods) = self.refractory_periods.clone() {
+                        network = network.refractory_periods(periods);
+                    }
+                    for (population, weight) in self.weight_distributions {
+                        network = network.weights(&population, weight);
+                    }
+                    let built = network.build(self.dt, self.delay_range, &mut rng);
+                    projection_models = network.synapse_channels(&built.2);
+                    (network.ranges(), built)
+                }
+            };
+        let n = neurons.len();
+        if let Some(gap_junctions) = &self.gap_junctions {
+            assert_eq!(
+                gap_junctions.neurons(),
+                n,
+                "gap junctions built for a different number of neurons"
+            );
+        }
+        apply_overrides(&self.neuron_overrides, &mut neurons, &mut connectivity);
+        if let Some(synapses) = self.synapses {
+            connectivity = SparseConnectivity::from_synapses(n, synapses, self.dt);
+            projection_models = (vec![], vec![]);
+        }
+        let (models, synapse_channels) = projection_models;
+        let channels = 1 + models.len();
+        if let (Some(periods), true) = (&self.refractory_periods, instantiated) {
+            // loaded neurons have no type, only a sign
+            for idx in 0..n {
+                neurons.refractory_period[idx] = periods.period(None, &neurons.synapse_types[idx]);
+            }
+        }
+
+        let poisson_sources = self
+            .poisson_inputs
+            .into_iter()
+            .map(|input| PoissonSource::new(input, &mut rng))
+            .collect();
+        let noise_source = NoiseSource::new(self.noise_model, n, self.thalamic_sigma, &mut rng);
+
+        let max_delay = connectivity.delays.iter().copied().max().unwrap_or(1) as usize;
+        let synapse_types = neurons.synapse_types.clone();
+        let mut synaptic_state = SynapticState::new(n).with_projection_models(models);
+        if let Some(nmda) = self.nmda {
+            synaptic_state = synaptic_state.with_nmda(nmda, &synapse_types);
+        }
+        let dopamine_stdp = self
+            .dopamine_stdp
+            .map(|params| DopamineStdp::new(params, &connectivity, &synapse_types));
+        let stdp = self
+            .stdp
+            .map(|params| Stdp::new(params, &connectivity, &synapse_types));
+        let inhibitory_stdp = self
+            .inhibitory_stdp
+            .map(|params| InhibitoryStdp::new(params, &connectivity, &synapse_types));
+        let stp = self
+            .short_term_plasticity
+            .map(|config| StpState::new(config, &connectivity, synapse_types));
+
+        let mut spike_record = SpikeRecord::new(
+            n,
+            self.spike_window
+                .map(|window| (window / self.dt).ceil() as usize),
+        );
+        spike_record.set_precise(self.precise_spike_times);
+        WorldState {
+            neurons,
+            connectivity,
+            delay_buffer: vec![vec![SpikeInput::new(n); channels]; max_delay],
+            synapse_channels,
+            stp,
+            dopamine_stdp,
+            stdp,
+            inhibitory_stdp,
+            homeostasis: self.homeostasis.map(|params| Homeostasis::new(params, n)),
+            gap_junctions: self.gap_junctions,
+            neuromodulators: vec![],
+            synapse_model: self.synapse_model,
+            synaptic_state,
+            stimuli: vec![],
+            controllers: vec![],
+            poisson_sources,
+            input_spikes: vec![],
+            thalamic_noise: self.thalamic_noise,
+            thalamic_sigma: self.thalamic_sigma,
+            noise_source,
+            populations,
+            positions,
+            spike_record,
+            v_traces: vec![],
+            input_traces: vec![],
+            voltage_synchrony: vec![],
+            lfp: None,
+            profile: None,
+            weight_history: None,
+            silenced: vec![false; n],
+            removed_synapses: vec![],
+            lesions: vec![],
+            time_step: 0,
+            dt: self.dt,
+            integrator: self.integrator,
+            rng,
+            event_driven: self.event_driven.map(|tolerance| EventDriven {
+                tolerance,
+                settled: vec![false; n],
+            }),
+            engine: Engine::Cpu,
+        }
+    }
+}
+
+/// Which neurons the event-driven mode currently skips.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+struct EventDriven {
+    /// Largest distance of `v` and `u` from the resting state for a neuron to settle.
+    tolerance: f64,
+    settled: Vec<bool>,
+}
+
+/// Where [`WorldState::step`] integrates the neurons.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub enum Backend {
+    /// Double precision on the CPU, parallelized with rayon.
+    #[default]
+    Cpu,
+    /// Single precision with explicit SIMD on one core, storing the weights densely.
+    Simd,
+    /// Single precision compute shader via wgpu, requires the `gpu` feature.
+    Gpu,
+    /// Double precision on the CPU, with the neurons split across the processes of an MPI job,
+    /// requires the `mpi` feature.
+    Mpi,
+}
+
+#[derive(Debug)]
+pub enum BackendError {
+    /// The network uses a feature the backend does not implement.
+    Unsupported(String),
+    /// The backend could not be initialized on this machine or build.
+    Unavailable(String),
+}
+
+impl fmt::Display for BackendError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            BackendError::Unsupported(what) => write!(f, "backend does not support {}", what),
+            BackendError::Unavailable(why) => write!(f, "backend unavailable: {}", why),
+        }
+    }
+}
+
+impl std::error::Error for BackendError {}
+
+/// State of the selected [`Backend`].
+#[derive(Default)]
+enum Engine {
+    #[default]
+    Cpu,
+    Simd(SimdBackend),
+    #[cfg(feature = "gpu")]
+    Gpu(Box<GpuBackend>),
+    #[cfg(feature = "mpi")]
+    Mpi(MpiBackend),
+}
+
+impl Engine {
+    /// Whether the engine gathers the previous step's spikes itself, bypassing the delay buffer.
+    fn gathers_spikes(&self) -> bool {
+        match self {
+            Engine::Cpu => false,
+            #[cfg(feature = "mpi")]
+            Engine::Mpi(_) => false,
+            _ => true,
+        }
+    }
+}
+
+/// Synaptic weights in compressed sparse row (CSR) form, with one row per presynaptic neuron
+/// listing its outgoing synapses.
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+pub struct SparseConnectivity {
+    /// Outgoing synapses of neuron `i` live at `row_offsets[i]..row_offsets[i + 1]`.
+    row_offsets: Vec<usize>,
+    /// Postsynaptic neuron of each synapse.
+    targets: Vec<usize>,
+    weights: Vec<f64>,
+    /// Conduction delay of each synapse in time steps, at least 1.
+    delays: Vec<u32>,
+}
+
+impl SparseConnectivity {
+    pub fn new() -> Self {
+        Self::with_capacity(0, 0)
+    }
+
+    pub fn with_capacity(neurons: usize, synapses: usize) -> Self {
+        let mut row_offsets = Vec::with_capacity(neurons + 1);
+        row_offsets.push(0);
+        SparseConnectivity {
+            row_offsets,
+            targets: Vec::with_capacity(synapses),
+            weights: Vec::with_capacity(synapses),
+            delays: Vec::with_capacity(synapses),
+        }
+    }
+
+    /// Builds the sparse form of a dense matrix indexed `[post][pre]`, skipping zero weights.
+    /// All synapses get a delay of one time step.
+    pub fn from_dense(matrix: &[Vec<f64>]) -> Self {
+        let n = matrix.len();
+        let mut connectivity = Self::with_capacity(n, 0);
+        for pre in 0..n {
+            for (post, row) in matrix.iter().enumerate() {
+                if row[pre] != 0. {
+                    connectivity.push(post, row[pre], 1);
+                }
+            }
+            connectivity.finish_row();
+        }
+        connectivity
+    }
+
+    /// Builds the connectivity of `neurons` neurons from `(pre, post, weight, delay)` synapses in
+    /// any order, with delays in milliseconds rounded to steps of `dt`.
+    ///
+    /// # Panics
+    ///
+    /// If a synapse connects a neuron outside the network.
+    pub fn from_synapses(neurons: usize, mut synapses: Vec<Synapse>, dt: f64) -> Self {
+        synapses.sort_by_key(|&(pre, _, _, _)| pre);
+        let mut connectivity = Self::with_capacity(neurons, synapses.len());
+        let mut synapses = synapses.into_iter().peekable();
+        for pre in 0..neurons {
+            while let Some((_, post, weight, delay_ms)) =
+                synapses.next_if(|&(from, _, _, _)| from == pre)
+            {
+                assert!(
+                    post < neurons,
+                    "synapse onto neuron {post} out of range for {neurons} neurons"
+                );
+                // a spike can arrive at the next step at the earliest
+                let delay = ((delay_ms / dt).round() as u32).max(1);
+                connectivity.push(post, weight, delay);
+            }
+            connectivity.finish_row();
+        }
+        assert!(
+            synapses.next().is_none(),
+            "synapse from a neuron outside the network"
+        );
+        connectivity
+    }
+
+    /// Appends a synapse from the presynaptic neuron whose row is currently being built.
+    pub fn push(&mut self, post: usize, weight: f64, delay: u32) {
+        assert!(delay >= 1, "synaptic delays must be at least one time step");
+        self.targets.push(post);
+        self.weights.push(weight);
+        self.delays.push(delay);
+    }
+
+    /// Closes the current presynaptic row, so following pushes belong to the next neuron.
+    pub fn finish_row(&mut self) {
+        self.row_offsets.push(self.targets.len());
+    }
+
+    /// Number of presynaptic rows.
+    pub fn neurons(&self) -> usize {
+        self.row_offsets.len() - 1
+    }
+
+    /// Number of stored synapses.
+    pub fn synapses(&self) -> usize {
+        self.targets.len()
+    }
+
+    /// Indices into the per-synapse slices of the outgoing synapses of `pre`.
+    pub fn row(&self, pre: usize) -> std::ops::Range<usize> {
+        self.row_offsets[pre]..self.row_offsets[pre + 1]
+    }
+
+    pub fn targets(&self) -> &[usize] {
+        &self.targets
+    }
+
+    pub fn weights(&self) -> &[f64] {
+        &self.weights
+    }
+
+    pub fn weights_mut(&mut self) -> &mut [f64] {
+        &mut self.weights
+    }
+
+    pub fn delays(&self) -> &[u32] {
+        &self.delays
+    }
+
+    /// Outgoing `(post, weight)` pairs of the presynaptic neuron `pre`.
+    pub fn outgoing(&self, pre: usize) -> impl Iterator<Item = (usize, f64)> + '_ {
+        let range = self.row(pre);
+        self.targets[range.clone()]
+            .iter()
+            .copied()
+            .zip(self.weights[range].iter().copied())
+    }
+
+    /// Weight of the synapse from `pre` onto `post`, or 0 if they are not connected.
+    pub fn weight(&self, pre: usize, post: usize) -> f64 {
+        self.outgoing(pre)
+            .find(|&(target, _)| target == post)
+            .map_or(0., |(_, weight)| weight)
+    }
+
+    /// Dense matrix indexed `[post][pre]`; only sensible for small networks.
+    pub fn to_dense(&self) -> Vec<Vec<f64>> {
+        let n = self.neurons();
+        let mut matrix = vec![vec![0.; n]; n];
+        for (pre, row) in self.row_offsets.windows(2).enumerate() {
+            for synapse in row[0]..row[1] {
+                matrix[self.targets[synapse]][pre] = self.weights[synapse];
+            }
+        }
+        matrix
+    }
+}
+
+#[derive(Serialize, Deserialize)]
 pub struct WorldState {
-    pub neurons: Vec<Neuron>,
-    pub action_potentials: Vec<Vec<bool>>,
+    pub neurons: Neurons,
+    pub connectivity: SparseConnectivity,
+    /// Ring buffer of synaptic input still in flight, indexed by arrival step modulo its length
+    /// and then by the channel of the synapse model it follows.
+    delay_buffer: Vec<Vec<SpikeInput>>,
+    /// Channel of every synapse in the delay buffer, 0 for the model of the world and the index
+    /// plus one of a projection model of the synaptic state, or empty if all are 0.
+    synapse_channels: Vec<usize>,
+    /// Short-term plasticity state, if enabled.
+    pub stp: Option<StpState>,
+    /// Dopamine-modulated STDP state, if enabled.
+    pub dopamine_stdp: Option<DopamineStdp>,
+    /// Spike-timing-dependent plasticity state, if enabled.
+    pub stdp: Option<Stdp>,
+    /// Inhibitory spike-timing-dependent plasticity state, if enabled.
+    pub inhibitory_stdp: Option<InhibitoryStdp>,
+    /// Intrinsic homeostatic plasticity state, if enabled.
+    pub homeostasis: Option<Homeostasis>,
+    /// Electrical coupling between neurons, if any.
+    pub gap_junctions: Option<GapJunctions>,
+    /// Neuromodulatory signals, see [`WorldState::add_neuromodulator`].
+    pub neuromodulators: Vec<Neuromodulator>,
+    pub synapse_model: SynapseModel,
+    pub synaptic_state: SynapticState,
+    /// Not part of checkpoints, since stimuli are arbitrary trait objects.
+    #[serde(skip)]
+    pub stimuli: Vec<AttachedStimulus>,
+    /// Not part of checkpoints either, for the same reason.
+    #[serde(skip)]
+    controllers: Vec<Box<dyn Controller>>,
+    pub poisson_sources: Vec<PoissonSource>,
+    /// Pending `(time, neuron, weight)` input spikes, sorted by time.
+    input_spikes: Vec<(f64, usize, f64)>,
+    pub thalamic_noise: bool,
+    /// Standard deviation of the thalamic noise relative to the paper's.
+    pub thalamic_sigma: f64,
+    /// Draws the thalamic noise, keeping the Ornstein–Uhlenbeck state while the noise is off.
+    pub noise_source: NoiseSource,
+    /// Named index ranges of the populations, in order.
+    populations: Vec<(String, Range<usize>)>,
+    /// Position of every neuron if the network was laid out on a [`crate::network::Sheet`].
+    pub positions: Option<Vec<[f64; 2]>>,
+    spike_record: SpikeRecord,
+    /// Membrane potential traces of the probed neurons, one sample per time step.
+    pub v_traces: Vec<(usize, Vec<f64>)>,
+    /// Thalamic input current onto the probed neurons, one sample per time step over that step.
+    pub input_traces: Vec<(usize, Vec<f64>)>,
+    /// Moments of the membrane potentials of groups of neurons for their synchrony index.
+    pub voltage_synchrony: Vec<VoltageSynchrony>,
+    /// Local field potential proxy, one sample per time step over that step, if recorded, see
+    /// [`WorldState::record_lfp`].
+    pub lfp: Option<Vec<f64>>,
+    /// Snapshots of the weights of the synapses from excitatory neurons, if recorded, see
+    /// [`WorldState::record_weights`].
+    pub weight_history: Option<WeightHistory>,
+    /// Time spent in every phase of the steps, if profiled, see [`WorldState::start_profiling`].
+    /// Not part of checkpoints, since it measures the run rather than the network.
+    #[serde(skip)]
+    pub profile: Option<Profile>,
+    /// Whether each neuron was silenced by a [`Lesion`].
+    silenced: Vec<bool>,
+    /// Sorted synapses removed by lesions, kept at zero weight.
+    removed_synapses: Vec<usize>,
+    /// Pending `(time, lesion)`s, sorted by time.
+    lesions: Vec<(f64, Lesion)>,
     pub time_step: usize,
+    /// Integration time step in milliseconds.
+    pub dt: f64,
+    /// Scheme integrating the membranes of the Izhikevich neurons.
+    pub integrator: Integrator,
+    /// Source of all randomness after construction, i.e. the thalamic input.
+    rng: ChaCha12Rng,
+    /// Settled neurons skipped by the event-driven mode, if enabled.
+    event_driven: Option<EventDriven>,
+    /// Checkpoints always resume on the CPU backend.
+    #[serde(skip)]
+    engine: Engine,
 }
 
 impl WorldState {
     pub fn new(excitatory: usize, inhibitory: usize) -> Self {
-        let n = excitatory + inhibitory;
-
-        let mut neurons = vec![];
-        for _ in 0..excitatory {
-            neurons.push(Neuron::from_synapse_type(SynapseType::Excitatory));
-        }
-        for _ in 0..inhibitory {
-            neurons.push(Neuron::from_synapse_type(SynapseType::Inhibitory));
-        }
-
-        let mut connection_matrix = vec![vec![0.; n]; n];
-        let mut rng = rand::thread_rng();
-        for (i, row) in connection_matrix.iter_mut().enumerate() {
-            for (j, cell) in row.iter_mut().enumerate() {
-                // don't allow self-connections
-                if i != j {
-                    if j < excitatory {
-                        *cell = 0.5 * rng.gen::<f64>();
-                    } else {
-                        // inhibitory connections actually inhibit
-                        *cell = -rng.gen::<f64>();
-                    }
+        Self::builder()
+            .excitatory(excitatory)
+            .inhibitory(inhibitory)
+            .build()
+    }
+
+    pub fn builder() -> WorldStateBuilder {
+        WorldStateBuilder::default()
+    }
+
+    /// Named index ranges of the populations making up the network.
+    pub fn populations(&self) -> Vec<(String, Range<usize>)> {
+        self.populations.clone()
+    }
+
+    /// Injects `stimulus` into `neurons` while the simulated time lies within `window`.
+    pub fn add_stimulus(
+        &mut self,
+        stimulus: impl Stimulus + 'static,
+        neurons: Range<usize>,
+        window: Range<f64>,
+    ) {
+        self.stimuli
+            .push(AttachedStimulus::new(stimulus, neurons, window));
+    }
+
+    /// Lets `controller` observe the network before every step and inject the currents it
+    /// returns during that step, for closed-loop stimulation.
+    pub fn add_controller(&mut self, controller: impl Controller + 'static) {
+        self.controllers.push(Box::new(controller));
+    }
+
+    /// Lets `modulator` scale the input, noise or adaptation of its neurons according to its
+    /// schedule from the next step on, relative to their adaptation now.
+    ///
+    /// # Panics
+    ///
+    /// If the neurons of `modulator` are out of range.
+    pub fn add_neuromodulator(&mut self, mut modulator: Neuromodulator) {
+        assert!(
+            modulator.neurons.end <= self.neurons.len(),
+            "neuromodulator neurons out of range for {} neurons",
+            self.neurons.len()
+        );
+        modulator.attach(&self.neurons.d);
+        self.neuromodulators.push(modulator);
+    }
+
+    /// Delivers an input spike of `weight` to the neuron of every `(time, neuron)` pair during the
+    /// step containing `time` milliseconds, applied like a recurrent synapse of that weight.
+    pub fn inject_spikes(&mut self, spikes: impl IntoIterator<Item = (f64, usize)>, weight: f64) {
+        self.input_spikes.extend(
+            spikes
+                .into_iter()
+                .map(|(time, neuron)| (time, neuron, weight)),
+        );
+        self.input_spikes.sort_by(|a, b| a.0.total_cmp(&b.0));
+    }
+
+    /// Feeds `values` into `neurons` for the next `duration` milliseconds as the spikes of
+    /// `encoding`, one channel per neuron from the first, each spike of `weight`.
+    ///
+    /// # Panics
+    ///
+    /// If there are more channels than `neurons`.
+    pub fn encode_spikes(
+        &mut self,
+        values: &[f64],
+        encoding: &Encoding,
+        neurons: Range<usize>,
+        weight: f64,
+        duration: f64,
+    ) {
+        assert!(
+            encoding.channels(values.len()) <= neurons.len(),
+            "expected a neuron for every channel"
+        );
+        let start = self.time();
+        let spikes = encoding.spikes(values, duration, self.dt, &mut self.rng);
+        self.inject_spikes(
+            spikes
+                .into_iter()
+                .map(|(time, channel)| (start + time, neurons.start + channel)),
+            weight,
+        );
+    }
+
+    /// Feeds `values` into `neurons` for the next `duration` milliseconds as constant currents of
+    /// `gain` times the channel intensities of `encoding`, one channel per neuron from the first.
+    ///
+    /// # Panics
+    ///
+    /// If there are more channels than `neurons`.
+    pub fn encode_currents(
+        &mut self,
+        values: &[f64],
+        encoding: &Encoding,
+        neurons: Range<usize>,
+        gain: f64,
+        duration: f64,
+    ) {
+        let intensities = encoding.intensities(values);
+        assert!(
+            intensities.len() <= neurons.len(),
+            "expected a neuron for every channel"
+        );
+        let start = self.time();
+        let neurons = neurons.start..neurons.start + intensities.len();
+        self.stimuli.push(
+            AttachedStimulus::new(
+                StepCurrent { amplitude: gain },
+                neurons,
+                start..start + duration,
+            )
+            .with_gains(intensities),
+        );
+    }
+
+    /// Injects `stimulus` through `electrode` while the simulated time lies within `window`, e.g.
+    /// to emulate microstimulation.
+    ///
+    /// # Panics
+    ///
+    /// If the neurons have no positions, i.e. the network was not laid out on a sheet.
+    pub fn add_electrode_stimulus(
+        &mut self,
+        stimulus: impl Stimulus + 'static,
+        electrode: &Electrode,
+        window: Range<f64>,
+    ) {
+        let positions = self
+            .positions
+            .as_ref()
+            .expect("electrodes need neurons placed on a sheet");
+        let gains = electrode.gains(positions);
+        self.stimuli
+            .push(AttachedStimulus::new(stimulus, 0..self.neurons.len(), window).with_gains(gains));
+    }
+
+    /// Records the membrane potential of `neurons` from now on, starting with the current value,
+    /// so a probe registered before the first step lines up with the spike record.
+    ///
+    /// # Panics
+    ///
+    /// If any of `neurons` is not in the network.
+    pub fn record_v(&mut self, neurons: impl IntoIterator<Item = usize>) {
+        for idx in neurons {
+            assert!(
+                idx < self.neurons.len(),
+                "neuron {idx} is not in the network"
+            );
+            self.v_traces.push((idx, vec![self.neurons.v[idx]]));
+        }
+    }
+
+    /// Records the thalamic input current onto `neurons` over every following step, including
+    /// homeostatic gains, e.g. for [`crate::analysis::SpikeTrains::spike_triggered_average`].
+    ///
+    /// # Panics
+    ///
+    /// If any of `neurons` is not in the network.
+    pub fn record_input(&mut self, neurons: impl IntoIterator<Item = usize>) {
+        for idx in neurons {
+            assert!(
+                idx < self.neurons.len(),
+                "neuron {idx} is not in the network"
+            );
+            self.input_traces.push((idx, vec![]));
+        }
+    }
+
+    /// Tracks the membrane potentials of `neurons` over every following step for their
+    /// Golomb–Rinzel synchrony index, see [`VoltageSynchrony::chi`].
+    pub fn track_voltage_synchrony(&mut self, neurons: Range<usize>) {
+        self.voltage_synchrony.push(VoltageSynchrony::new(neurons));
+    }
+
+    /// Records a proxy of the local field potential over every following step: the sum of the
+    /// absolute excitatory and inhibitory synaptic currents into the excitatory neurons, as
+    /// pyramidal cells dominate the extracellular field (Mazzoni et al., 2008), before
+    /// homeostatic gains and without stimuli or thalamic input.
+    pub fn record_lfp(&mut self) {
+        self.lfp.get_or_insert_with(Vec::new);
+    }
+
+    /// Times the noise, synapses, input, membrane integration, plasticity and recording of every
+    /// following step, resetting any earlier profile.
+    pub fn start_profiling(&mut self) {
+        self.profile = Some(Profile::default());
+    }
+
+    /// Snapshots the weights of the synapses from excitatory neurons now and every `interval`
+    /// milliseconds after, with `samples` of them followed individually and the distribution in
+    /// 50 bins up to the bound of the plasticity rule, or the largest weight without one.
+    pub fn record_weights(&mut self, interval: f64, samples: usize) {
+        let w_max = match (&self.stdp, &self.dopamine_stdp) {
+            (Some(stdp), _) => stdp.params.w_max,
+            (None, Some(stdp)) => stdp.params.w_max,
+            (None, None) => self
+                .connectivity
+                .weights()
+                .iter()
+                .copied()
+                .fold(0., f64::max),
+        };
+        self.weight_history = Some(WeightHistory::new(
+            &self.connectivity,
+            &self.neurons.synapse_types,
+            self.time_step,
+            self.dt,
+            (interval / self.dt).round() as usize,
+            samples,
+            if w_max > 0. { w_max / 50. } else { 1. },
+        ));
+    }
+
+    /// Applies `lesion` from the current step on.
+    ///
+    /// # Panics
+    ///
+    /// If the lesion refers to a neuron outside the network, see [`Lesion::check`].
+    pub fn lesion(&mut self, lesion: &Lesion) {
+        let n = self.neurons.len();
+        if let Err(err) = lesion.check(n) {
+            panic!("{err}");
+        }
+        match lesion {
+            Lesion::Neurons(neurons) => {
+                for &idx in neurons {
+                    self.silenced[idx] = true;
+                    self.removed_synapses.extend(self.connectivity.row(idx));
+                }
+            }
+            Lesion::Synapses { pre, post } => {
+                let mut targets = vec![false; n];
+                for &idx in post {
+                    targets[idx] = true;
+                }
+                for &idx in pre {
+                    let row = self.connectivity.row(idx);
+                    self.removed_synapses
+                        .extend(row.filter(|&synapse| targets[self.connectivity.targets[synapse]]));
                 }
             }
         }
+        self.removed_synapses.sort_unstable();
+        self.removed_synapses.dedup();
+        for &synapse in &self.removed_synapses {
+            self.connectivity.weights[synapse] = 0.;
+        }
+        // the single precision backends copy the weights when they start, while the MPI one
+        // reads them from the world and can only be initialized once
+        if let backend @ (Backend::Simd | Backend::Gpu) = self.backend() {
+            self.set_backend(backend)
+                .expect("the backend was initialized before");
+        }
+    }
+
+    /// Applies `lesion` at the first step starting at or after `time` milliseconds.
+    ///
+    /// # Panics
+    ///
+    /// If the lesion refers to a neuron outside the network, see [`Lesion::check`].
+    pub fn schedule_lesion(&mut self, time: f64, lesion: Lesion) {
+        if let Err(err) = lesion.check(self.neurons.len()) {
+            panic!("{err}");
+        }
+        let idx = self.lesions.partition_point(|&(t, _)| t <= time);
+        self.lesions.insert(idx, (time, lesion));
+    }
+
+    /// Whether each neuron was silenced by a lesion.
+    pub fn silenced(&self) -> &[bool] {
+        &self.silenced
+    }
+
+    /// Sorted onset times in milliseconds of the events of all attached stimuli.
+    pub fn stimulus_onsets(&self) -> Vec<f64> {
+        let mut onsets: Vec<f64> = self.stimuli.iter().flat_map(|s| s.onsets()).collect();
+        onsets.sort_by(f64::total_cmp);
+        onsets.dedup();
+        onsets
+    }
+
+    pub fn backend(&self) -> Backend {
+        match self.engine {
+            Engine::Cpu => Backend::Cpu,
+            Engine::Simd(_) => Backend::Simd,
+            #[cfg(feature = "gpu")]
+            Engine::Gpu(_) => Backend::Gpu,
+            #[cfg(feature = "mpi")]
+            Engine::Mpi(_) => Backend::Mpi,
+        }
+    }
+
+    /// Rank of this process among those sharing the network under [`Backend::Mpi`], or 0. Every
+    /// process holds all spikes, so only rank 0 needs to write results.
+    pub fn rank(&self) -> usize {
+        match &self.engine {
+            #[cfg(feature = "mpi")]
+            Engine::Mpi(mpi) => mpi.rank(),
+            _ => 0,
+        }
+    }
+
+    /// Switches the backend used by the following steps. The single precision backends copy the
+    /// weights when selected, so later changes to `connectivity` are not seen until they are
+    /// selected again. They support neither conduction delays, plastic synapses nor
+    /// conductance or NMDA synapses. The MPI backend steps every neuron clock-driven, and MPI can
+    /// only be initialized once per process.
+    pub fn set_backend(&mut self, backend: Backend) -> Result<(), BackendError> {
+        if let Backend::Simd | Backend::Gpu = backend {
+            self.check_single_precision()?;
+        }
+        let engine = match backend {
+            Backend::Cpu => Engine::Cpu,
+            Backend::Simd => Engine::Simd(SimdBackend::new(self)),
+            #[cfg(feature = "gpu")]
+            Backend::Gpu => Engine::Gpu(Box::new(GpuBackend::new(self)?)),
+            #[cfg(not(feature = "gpu"))]
+            Backend::Gpu => {
+                return Err(BackendError::Unavailable(
+                    "rebuild with `--features gpu`".into(),
+                ))
+            }
+            #[cfg(feature = "mpi")]
+            Backend::Mpi => Engine::Mpi(MpiBackend::new(self.neurons.len())?),
+            #[cfg(not(feature = "mpi"))]
+            Backend::Mpi => {
+                return Err(BackendError::Unavailable(
+                    "rebuild with `--features mpi`".into(),
+                ))
+            }
+        };
+
+        // the single precision backends gather the previous step's spikes themselves
+        match (self.engine.gathers_spikes(), engine.gathers_spikes()) {
+            (false, false) => {}
+            (true, false) => {
+                let spikes = self.last_spikes().to_vec();
+                self.schedule(&spikes);
+            }
+            (_, true) => {
+                let n = self.neurons.len();
+                let channels = self.delay_buffer[0].len();
+                self.delay_buffer =
+                    vec![vec![SpikeInput::new(n); channels]; self.delay_buffer.len()];
+            }
+        }
+        self.engine = engine;
+        Ok(())
+    }
 
-        neurons.iter_mut().enumerate().for_each(|(i, neuron)| {
-            neuron.connect(connection_matrix[i].clone());
+    /// Switches between clock-driven stepping, updating every neuron at every step, and
+    /// event-driven stepping, which skips neurons that sit at rest and receive no input. A neuron
+    /// without input is moved onto its resting state once within `tolerance` of it, which is the
+    /// only deviation from clock-driven stepping, so with a small tolerance the spikes match. This
+    /// pays off when few neurons receive input, i.e. without thalamic noise. `None` returns to
+    /// clock-driven stepping. Only the CPU backend skips neurons.
+    pub fn set_event_driven(&mut self, tolerance: Option<f64>) {
+        self.event_driven = tolerance.map(|tolerance| EventDriven {
+            tolerance,
+            settled: vec![false; self.neurons.len()],
         });
+    }
 
-        WorldState {
-            neurons,
-            action_potentials: vec![vec![false; n]],
-            time_step: 0,
+    /// Number of neurons the event-driven mode skips while they receive no input.
+    pub fn settled_neurons(&self) -> usize {
+        self.event_driven.as_ref().map_or(0, |event_driven| {
+            event_driven
+                .settled
+                .iter()
+                .filter(|&&settled| settled)
+                .count()
+        })
+    }
+
+    fn check_single_precision(&self) -> Result<(), BackendError> {
+        if self.connectivity.delays.iter().any(|&delay| delay != 1) {
+            return Err(BackendError::Unsupported(
+                "conduction delays longer than one step".into(),
+            ));
+        }
+        if self.stp.is_some()
+            || self.dopamine_stdp.is_some()
+            || self.stdp.is_some()
+            || self.inhibitory_stdp.is_some()
+        {
+            return Err(BackendError::Unsupported("plastic synapses".into()));
+        }
+        if self.homeostasis.is_some() {
+            return Err(BackendError::Unsupported("homeostatic plasticity".into()));
         }
+        if self.gap_junctions.is_some() {
+            return Err(BackendError::Unsupported("gap junctions".into()));
+        }
+        if self.neurons.has_dynamics() {
+            return Err(BackendError::Unsupported(
+                "neuron models other than Izhikevich".into(),
+            ));
+        }
+        if self.integrator != Integrator::Euler {
+            return Err(BackendError::Unsupported(
+                "integrators other than Euler".into(),
+            ));
+        }
+        if self.spike_record.is_precise() {
+            return Err(BackendError::Unsupported("precise spike times".into()));
+        }
+        if self
+            .neurons
+            .refractory_period
+            .iter()
+            .any(|&period| period > 0.)
+        {
+            return Err(BackendError::Unsupported("refractory periods".into()));
+        }
+        if self.synapse_model != SynapseModel::Current
+            || self.synaptic_state.projection_models().next().is_some()
+            || self.synaptic_state.nmda().is_some()
+        {
+            return Err(BackendError::Unsupported(
+                "conductance-based or NMDA synapses".into(),
+            ));
+        }
+        Ok(())
+    }
+
+    /// Advances the simulation by `steps` time steps.
+    pub fn run(&mut self, steps: usize) {
+        for _ in 0..steps {
+            self.step();
+        }
+    }
+
+    /// Advances the simulation by `milliseconds` of simulated time.
+    pub fn run_for(&mut self, milliseconds: f64) {
+        self.run((milliseconds / self.dt).round() as usize);
+    }
+
+    /// Simulated time in milliseconds.
+    pub fn time(&self) -> f64 {
+        self.time_step as f64 * self.dt
+    }
+
+    /// Spikes emitted during the most recent time step, indexed by neuron.
+    pub fn last_spikes(&self) -> &[bool] {
+        self.spike_record.last()
+    }
+
+    /// All recorded spikes as `(time_step, neuron_idx)` pairs, within the spike window if one
+    /// is set.
+    pub fn spikes(&self) -> impl Iterator<Item = (usize, usize)> + '_ {
+        self.spike_record.since(0)
+    }
+
+    /// All recorded spikes as `(time, neuron_idx)` pairs with the time in milliseconds, within the
+    /// spike window if one is set. Precise spike times are interpolated within their step, others
+    /// fall at its end.
+    pub fn spike_times(&self) -> impl Iterator<Item = (f64, usize)> + '_ {
+        self.spike_record.times(self.dt)
+    }
+
+    /// The recorded spikes by time step.
+    pub fn spike_record(&self) -> &SpikeRecord {
+        &self.spike_record
+    }
+
+    /// Whether to time spikes within their step from now on, by interpolating linearly where the
+    /// membrane crossed the spike cutoff, see [`NeuronModel::crossing`]. The times go into the
+    /// spike record and STDP, where a spike arrives after its conduction delay at the same offset
+    /// within a later step. Synaptic input still takes effect over the step of the arrival, and
+    /// the single precision backends do not support it.
+    ///
+    /// [`NeuronModel::crossing`]: crate::NeuronModel::crossing
+    pub fn set_precise_spike_times(&mut self, precise: bool) {
+        self.spike_record.set_precise(precise);
+    }
+
+    /// Keeps only the spikes of the last `milliseconds` from now on, or all of them for `None`,
+    /// see [`WorldStateBuilder::spike_window`].
+    pub fn set_spike_window(&mut self, milliseconds: Option<f64>) {
+        let dt = self.dt;
+        self.spike_record
+            .set_window(milliseconds.map(|window| (window / dt).ceil() as usize));
     }
 
     pub fn step(&mut self) {
-        let thalamic_generator = Normal::new(0., 1.).unwrap();
-        let thalamic_input: Vec<f64> = thalamic_generator
-            .sample_iter(&mut thread_rng())
-            .take(self.neurons.len())
-            .collect();
+        let mut clock = self.profile.is_some().then(Instant::now);
+        let due = self.lesions.partition_point(|&(t, _)| t <= self.time());
+        for (_, lesion) in self.lesions.drain(..due).collect::<Vec<_>>() {
+            self.lesion(&lesion);
+        }
+        self.lap(Phase::Input, &mut clock);
 
-        // Get synaptic input once before parallel processing
-        let synaptic_input = self.action_potentials[self.time_step].clone();
+        let n = self.neurons.len();
+        let mut thalamic_input: Vec<f64> = if self.thalamic_noise {
+            self.noise_source.sample(self.thalamic_sigma, self.dt)
+        } else {
+            vec![0.; n]
+        };
+        self.lap(Phase::Noise, &mut clock);
 
-        // Process neurons in parallel
-        let spikes: Vec<bool> = self
-            .neurons
-            .par_iter_mut() // Parallel mutable iterator
-            .zip(thalamic_input.par_iter()) // Parallel immutable iterator
-            .map(|(neuron, i)| neuron.step(*i, &synaptic_input))
+        let dt = self.dt;
+
+        // Input whose conduction delay ends at this step
+        let slot = self.time_step % self.delay_buffer.len();
+        let channels = self.delay_buffer[slot].len();
+        let mut arriving = std::mem::replace(
+            &mut self.delay_buffer[slot],
+            vec![SpikeInput::new(n); channels],
+        );
+        // external input follows the model of the world
+        for source in &self.poisson_sources {
+            source.deliver(dt, &mut self.rng, &mut arriving[0]);
+        }
+        let end = self.time() + dt;
+        let due = self.input_spikes.partition_point(|&(t, ..)| t < end);
+        for (_, neuron, weight) in self.input_spikes.drain(..due) {
+            arriving[0].add(neuron, weight);
+        }
+        let (excitatory, inhibitory) = self.synaptic_state.currents_by_sign(
+            &self.synapse_model,
+            &arriving,
+            &self.neurons.v,
+            dt,
+        );
+        self.lap(Phase::Synapses, &mut clock);
+        if let Some(lfp) = &mut self.lfp {
+            let sample = (0..n)
+                .filter(|&idx| self.neurons.synapse_types[idx] == SynapseType::Excitatory)
+                .map(|idx| excitatory[idx].abs() + inhibitory[idx].abs())
+                .sum();
+            lfp.push(sample);
+        }
+        self.lap(Phase::Recording, &mut clock);
+        let mut input_current: Vec<f64> = excitatory
+            .iter()
+            .zip(&inhibitory)
+            .map(|(exc, inh)| exc + inh)
             .collect();
+        if let Some(gap_junctions) = &self.gap_junctions {
+            for (input, current) in input_current
+                .iter_mut()
+                .zip(gap_junctions.currents(&self.neurons.v))
+            {
+                *input += current;
+            }
+        }
+        self.lap(Phase::Synapses, &mut clock);
+        let t = self.time();
+        for stimulus in &self.stimuli {
+            stimulus.apply(t, &mut input_current);
+        }
+        let mut controllers = std::mem::take(&mut self.controllers);
+        for controller in &mut controllers {
+            let currents = controller.control(self);
+            assert_eq!(
+                currents.len(),
+                n,
+                "controller currents must cover every neuron"
+            );
+            for (input, current) in input_current.iter_mut().zip(currents) {
+                *input += current;
+            }
+        }
+        self.controllers = controllers;
+
+        if let Some(homeostasis) = &self.homeostasis {
+            for ((thalamic, current), gain) in thalamic_input
+                .iter_mut()
+                .zip(&mut input_current)
+                .zip(&homeostasis.gains)
+            {
+                *thalamic *= gain;
+                *current *= gain;
+            }
+        }
+
+        for modulator in &self.neuromodulators {
+            modulator.apply(
+                t,
+                &mut thalamic_input,
+                &mut input_current,
+                &mut self.neurons.d,
+            );
+        }
+
+        for (idx, _) in self.silenced.iter().enumerate().filter(|&(_, &s)| s) {
+            thalamic_input[idx] = 0.;
+            input_current[idx] = 0.;
+        }
+        self.lap(Phase::Input, &mut clock);
+
+        for (idx, trace) in &mut self.input_traces {
+            trace.push(
+                self.neurons
+                    .thalamic_current(*idx, thalamic_input[*idx], dt),
+            );
+        }
+        self.lap(Phase::Recording, &mut clock);
+
+        let mut spikes: Vec<bool> = match &mut self.engine {
+            Engine::Cpu => match &mut self.event_driven {
+                Some(EventDriven { tolerance, settled }) => self.neurons.step_event_driven(
+                    &thalamic_input,
+                    &input_current,
+                    dt,
+                    self.integrator,
+                    *tolerance,
+                    settled,
+                ),
+                None => self
+                    .neurons
+                    .step(&thalamic_input, &input_current, dt, self.integrator),
+            },
+            Engine::Simd(simd) => {
+                let external = external_current(&self.neurons, &thalamic_input, &input_current, dt);
+                simd.step(&external, &mut self.neurons)
+            }
+            #[cfg(feature = "gpu")]
+            Engine::Gpu(gpu) => {
+                let external = external_current(&self.neurons, &thalamic_input, &input_current, dt);
+                gpu.step(&external, &mut self.neurons)
+            }
+            #[cfg(feature = "mpi")]
+            Engine::Mpi(mpi) => mpi.step(
+                &thalamic_input,
+                &input_current,
+                dt,
+                self.integrator,
+                &mut self.neurons,
+            ),
+        };
+        // a silenced neuron may still be on its way to a spike when its input is cut
+        for (spiked, &silenced) in spikes.iter_mut().zip(&self.silenced) {
+            *spiked &= !silenced;
+        }
+        self.lap(Phase::Membrane, &mut clock);
+        // the single precision backends gather the spikes at the next step instead
+        if !self.engine.gathers_spikes() {
+            self.schedule(&spikes);
+        }
+        self.lap(Phase::Synapses, &mut clock);
+        let offsets = self
+            .spike_record
+            .is_precise()
+            .then_some(&self.neurons.spike_offset[..]);
+        if let Some(stdp) = &mut self.dopamine_stdp {
+            let time = (self.time_step + 1) as f64 * dt;
+            stdp.on_step(&spikes, offsets, time, dt, &mut self.connectivity);
+        }
+        if let Some(stdp) = &mut self.stdp {
+            stdp.on_step(&spikes, offsets, self.time_step, dt, &mut self.connectivity);
+        }
+        if let Some(stdp) = &mut self.inhibitory_stdp {
+            stdp.on_step(&spikes, dt, &mut self.connectivity);
+        }
+        for &synapse in &self.removed_synapses {
+            self.connectivity.weights[synapse] = 0.;
+        }
+        if let Some(homeostasis) = &mut self.homeostasis {
+            homeostasis.on_step(&spikes, dt, &mut self.neurons);
+        }
+        self.lap(Phase::Plasticity, &mut clock);
+
+        for (idx, trace) in &mut self.v_traces {
+            trace.push(self.neurons.v[*idx]);
+        }
+        for synchrony in &mut self.voltage_synchrony {
+            synchrony.on_step(&self.neurons.v);
+        }
 
         self.time_step += 1;
-        self.action_potentials.push(spikes);
+        if let Some(history) = &mut self.weight_history {
+            history.on_step(self.time_step, dt, &self.connectivity);
+        }
+        self.spike_record.push(spikes, &self.neurons.spike_offset);
+        self.lap(Phase::Recording, &mut clock);
+        if let Some(profile) = &mut self.profile {
+            profile.steps += 1;
+        }
     }
+
+    /// Adds the time since `clock` to `phase` of the profile, if any, and restarts it.
+    fn lap(&mut self, phase: Phase, clock: &mut Option<Instant>) {
+        if let (Some(profile), Some(clock)) = (&mut self.profile, clock) {
+            profile.lap(phase, clock);
+        }
+    }
+
+    /// Schedules the spikes emitted during the current step along their outgoing synapses.
+    fn schedule(&mut self, spikes: &[bool]) {
+        let len = self.delay_buffer.len();
+        let spike_time = (self.time_step + 1) as f64 * self.dt;
+        for (pre, _) in spikes.iter().enumerate().filter(|&(_, &spiked)| spiked) {
+            let efficacies = self
+                .stp
+                .as_mut()
+                .map(|stp| stp.on_spike(pre, spike_time, &self.connectivity));
+            for (k, synapse) in self.connectivity.row(pre).enumerate() {
+                let efficacy = efficacies.as_ref().map_or(1., |e| e[k]);
+                let arrival = (self.time_step + self.connectivity.delays[synapse] as usize) % len;
+                let channel = self.synapse_channels.get(synapse).copied().unwrap_or(0);
+                self.delay_buffer[arrival][channel].add(
+                    self.connectivity.targets[synapse],
+                    efficacy * self.connectivity.weights[synapse],
+                );
+            }
+        }
+    }
+}
+
+/// Thalamic plus input current of every neuron in single precision, for the backends that only
+/// integrate the recurrent input themselves.
+fn external_current(
+    neurons: &Neurons,
+    thalamic_input: &[f64],
+    input_current: &[f64],
+    dt: f64,
+) -> Vec<f32> {
+    thalamic_input
+        .iter()
+        .zip(input_current)
+        .enumerate()
+        .map(|(idx, (i, current))| (neurons.thalamic_current(idx, *i, dt) + current) as f32)
+        .collect()
 }
@@ -0,0 +1,378 @@
+use std::collections::HashMap;
+use std::error::Error;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use hdf5::types::{VarLenAscii, VarLenUnicode};
+use serde_json::Value;
+
+use crate::network::{InstantiatedNetwork, Synapse};
+use crate::neuron::{Neuron, NeuronParameters, SynapseType};
+
+type Result<T> = std::result::Result<T, Box<dyn Error>>;
+
+/// Per-node or per-edge values that are read from the HDF5 groups when present.
+const NODE_ATTRIBUTES: [&str; 8] = ["a", "b", "c", "d", "V_m", "U_m", "x", "y"];
+const EDGE_ATTRIBUTES: [&str; 3] = ["syn_weight", "delay", "nsyns"];
+
+/// Reads the network of a SONATA circuit config, as written by e.g. BMTK, or of a simulation
+/// config pointing to one with `"network"`.
+///
+/// The Izhikevich parameters of every node are taken from its `a`, `b`, `c` and `d`, with
+/// optional initial `V_m` and `U_m` as in NEST's `izhikevich` model, looked up in the node's
+/// `dynamics_params` in the nodes file, in the group's per-node datasets, in the JSON file named
+/// by the node type's `dynamics_params` in the point neuron models directory, and in the node
+/// types table, in that order. Nodes of type `ei = i` are inhibitory. Nodes with `model_type`
+/// `virtual` are external inputs and are skipped together with their edges.
+///
+/// Weights are `syn_weight * nsyns`, made negative from inhibitory neurons, and delays default to
+/// 1 ms. Every node population becomes a population of the network, and the positions are
+/// taken from `x` and `y` if all neurons have them.
+pub fn load_sonata(config: impl AsRef<Path>) -> Result<InstantiatedNetwork> {
+    let config = fs::canonicalize(config)?;
+    let json: Value = serde_json::from_str(&fs::read_to_string(&config)?)?;
+    let dir = config.parent().unwrap_or(Path::new("/"));
+    if let (None, Some(network)) = (json.get("networks"), json["network"].as_str()) {
+        return load_sonata(resolve(&json, dir, network));
+    }
+
+    let components = json["components"]["point_neuron_models_dir"]
+        .as_str()
+        .map(|path| resolve(&json, dir, path));
+    let files = |kind: &str, file: &str, types: &str| -> Result<Vec<(PathBuf, PathBuf)>> {
+        let Some(entries) = json["networks"][kind].as_array() else {
+            return Ok(vec![]);
+        };
+        entries
+            .iter()
+            .map(
+                |entry| match (entry[file].as_str(), entry[types].as_str()) {
+                    (Some(file), Some(types)) => {
+                        Ok((resolve(&json, dir, file), resolve(&json, dir, types)))
+                    }
+                    _ => {
+                        Err(format!("`networks.{kind}` entries need `{file}` and `{types}`").into())
+                    }
+                },
+            )
+            .collect()
+    };
+
+    let mut network = InstantiatedNetwork::default();
+    let mut positions = vec![];
+    let mut index = NodeIndex::default();
+    for (nodes, node_types) in files("nodes", "nodes_file", "node_types_file")? {
+        let types = read_types(&node_types, "node_type_id", components.as_deref())?;
+        read_nodes(&nodes, &types, &mut network, &mut positions, &mut index)?;
+    }
+    for (edges, edge_types) in files("edges", "edges_file", "edge_types_file")? {
+        let types = read_types(&edge_types, "edge_type_id", None)?;
+        let synapses = read_edges(&edges, &types, &network.neurons.synapse_types, &index)?;
+        network.synapses.extend(synapses);
+    }
+    network.positions = positions.into_iter().collect();
+    Ok(network)
+}
+
+/// Expands the manifest variables of a config in `path`, relative to the config's `dir`.
+fn resolve(json: &Value, dir: &Path, path: &str) -> PathBuf {
+    let mut variables: Vec<(String, String)> = json["manifest"]
+        .as_object()
+        .into_iter()
+        .flatten()
+        .filter_map(|(name, value)| Some((name.clone(), value.as_str()?.to_owned())))
+        .collect();
+    variables.push(("$configdir".into(), dir.display().to_string()));
+    // longest first, so `$BASE` does not clobber `$BASE_DIR`
+    variables.sort_by_key(|(name, _)| std::cmp::Reverse(name.len()));
+
+    let mut path = path.to_owned();
+    // variables may refer to each other
+    for _ in 0..variables.len() {
+        let expanded = variables.iter().fold(path.clone(), |path, (name, value)| {
+            path.replace(&format!("${{{}}}", name.trim_start_matches('$')), value)
+                .replace(name.as_str(), value)
+        });
+        if expanded == path {
+            break;
+        }
+        path = expanded;
+    }
+    dir.join(path)
+}
+
+/// Columns of a node or edge types table by type id, with the contents of the JSON file named in
+/// `dynamics_params` merged in when `components` is given.
+fn read_types(
+    path: &Path,
+    id_column: &str,
+    components: Option<&Path>,
+) -> Result<HashMap<u64, HashMap<String, String>>> {
+    let text = fs::read_to_string(path)?;
+    let mut lines = text.lines().filter(|line| !line.trim().is_empty());
+    let header = split_fields(lines.next().unwrap_or_default());
+    let mut types = HashMap::new();
+    for line in lines {
+        let mut columns: HashMap<String, String> =
+            header.iter().cloned().zip(split_fields(line)).collect();
+        let id = columns
+            .get(id_column)
+            .ok_or_else(|| format!("{}: missing `{id_column}`", path.display()))?
+            .parse()?;
+        if let (Some(dir), Some(file)) = (components, columns.get("dynamics_params")) {
+            let file = dir.join(file);
+            if file.extension().is_some_and(|ext| ext == "json") {
+                let params: Value = serde_json::from_str(&fs::read_to_string(&file)?)?;
+                for (name, value) in params.as_object().into_iter().flatten() {
+                    let value = match value.as_str() {
+                        Some(value) => value.to_owned(),
+                        None => value.to_string(),
+                    };
+                    columns.insert(name.clone(), value);
+                }
+            }
+        }
+        types.insert(id, columns);
+    }
+    Ok(types)
+}
+
+/// Splits a line of a space separated SONATA table, keeping double-quoted fields together.
+fn split_fields(line: &str) -> Vec<String> {
+    let mut fields = vec![];
+    let mut field = String::new();
+    let mut quoted = false;
+    for char in line.chars() {
+        match char {
+            '"' => quoted = !quoted,
+            char if char.is_whitespace() && !quoted => {
+                if !field.is_empty() {
+                    fields.push(std::mem::take(&mut field));
+                }
+            }
+            char => field.push(char),
+        }
+    }
+    if !field.is_empty() {
+        fields.push(field);
+    }
+    fields
+}
+
+/// Index in the network of every simulated node by population and node id.
+#[derive(Default)]
+struct NodeIndex {
+    nodes: HashMap<(String, u64), usize>,
+    populations: Vec<String>,
+}
+
+/// Row-aligned per-node or per-edge values of one HDF5 population.
+struct Rows {
+    type_ids: Vec<u64>,
+    group_ids: Vec<u64>,
+    group_indices: Vec<u64>,
+    /// Datasets of each group by name, from the group or its `dynamics_params` subgroup.
+    groups: HashMap<u64, HashMap<String, Vec<f64>>>,
+}
+
+impl Rows {
+    fn read(population: &hdf5::Group, kind: &str, attributes: &[&str]) -> Result<Self> {
+        let type_ids: Vec<u64> = population.dataset(&format!("{kind}_type_id"))?.read_raw()?;
+        let count = type_ids.len();
+        let (group_ids, group_indices) = if population.link_exists(&format!("{kind}_group_id")) {
+            (
+                population
+                    .dataset(&format!("{kind}_group_id"))?
+                    .read_raw()?,
+                population
+                    .dataset(&format!("{kind}_group_index"))?
+                    .read_raw()?,
+            )
+        } else {
+            (vec![0; count], (0..count as u64).collect())
+        };
+
+        let mut groups = HashMap::new();
+        for &group_id in &group_ids {
+            if groups.contains_key(&group_id) || !population.link_exists(&group_id.to_string()) {
+                continue;
+            }
+            let group = population.group(&group_id.to_string())?;
+            let has_dynamics = group.link_exists("dynamics_params");
+            let mut values: HashMap<String, Vec<f64>> = HashMap::new();
+            for &name in attributes {
+                // the node's dynamics_params take precedence
+                let dynamics = format!("dynamics_params/{name}");
+                if has_dynamics && group.link_exists(&dynamics) {
+                    values.insert(name.into(), group.dataset(&dynamics)?.read_raw()?);
+                } else if group.link_exists(name) {
+                    values.insert(name.into(), group.dataset(name)?.read_raw()?);
+                }
+            }
+            groups.insert(group_id, values);
+        }
+        Ok(Rows {
+            type_ids,
+            group_ids,
+            group_indices,
+            groups,
+        })
+    }
+
+    /// Per-row value of `name`, falling back to the column of the row's type.
+    fn value(
+        &self,
+        row: usize,
+        name: &str,
+        types: &HashMap<u64, HashMap<String, String>>,
+    ) -> Result<Option<f64>> {
+        let group = self.groups.get(&self.group_ids[row]);
+        if let Some(values) = group.and_then(|values| values.get(name)) {
+            let value = values
+                .get(self.group_indices[row] as usize)
+                .ok_or_else(|| format!("group index of row {row} out of range for `{name}`"))?;
+            return Ok(Some(*value));
+        }
+        match self.column(row, name, types)? {
+            Some(value) => Ok(Some(value.parse()?)),
+            None => Ok(None),
+        }
+    }
+
+    fn column<'a>(
+        &self,
+        row: usize,
+        name: &str,
+        types: &'a HashMap<u64, HashMap<String, String>>,
+    ) -> Result<Option<&'a str>> {
+        let type_id = self.type_ids[row];
+        let columns = types
+            .get(&type_id)
+            .ok_or_else(|| format!("type {type_id} missing from the types table"))?;
+        Ok(columns
+            .get(name)
+            .map(String::as_str)
+            .filter(|value| *value != "NULL"))
+    }
+}
+
+fn read_nodes(
+    path: &Path,
+    types: &HashMap<u64, HashMap<String, String>>,
+    network: &mut InstantiatedNetwork,
+    positions: &mut Vec<Option<[f64; 2]>>,
+    index: &mut NodeIndex,
+) -> Result<()> {
+    let file = hdf5::File::open(path)?;
+    let nodes = file.group("nodes")?;
+    for name in nodes.member_names()? {
+        let population = nodes.group(&name)?;
+        let rows = Rows::read(&population, "node", &NODE_ATTRIBUTES)?;
+        let node_ids: Vec<u64> = if population.link_exists("node_id") {
+            population.dataset("node_id")?.read_raw()?
+        } else {
+            (0..rows.type_ids.len() as u64).collect()
+        };
+
+        let start = network.neurons.len();
+        for (row, &node_id) in node_ids.iter().enumerate() {
+            if rows.column(row, "model_type", types)? == Some("virtual") {
+                continue;
+            }
+            let value = |key: &str| rows.value(row, key, types);
+            let parameter = |key: &str| -> Result<f64> {
+                value(key)?.ok_or_else(|| {
+                    format!("node {node_id} of `{name}` has no Izhikevich parameter `{key}`").into()
+                })
+            };
+            let synapse_type = match rows.column(row, "ei", types)? {
+                Some("i") => SynapseType::Inhibitory,
+                _ => SynapseType::Excitatory,
+            };
+            let mut neuron = Neuron::new(
+                NeuronParameters {
+                    a: parameter("a")?,
+                    b: parameter("b")?,
+                    c: parameter("c")?,
+                    d: parameter("d")?,
+                },
+                synapse_type,
+            );
+            if let Some(v) = value("V_m")? {
+                neuron.v = v;
+                neuron.u = neuron.parameters().b * v;
+            }
+            if let Some(u) = value("U_m")? {
+                neuron.u = u;
+            }
+            index
+                .nodes
+                .insert((name.clone(), node_id), network.neurons.len());
+            network.neurons.push(neuron);
+            positions.push(value("x")?.zip(value("y")?).map(|(x, y)| [x, y]));
+        }
+        let end = network.neurons.len();
+        if end > start {
+            network.populations.push((name.clone(), start..end));
+        }
+        index.populations.push(name);
+    }
+    Ok(())
+}
+
+fn read_edges(
+    path: &Path,
+    types: &HashMap<u64, HashMap<String, String>>,
+    synapse_types: &[SynapseType],
+    index: &NodeIndex,
+) -> Result<Vec<Synapse>> {
+    let mut synapses = vec![];
+    let file = hdf5::File::open(path)?;
+    let edges = file.group("edges")?;
+    for name in edges.member_names()? {
+        let population = edges.group(&name)?;
+        let rows = Rows::read(&population, "edge", &EDGE_ATTRIBUTES)?;
+        let source_ids = population.dataset("source_node_id")?;
+        let target_ids = population.dataset("target_node_id")?;
+        let source_population = node_population(&source_ids, index)?;
+        let target_population = node_population(&target_ids, index)?;
+        let source_ids: Vec<u64> = source_ids.read_raw()?;
+        let target_ids: Vec<u64> = target_ids.read_raw()?;
+
+        for row in 0..rows.type_ids.len() {
+            let pre = index
+                .nodes
+                .get(&(source_population.clone(), source_ids[row]));
+            let post = index
+                .nodes
+                .get(&(target_population.clone(), target_ids[row]));
+            // edges from external inputs
+            let (Some(&pre), Some(&post)) = (pre, post) else {
+                continue;
+            };
+            let mut weight = rows.value(row, "syn_weight", types)?.unwrap_or(1.)
+                * rows.value(row, "nsyns", types)?.unwrap_or(1.);
+            if synapse_types[pre] == SynapseType::Inhibitory {
+                weight = -weight.abs();
+            }
+            let delay = rows.value(row, "delay", types)?.unwrap_or(1.);
+            synapses.push((pre, post, weight, delay));
+        }
+    }
+    Ok(synapses)
+}
+
+/// The node population the source or target ids of an edge population refer to, which may be
+/// omitted when there is a single one.
+fn node_population(ids: &hdf5::Dataset, index: &NodeIndex) -> Result<String> {
+    if let Ok(attr) = ids.attr("node_population") {
+        if let Ok(name) = attr.read_scalar::<VarLenUnicode>() {
+            return Ok(name.as_str().to_owned());
+        }
+        return Ok(attr.read_scalar::<VarLenAscii>()?.as_str().to_owned());
+    }
+    match index.populations.as_slice() {
+        [name] => Ok(name.clone()),
+        _ => Err(format!("`{}` does not name its node population", ids.name()).into()),
+    }
+}
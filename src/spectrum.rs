@@ -0,0 +1,197 @@
+use std::f64::consts::PI;
+use std::fmt;
+use std::io::{self, Write};
+use std::ops::Range;
+
+use rustfft::num_complex::Complex;
+use rustfft::FftPlanner;
+
+/// Frequency bands of cortical rhythms.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Band {
+    Delta,
+    Theta,
+    Alpha,
+    Beta,
+    Gamma,
+}
+
+impl Band {
+    pub const ALL: [Band; 5] = [
+        Band::Delta,
+        Band::Theta,
+        Band::Alpha,
+        Band::Beta,
+        Band::Gamma,
+    ];
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            Band::Delta => "delta",
+            Band::Theta => "theta",
+            Band::Alpha => "alpha",
+            Band::Beta => "beta",
+            Band::Gamma => "gamma",
+        }
+    }
+
+    /// Frequencies of the band in Hz.
+    pub fn range(&self) -> Range<f64> {
+        match self {
+            Band::Delta => 1.0..4.0,
+            Band::Theta => 4.0..8.0,
+            Band::Alpha => 8.0..13.0,
+            Band::Beta => 13.0..30.0,
+            Band::Gamma => 30.0..100.0,
+        }
+    }
+}
+
+impl fmt::Display for Band {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.name())
+    }
+}
+
+/// One-sided power spectral density of a signal, see [`PowerSpectrum::welch`].
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct PowerSpectrum {
+    /// Frequencies in Hz, from 0 up to the Nyquist frequency.
+    pub frequencies: Vec<f64>,
+    /// Power per Hz at each frequency, in squared units of the signal.
+    pub power: Vec<f64>,
+}
+
+impl PowerSpectrum {
+    /// Welch estimate of the spectrum of `signal` sampled every `dt` milliseconds: the average
+    /// periodogram of Hann-windowed segments of `segment` milliseconds overlapping by half, each
+    /// without its mean. Segments longer than the signal are shortened to it, and the spectrum
+    /// is empty for less than two samples.
+    pub fn welch(signal: &[f64], dt: f64, segment: f64) -> Self {
+        let len = ((segment / dt).round() as usize).min(signal.len());
+        if len < 2 {
+            return PowerSpectrum::default();
+        }
+        let hop = len / 2;
+        let sampling_rate = 1000. / dt;
+        let window: Vec<f64> = (0..len)
+            .map(|k| 0.5 * (1. - (2. * PI * k as f64 / len as f64).cos()))
+            .collect();
+        let window_power: f64 = window.iter().map(|w| w * w).sum();
+        let fft = FftPlanner::new().plan_fft_forward(len);
+
+        let bins = len / 2 + 1;
+        let mut power = vec![0.; bins];
+        let mut segments = 0;
+        let mut buffer = vec![Complex::default(); len];
+        for start in (0..=signal.len() - len).step_by(hop) {
+            let samples = &signal[start..start + len];
+            let mean = samples.iter().sum::<f64>() / len as f64;
+            for ((value, sample), w) in buffer.iter_mut().zip(samples).zip(&window) {
+                *value = Complex::new((sample - mean) * w, 0.);
+            }
+            fft.process(&mut buffer);
+            for (k, (power, value)) in power.iter_mut().zip(&buffer).enumerate() {
+                // every frequency but 0 and Nyquist also appears negated
+                let sides = if k == 0 || 2 * k == len { 1. } else { 2. };
+                *power += sides * value.norm_sqr() / (sampling_rate * window_power);
+            }
+            segments += 1;
+        }
+        for power in &mut power {
+            *power /= segments as f64;
+        }
+
+        PowerSpectrum {
+            frequencies: (0..bins)
+                .map(|k| k as f64 * sampling_rate / len as f64)
+                .collect(),
+            power,
+        }
+    }
+
+    /// Spacing of the frequencies in Hz.
+    pub fn resolution(&self) -> f64 {
+        self.frequencies.get(1).copied().unwrap_or(0.)
+    }
+
+    /// Power of the frequencies within `range` in Hz, in squared units of the signal.
+    pub fn band_power(&self, range: Range<f64>) -> f64 {
+        self.in_range(range).map(|(_, power)| power).sum::<f64>() * self.resolution()
+    }
+
+    /// Power of all frequencies above 0.
+    pub fn total_power(&self) -> f64 {
+        self.power.iter().skip(1).sum::<f64>() * self.resolution()
+    }
+
+    /// Frequency of the largest power within `range` in Hz, if it holds any frequency.
+    pub fn peak(&self, range: Range<f64>) -> Option<f64> {
+        self.in_range(range)
+            .max_by(|a, b| a.1.total_cmp(&b.1))
+            .map(|(frequency, _)| frequency)
+    }
+
+    fn in_range(&self, range: Range<f64>) -> impl Iterator<Item = (f64, f64)> + '_ {
+        self.frequencies
+            .iter()
+            .copied()
+            .zip(self.power.iter().copied())
+            .filter(move |(frequency, _)| range.contains(frequency))
+    }
+
+    /// Writes one `frequency,power` CSV row per frequency.
+    pub fn write_csv<W: Write>(&self, mut writer: W) -> io::Result<()> {
+        writeln!(writer, "frequency,power")?;
+        for (frequency, power) in self.frequencies.iter().zip(&self.power) {
+            writeln!(writer, "{frequency},{power}")?;
+        }
+        writer.flush()
+    }
+}
+
+/// Table of the power in every [`Band`] with its share of the total power and its peak
+/// frequency, "-" for bands beyond the Nyquist frequency.
+impl fmt::Display for PowerSpectrum {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(
+            f,
+            "{:<8} {:>12} {:>12} {:>8} {:>10}",
+            "band", "range (Hz)", "power", "share", "peak (Hz)"
+        )?;
+        let total = self.total_power();
+        for band in Band::ALL {
+            let range = band.range();
+            let label = format!("{}-{}", range.start, range.end);
+            match self.peak(range.clone()) {
+                Some(peak) => {
+                    let power = self.band_power(range);
+                    let share = if total > 0. {
+                        format!("{:.1}%", 100. * power / total)
+                    } else {
+                        "-".to_string()
+                    };
+                    writeln!(
+                        f,
+                        "{:<8} {:>12} {:>12.4} {:>8} {:>10.1}",
+                        band.name(),
+                        label,
+                        power,
+                        share,
+                        peak
+                    )?;
+                }
+                None => writeln!(
+                    f,
+                    "{:<8} {:>12} {:>12} {:>8} {:>10}",
+                    band.name(),
+                    label,
+                    "-",
+                    "-",
+                    "-"
+                )?,
+            }
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,147 @@
+use std::collections::VecDeque;
+
+use serde::{Deserialize, Serialize};
+
+/// Spikes of a [`crate::WorldState`] by time step, as the indices of the neurons that spiked, for
+/// the whole run or only a window of the most recent steps so that long runs take bounded
+/// memory. Step 0 is the initial state without spikes, and step `k` holds the spikes emitted
+/// during the `k`th step. A precise record also keeps the offset of every spike within its step,
+/// for spike times finer than the step.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct SpikeRecord {
+    /// Number of steps kept, or `None` for all of them.
+    window: Option<usize>,
+    /// Step of the first kept entry.
+    first_step: usize,
+    steps: VecDeque<Vec<usize>>,
+    /// Offsets of the spikes of every kept step as fractions of the step, if precise.
+    offsets: Option<VecDeque<Vec<f64>>>,
+    /// Spikes of the most recent step by neuron.
+    last: Vec<bool>,
+}
+
+impl SpikeRecord {
+    /// Empty record of `neurons` keeping the last `window` steps, or all of them for `None`.
+    pub fn new(neurons: usize, window: Option<usize>) -> Self {
+        SpikeRecord {
+            window: window.map(|window| window.max(1)),
+            first_step: 0,
+            steps: VecDeque::from([vec![]]),
+            offsets: None,
+            last: vec![false; neurons],
+        }
+    }
+
+    /// Number of steps kept, or `None` for all of them.
+    pub fn window(&self) -> Option<usize> {
+        self.window
+    }
+
+    /// Keeps the last `window` steps from now on, or all of them for `None`, dropping older
+    /// ones right away.
+    pub fn set_window(&mut self, window: Option<usize>) {
+        self.window = window.map(|window| window.max(1));
+        if let Some(window) = self.window {
+            while self.steps.len() > window {
+                self.pop_front();
+            }
+        }
+    }
+
+    /// Whether the offsets of the spikes within their steps are kept.
+    pub fn is_precise(&self) -> bool {
+        self.offsets.is_some()
+    }
+
+    /// Keeps the offsets of the spikes within their steps from now on, or stops, with the kept
+    /// spikes at the end of their steps.
+    pub fn set_precise(&mut self, precise: bool) {
+        match (precise, &self.offsets) {
+            (true, None) => {
+                let offsets = self.steps.iter().map(|step| vec![1.; step.len()]);
+                self.offsets = Some(offsets.collect());
+            }
+            (false, Some(_)) => self.offsets = None,
+            _ => {}
+        }
+    }
+
+    fn pop_front(&mut self) {
+        self.steps.pop_front();
+        if let Some(offsets) = &mut self.offsets {
+            offsets.pop_front();
+        }
+        self.first_step += 1;
+    }
+
+    /// Earliest step still kept.
+    pub fn first_step(&self) -> usize {
+        self.first_step
+    }
+
+    /// Most recent step.
+    pub fn last_step(&self) -> usize {
+        self.first_step + self.steps.len() - 1
+    }
+
+    /// Spikes of the most recent step, indexed by neuron.
+    pub fn last(&self) -> &[bool] {
+        &self.last
+    }
+
+    /// Appends the spikes of the next step by neuron, with the `offsets` of every neuron's last
+    /// spike within its step kept if precise, dropping the oldest step beyond the window.
+    pub fn push(&mut self, spikes: Vec<bool>, offsets: &[f64]) {
+        let neurons: Vec<usize> = spikes
+            .iter()
+            .enumerate()
+            .filter(|&(_, &spiked)| spiked)
+            .map(|(neuron_idx, _)| neuron_idx)
+            .collect();
+        if let Some(kept) = &mut self.offsets {
+            kept.push_back(neurons.iter().map(|&idx| offsets[idx]).collect());
+        }
+        self.steps.push_back(neurons);
+        self.last = spikes;
+        if self.window.is_some_and(|window| self.steps.len() > window) {
+            self.pop_front();
+        }
+    }
+
+    /// Neurons that spiked in `time_step`, if it is kept.
+    pub fn step(&self, time_step: usize) -> Option<&[usize]> {
+        let idx = time_step.checked_sub(self.first_step)?;
+        self.steps.get(idx).map(Vec::as_slice)
+    }
+
+    /// Kept spikes from `time_step` on as `(time_step, neuron_idx)` pairs.
+    pub fn since(&self, time_step: usize) -> impl Iterator<Item = (usize, usize)> + '_ {
+        let skip = time_step.saturating_sub(self.first_step);
+        self.steps
+            .iter()
+            .enumerate()
+            .skip(skip)
+            .flat_map(move |(idx, neurons)| {
+                let time_step = self.first_step + idx;
+                neurons
+                    .iter()
+                    .map(move |&neuron_idx| (time_step, neuron_idx))
+            })
+    }
+
+    /// Kept spikes as `(time, neuron_idx)` pairs with the time in milliseconds for steps of `dt`,
+    /// at the end of its step or, if precise, where the neuron crossed the spike cutoff.
+    pub fn times(&self, dt: f64) -> impl Iterator<Item = (f64, usize)> + '_ {
+        self.steps
+            .iter()
+            .enumerate()
+            .flat_map(move |(idx, neurons)| {
+                let offsets = self.offsets.as_ref().map(|offsets| &offsets[idx]);
+                let start = (self.first_step + idx) as f64 - 1.;
+                neurons.iter().enumerate().map(move |(k, &neuron_idx)| {
+                    let offset = offsets.map_or(1., |offsets| offsets[k]);
+                    ((start + offset) * dt, neuron_idx)
+                })
+            })
+    }
+}
@@ -0,0 +1,310 @@
+use std::ops::Range;
+
+use rand::{Rng, SeedableRng};
+use rand_chacha::ChaCha12Rng;
+use rand_distr::{Distribution, Normal};
+use rayon::prelude::*;
+use serde::{Deserialize, Serialize};
+
+use crate::network::DistanceKernel;
+use crate::simulator::SparseConnectivity;
+use crate::synapse::SpikeInput;
+
+/// Current injected by an external stimulus, in the same units as the thalamic input.
+pub trait Stimulus: Send + Sync {
+    /// Current at `t` milliseconds after the stimulus window opened.
+    fn current(&self, t: f64) -> f64;
+
+    /// Event onsets in milliseconds after the window opened, for a window of `duration`.
+    fn onsets(&self, _duration: f64) -> Vec<f64> {
+        vec![0.]
+    }
+}
+
+/// Constant current for the whole window, e.g. a current step or a DC drive of a population.
+#[derive(Clone, Debug, PartialEq)]
+pub struct StepCurrent {
+    pub amplitude: f64,
+}
+
+impl Stimulus for StepCurrent {
+    fn current(&self, _t: f64) -> f64 {
+        self.amplitude
+    }
+}
+
+/// Current changing linearly from `from` to `to` over `duration` milliseconds, then held.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Ramp {
+    pub from: f64,
+    pub to: f64,
+    pub duration: f64,
+}
+
+impl Stimulus for Ramp {
+    fn current(&self, t: f64) -> f64 {
+        let progress = (t / self.duration).clamp(0., 1.);
+        self.from + (self.to - self.from) * progress
+    }
+}
+
+/// Rectangular pulses of `width` milliseconds repeating every `period` milliseconds.
+#[derive(Clone, Debug, PartialEq)]
+pub struct PulseTrain {
+    pub amplitude: f64,
+    pub period: f64,
+    pub width: f64,
+}
+
+impl Stimulus for PulseTrain {
+    fn current(&self, t: f64) -> f64 {
+        if t % self.period < self.width {
+            self.amplitude
+        } else {
+            0.
+        }
+    }
+
+    fn onsets(&self, duration: f64) -> Vec<f64> {
+        (0..)
+            .map(|k| k as f64 * self.period)
+            .take_while(|&onset| onset < duration)
+            .collect()
+    }
+}
+
+/// Stimulating electrode at a point of the sheet the neurons are placed on, injecting current
+/// into the neurons within `radius` of it, scaled down with their distance by `falloff` or
+/// equally without one. Distances are Euclidean, without wrapping around periodic sheets.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Electrode {
+    pub position: [f64; 2],
+    pub radius: f64,
+    pub falloff: Option<DistanceKernel>,
+}
+
+impl Electrode {
+    /// Share of the current reaching each neuron at `positions`.
+    pub fn gains(&self, positions: &[[f64; 2]]) -> Vec<f64> {
+        positions
+            .iter()
+            .map(|&[x, y]| {
+                let distance = (x - self.position[0]).hypot(y - self.position[1]);
+                match &self.falloff {
+                    _ if distance > self.radius => 0.,
+                    Some(kernel) => kernel.factor(distance),
+                    None => 1.,
+                }
+            })
+            .collect()
+    }
+}
+
+/// A stimulus applied to a range of neurons during a time window in milliseconds.
+pub struct AttachedStimulus {
+    pub stimulus: Box<dyn Stimulus>,
+    pub neurons: Range<usize>,
+    pub window: Range<f64>,
+    /// Share of the current reaching each of `neurons`, all of it if `None`.
+    pub gains: Option<Vec<f64>>,
+}
+
+impl AttachedStimulus {
+    pub fn new(
+        stimulus: impl Stimulus + 'static,
+        neurons: Range<usize>,
+        window: Range<f64>,
+    ) -> Self {
+        AttachedStimulus {
+            stimulus: Box::new(stimulus),
+            neurons,
+            window,
+            gains: None,
+        }
+    }
+
+    /// Scales the current into each of the neurons by `gains`.
+    pub fn with_gains(mut self, gains: Vec<f64>) -> Self {
+        assert_eq!(
+            gains.len(),
+            self.neurons.len(),
+            "expected one gain per stimulated neuron"
+        );
+        self.gains = Some(gains);
+        self
+    }
+
+    /// Absolute event onset times in milliseconds.
+    pub fn onsets(&self) -> Vec<f64> {
+        let duration = self.window.end - self.window.start;
+        self.stimulus
+            .onsets(duration)
+            .into_iter()
+            .map(|onset| self.window.start + onset)
+            .collect()
+    }
+
+    /// Adds the stimulus current at time `t` into `currents`, indexed by neuron.
+    pub fn apply(&self, t: f64, currents: &mut [f64]) {
+        if !self.window.contains(&t) {
+            return;
+        }
+        let current = self.stimulus.current(t - self.window.start);
+        match &self.gains {
+            Some(gains) => {
+                for (i, gain) in self.neurons.clone().zip(gains) {
+                    currents[i] += gain * current;
+                }
+            }
+            None => {
+                for i in self.neurons.clone() {
+                    currents[i] += current;
+                }
+            }
+        }
+    }
+}
+
+/// External population of independent Poisson spike sources projecting onto the network.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct PoissonInput {
+    /// Number of sources in the population.
+    pub sources: usize,
+    /// Firing rate of every source in Hz.
+    pub rate: f64,
+    /// Weight of each source synapse, applied like a recurrent excitatory weight.
+    pub weight: f64,
+    /// Probability that a source connects to any given neuron of `targets`.
+    pub connection_probability: f64,
+    pub targets: Range<usize>,
+}
+
+/// A [`PoissonInput`] with its wiring onto the network.
+#[derive(Serialize, Deserialize)]
+pub struct PoissonSource {
+    pub input: PoissonInput,
+    /// One row per source.
+    pub connectivity: SparseConnectivity,
+}
+
+impl PoissonSource {
+    pub fn new<R: Rng + ?Sized>(input: PoissonInput, rng: &mut R) -> Self {
+        let mut connectivity = SparseConnectivity::new();
+        for _ in 0..input.sources {
+            for post in input.targets.clone() {
+                if rng.gen::<f64>() < input.connection_probability {
+                    connectivity.push(post, input.weight, 1);
+                }
+            }
+            connectivity.finish_row();
+        }
+        PoissonSource {
+            input,
+            connectivity,
+        }
+    }
+
+    /// Samples which sources fire during a step of `dt` milliseconds and adds their spikes
+    /// to `arriving`.
+    pub fn deliver<R: Rng + ?Sized>(&self, dt: f64, rng: &mut R, arriving: &mut SpikeInput) {
+        let p = self.input.rate * dt / 1000.;
+        for source in 0..self.input.sources {
+            if rng.gen::<f64>() < p {
+                for (post, weight) in self.connectivity.outgoing(source) {
+                    arriving.add(post, weight);
+                }
+            }
+        }
+    }
+}
+
+/// Temporal structure of the thalamic input. Its fluctuations are scaled by
+/// [`crate::WorldStateBuilder::thalamic_sigma`] under either model.
+#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
+pub enum NoiseModel {
+    /// Independent Gaussian samples every step as in the paper.
+    #[default]
+    White,
+    /// An Ornstein–Uhlenbeck process per neuron, relaxing towards `mean` with the time constant
+    /// `tau` in milliseconds and fluctuating around it with the stationary standard deviation
+    /// `sigma`. Both are relative to the paper's noise at a 1 ms step, so `sigma = 1` matches
+    /// its amplitude while correlating it over about `tau`.
+    OrnsteinUhlenbeck { mean: f64, sigma: f64, tau: f64 },
+}
+
+/// The thalamic input of every neuron, drawn according to its [`NoiseModel`]. Every neuron draws
+/// from a ChaCha stream of its own, so the neurons are sampled in parallel with the same result
+/// for any number of threads.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct NoiseSource {
+    pub model: NoiseModel,
+    /// Deviation of the Ornstein–Uhlenbeck process of every neuron from its mean.
+    deviation: Vec<f64>,
+    /// Stream of every neuron, all with the same key and numbered by neuron.
+    streams: Vec<ChaCha12Rng>,
+}
+
+impl NoiseSource {
+    /// Takes the key of the streams of `n` neurons from `rng` and starts the Ornstein–Uhlenbeck
+    /// processes from their stationary distribution.
+    pub fn new<R: Rng + ?Sized>(
+        model: NoiseModel,
+        n: usize,
+        thalamic_sigma: f64,
+        rng: &mut R,
+    ) -> Self {
+        let key: [u8; 32] = rng.gen();
+        let mut streams: Vec<ChaCha12Rng> = (0..n)
+            .map(|neuron| {
+                let mut stream = ChaCha12Rng::from_seed(key);
+                stream.set_stream(neuron as u64);
+                stream
+            })
+            .collect();
+        let deviation = match model {
+            NoiseModel::White => vec![],
+            NoiseModel::OrnsteinUhlenbeck { sigma, .. } => streams
+                .iter_mut()
+                .map(|stream| sigma * normal(thalamic_sigma).sample(stream))
+                .collect(),
+        };
+        NoiseSource {
+            model,
+            deviation,
+            streams,
+        }
+    }
+
+    /// Thalamic input of every neuron over a step of `dt` milliseconds, in the units of the white
+    /// noise samples taken by [`crate::Neurons::step`].
+    pub fn sample(&mut self, thalamic_sigma: f64, dt: f64) -> Vec<f64> {
+        let normal = normal(thalamic_sigma);
+        match self.model {
+            NoiseModel::White => self
+                .streams
+                .par_iter_mut()
+                .map(|stream| normal.sample(stream))
+                .collect(),
+            NoiseModel::OrnsteinUhlenbeck { mean, sigma, tau } => {
+                // exact update over the step, which stays stable for any dt
+                let decay = (-dt / tau).exp();
+                let diffusion = sigma * (1. - decay * decay).sqrt();
+                // the neurons scale white noise by 1 / sqrt(dt), which a current must undo
+                let sqrt_dt = dt.sqrt();
+                self.deviation.resize(self.streams.len(), 0.);
+                self.deviation
+                    .par_iter_mut()
+                    .zip(&mut self.streams)
+                    .map(|(deviation, stream)| {
+                        *deviation = decay * *deviation + diffusion * normal.sample(stream);
+                        (mean + *deviation) * sqrt_dt
+                    })
+                    .collect()
+            }
+        }
+    }
+}
+
+fn normal(thalamic_sigma: f64) -> Normal<f64> {
+    Normal::new(0., thalamic_sigma).expect("thalamic noise sigma must be non-negative")
+}
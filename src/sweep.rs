@@ -0,0 +1,281 @@
+use std::fmt;
+use std::io::{self, Write};
+use std::str::FromStr;
+
+use rayon::prelude::*;
+
+use crate::analysis::{RateSummary, SpikeTrains};
+use crate::checkpoint::write_checkpoint;
+use crate::neuron::SynapseType;
+use crate::simulator::{WorldState, WorldStateBuilder};
+
+/// A parameter varied by a [`Sweep`].
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum SweepParameter {
+    /// Factor on the weights of all synapses from inhibitory neurons.
+    InhibitoryWeightScale,
+    /// Factor on the weights of all synapses from excitatory neurons.
+    ExcitatoryWeightScale,
+    /// Standard deviation of the thalamic noise relative to the paper's.
+    ThalamicSigma,
+    /// Connection probability of the default network.
+    ConnectionProbability,
+}
+
+impl SweepParameter {
+    pub const ALL: [SweepParameter; 4] = [
+        SweepParameter::InhibitoryWeightScale,
+        SweepParameter::ExcitatoryWeightScale,
+        SweepParameter::ThalamicSigma,
+        SweepParameter::ConnectionProbability,
+    ];
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            SweepParameter::InhibitoryWeightScale => "inhibitory-weight-scale",
+            SweepParameter::ExcitatoryWeightScale => "excitatory-weight-scale",
+            SweepParameter::ThalamicSigma => "thalamic-sigma",
+            SweepParameter::ConnectionProbability => "connection-probability",
+        }
+    }
+
+    /// Sets the parameter on the builder, if it is a construction parameter.
+    fn configure(&self, builder: WorldStateBuilder, value: f64) -> WorldStateBuilder {
+        match self {
+            SweepParameter::ThalamicSigma => builder.thalamic_sigma(value),
+            SweepParameter::ConnectionProbability => builder.connection_probability(value),
+            _ => builder,
+        }
+    }
+
+    /// Sets the parameter on the built world, if it applies to the drawn weights or the noise.
+    pub(crate) fn apply(&self, world: &mut WorldState, value: f64) {
+        let scaled = match self {
+            SweepParameter::InhibitoryWeightScale => SynapseType::Inhibitory,
+            SweepParameter::ExcitatoryWeightScale => SynapseType::Excitatory,
+            SweepParameter::ThalamicSigma => {
+                world.thalamic_sigma = value;
+                return;
+            }
+            SweepParameter::ConnectionProbability => return,
+        };
+        for pre in 0..world.connectivity.neurons() {
+            if world.neurons.synapse_types[pre] == scaled {
+                let row = world.connectivity.row(pre);
+                for weight in &mut world.connectivity.weights_mut()[row] {
+                    *weight *= value;
+                }
+            }
+        }
+    }
+}
+
+impl fmt::Display for SweepParameter {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.name())
+    }
+}
+
+impl FromStr for SweepParameter {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        SweepParameter::ALL
+            .into_iter()
+            .find(|parameter| parameter.name() == s)
+            .ok_or_else(|| {
+                let names: Vec<_> = SweepParameter::ALL.iter().map(|p| p.name()).collect();
+                format!(
+                    "unknown parameter `{s}`, expected one of {}",
+                    names.join(", ")
+                )
+            })
+    }
+}
+
+/// Values taken by one parameter of a [`Sweep`].
+#[derive(Clone, Debug, PartialEq)]
+pub struct SweepAxis {
+    pub parameter: SweepParameter,
+    pub values: Vec<f64>,
+}
+
+impl SweepAxis {
+    /// `count` evenly spaced values from `start` to `stop`, both included.
+    pub fn linspace(parameter: SweepParameter, start: f64, stop: f64, count: usize) -> Self {
+        let step = (stop - start) / count.saturating_sub(1).max(1) as f64;
+        SweepAxis {
+            parameter,
+            values: (0..count).map(|k| start + k as f64 * step).collect(),
+        }
+    }
+}
+
+impl FromStr for SweepAxis {
+    type Err = String;
+
+    /// Parses `name=start:stop:count` or `name=v1,v2,...`.
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let (name, values) = s.split_once('=').ok_or_else(|| {
+            format!("expected `name=start:stop:count` or `name=v1,v2,...`, got `{s}`")
+        })?;
+        let parameter = name.parse()?;
+        let number = |value: &str| {
+            value
+                .trim()
+                .parse::<f64>()
+                .map_err(|err| format!("invalid value `{value}`: {err}"))
+        };
+        match values.split(':').collect::<Vec<_>>().as_slice() {
+            [start, stop, count] => {
+                let count = count
+                    .trim()
+                    .parse()
+                    .map_err(|err| format!("invalid count `{count}`: {err}"))?;
+                Ok(SweepAxis::linspace(
+                    parameter,
+                    number(start)?,
+                    number(stop)?,
+                    count,
+                ))
+            }
+            [_] => Ok(SweepAxis {
+                parameter,
+                values: values.split(',').map(number).collect::<Result<_, _>>()?,
+            }),
+            _ => Err(format!("expected `start:stop:count`, got `{values}`")),
+        }
+    }
+}
+
+/// Runs a network for every point of the grid spanned by the axes, in parallel.
+#[derive(Clone, Debug)]
+pub struct Sweep {
+    pub axes: Vec<SweepAxis>,
+    /// Simulated time of every run in milliseconds.
+    pub duration: f64,
+    /// Bin width of the population rates in milliseconds.
+    pub rate_bin: f64,
+}
+
+/// Outcome of one point of a [`Sweep`].
+#[derive(Clone, Debug, PartialEq)]
+pub struct SweepPoint {
+    /// Value of every axis, in the order of the axes.
+    pub values: Vec<f64>,
+    pub summary: RateSummary,
+    /// Mean coefficient of variation of the inter-spike intervals over neurons with at least two
+    /// intervals.
+    pub mean_isi_cv: Option<f64>,
+}
+
+impl Sweep {
+    /// Every combination of axis values, varying the last axis fastest.
+    pub fn grid(&self) -> Vec<Vec<f64>> {
+        self.axes.iter().fold(vec![vec![]], |points, axis| {
+            points
+                .iter()
+                .flat_map(|point| {
+                    axis.values.iter().map(move |&value| {
+                        let mut point = point.clone();
+                        point.push(value);
+                        point
+                    })
+                })
+                .collect()
+        })
+    }
+
+    /// Builds a world from `builder` for every point of the grid and runs it. With a seed set on
+    /// the builder, all points start from the same random draw.
+    pub fn run(&self, builder: &WorldStateBuilder) -> Vec<SweepPoint> {
+        self.grid()
+            .into_par_iter()
+            .map(|values| {
+                let builder = self
+                    .axes
+                    .iter()
+                    .zip(&values)
+                    .fold(builder.clone(), |builder, (axis, &value)| {
+                        axis.parameter.configure(builder, value)
+                    });
+                self.run_point(values, builder.build())
+            })
+            .collect()
+    }
+
+    /// Runs every point of the grid from a copy of `world`, e.g. one brought to a steady state by
+    /// a burn-in once for all points, measuring only the time after it. Every copy continues
+    /// with the same random state, while stimuli attached to `world` are left out as by
+    /// [`crate::save_checkpoint`].
+    ///
+    /// # Panics
+    ///
+    /// If an axis varies the connection probability, which only applies when building.
+    pub fn run_from(&self, world: &mut WorldState) -> Vec<SweepPoint> {
+        assert!(
+            self.axes
+                .iter()
+                .all(|axis| axis.parameter != SweepParameter::ConnectionProbability),
+            "the connection probability cannot vary from a built network"
+        );
+        let mut state = vec![];
+        write_checkpoint(&mut state, world).expect("writing to memory does not fail");
+        self.grid()
+            .into_par_iter()
+            .map(|values| {
+                let world =
+                    bincode::deserialize(&state).expect("the state was serialized just before");
+                self.run_point(values, world)
+            })
+            .collect()
+    }
+
+    /// Runs `world` with the axis `values` applied from its current time on.
+    fn run_point(&self, values: Vec<f64>, mut world: WorldState) -> SweepPoint {
+        for (axis, &value) in self.axes.iter().zip(&values) {
+            axis.parameter.apply(&mut world, value);
+        }
+        let start = world.time();
+        world.run_for(self.duration);
+
+        let trains = SpikeTrains::from_world(&world).after(start);
+        let cvs: Vec<f64> = trains.isi_cv().into_iter().flatten().collect();
+        SweepPoint {
+            values,
+            summary: RateSummary::new(&trains, &world.populations(), self.rate_bin),
+            mean_isi_cv: (!cvs.is_empty()).then(|| cvs.iter().sum::<f64>() / cvs.len() as f64),
+        }
+    }
+
+    /// Writes one CSV row per point with the axis values followed by the mean, standard deviation
+    /// and peak rate in Hz of every population and the mean ISI CV.
+    pub fn write_csv<W: Write>(&self, mut writer: W, points: &[SweepPoint]) -> io::Result<()> {
+        let mut header: Vec<String> = self
+            .axes
+            .iter()
+            .map(|axis| axis.parameter.to_string())
+            .collect();
+        if let Some(point) = points.first() {
+            for population in &point.summary.populations {
+                for column in ["rate", "rate_std", "peak_rate"] {
+                    header.push(format!("{}_{column}", population.name));
+                }
+            }
+        }
+        header.push("isi_cv".into());
+        writeln!(writer, "{}", header.join(","))?;
+
+        for point in points {
+            let mut row: Vec<String> = point.values.iter().map(f64::to_string).collect();
+            for population in &point.summary.populations {
+                row.push(population.mean_rate.to_string());
+                row.push(population.rate_std.to_string());
+                row.push(population.peak_rate.to_string());
+            }
+            row.push(point.mean_isi_cv.map_or(String::new(), |cv| cv.to_string()));
+            writeln!(writer, "{}", row.join(","))?;
+        }
+        writer.flush()
+    }
+}
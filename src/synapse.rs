@@ -0,0 +1,352 @@
+use serde::{Deserialize, Serialize};
+
+use crate::neuron::SynapseType;
+
+/// Resting potential the conductance increments are normalized against, in mV.
+pub const V_REST: f64 = -65.;
+
+/// How arriving spikes are turned into synaptic current.
+#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
+pub enum SynapseModel {
+    /// Each spike injects its weight as a delta current pulse, as in the original paper.
+    #[default]
+    Current,
+    /// Spikes increment exponentially decaying conductances driving `g * (E_rev - v)`.
+    Conductance(ConductanceParameters),
+    /// Each spike injects a current that rises and decays exponentially, delivering the charge
+    /// of its weight independently of `v`.
+    Exponential(ExponentialParameters),
+}
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct ConductanceParameters {
+    /// Decay time constant of excitatory conductances in milliseconds.
+    pub tau_exc: f64,
+    /// Decay time constant of inhibitory conductances in milliseconds.
+    pub tau_inh: f64,
+    /// Excitatory reversal potential in mV.
+    pub e_exc: f64,
+    /// Inhibitory reversal potential in mV.
+    pub e_inh: f64,
+}
+
+impl Default for ConductanceParameters {
+    /// AMPA and GABA_A like kinetics.
+    fn default() -> Self {
+        ConductanceParameters {
+            tau_exc: 5.,
+            tau_inh: 6.,
+            e_exc: 0.,
+            e_inh: -80.,
+        }
+    }
+}
+
+/// Rise and decay time constants of the synaptic currents of [`SynapseModel::Exponential`] by
+/// receptor class, each current following the difference of two exponentials. A rise time
+/// constant of 0 makes the current jump and then decay.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct ExponentialParameters {
+    /// Rise time constant of excitatory (AMPA) currents in milliseconds.
+    pub tau_rise_exc: f64,
+    /// Decay time constant of excitatory currents in milliseconds, longer than the rise.
+    pub tau_decay_exc: f64,
+    /// Rise time constant of inhibitory (GABA_A) currents in milliseconds.
+    pub tau_rise_inh: f64,
+    /// Decay time constant of inhibitory currents in milliseconds, longer than the rise.
+    pub tau_decay_inh: f64,
+}
+
+impl Default for ExponentialParameters {
+    /// Fast AMPA and slower GABA_A kinetics.
+    fn default() -> Self {
+        ExponentialParameters {
+            tau_rise_exc: 0.5,
+            tau_decay_exc: 5.,
+            tau_rise_inh: 1.,
+            tau_decay_inh: 10.,
+        }
+    }
+}
+
+impl ExponentialParameters {
+    /// Whether every rise is non-negative and shorter than its decay.
+    pub fn is_valid(&self) -> bool {
+        (0. <= self.tau_rise_exc && self.tau_rise_exc < self.tau_decay_exc)
+            && (0. <= self.tau_rise_inh && self.tau_rise_inh < self.tau_decay_inh)
+    }
+}
+
+/// Per step factors of a difference of two exponential traces with unit charge.
+struct DoubleExponential {
+    rise_decay: f64,
+    decay_decay: f64,
+    /// Charge per unit of each trace delivered during a step, over the step.
+    rise_current: f64,
+    decay_current: f64,
+}
+
+impl DoubleExponential {
+    fn new(tau_rise: f64, tau_decay: f64, dt: f64) -> Self {
+        let rise_decay = (-dt / tau_rise).exp();
+        let decay_decay = (-dt / tau_decay).exp();
+        let scale = 1. / (tau_decay - tau_rise) / dt;
+        DoubleExponential {
+            rise_decay,
+            decay_decay,
+            rise_current: scale * tau_rise * (1. - rise_decay),
+            decay_current: scale * tau_decay * (1. - decay_decay),
+        }
+    }
+
+    /// Adds `weight` to both traces and returns the mean current over the step before decaying
+    /// them.
+    fn step(&self, rise: &mut f64, decay: &mut f64, weight: f64) -> f64 {
+        *rise += weight;
+        *decay += weight;
+        let current = *decay * self.decay_current - *rise * self.rise_current;
+        *rise *= self.rise_decay;
+        *decay *= self.decay_decay;
+        current
+    }
+}
+
+/// Slow voltage-dependent NMDA channel driven by excitatory spikes alongside the fast AMPA
+/// component described by [`SynapseModel`].
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct NmdaParameters {
+    /// NMDA to AMPA charge ratio of excitatory synapses onto excitatory neurons, in the
+    /// unblocked state.
+    pub ratio_to_excitatory: f64,
+    /// NMDA to AMPA charge ratio of excitatory synapses onto inhibitory neurons.
+    pub ratio_to_inhibitory: f64,
+    /// Decay time constant in milliseconds.
+    pub tau: f64,
+    /// Reversal potential in mV.
+    pub e_rev: f64,
+    /// Extracellular Mg2+ concentration in mM.
+    pub mg: f64,
+}
+
+impl Default for NmdaParameters {
+    fn default() -> Self {
+        NmdaParameters {
+            ratio_to_excitatory: 1.,
+            ratio_to_inhibitory: 0.5,
+            tau: 100.,
+            e_rev: 0.,
+            mg: 1.,
+        }
+    }
+}
+
+impl NmdaParameters {
+    /// Fraction of channels not blocked by Mg2+ at membrane potential `v` (Jahr & Stevens).
+    pub fn mg_block(&self, v: f64) -> f64 {
+        1. / (1. + self.mg / 3.57 * (-0.062 * v).exp())
+    }
+
+    pub fn ratio(&self, post: &SynapseType) -> f64 {
+        match post {
+            SynapseType::Excitatory => self.ratio_to_excitatory,
+            SynapseType::Inhibitory => self.ratio_to_inhibitory,
+        }
+    }
+}
+
+/// Summed weights of the spikes arriving at each neuron, split by sign.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct SpikeInput {
+    pub excitatory: Vec<f64>,
+    pub inhibitory: Vec<f64>,
+}
+
+impl SpikeInput {
+    pub fn new(n: usize) -> Self {
+        SpikeInput {
+            excitatory: vec![0.; n],
+            inhibitory: vec![0.; n],
+        }
+    }
+
+    pub fn add(&mut self, post: usize, weight: f64) {
+        if weight >= 0. {
+            self.excitatory[post] += weight;
+        } else {
+            self.inhibitory[post] += weight;
+        }
+    }
+}
+
+/// Synaptic conductances of every neuron; `g_exc` and `g_inh` are unused in
+/// [`SynapseModel::Current`] mode and `g_nmda` without NMDA. In [`SynapseModel::Exponential`]
+/// mode `g_exc` and `g_inh` hold the decaying traces of the currents and `rise_exc` and
+/// `rise_inh` their rising ones, which are unused otherwise.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct SynapticState {
+    pub g_exc: Vec<f64>,
+    pub g_inh: Vec<f64>,
+    pub g_nmda: Vec<f64>,
+    pub rise_exc: Vec<f64>,
+    pub rise_inh: Vec<f64>,
+    /// NMDA parameters and the charge ratio of each neuron's NMDA input, if enabled.
+    nmda: Option<(NmdaParameters, Vec<f64>)>,
+    /// Models of the synapses that don't follow the one of the world, each with the
+    /// conductances of its own, see [`crate::Projection::synapse_model`].
+    projections: Vec<(SynapseModel, SynapticState)>,
+}
+
+impl SynapticState {
+    pub fn new(n: usize) -> Self {
+        SynapticState {
+            g_exc: vec![0.; n],
+            g_inh: vec![0.; n],
+            g_nmda: vec![0.; n],
+            rise_exc: vec![0.; n],
+            rise_inh: vec![0.; n],
+            nmda: None,
+            projections: vec![],
+        }
+    }
+
+    /// Enables the NMDA channel, with ratios chosen by the type of each postsynaptic neuron.
+    pub fn with_nmda(mut self, params: NmdaParameters, synapse_types: &[SynapseType]) -> Self {
+        let ratios = synapse_types
+            .iter()
+            .map(|post| params.ratio(post))
+            .collect();
+        self.nmda = Some((params, ratios));
+        self
+    }
+
+    pub fn nmda(&self) -> Option<&NmdaParameters> {
+        self.nmda.as_ref().map(|(params, _)| params)
+    }
+
+    /// Adds synapses following each of `models` instead of the model of the world, whose input
+    /// comes after that of the others in [`SynapticState::currents`].
+    pub fn with_projection_models(mut self, models: Vec<SynapseModel>) -> Self {
+        let n = self.g_exc.len();
+        self.projections = models
+            .into_iter()
+            .map(|model| (model, SynapticState::new(n)))
+            .collect();
+        self
+    }
+
+    /// Models of the synapses that don't follow the one of the world.
+    pub fn projection_models(&self) -> impl Iterator<Item = &SynapseModel> + '_ {
+        self.projections.iter().map(|(model, _)| model)
+    }
+
+    /// Synaptic current into each neuron over the next `dt` milliseconds given membrane
+    /// potentials `v`, from the `inputs` of the synapses following `model` first and then those
+    /// following each of the projection models in order. A conductance increment is scaled so
+    /// that a spike arriving at rest transfers the same charge as the corresponding delta pulse
+    /// in current mode.
+    pub fn currents(
+        &mut self,
+        model: &SynapseModel,
+        inputs: &[SpikeInput],
+        v: &[f64],
+        dt: f64,
+    ) -> Vec<f64> {
+        let (excitatory, inhibitory) = self.currents_by_sign(model, inputs, v, dt);
+        excitatory
+            .iter()
+            .zip(&inhibitory)
+            .map(|(exc, inh)| exc + inh)
+            .collect()
+    }
+
+    /// [`SynapticState::currents`] split into the excitatory current, including NMDA, and the
+    /// inhibitory current.
+    pub fn currents_by_sign(
+        &mut self,
+        model: &SynapseModel,
+        inputs: &[SpikeInput],
+        v: &[f64],
+        dt: f64,
+    ) -> (Vec<f64>, Vec<f64>) {
+        let (mut excitatory, mut inhibitory) = self.fast_currents(model, &inputs[0], v, dt);
+        for ((model, state), input) in self.projections.iter_mut().zip(&inputs[1..]) {
+            let (exc, inh) = state.fast_currents(model, input, v, dt);
+            for (total, current) in excitatory.iter_mut().zip(exc) {
+                *total += current;
+            }
+            for (total, current) in inhibitory.iter_mut().zip(inh) {
+                *total += current;
+            }
+        }
+
+        // every excitatory synapse has an NMDA component, whatever its fast one follows
+        if let Some((params, ratios)) = &self.nmda {
+            let scale = 1. / (params.tau * (params.e_rev - V_REST).abs());
+            let decay = (-dt / params.tau).exp();
+            for (i, current) in excitatory.iter_mut().enumerate() {
+                let drive: f64 = inputs.iter().map(|input| input.excitatory[i]).sum();
+                self.g_nmda[i] += ratios[i] * drive * scale;
+                *current += self.g_nmda[i] * params.mg_block(v[i]) * (params.e_rev - v[i]);
+                self.g_nmda[i] *= decay;
+            }
+        }
+
+        (excitatory, inhibitory)
+    }
+
+    /// AMPA and GABA_A currents according to `model`.
+    fn fast_currents(
+        &mut self,
+        model: &SynapseModel,
+        input: &SpikeInput,
+        v: &[f64],
+        dt: f64,
+    ) -> (Vec<f64>, Vec<f64>) {
+        match model {
+            SynapseModel::Current => (
+                input.excitatory.iter().map(|exc| exc / dt).collect(),
+                input.inhibitory.iter().map(|inh| inh / dt).collect(),
+            ),
+            SynapseModel::Conductance(params) => {
+                let exc_scale = 1. / (params.tau_exc * (params.e_exc - V_REST).abs());
+                let inh_scale = 1. / (params.tau_inh * (params.e_inh - V_REST).abs());
+                let exc_decay = (-dt / params.tau_exc).exp();
+                let inh_decay = (-dt / params.tau_inh).exp();
+
+                (0..v.len())
+                    .map(|i| {
+                        self.g_exc[i] += input.excitatory[i] * exc_scale;
+                        self.g_inh[i] -= input.inhibitory[i] * inh_scale;
+                        let currents = (
+                            self.g_exc[i] * (params.e_exc - v[i]),
+                            self.g_inh[i] * (params.e_inh - v[i]),
+                        );
+                        self.g_exc[i] *= exc_decay;
+                        self.g_inh[i] *= inh_decay;
+                        currents
+                    })
+                    .unzip()
+            }
+            SynapseModel::Exponential(params) => {
+                let exc = DoubleExponential::new(params.tau_rise_exc, params.tau_decay_exc, dt);
+                let inh = DoubleExponential::new(params.tau_rise_inh, params.tau_decay_inh, dt);
+                (0..v.len())
+                    .map(|i| {
+                        (
+                            exc.step(
+                                &mut self.rise_exc[i],
+                                &mut self.g_exc[i],
+                                input.excitatory[i],
+                            ),
+                            inh.step(
+                                &mut self.rise_inh[i],
+                                &mut self.g_inh[i],
+                                input.inhibitory[i],
+                            ),
+                        )
+                    })
+                    .unzip()
+            }
+        }
+    }
+}
@@ -0,0 +1,251 @@
+use std::io::{self, Write};
+use std::ops::Range;
+
+use rand::{Rng, SeedableRng};
+use rand_chacha::ChaCha12Rng;
+
+use crate::simulator::WorldState;
+use crate::stimulus::StepCurrent;
+
+/// Trial structure of a reward learning task run with [`RewardLearning`]: the cue presented in
+/// every trial and the reward earned by the action the network takes.
+pub trait Task: Send {
+    /// Number of distinct cues.
+    fn cues(&self) -> usize;
+
+    /// Number of distinct actions.
+    fn actions(&self) -> usize;
+
+    /// Cue presented in the `trial`th trial.
+    fn cue(&mut self, trial: usize) -> usize;
+
+    /// Dopamine released after `action` in response to `cue`, where `None` means that the network
+    /// did not commit to an action.
+    fn reward(&self, cue: usize, action: Option<usize>) -> f64;
+}
+
+/// Two-alternative forced choice: one of two cues is drawn at random in every trial, and choosing
+/// the action of the same index is rewarded. The network of
+/// [`crate::WorldStateBuilder::two_alternative_choice`] learns it from chance to most trials
+/// correct within a few hundred trials.
+#[derive(Clone, Debug)]
+pub struct TwoAlternativeChoice {
+    /// Dopamine released after a correct choice.
+    pub reward: f64,
+    /// Dopamine removed after a wrong choice.
+    pub punishment: f64,
+    rng: ChaCha12Rng,
+}
+
+impl TwoAlternativeChoice {
+    pub fn new(seed: u64) -> Self {
+        TwoAlternativeChoice {
+            reward: 1.,
+            punishment: 1.,
+            rng: ChaCha12Rng::seed_from_u64(seed),
+        }
+    }
+}
+
+impl Task for TwoAlternativeChoice {
+    fn cues(&self) -> usize {
+        2
+    }
+
+    fn actions(&self) -> usize {
+        2
+    }
+
+    fn cue(&mut self, _trial: usize) -> usize {
+        self.rng.gen_range(0..2)
+    }
+
+    fn reward(&self, cue: usize, action: Option<usize>) -> f64 {
+        match action {
+            Some(action) if action == cue => self.reward,
+            Some(_) => -self.punishment,
+            None => 0.,
+        }
+    }
+}
+
+/// Trial loop of a reward learning task: every trial drives the neurons of its cue with a current
+/// step, decodes the action as the group of output neurons that spiked most during the decision
+/// window, and releases the dopamine of the [`Task`]'s reward, which the dopamine-modulated
+/// STDP of the network turns into weight changes.
+#[derive(Clone, Debug, PartialEq)]
+pub struct RewardLearning {
+    /// Neurons driven by each cue.
+    pub cue_neurons: Vec<Range<usize>>,
+    /// Neurons whose spikes count for each action.
+    pub action_neurons: Vec<Range<usize>>,
+    /// Current into the cue neurons.
+    pub cue_amplitude: f64,
+    /// Duration of the cue in milliseconds.
+    pub cue_duration: f64,
+    /// Milliseconds from the cue onset during which the spikes of the action neurons count.
+    pub decision_window: f64,
+    /// Milliseconds from the end of the decision window until the reward is released.
+    pub reward_delay: f64,
+    /// Milliseconds from the end of the decision window until the next trial.
+    pub inter_trial_interval: f64,
+}
+
+/// Outcome of one trial of a [`RewardLearning`] task.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Trial {
+    /// Time of the cue onset in milliseconds.
+    pub time: f64,
+    pub cue: usize,
+    /// Spikes of the neurons of every action during the decision window.
+    pub votes: Vec<usize>,
+    /// Action with the most votes, `None` without spikes or on a tie.
+    pub action: Option<usize>,
+    pub reward: f64,
+}
+
+impl RewardLearning {
+    pub fn new(cue_neurons: Vec<Range<usize>>, action_neurons: Vec<Range<usize>>) -> Self {
+        RewardLearning {
+            cue_neurons,
+            action_neurons,
+            cue_amplitude: 20.,
+            cue_duration: 10.,
+            decision_window: 30.,
+            reward_delay: 20.,
+            inter_trial_interval: 500.,
+        }
+    }
+
+    /// Drives the populations named `cues` and reads out those named `actions`, e.g. `cue_0`,
+    /// `cue_1` and `action_0`, `action_1` of
+    /// [`crate::NetworkBuilder::two_alternative_choice`].
+    ///
+    /// # Panics
+    ///
+    /// If `world` has no population of one of the names.
+    pub fn for_populations(world: &WorldState, cues: &[&str], actions: &[&str]) -> Self {
+        let populations = world.populations();
+        let neurons = |names: &[&str]| {
+            names
+                .iter()
+                .map(|&name| {
+                    populations
+                        .iter()
+                        .find(|(population, _)| population == name)
+                        .map(|(_, range)| range.clone())
+                        .unwrap_or_else(|| panic!("no population `{name}`"))
+                })
+                .collect()
+        };
+        RewardLearning::new(neurons(cues), neurons(actions))
+    }
+
+    /// Runs `trials` trials of `task` on `world`, numbered from `first_trial`.
+    ///
+    /// # Panics
+    ///
+    /// If `world` has no dopamine-modulated STDP, or the cues or actions of `task` do not match
+    /// the neuron groups.
+    pub fn run(
+        &self,
+        world: &mut WorldState,
+        task: &mut dyn Task,
+        first_trial: usize,
+        trials: usize,
+    ) -> Vec<Trial> {
+        (first_trial..first_trial + trials)
+            .map(|trial| self.run_trial(world, task, trial))
+            .collect()
+    }
+
+    /// Runs the `trial`th trial of `task` on `world`, until the onset of the next one.
+    ///
+    /// # Panics
+    ///
+    /// See [`RewardLearning::run`].
+    pub fn run_trial(&self, world: &mut WorldState, task: &mut dyn Task, trial: usize) -> Trial {
+        assert!(
+            world.dopamine_stdp.is_some(),
+            "reward learning needs dopamine-modulated STDP"
+        );
+        assert_eq!(
+            task.cues(),
+            self.cue_neurons.len(),
+            "one neuron group per cue"
+        );
+        assert_eq!(
+            task.actions(),
+            self.action_neurons.len(),
+            "one neuron group per action"
+        );
+
+        let cue = task.cue(trial);
+        let time = world.time();
+        let stimulus = world.stimuli.len();
+        world.add_stimulus(
+            StepCurrent {
+                amplitude: self.cue_amplitude,
+            },
+            self.cue_neurons[cue].clone(),
+            time..time + self.cue_duration,
+        );
+        let mut votes = vec![0; self.action_neurons.len()];
+        for _ in 0..(self.decision_window / world.dt).round() as usize {
+            world.step();
+            let spikes = world.last_spikes();
+            for (votes, neurons) in votes.iter_mut().zip(&self.action_neurons) {
+                *votes += spikes[neurons.clone()]
+                    .iter()
+                    .filter(|&&spiked| spiked)
+                    .count();
+            }
+        }
+        world.stimuli.remove(stimulus);
+
+        let most = votes.iter().copied().max().unwrap_or(0);
+        let mut winners = (0..votes.len()).filter(|&action| votes[action] == most);
+        let action = match (winners.next(), winners.next()) {
+            (Some(action), None) if most > 0 => Some(action),
+            _ => None,
+        };
+        let reward = task.reward(cue, action);
+        let release = world.time() + self.reward_delay;
+        if let Some(stdp) = &mut world.dopamine_stdp {
+            stdp.schedule(release, reward);
+        }
+        world.run_for(self.inter_trial_interval);
+        Trial {
+            time,
+            cue,
+            votes,
+            action,
+            reward,
+        }
+    }
+}
+
+/// Writes one `trial,time,cue,action,reward` CSV row per trial, numbered from `first_trial`, with
+/// an empty action when the network did not commit to one.
+pub fn write_trials_csv<W: Write>(
+    mut writer: W,
+    trials: &[Trial],
+    first_trial: usize,
+) -> io::Result<()> {
+    writeln!(writer, "trial,time,cue,action,reward")?;
+    for (idx, trial) in trials.iter().enumerate() {
+        let action = trial
+            .action
+            .map(|action| action.to_string())
+            .unwrap_or_default();
+        writeln!(
+            writer,
+            "{},{},{},{action},{}",
+            first_trial + idx,
+            trial.time,
+            trial.cue,
+            trial.reward
+        )?;
+    }
+    writer.flush()
+}
@@ -0,0 +1,91 @@
+use plotters::prelude::*;
+use plotters_canvas::CanvasBackend;
+use wasm_bindgen::prelude::*;
+
+use crate::plot::recent_raster;
+use crate::simulator::WorldState;
+use crate::stimulus::StepCurrent;
+
+/// JavaScript handle of a [`WorldState`] for running the network interactively in a web page.
+/// Steps run on the calling thread, so a page advances it a few steps per animation frame.
+#[wasm_bindgen]
+pub struct Simulation {
+    world: WorldState,
+}
+
+#[wasm_bindgen]
+impl Simulation {
+    /// Builds the network of the paper; without a seed it draws one from the browser.
+    #[wasm_bindgen(constructor)]
+    pub fn new(excitatory: usize, inhibitory: usize, seed: Option<u32>) -> Simulation {
+        let mut builder = WorldState::builder()
+            .excitatory(excitatory)
+            .inhibitory(inhibitory);
+        if let Some(seed) = seed {
+            builder = builder.seed(seed.into());
+        }
+        Simulation {
+            world: builder.build(),
+        }
+    }
+
+    /// Advances the simulation by `steps` time steps.
+    pub fn step(&mut self, steps: usize) {
+        self.world.run(steps);
+    }
+
+    /// Simulated time in milliseconds.
+    #[wasm_bindgen(getter)]
+    pub fn time(&self) -> f64 {
+        self.world.time()
+    }
+
+    /// Number of neurons that fired in the last step.
+    #[wasm_bindgen(getter)]
+    pub fn firing(&self) -> usize {
+        self.world
+            .last_spikes()
+            .iter()
+            .filter(|&&spike| spike)
+            .count()
+    }
+
+    #[wasm_bindgen(getter)]
+    pub fn thalamic_noise(&self) -> bool {
+        self.world.thalamic_noise
+    }
+
+    #[wasm_bindgen(setter)]
+    pub fn set_thalamic_noise(&mut self, thalamic_noise: bool) {
+        self.world.thalamic_noise = thalamic_noise;
+    }
+
+    /// Injects a constant current into neurons `start..stop` for the next `duration`
+    /// milliseconds.
+    pub fn stimulate(
+        &mut self,
+        amplitude: f64,
+        start: usize,
+        stop: usize,
+        duration: f64,
+    ) -> Result<(), JsError> {
+        let n = self.world.neurons.len();
+        if start >= stop || stop > n {
+            return Err(JsError::new(&format!(
+                "neurons {start}..{stop} out of range for {n} neurons"
+            )));
+        }
+        let now = self.world.time();
+        self.world
+            .add_stimulus(StepCurrent { amplitude }, start..stop, now..now + duration);
+        Ok(())
+    }
+
+    /// Draws the raster of the last `window_ms` milliseconds onto the canvas with id `canvas`.
+    pub fn draw(&self, canvas: &str, window_ms: f64) -> Result<(), JsError> {
+        let backend = CanvasBackend::new(canvas)
+            .ok_or_else(|| JsError::new(&format!("no canvas with id `{canvas}`")))?;
+        recent_raster(&backend.into_drawing_area(), &self.world, window_ms)
+            .map_err(|err| JsError::new(&err.to_string()))
+    }
+}
@@ -0,0 +1,130 @@
+use std::error::Error;
+use std::fs;
+use std::io::{self, Write};
+use std::path::Path;
+
+use crate::network::Synapse;
+use crate::npy::read_npy;
+use crate::simulator::SparseConnectivity;
+
+/// Writes one `pre,post,weight,delay` CSV row per synapse of `connectivity`, with delays in
+/// milliseconds for steps of `dt`, to be read back with [`read_weights_csv`].
+pub fn write_weights_csv<W: Write>(
+    mut writer: W,
+    connectivity: &SparseConnectivity,
+    dt: f64,
+) -> io::Result<()> {
+    writeln!(writer, "pre,post,weight,delay")?;
+    for pre in 0..connectivity.neurons() {
+        for synapse in connectivity.row(pre) {
+            writeln!(
+                writer,
+                "{pre},{},{},{}",
+                connectivity.targets()[synapse],
+                connectivity.weights()[synapse],
+                connectivity.delays()[synapse] as f64 * dt
+            )?;
+        }
+    }
+    writer.flush()
+}
+
+/// Reads the `(pre, post, weight, delay)` synapses written with [`write_weights_csv`], for
+/// [`crate::WorldStateBuilder::synapses`].
+pub fn read_weights_csv(path: &Path) -> Result<Vec<Synapse>, Box<dyn Error>> {
+    let text = fs::read_to_string(path)?;
+    let mut lines = text
+        .lines()
+        .enumerate()
+        .filter(|(_, line)| !line.trim().is_empty());
+    match lines.next() {
+        Some((_, header)) if header.trim() == "pre,post,weight,delay" => {}
+        Some((_, header)) => {
+            return Err(format!("expected `pre,post,weight,delay`, got `{header}`").into())
+        }
+        None => return Ok(vec![]),
+    }
+    let synapses = lines
+        .map(|(number, line)| {
+            let fields: Vec<&str> = line.split(',').map(str::trim).collect();
+            let [pre, post, weight, delay] = fields[..] else {
+                return Err(format!(
+                    "line {}: expected `pre,post,weight,delay`",
+                    number + 1
+                ));
+            };
+            let index = |field: &str| {
+                field
+                    .parse::<usize>()
+                    .map_err(|err| format!("line {}: `{field}`: {err}", number + 1))
+            };
+            let number_at = |field: &str| {
+                field
+                    .parse::<f64>()
+                    .map_err(|err| format!("line {}: `{field}`: {err}", number + 1))
+            };
+            Ok((
+                index(pre)?,
+                index(post)?,
+                number_at(weight)?,
+                number_at(delay)?,
+            ))
+        })
+        .collect::<Result<Vec<_>, String>>()?;
+    Ok(synapses)
+}
+
+/// Reads a square weight matrix whose entry in row `pre` and column `post` is the signed weight
+/// of the synapse from `pre` onto `post`, or 0 for none, from a `.npy` file or else a CSV file
+/// of one row per line separated by commas or whitespace, for
+/// [`crate::WorldStateBuilder::synapses`]. All synapses get a delay of `delay` milliseconds.
+/// Returns the number of neurons with the synapses.
+pub fn read_weight_matrix(
+    path: &Path,
+    delay: f64,
+) -> Result<(usize, Vec<Synapse>), Box<dyn Error>> {
+    let (rows, columns, entries) = if path.extension().is_some_and(|ext| ext == "npy") {
+        let array = read_npy(path)?;
+        let [rows, columns] = array.shape[..] else {
+            return Err(format!("expected a matrix, got shape {:?}", array.shape).into());
+        };
+        (rows, columns, array.data)
+    } else {
+        let text = fs::read_to_string(path)?;
+        let mut entries = vec![];
+        let (mut rows, mut columns) = (0, None);
+        for (number, line) in text.lines().enumerate() {
+            let line = line.trim();
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+            let start = entries.len();
+            for field in line
+                .split(|c: char| c == ',' || c.is_whitespace())
+                .filter(|field| !field.is_empty())
+            {
+                let weight = field
+                    .parse::<f64>()
+                    .map_err(|err| format!("line {}: `{field}`: {err}", number + 1))?;
+                entries.push(weight);
+            }
+            let width = entries.len() - start;
+            let expected = *columns.get_or_insert(width);
+            if width != expected {
+                return Err(format!("line {}: expected {expected} columns", number + 1).into());
+            }
+            rows += 1;
+        }
+        (rows, columns.unwrap_or(0), entries)
+    };
+    if rows != columns {
+        return Err(format!("expected a square matrix, got {rows}×{columns}").into());
+    }
+    let synapses = entries
+        .into_iter()
+        .enumerate()
+        .filter(|&(_, weight)| weight != 0.)
+        .map(|(idx, weight)| (idx / columns, idx % columns, weight, delay))
+        .collect();
+    Ok((rows, synapses))
+}
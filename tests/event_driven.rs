@@ -0,0 +1,50 @@
+use izhikevich_rs::{PoissonInput, SpikeTrains, WorldState, WorldStateBuilder};
+
+const NEURONS: usize = 200;
+
+/// A sparse network without thalamic noise, driven by a Poisson input few enough neurons
+/// receive at every step that most of them settle at rest in between.
+fn network() -> WorldStateBuilder {
+    WorldState::builder()
+        .excitatory(160)
+        .inhibitory(40)
+        .connection_probability(0.1)
+        .thalamic_noise(false)
+        .poisson_input(PoissonInput {
+            sources: 20,
+            rate: 1.,
+            weight: 20.,
+            connection_probability: 0.1,
+            targets: 0..NEURONS,
+        })
+        .seed(7)
+}
+
+#[test]
+fn event_driven_stepping_matches_clock_driven() {
+    let mut clock_driven = network().build();
+    let mut event_driven = network().event_driven(1e-6).build();
+    for world in [&mut clock_driven, &mut event_driven] {
+        world.record_v(0..NEURONS);
+    }
+    let mut skipped = 0;
+    for _ in 0..1000 {
+        clock_driven.step();
+        event_driven.step();
+        skipped += event_driven.settled_neurons();
+    }
+    assert!(skipped > 0, "no neuron was ever skipped");
+
+    let spikes = SpikeTrains::from_world(&clock_driven);
+    assert!(spikes.trains.iter().any(|train| !train.is_empty()));
+    assert_eq!(spikes, SpikeTrains::from_world(&event_driven));
+    // settling moves a membrane by at most the tolerance, which later spikes amplify only a little
+    for ((neuron, clock), (_, event)) in clock_driven.v_traces.iter().zip(&event_driven.v_traces) {
+        for (v_clock, v_event) in clock.iter().zip(event) {
+            assert!(
+                (v_clock - v_event).abs() < 1e-3,
+                "membrane of neuron {neuron} differs: {v_clock} mV against {v_event} mV"
+            );
+        }
+    }
+}
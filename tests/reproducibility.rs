@@ -0,0 +1,47 @@
+use izhikevich_rs::{load_checkpoint, save_checkpoint, SpikeTrains, WorldState};
+
+const STEPS: usize = 1000;
+
+/// The paper's network with conduction delays and the spike-timing plasticity of
+/// polychronization, so more state has to come out the same.
+fn network(seed: u64) -> WorldState {
+    WorldState::builder().seed(seed).polychronization().build()
+}
+
+fn spikes(mut world: WorldState) -> SpikeTrains {
+    world.run(STEPS);
+    SpikeTrains::from_world(&world)
+}
+
+#[test]
+fn same_seed_gives_same_raster() {
+    let raster = spikes(network(1));
+    assert!(raster.trains.iter().any(|train| !train.is_empty()));
+    assert_eq!(raster, spikes(network(1)));
+    assert_ne!(raster, spikes(network(2)));
+}
+
+#[test]
+fn thread_count_does_not_change_the_raster() {
+    let in_pool = |threads| {
+        let pool = rayon::ThreadPoolBuilder::new()
+            .num_threads(threads)
+            .build()
+            .unwrap();
+        pool.install(|| spikes(network(1)))
+    };
+    assert_eq!(in_pool(1), in_pool(4));
+}
+
+#[test]
+fn resuming_a_checkpoint_continues_the_run() {
+    let path = std::env::temp_dir().join(format!("checkpoint-{}.bin", std::process::id()));
+    let mut world = network(1);
+    world.run(STEPS / 2);
+    save_checkpoint(&path, &mut world).unwrap();
+    let mut resumed = load_checkpoint(&path).unwrap();
+    std::fs::remove_file(&path).unwrap();
+    resumed.run(STEPS - STEPS / 2);
+
+    assert_eq!(SpikeTrains::from_world(&resumed), spikes(network(1)));
+}
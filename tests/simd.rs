@@ -0,0 +1,31 @@
+use izhikevich_rs::simulator::Backend;
+use izhikevich_rs::{SpikeTrains, WorldState};
+
+/// Spikes of the paper's network after `steps`, seeded, on `backend`.
+fn spikes(backend: Backend, steps: usize) -> SpikeTrains {
+    let mut world = WorldState::builder().seed(13).build();
+    world.set_backend(backend).unwrap();
+    world.run(steps);
+    SpikeTrains::from_world(&world)
+}
+
+#[test]
+fn simd_backend_matches_cpu() {
+    // single precision rounds differently, which the chaotic network amplifies into a different
+    // raster after a few hundred milliseconds, so only the start matches spike for spike
+    assert_eq!(spikes(Backend::Cpu, 200), spikes(Backend::Simd, 200));
+
+    let count = |spikes: &SpikeTrains| spikes.trains.iter().map(Vec::len).sum::<usize>() as f64;
+    let cpu = count(&spikes(Backend::Cpu, 1000));
+    let simd = count(&spikes(Backend::Simd, 1000));
+    assert!(
+        (cpu - simd).abs() < 0.02 * cpu,
+        "{simd} spikes on the SIMD backend against {cpu} on the CPU"
+    );
+}
+
+#[test]
+fn simd_backend_rejects_delays() {
+    let mut world = WorldState::builder().delay_range(1, 20).build();
+    assert!(world.set_backend(Backend::Simd).is_err());
+}